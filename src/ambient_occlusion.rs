@@ -0,0 +1,229 @@
+use crate::math::Vec3f;
+use crate::mesh::Mesh;
+use crate::sampling::hemisphere_cosine_sample;
+use crate::scene::Scene;
+
+// How far occluders count, in world units; anything further away doesn't darken
+const AO_RAY_LENGTH: f32 = 1.5;
+// Rays start this far off the surface so they don't hit the vertex's own triangles
+const AO_RAY_OFFSET: f32 = 1e-3;
+
+/// One object's triangles in world space, with a bounding sphere to skip it quickly
+struct Occluder {
+    triangles: Vec<[Vec3f; 3]>,
+    center: Vec3f,
+    radius: f32,
+}
+
+impl Scene {
+    ///
+    /// Bakes ambient occlusion into every mesh's vertex_ao: from each vertex, `samples_per_vertex`
+    /// cosine-weighted rays go out over the hemisphere around its normal, and the factor is the
+    /// share that travel AO_RAY_LENGTH without hitting any triangle in the scene. Lighting then
+    /// scales ambient light by it, so corners and creases come out darker.
+    ///
+    /// Uses the objects' current transforms; rebake after moving things. Brute force over every
+    /// triangle, so expect it to take a moment on larger scenes.
+    ///
+    pub fn bake_vertex_ao(&mut self, samples_per_vertex: usize) {
+        let samples_per_vertex = samples_per_vertex.max(1);
+
+        let occluders: Vec<Occluder> = self.game_objects
+            .iter()
+            .map(|game_object| {
+                let vertices = game_object.mesh.transform_vertices(&game_object.get_world_matrix());
+                let (center, radius) = game_object.get_bounding_sphere();
+                Occluder {
+                    triangles: game_object.mesh.triangles
+                        .iter()
+                        .map(|triangle| triangle.indices.map(|index| vertices[index]))
+                        .collect(),
+                    center,
+                    radius,
+                }
+            })
+            .collect();
+
+        let mut baked = Vec::with_capacity(self.game_objects.len());
+        for game_object in &self.game_objects {
+            let world_matrix = game_object.get_world_matrix();
+            let normal_matrix = game_object.get_normal_matrix();
+            let vertices = game_object.mesh.transform_vertices(&world_matrix);
+            let normals = game_object.mesh.compute_vertex_normals();
+
+            let ao: Vec<f32> = vertices
+                .iter()
+                .zip(&normals)
+                .enumerate()
+                .map(|(index, (vertex, normal))| {
+                    let normal = normal_matrix.multiply_vector(normal).normalize();
+                    if normal.length() == 0.0 {
+                        return 1.0; // Loose vertex, nothing to shade
+                    }
+                    let origin = *vertex + normal * AO_RAY_OFFSET;
+                    let rotation = hash_to_unit(index as u32);
+
+                    let open = (0..samples_per_vertex)
+                        .filter(|&sample| {
+                            let direction = cosine_hemisphere(normal, sample, samples_per_vertex, rotation);
+                            !occluders.iter().any(|occluder| occluder.blocks(origin, direction))
+                        })
+                        .count();
+                    open as f32 / samples_per_vertex as f32
+                })
+                .collect();
+            baked.push(ao);
+        }
+
+        for (game_object, ao) in self.game_objects.iter_mut().zip(baked) {
+            game_object.mesh.vertex_ao = ao;
+        }
+    }
+}
+
+impl Occluder {
+    fn blocks(&self, origin: Vec3f, direction: Vec3f) -> bool {
+        // Skip the object if the ray segment can't reach its bounding sphere
+        let to_center = self.center - origin;
+        let along = to_center.dot(&direction).clamp(0.0, AO_RAY_LENGTH);
+        let closest = origin + direction * along;
+        if (closest - self.center).length() > self.radius {
+            return false;
+        }
+
+        self.triangles
+            .iter()
+            .any(|&[v0, v1, v2]| ray_hits_triangle(origin, direction, v0, v1, v2, AO_RAY_LENGTH))
+    }
+}
+
+impl Mesh {
+    ///
+    /// Ambient occlusion per vertex from the mesh's own triangles, in its own space: `num_rays`
+    /// cosine-weighted rays over the hemisphere around each vertex normal, each counted as
+    /// blocked if it hits a triangle within `max_distance`. Returns the share left unblocked
+    /// (1 = open), ready to store in vertex_ao. Scene::bake_vertex_ao does the same with the
+    /// other objects in the scene as occluders.
+    ///
+    pub fn bake_ambient_occlusion(&self, num_rays: usize, max_distance: f32) -> Vec<f32> {
+        self.bake_ambient_occlusion_with_progress(num_rays, max_distance, |_, _| {})
+    }
+
+    /// bake_ambient_occlusion, calling `progress(done, total)` after each vertex
+    pub fn bake_ambient_occlusion_with_progress(&self, num_rays: usize, max_distance: f32,
+                                                mut progress: impl FnMut(usize, usize)) -> Vec<f32> {
+        let num_rays = num_rays.max(1);
+        let normals = self.compute_vertex_normals();
+        let triangles: Vec<[Vec3f; 3]> = self.triangles
+            .iter()
+            .map(|triangle| triangle.indices.map(|index| self.vertices[index]))
+            .collect();
+
+        let total = self.vertices.len();
+        let mut ao = Vec::with_capacity(total);
+        for (index, (vertex, normal)) in self.vertices.iter().zip(&normals).enumerate() {
+            let open = if normal.length() == 0.0 {
+                num_rays // Loose vertex, nothing to shade
+            } else {
+                let origin = *vertex + *normal * AO_RAY_OFFSET;
+                let rotation = hash_to_unit(index as u32);
+                (0..num_rays)
+                    .filter(|&sample| {
+                        let direction = cosine_hemisphere(*normal, sample, num_rays, rotation);
+                        !triangles
+                            .iter()
+                            .any(|&[v0, v1, v2]| ray_hits_triangle(origin, direction, v0, v1, v2, max_distance))
+                    })
+                    .count()
+            };
+            ao.push(open as f32 / num_rays as f32);
+            progress(index + 1, total);
+        }
+        ao
+    }
+}
+
+/// Möller–Trumbore, double-sided, for hits between 0 and `max_distance`
+fn ray_hits_triangle(origin: Vec3f, direction: Vec3f, v0: Vec3f, v1: Vec3f, v2: Vec3f, max_distance: f32) -> bool {
+    let edge1 = v1 - v0;
+    let edge2 = v2 - v0;
+    let p = direction.cross(&edge2);
+    let det = edge1.dot(&p);
+    if det.abs() < 1e-8 {
+        return false; // Parallel
+    }
+
+    let inv_det = 1.0 / det;
+    let to_origin = origin - v0;
+    let u = to_origin.dot(&p) * inv_det;
+    if !(0.0..=1.0).contains(&u) {
+        return false;
+    }
+    let q = to_origin.cross(&edge1);
+    let v = direction.dot(&q) * inv_det;
+    if v < 0.0 || u + v > 1.0 {
+        return false;
+    }
+
+    let t = edge2.dot(&q) * inv_det;
+    t > AO_RAY_OFFSET && t < max_distance
+}
+
+///
+/// Sample `index` of `count` from a Hammersley set mapped to a cosine-weighted hemisphere
+/// around `normal`. `rotation` spins the set per vertex so neighbours don't share the same
+/// rays (which would show up as banding).
+///
+fn cosine_hemisphere(normal: Vec3f, index: usize, count: usize, rotation: f32) -> Vec3f {
+    let u = (index as f32 + 0.5) / count as f32;
+    let v = (radical_inverse(index as u32) + rotation).fract();
+
+    hemisphere_cosine_sample(normal, u, v)
+}
+
+// Van der Corput sequence in base 2: the bits of `i` mirrored behind the binary point
+fn radical_inverse(i: u32) -> f32 {
+    i.reverse_bits() as f32 / 4_294_967_296.0
+}
+
+// Cheap integer hash to [0, 1)
+fn hash_to_unit(value: u32) -> f32 {
+    let mut x = value.wrapping_mul(0x9E37_79B9);
+    x ^= x >> 16;
+    x = x.wrapping_mul(0x85EB_CA6B);
+    x ^= x >> 13;
+    (x >> 8) as f32 / (1 << 24) as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scene::GameObject;
+
+    #[test]
+    fn open_plane_is_unoccluded() {
+        let mut scene = Scene::new();
+        scene.add_game_object(GameObject::new(Mesh::create_plane(4.0, 4.0, 4, 4)));
+        scene.bake_vertex_ao(64);
+
+        let ao = &scene.game_objects[0].mesh.vertex_ao;
+        assert_eq!(ao.len(), 25);
+        assert!(ao.iter().all(|&open| (open - 1.0).abs() < 1e-6), "{ao:?}");
+        let alone = Mesh::create_plane(4.0, 4.0, 4, 4).bake_ambient_occlusion(64, AO_RAY_LENGTH);
+        assert!(alone.iter().all(|&open| (open - 1.0).abs() < 1e-6), "{alone:?}");
+    }
+
+    #[test]
+    fn inner_corner_of_a_box_is_occluded() {
+        // Inside out, so every corner's normal points into the box
+        let mut room = Mesh::create_cube();
+        room.flip_winding();
+        let ao = room.bake_ambient_occlusion(256, AO_RAY_LENGTH);
+
+        assert_eq!(ao.len(), 8);
+        for (corner, &open) in room.vertices.iter().zip(&ao) {
+            assert!(open < 0.75, "corner {corner:?} should be well occluded, got {open}");
+            assert!(open > 0.0, "corner {corner:?} still sees down the middle of the box, got {open}");
+        }
+    }
+}
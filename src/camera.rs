@@ -1,4 +1,170 @@
+use std::fmt;
+use crate::coordinate_system::CoordinateSystem;
 use crate::math::{Mat4x4, Vec3f};
+use crate::renderer::Viewport;
+
+/// Reasons a camera parameter can be rejected.
+/// All of these would otherwise produce a NaN projection matrix (and a black screen).
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum CameraError {
+    InvalidFov(f32),                         // Must be in (0, 180) degrees
+    InvalidNearPlane(f32),                   // Must be > 0
+    InvalidFarPlane { near: f32, far: f32 }, // Must be > near
+    InvalidAspect(f32),                      // Must be > 0
+}
+
+impl fmt::Display for CameraError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CameraError::InvalidFov(fov) =>
+                write!(f, "field of view must be between 0 and 180 degrees (got {} radians)", fov),
+            CameraError::InvalidNearPlane(near) =>
+                write!(f, "near plane must be greater than 0 (got {})", near),
+            CameraError::InvalidFarPlane { near, far } =>
+                write!(f, "far plane must be greater than near plane (got near {}, far {})", near, far),
+            CameraError::InvalidAspect(aspect) =>
+                write!(f, "aspect ratio must be greater than 0 (got {})", aspect),
+        }
+    }
+}
+
+impl std::error::Error for CameraError {}
+
+/// How the projection aspect ratio follows the window
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum AspectPolicy {
+    Stretch,    // Use the whole window, whatever its shape
+    Fixed(f32), // Lock to width/height ratio and add black bars around it
+}
+
+/// A plane stored as normal · p + distance = 0, with the normal pointing "inside"
+#[derive(Copy, Clone, Debug)]
+pub struct Plane {
+    pub normal: Vec3f,
+    pub distance: f32,
+}
+
+impl Plane {
+    /// Builds a plane from (a, b, c, d) coefficients, normalizing so distances are in world units
+    pub fn from_coefficients(a: f32, b: f32, c: f32, d: f32) -> Self {
+        let normal = Vec3f::new(a, b, c);
+        let length = normal.length();
+        if length > 0.0 {
+            Self { normal: normal / length, distance: d / length }
+        } else {
+            Self { normal, distance: d }
+        }
+    }
+
+    /// Positive in front of the plane (inside), negative behind it
+    pub fn signed_distance(&self, point: &Vec3f) -> f32 {
+        self.normal.dot(point) + self.distance
+    }
+}
+
+/// The six planes bounding what a camera can see, in world space
+#[derive(Copy, Clone, Debug)]
+pub struct Frustum {
+    pub planes: [Plane; 6], // left, right, bottom, top, near, far
+}
+
+impl Frustum {
+    ///
+    /// Extracts the planes straight from a view-projection matrix (Gribb/Hartmann).
+    /// A point is inside when -w <= x, y, z <= w in clip space, and each of those
+    /// six inequalities is a plane made from the w row plus/minus one other row.
+    ///
+    pub fn from_matrix(view_projection: &Mat4x4) -> Self {
+        let r0 = view_projection.get_row(0);
+        let r1 = view_projection.get_row(1);
+        let r2 = view_projection.get_row(2);
+        let r3 = view_projection.get_row(3);
+
+        let plane = |sign: f32, row: [f32; 4]| {
+            Plane::from_coefficients(
+                r3[0] + sign * row[0],
+                r3[1] + sign * row[1],
+                r3[2] + sign * row[2],
+                r3[3] + sign * row[3],
+            )
+        };
+
+        Self {
+            planes: [
+                plane(1.0, r0),  // left
+                plane(-1.0, r0), // right
+                plane(1.0, r1),  // bottom
+                plane(-1.0, r1), // top
+                plane(1.0, r2),  // near
+                plane(-1.0, r2), // far
+            ],
+        }
+    }
+
+    pub fn contains_point(&self, point: &Vec3f) -> bool {
+        self.planes.iter().all(|plane| plane.signed_distance(point) >= 0.0)
+    }
+
+    /// Conservative test: true if any part of the sphere might be visible
+    pub fn intersects_sphere(&self, center: &Vec3f, radius: f32) -> bool {
+        self.planes.iter().all(|plane| plane.signed_distance(center) >= -radius)
+    }
+}
+
+///
+/// View and projection matrices along with the camera settings they were built from.
+/// Only used while those settings still match, so writing Camera's fields directly can
+/// never leave a stale matrix in use; it just costs a rebuild until update_matrices runs.
+///
+#[derive(Copy, Clone)]
+struct CachedMatrices {
+    position: Vec3f,
+    target: Vec3f,
+    up: Vec3f,
+    fov: f32,
+    aspect: f32,
+    near: f32,
+    far: f32,
+    coordinate_system: CoordinateSystem,
+    view: Mat4x4,
+    projection: Mat4x4,
+    view_projection: Mat4x4,
+    inverse_view_projection: Option<Mat4x4>,
+}
+
+impl CachedMatrices {
+    fn build(camera: &Camera) -> Self {
+        let view = camera.build_view_matrix();
+        let projection = Mat4x4::perspective(camera.fov, camera.aspect, camera.near, camera.far);
+        let view_projection = projection.multiply(&view);
+        Self {
+            position: camera.position,
+            target: camera.target,
+            up: camera.up,
+            fov: camera.fov,
+            aspect: camera.aspect,
+            near: camera.near,
+            far: camera.far,
+            coordinate_system: camera.coordinate_system,
+            view,
+            projection,
+            view_projection,
+            inverse_view_projection: view_projection.inverse(),
+        }
+    }
+
+    fn is_current(&self, camera: &Camera) -> bool {
+        let same = |a: Vec3f, b: Vec3f| a.x == b.x && a.y == b.y && a.z == b.z;
+        same(self.position, camera.position)
+            && same(self.target, camera.target)
+            && same(self.up, camera.up)
+            && self.fov == camera.fov
+            && self.aspect == camera.aspect
+            && self.near == camera.near
+            && self.far == camera.far
+            && self.coordinate_system == camera.coordinate_system
+    }
+}
 
 #[derive(Copy, Clone)]
 pub struct Camera {
@@ -9,6 +175,11 @@ pub struct Camera {
     pub aspect: f32,     // Width / Height ratio
     pub near: f32,       // Near clipping plane
     pub far: f32,        // Far clipping plane
+    pub aspect_policy: AspectPolicy,
+    pub focus_distance: f32, // Distance that is perfectly sharp (depth of field)
+    pub focus_range: f32,    // Width of the sharp band around focus_distance
+    pub coordinate_system: CoordinateSystem, // Of the world it looks at; left-handed worlds are mirrored into view
+    cached_matrices: Option<CachedMatrices>, // See update_matrices
 }
 
 impl Camera {
@@ -21,6 +192,11 @@ impl Camera {
             aspect: 4.0 / 3.0,                // 4:3 aspect ratio
             near: 0.1,
             far: 100.0,
+            aspect_policy: AspectPolicy::Stretch,
+            focus_distance: 8.0,
+            focus_range: 4.0,
+            coordinate_system: CoordinateSystem::default(),
+            cached_matrices: None,
         }
     }
 
@@ -28,18 +204,209 @@ impl Camera {
         Self::new(eye, target, up)
     }
 
+    pub fn builder() -> CameraBuilder {
+        CameraBuilder::new()
+    }
+
+    /// Sets the vertical field of view (in radians), rejecting values outside (0, PI)
+    pub fn set_fov(&mut self, fov: f32) -> Result<(), CameraError> {
+        validate_fov(fov)?;
+        self.fov = fov;
+        Ok(())
+    }
+
+    /// Sets both clipping planes at once, so near/far can't be left in an invalid order
+    pub fn set_clip_planes(&mut self, near: f32, far: f32) -> Result<(), CameraError> {
+        validate_clip_planes(near, far)?;
+        self.near = near;
+        self.far = far;
+        Ok(())
+    }
+
+    ///
+    /// Circle of confusion for a view distance, from 0 (sharp) to 1 (fully blurred).
+    /// Everything within focus_range / 2 of the focus distance is sharp, and the blur
+    /// then ramps up linearly over another focus_range. Infinite distance (sky) is fully blurred.
+    ///
+    pub fn circle_of_confusion(&self, distance: f32) -> f32 {
+        if !distance.is_finite() {
+            return 1.0;
+        }
+
+        let half_range = self.focus_range.max(0.0) * 0.5;
+        let defocus = (distance - self.focus_distance).abs() - half_range;
+        if defocus <= 0.0 {
+            return 0.0;
+        }
+        if self.focus_range <= 0.0 {
+            return 1.0;
+        }
+        (defocus / self.focus_range).min(1.0)
+    }
+
+    ///
+    /// Builds the view, projection and view-projection matrices (and the inverse) and keeps
+    /// them until position, target, up, fov, aspect, near or far change. The getters below
+    /// use them while they match and build fresh matrices when they don't, so calling this
+    /// once the camera is set up for a frame is all it takes. Scene::render does.
+    ///
+    pub fn update_matrices(&mut self) {
+        if self.current_matrices().is_none() {
+            self.cached_matrices = Some(CachedMatrices::build(self));
+        }
+    }
+
+    // The cached matrices, if they were built from the camera as it is now
+    fn current_matrices(&self) -> Option<&CachedMatrices> {
+        self.cached_matrices.as_ref().filter(|cached| cached.is_current(self))
+    }
+
     pub fn get_view_matrix(&self) -> Mat4x4 {
-        Mat4x4::look_at(self.position, self.target, self.up)
+        match self.current_matrices() {
+            Some(cached) => cached.view,
+            None => self.build_view_matrix(),
+        }
+    }
+
+    // look_at builds a right-handed view, so a left-handed world is mirrored into it first
+    fn build_view_matrix(&self) -> Mat4x4 {
+        if !self.coordinate_system.is_left_handed() {
+            return Mat4x4::look_at(self.position, self.target, self.up);
+        }
+        let mirror = |v: Vec3f| Vec3f::new(v.x, v.y, -v.z);
+        Mat4x4::look_at(mirror(self.position), mirror(self.target), mirror(self.up))
+            .multiply(&Mat4x4::scale(1.0, 1.0, -1.0))
     }
 
     pub fn get_projection_matrix(&self) -> Mat4x4 {
-        Mat4x4::perspective(self.fov, self.aspect, self.near, self.far)
+        match self.current_matrices() {
+            Some(cached) => cached.projection,
+            None => Mat4x4::perspective(self.fov, self.aspect, self.near, self.far),
+        }
+    }
+
+    pub fn get_view_projection_matrix(&self) -> Mat4x4 {
+        match self.current_matrices() {
+            Some(cached) => cached.view_projection,
+            None => self.get_projection_matrix().multiply(&self.get_view_matrix()),
+        }
+    }
+
+    /// Clip space back to world space, for unprojecting; None if the matrix can't be inverted
+    pub fn get_inverse_view_projection_matrix(&self) -> Option<Mat4x4> {
+        match self.current_matrices() {
+            Some(cached) => cached.inverse_view_projection,
+            None => self.get_view_projection_matrix().inverse(),
+        }
+    }
+
+    pub fn frustum(&self) -> Frustum {
+        Frustum::from_matrix(&self.get_view_projection_matrix())
+    }
+
+    ///
+    /// The 8 corners of the view frustum in world space, found by pushing the corners
+    /// of the NDC cube back through the inverse view-projection matrix.
+    /// Order: near plane (bottom-left, bottom-right, top-right, top-left), then the far plane in the same order.
+    ///
+    pub fn frustum_corners(&self) -> [Vec3f; 8] {
+        let inverse = match self.get_inverse_view_projection_matrix() {
+            Some(inverse) => inverse,
+            None => return [self.position; 8],
+        };
+
+        let ndc_corners = [
+            Vec3f::new(-1.0, -1.0, -1.0),
+            Vec3f::new( 1.0, -1.0, -1.0),
+            Vec3f::new( 1.0,  1.0, -1.0),
+            Vec3f::new(-1.0,  1.0, -1.0),
+            Vec3f::new(-1.0, -1.0,  1.0),
+            Vec3f::new( 1.0, -1.0,  1.0),
+            Vec3f::new( 1.0,  1.0,  1.0),
+            Vec3f::new(-1.0,  1.0,  1.0),
+        ];
+
+        // multiply_point does the divide by w for us
+        ndc_corners.map(|corner| inverse.multiply_point(&corner))
+    }
+
+    ///
+    /// Splits the frustum at the given view distances (e.g. for shadow cascades).
+    /// Distances outside (near, far) are ignored, so [10.0, 30.0] gives
+    /// three slices: near..10, 10..30 and 30..far.
+    ///
+    pub fn split_frustum(&self, splits: &[f32]) -> Vec<[Vec3f; 8]> {
+        let corners = self.frustum_corners();
+
+        let mut distances = vec![self.near];
+        distances.extend(splits.iter().copied().filter(|d| *d > self.near && *d < self.far));
+        distances.push(self.far);
+
+        // Each frustum edge runs straight from a near corner to its far corner,
+        // and view depth changes linearly along it, so we can just lerp.
+        let corners_at = |distance: f32| -> [Vec3f; 4] {
+            let t = (distance - self.near) / (self.far - self.near);
+            [0, 1, 2, 3].map(|i| corners[i] + (corners[i + 4] - corners[i]) * t)
+        };
+
+        distances
+            .windows(2)
+            .map(|pair| {
+                let near = corners_at(pair[0]);
+                let far = corners_at(pair[1]);
+                [near[0], near[1], near[2], near[3], far[0], far[1], far[2], far[3]]
+            })
+            .collect()
     }
 
     pub fn set_aspect_ratio(&mut self, width: f32, height: f32) {
         self.aspect = width / height;
     }
 
+    pub fn set_aspect_policy(&mut self, policy: AspectPolicy) {
+        self.aspect_policy = policy;
+    }
+
+    ///
+    /// The part of a window_width x window_height framebuffer this camera draws into.
+    /// With a fixed aspect the image is centered: bars go top/bottom (letterbox) when the
+    /// window is too tall, and left/right (pillarbox) when it is too wide.
+    ///
+    pub fn compute_viewport(&self, window_width: u32, window_height: u32) -> Viewport {
+        let ratio = match self.aspect_policy {
+            AspectPolicy::Fixed(ratio) if ratio > 0.0 && window_width > 0 && window_height > 0 => ratio,
+            _ => return Viewport::new(0, 0, window_width, window_height),
+        };
+
+        let window_ratio = window_width as f32 / window_height as f32;
+        if window_ratio > ratio {
+            // Too wide: pillarbox
+            let width = ((window_height as f32 * ratio).round() as u32).clamp(1, window_width);
+            Viewport::new((window_width - width) / 2, 0, width, window_height)
+        } else {
+            // Too tall: letterbox
+            let height = ((window_width as f32 / ratio).round() as u32).clamp(1, window_height);
+            Viewport::new(0, (window_height - height) / 2, window_width, height)
+        }
+    }
+
+    ///
+    /// Turns a pixel into a world-space ray (origin on the near plane, normalized direction).
+    /// Returns None for pixels in the letterbox/pillarbox bars.
+    ///
+    pub fn screen_to_ray(&self, x: f32, y: f32, window_width: u32, window_height: u32) -> Option<(Vec3f, Vec3f)> {
+        let viewport = self.compute_viewport(window_width, window_height);
+        let ndc = viewport.screen_to_ndc(x, y)?;
+
+        let mut camera = *self;
+        camera.aspect = viewport.aspect_ratio();
+        let inverse = camera.get_inverse_view_projection_matrix()?;
+
+        let near_point = inverse.multiply_point(&Vec3f::new(ndc.x, ndc.y, -1.0));
+        let far_point = inverse.multiply_point(&Vec3f::new(ndc.x, ndc.y, 1.0));
+        Some((near_point, (far_point - near_point).normalize()))
+    }
+
     // Camera movement methods
     pub fn move_forward(&mut self, distance: f32) {
         let forward = (self.target - self.position).normalize();
@@ -98,10 +465,261 @@ impl Camera {
     }
 
     pub fn get_right_vector(&self) -> Vec3f {
-        self.get_forward_vector().cross(&self.up).normalize()
+        let right = self.get_forward_vector().cross(&self.up).normalize();
+        // The cross product follows the right hand; in a left-handed world that's the camera's left
+        if self.coordinate_system.is_left_handed() { -right } else { right }
     }
 
     pub fn get_up_vector(&self) -> Vec3f {
         self.up
     }
-}
\ No newline at end of file
+}
+
+///
+/// Builds a camera with validated parameters.
+/// Anything not set falls back to the same defaults as Camera::new.
+///
+pub struct CameraBuilder {
+    position: Vec3f,
+    target: Vec3f,
+    up: Vec3f,
+    fov_degrees: f32,
+    near: f32,
+    far: f32,
+    aspect: f32,
+    coordinate_system: CoordinateSystem,
+}
+
+impl CameraBuilder {
+    pub fn new() -> Self {
+        Self {
+            position: Vec3f::new(0.0, 0.0, 8.0),
+            target: Vec3f::zero(),
+            up: Vec3f::up(),
+            fov_degrees: 45.0,
+            near: 0.1,
+            far: 100.0,
+            aspect: 4.0 / 3.0,
+            coordinate_system: CoordinateSystem::default(),
+        }
+    }
+
+    /// Sets the world's coordinate system, and up to its up axis (call up() after to override)
+    pub fn coordinate_system(mut self, coordinate_system: CoordinateSystem) -> Self {
+        self.coordinate_system = coordinate_system;
+        self.up = coordinate_system.up();
+        self
+    }
+
+    pub fn position(mut self, position: Vec3f) -> Self {
+        self.position = position;
+        self
+    }
+
+    pub fn target(mut self, target: Vec3f) -> Self {
+        self.target = target;
+        self
+    }
+
+    pub fn up(mut self, up: Vec3f) -> Self {
+        self.up = up;
+        self
+    }
+
+    pub fn fov_degrees(mut self, fov_degrees: f32) -> Self {
+        self.fov_degrees = fov_degrees;
+        self
+    }
+
+    pub fn near(mut self, near: f32) -> Self {
+        self.near = near;
+        self
+    }
+
+    pub fn far(mut self, far: f32) -> Self {
+        self.far = far;
+        self
+    }
+
+    pub fn aspect(mut self, aspect: f32) -> Self {
+        self.aspect = aspect;
+        self
+    }
+
+    pub fn build(self) -> Result<Camera, CameraError> {
+        let fov = self.fov_degrees.to_radians();
+        validate_fov(fov)?;
+        validate_clip_planes(self.near, self.far)?;
+        if !(self.aspect > 0.0 && self.aspect.is_finite()) {
+            return Err(CameraError::InvalidAspect(self.aspect));
+        }
+
+        let mut camera = Camera::new(self.position, self.target, self.up);
+        camera.fov = fov;
+        camera.near = self.near;
+        camera.far = self.far;
+        camera.aspect = self.aspect;
+        camera.coordinate_system = self.coordinate_system;
+        Ok(camera)
+    }
+}
+
+impl Default for CameraBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Written as !(x > 0) so NaN is rejected too
+fn validate_fov(fov: f32) -> Result<(), CameraError> {
+    if !(fov > 0.0 && fov < std::f32::consts::PI) {
+        return Err(CameraError::InvalidFov(fov));
+    }
+    Ok(())
+}
+
+fn validate_clip_planes(near: f32, far: f32) -> Result<(), CameraError> {
+    if !(near > 0.0 && near.is_finite()) {
+        return Err(CameraError::InvalidNearPlane(near));
+    }
+    if !(far > near && far.is_finite()) {
+        return Err(CameraError::InvalidFarPlane { near, far });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn defaults_build() {
+        assert!(Camera::builder().build().is_ok());
+    }
+
+    #[test]
+    fn rejects_bad_fov() {
+        for fov in [0.0, -10.0, 180.0, 270.0, f32::NAN] {
+            assert!(matches!(Camera::builder().fov_degrees(fov).build(), Err(CameraError::InvalidFov(_))), "fov {fov}");
+        }
+    }
+
+    #[test]
+    fn rejects_near_at_or_below_zero() {
+        for near in [0.0, -0.1, f32::NAN] {
+            assert!(matches!(Camera::builder().near(near).build(), Err(CameraError::InvalidNearPlane(_))), "near {near}");
+        }
+    }
+
+    #[test]
+    fn rejects_far_at_or_before_near() {
+        assert_eq!(
+            Camera::builder().near(5.0).far(5.0).build().err(),
+            Some(CameraError::InvalidFarPlane { near: 5.0, far: 5.0 })
+        );
+        assert!(matches!(Camera::builder().near(1.0).far(0.5).build(), Err(CameraError::InvalidFarPlane { .. })));
+        assert!(matches!(Camera::builder().far(f32::INFINITY).build(), Err(CameraError::InvalidFarPlane { .. })));
+    }
+
+    #[test]
+    fn rejects_bad_aspect() {
+        for aspect in [0.0, -1.5, f32::NAN, f32::INFINITY] {
+            assert!(matches!(Camera::builder().aspect(aspect).build(), Err(CameraError::InvalidAspect(_))), "aspect {aspect}");
+        }
+    }
+
+    #[test]
+    fn frustum_corners_sit_at_near_and_far_along_their_rays() {
+        let (near, far, fov_degrees, aspect) = (0.5, 50.0, 60.0f32, 16.0 / 9.0);
+        let camera = Camera::builder()
+            .position(Vec3f::new(1.0, 2.0, 3.0))
+            .target(Vec3f::new(4.0, 2.0, -1.0))
+            .near(near)
+            .far(far)
+            .fov_degrees(fov_degrees)
+            .aspect(aspect)
+            .build()
+            .unwrap();
+        let (forward, right, up) = (camera.get_forward_vector(), camera.get_right_vector(), camera.get_up_vector());
+        let half_height = (fov_degrees.to_radians() * 0.5).tan();
+        let half_width = half_height * aspect;
+        let corners = camera.frustum_corners();
+
+        // Bottom-left, bottom-right, top-right, top-left on each plane
+        let signs = [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)];
+        for (i, (x_sign, y_sign)) in signs.into_iter().enumerate() {
+            let (near_offset, far_offset) = (corners[i] - camera.position, corners[i + 4] - camera.position);
+            for (offset, depth) in [(near_offset, near), (far_offset, far)] {
+                let close = |value: f32, expected: f32| (value - expected).abs() <= 1e-4 * depth.max(1.0);
+                assert!(close(offset.dot(&forward), depth), "corner {i} at depth {}", offset.dot(&forward));
+                assert!(close(offset.dot(&right), x_sign * depth * half_width), "corner {i}: {offset:?}");
+                assert!(close(offset.dot(&up), y_sign * depth * half_height), "corner {i}: {offset:?}");
+            }
+
+            // Each near corner is on the ray from the eye through its far corner, `near` along it
+            let ray = far_offset.normalize();
+            assert!((near_offset.normalize() - ray).length() < 1e-4, "corner {i} off its ray");
+            let along_ray = near / ray.dot(&forward);
+            assert!((near_offset.length() - along_ray).abs() < 1e-4, "corner {i}: {}", near_offset.length());
+        }
+    }
+
+    #[test]
+    fn fixed_aspect_viewport_letterboxes_and_pillarboxes() {
+        let mut camera = Camera::new(Vec3f::new(0.0, 0.0, 5.0), Vec3f::zero(), Vec3f::up());
+        camera.set_aspect_policy(AspectPolicy::Fixed(16.0 / 9.0));
+
+        let cases = [
+            ((1920, 1080), Viewport::new(0, 0, 1920, 1080)),   // Exact fit
+            ((1280, 720), Viewport::new(0, 0, 1280, 720)),     // Exact fit, smaller
+            ((1920, 1200), Viewport::new(0, 60, 1920, 1080)),  // Too tall: letterbox
+            ((800, 800), Viewport::new(0, 175, 800, 450)),
+            ((2560, 1080), Viewport::new(320, 0, 1920, 1080)), // Too wide: pillarbox
+            ((1000, 100), Viewport::new(411, 0, 178, 100)),
+            ((1, 1000), Viewport::new(0, 499, 1, 1)),          // Never shrinks to nothing
+            ((0, 0), Viewport::new(0, 0, 0, 0)),               // Minimized window
+        ];
+        for ((width, height), expected) in cases {
+            assert_eq!(camera.compute_viewport(width, height), expected, "{width}x{height}");
+        }
+
+        // Clicks in the bars don't make rays, clicks in the picture do
+        assert!(camera.screen_to_ray(960.0, 30.0, 1920, 1200).is_none());
+        assert!(camera.screen_to_ray(960.0, 600.0, 1920, 1200).is_some());
+        assert!(camera.screen_to_ray(100.0, 540.0, 2560, 1080).is_none());
+
+        camera.set_aspect_policy(AspectPolicy::Stretch);
+        for (width, height) in [(1920, 1200), (2560, 1080), (0, 0)] {
+            assert_eq!(camera.compute_viewport(width, height), Viewport::new(0, 0, width, height));
+        }
+    }
+
+    #[test]
+    fn circle_of_confusion_is_zero_in_focus_and_grows_to_the_maximum_either_side() {
+        let mut camera = Camera::new(Vec3f::new(0.0, 0.0, 5.0), Vec3f::zero(), Vec3f::up());
+        camera.focus_distance = 10.0;
+        camera.focus_range = 4.0;
+
+        assert_eq!(camera.circle_of_confusion(10.0), 0.0);
+        assert_eq!(camera.circle_of_confusion(8.5), 0.0);
+        assert_eq!(camera.circle_of_confusion(11.9), 0.0);
+        assert!((camera.circle_of_confusion(6.0) - 0.5).abs() < 1e-6);
+        assert!((camera.circle_of_confusion(14.0) - 0.5).abs() < 1e-6);
+
+        // Growing towards the camera and away from it, never past 1
+        let mut previous = 0.0;
+        for distance in [7.5, 7.0, 5.0, 4.0, 2.0, 0.1] {
+            let coc = camera.circle_of_confusion(distance);
+            assert!(coc >= previous && coc <= 1.0, "near {distance}: {coc}");
+            previous = coc;
+        }
+        assert_eq!(previous, 1.0);
+        let mut previous = 0.0;
+        for distance in [12.5, 13.0, 15.0, 16.0, 50.0, f32::INFINITY] {
+            let coc = camera.circle_of_confusion(distance);
+            assert!(coc >= previous && coc <= 1.0, "far {distance}: {coc}");
+            previous = coc;
+        }
+        assert_eq!(previous, 1.0);
+    }
+}
@@ -0,0 +1,294 @@
+use crate::camera::Camera;
+use crate::input::{InputManager, VK_A, VK_D, VK_LSHIFT, VK_S, VK_SPACE, VK_W};
+use crate::math::Vec3f;
+
+/// State for grounded first-person movement
+#[derive(Copy, Clone, Debug)]
+pub struct FirstPersonState {
+    pub walk_speed: f32,        // Units per second
+    pub eye_height: f32,        // Camera height above the ground
+    pub gravity: f32,           // Units per second²
+    pub jump_speed: f32,        // Initial upward speed, apex is jump_speed² / (2 * gravity)
+    pub bob_enabled: bool,
+    pub bob_amplitude: f32,     // Vertical bob in units at full walk speed
+    pub bob_frequency: f32,     // Bobs per second at full walk speed
+    pub vertical_velocity: f32,
+    pub grounded: bool,
+    bob_phase: f32,
+    bob_offset: f32,            // Currently applied to the camera, removed again before moving
+    heading: Vec3f,             // Last usable horizontal forward, for when looking straight up/down
+}
+
+impl FirstPersonState {
+    pub fn new() -> Self {
+        Self {
+            walk_speed: 3.5,
+            eye_height: 1.7,
+            gravity: 9.81,
+            jump_speed: 4.5,
+            bob_enabled: true,
+            bob_amplitude: 0.05,
+            bob_frequency: 1.8,
+            vertical_velocity: 0.0,
+            grounded: false,
+            bob_phase: 0.0,
+            bob_offset: 0.0,
+            heading: Vec3f::forward(),
+        }
+    }
+
+    pub fn bob_offset(&self) -> f32 {
+        self.bob_offset
+    }
+}
+
+impl Default for FirstPersonState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+///
+/// Turns input into camera movement.
+/// FreeFly is the original fly-anywhere mode; FirstPerson walks on the ground with gravity,
+/// jumping and head-bob.
+///
+#[derive(Copy, Clone, Debug)]
+pub enum CameraController {
+    FreeFly { speed: f32 },
+    FirstPerson(FirstPersonState),
+}
+
+impl CameraController {
+    pub fn free_fly() -> Self {
+        CameraController::FreeFly { speed: 3.5 }
+    }
+
+    pub fn first_person() -> Self {
+        CameraController::FirstPerson(FirstPersonState::new())
+    }
+
+    /// Switches between free-fly and first-person, keeping the camera where it is
+    pub fn toggle_mode(&mut self, camera: &mut Camera) {
+        *self = match self {
+            CameraController::FreeFly { .. } => CameraController::first_person(),
+            CameraController::FirstPerson(state) => {
+                // Take the head-bob back out so the camera doesn't keep the offset
+                shift_y(camera, -state.bob_offset);
+                CameraController::free_fly()
+            }
+        };
+    }
+
+    /// Moves the camera for one frame. `ground_height` is the floor under the camera (first-person only).
+    pub fn update(&mut self, camera: &mut Camera, input: &mut InputManager, delta_time: f32, ground_height: f32) {
+        match self {
+            CameraController::FreeFly { speed } => update_free_fly(camera, input, delta_time, *speed),
+            CameraController::FirstPerson(state) => update_first_person(state, camera, input, delta_time, ground_height),
+        }
+        apply_mouse_look(camera, input);
+    }
+}
+
+impl Default for CameraController {
+    fn default() -> Self {
+        Self::free_fly()
+    }
+}
+
+// WASD + up/down (units/second) along the camera's own axes
+fn update_free_fly(camera: &mut Camera, input: &InputManager, delta_time: f32, speed: f32) {
+    if input.is_key_pressed(VK_W) {
+        camera.move_forward(speed * delta_time);
+    }
+    if input.is_key_pressed(VK_S) {
+        camera.move_forward(-speed * delta_time);
+    }
+    if input.is_key_pressed(VK_A) {
+        camera.move_right(-speed * delta_time);
+    }
+    if input.is_key_pressed(VK_D) {
+        camera.move_right(speed * delta_time);
+    }
+    if input.is_key_pressed(VK_SPACE) {
+        camera.move_up(speed * delta_time);
+    }
+    if input.is_key_pressed(VK_LSHIFT) {
+        camera.move_up(-speed * delta_time);
+    }
+}
+
+fn update_first_person(state: &mut FirstPersonState, camera: &mut Camera, input: &InputManager,
+                       delta_time: f32, ground_height: f32) {
+    // Work from the un-bobbed position
+    shift_y(camera, -state.bob_offset);
+
+    // Forward flattened onto XZ, so looking down doesn't make W dig into the floor
+    let forward = camera.get_forward_vector();
+    let flat_forward = Vec3f::new(forward.x, 0.0, forward.z);
+    if flat_forward.length() > 1e-4 {
+        state.heading = flat_forward.normalize();
+    }
+    let flat_right = state.heading.cross(&Vec3f::up()).normalize();
+
+    let mut wish = Vec3f::zero();
+    if input.is_key_pressed(VK_W) { wish = wish + state.heading; }
+    if input.is_key_pressed(VK_S) { wish = wish - state.heading; }
+    if input.is_key_pressed(VK_D) { wish = wish + flat_right; }
+    if input.is_key_pressed(VK_A) { wish = wish - flat_right; }
+
+    let horizontal_move = wish.normalize() * state.walk_speed * delta_time;
+    camera.position = camera.position + horizontal_move;
+    camera.target = camera.target + horizontal_move;
+
+    // Jumping and gravity
+    if state.grounded && input.is_key_pressed(VK_SPACE) {
+        state.vertical_velocity = state.jump_speed;
+        state.grounded = false;
+    }
+    state.vertical_velocity -= state.gravity * delta_time;
+    shift_y(camera, state.vertical_velocity * delta_time);
+
+    let eye_floor = ground_height + state.eye_height;
+    if camera.position.y <= eye_floor {
+        shift_y(camera, eye_floor - camera.position.y);
+        state.vertical_velocity = 0.0;
+        state.grounded = true;
+    }
+
+    // Head-bob follows horizontal speed, and settles back to zero when standing still or airborne
+    let speed_fraction = if delta_time > 0.0 {
+        (horizontal_move.length() / delta_time / state.walk_speed).min(1.0)
+    } else {
+        0.0
+    };
+    if state.bob_enabled && state.grounded && speed_fraction > 0.0 {
+        state.bob_phase += delta_time * state.bob_frequency * speed_fraction * std::f32::consts::TAU;
+        state.bob_offset = state.bob_phase.sin() * state.bob_amplitude * speed_fraction;
+    } else {
+        state.bob_offset *= 1.0 - (delta_time * 10.0).min(1.0);
+        if state.bob_offset.abs() < 1e-4 {
+            state.bob_offset = 0.0;
+            state.bob_phase = 0.0;
+        }
+    }
+    shift_y(camera, state.bob_offset);
+}
+
+// mouse-look (in radians), using the camera API
+fn apply_mouse_look(camera: &mut Camera, input: &mut InputManager) {
+    let md = input.get_mouse_delta(); // scaled by sensitivity
+    if !input.is_mouse_captured() {
+        return;
+    }
+
+    let yaw_delta = md.x * 0.002;
+    let pitch_delta = -md.y * 0.002;
+
+    let fwd = camera.get_forward_vector();
+    let mut yaw = fwd.z.atan2(fwd.x);
+    let mut pitch = (fwd.y / fwd.length()).asin();
+
+    let half_pi = std::f32::consts::FRAC_PI_2;
+    yaw += yaw_delta;
+    pitch = (pitch + pitch_delta).clamp(-half_pi + 0.001, half_pi - 0.001);
+
+    let new_dir = Vec3f::new(
+        pitch.cos() * yaw.cos(),
+        pitch.sin(),
+        pitch.cos() * yaw.sin(),
+    );
+    camera.look_in_direction(new_dir);
+}
+
+// Moves camera and target straight up/down in world space, keeping the view direction
+fn shift_y(camera: &mut Camera, distance: f32) {
+    camera.position.y += distance;
+    camera.target.y += distance;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DT: f32 = 1.0 / 1000.0;
+
+    // First-person controller standing on a floor at y = 0, looking along `direction`
+    fn standing(direction: Vec3f) -> (CameraController, Camera, FirstPersonState) {
+        let state = FirstPersonState::new();
+        let position = Vec3f::new(0.0, state.eye_height, 0.0);
+        let mut camera = Camera::new(position, position + direction, Vec3f::up());
+        let mut controller = CameraController::first_person();
+        // One idle frame to land on the floor
+        controller.update(&mut camera, &mut InputManager::new(), DT, 0.0);
+        (controller, camera, state)
+    }
+
+    fn state(controller: &CameraController) -> FirstPersonState {
+        match controller {
+            CameraController::FirstPerson(state) => *state,
+            CameraController::FreeFly { .. } => panic!("expected first-person"),
+        }
+    }
+
+    #[test]
+    fn looking_straight_down_still_walks_level() {
+        let (mut controller, mut camera, defaults) = standing(Vec3f::new(0.0, -1.0, 0.0));
+        let mut input = InputManager::new();
+        input.on_key_down(VK_W);
+        let start = camera.position;
+        for _ in 0..500 {
+            controller.update(&mut camera, &mut input, DT, 0.0);
+        }
+
+        let moved = camera.position - start;
+        let horizontal = Vec3f::new(moved.x, 0.0, moved.z).length();
+        assert!((horizontal - defaults.walk_speed * 0.5).abs() < 1e-2, "walked {horizontal}");
+        // Only head-bob may move the eye vertically
+        assert!((camera.position.y - defaults.eye_height).abs() <= defaults.bob_amplitude + 1e-4,
+                "eye at {}", camera.position.y);
+        assert!(camera.get_forward_vector().y < -0.99, "still looking down");
+    }
+
+    #[test]
+    fn jump_apex_is_jump_speed_squared_over_two_gravity() {
+        let (mut controller, mut camera, defaults) = standing(Vec3f::forward());
+        assert!(state(&controller).grounded);
+
+        let mut input = InputManager::new();
+        input.on_key_down(VK_SPACE);
+        controller.update(&mut camera, &mut input, DT, 0.0);
+        input.on_key_up(VK_SPACE);
+
+        let mut apex = camera.position.y;
+        for _ in 0..2000 {
+            controller.update(&mut camera, &mut input, DT, 0.0);
+            apex = apex.max(camera.position.y);
+        }
+
+        let expected = defaults.jump_speed * defaults.jump_speed / (2.0 * defaults.gravity);
+        let height = apex - defaults.eye_height;
+        assert!((height - expected).abs() < expected * 0.01, "apex {height}, expected {expected}");
+        assert!(state(&controller).grounded, "landed again");
+        assert!((camera.position.y - defaults.eye_height).abs() < 1e-5);
+    }
+
+    #[test]
+    fn head_bob_settles_to_zero_when_standing_still() {
+        let (mut controller, mut camera, defaults) = standing(Vec3f::forward());
+        let mut input = InputManager::new();
+        input.on_key_down(VK_W);
+        // A bit past a quarter bob cycle, so the offset is well away from zero when stopping
+        for _ in 0..150 {
+            controller.update(&mut camera, &mut input, DT, 0.0);
+        }
+        assert!(state(&controller).bob_offset().abs() > defaults.bob_amplitude * 0.5);
+
+        input.on_key_up(VK_W);
+        for _ in 0..1000 {
+            controller.update(&mut camera, &mut input, DT, 0.0);
+        }
+        assert_eq!(state(&controller).bob_offset(), 0.0);
+        assert!((camera.position.y - defaults.eye_height).abs() < 1e-5, "eye at {}", camera.position.y);
+    }
+}
@@ -0,0 +1,81 @@
+use crate::math::Vec3f;
+
+/// A cinematic path for the camera.
+/// Each control point is a (position, look-target) pair, and the camera flies
+/// through all of them over `duration` seconds.
+pub struct CameraPath {
+    pub control_points: Vec<(Vec3f, Vec3f)>,
+    pub duration: f32, // Seconds to fly the whole path
+}
+
+impl CameraPath {
+    pub fn new(duration: f32) -> Self {
+        Self {
+            control_points: Vec::new(),
+            duration,
+        }
+    }
+
+    pub fn with_control_point(mut self, position: Vec3f, target: Vec3f) -> Self {
+        self.add_control_point(position, target);
+        self
+    }
+
+    pub fn add_control_point(&mut self, position: Vec3f, target: Vec3f) {
+        self.control_points.push((position, target));
+    }
+
+    ///
+    /// Evaluates the path at t in [0, 1] and returns (position, target).
+    /// The path is split into one segment per pair of neighbouring control points,
+    /// and each segment is a Catmull-Rom curve using the points before and after it.
+    /// At the ends we repeat the first/last point, so the curve still passes through them.
+    ///
+    pub fn evaluate(&self, t: f32) -> (Vec3f, Vec3f) {
+        let count = self.control_points.len();
+        if count == 0 {
+            return (Vec3f::zero(), Vec3f::forward());
+        }
+        if count == 1 {
+            return self.control_points[0];
+        }
+
+        // Find which segment we are in, and how far along it
+        let segments = count - 1;
+        let scaled = t.clamp(0.0, 1.0) * segments as f32;
+        let segment = (scaled.floor() as usize).min(segments - 1);
+        let local_t = scaled - segment as f32;
+
+        // The four surrounding control points (clamped at the ends)
+        let i0 = segment.saturating_sub(1);
+        let i1 = segment;
+        let i2 = segment + 1;
+        let i3 = (segment + 2).min(count - 1);
+
+        let (p0, t0) = self.control_points[i0];
+        let (p1, t1) = self.control_points[i1];
+        let (p2, t2) = self.control_points[i2];
+        let (p3, t3) = self.control_points[i3];
+
+        (
+            catmull_rom(p0, p1, p2, p3, local_t),
+            catmull_rom(t0, t1, t2, t3, local_t),
+        )
+    }
+}
+
+///
+/// Catmull-Rom spline between p1 and p2, using p0 and p3 to shape the tangents.
+/// q(t) = 0.5 * (2*p1 + (-p0 + p2)*t + (2*p0 - 5*p1 + 4*p2 - p3)*t² + (-p0 + 3*p1 - 3*p2 + p3)*t³)
+///
+pub fn catmull_rom(p0: Vec3f, p1: Vec3f, p2: Vec3f, p3: Vec3f, t: f32) -> Vec3f {
+    let t2 = t * t;
+    let t3 = t2 * t;
+
+    let a = p1 * 2.0;
+    let b = (p2 - p0) * t;
+    let c = (p0 * 2.0 - p1 * 5.0 + p2 * 4.0 - p3) * t2;
+    let d = (-p0 + p1 * 3.0 - p2 * 3.0 + p3) * t3;
+
+    (a + b + c + d) * 0.5
+}
@@ -0,0 +1,345 @@
+use crate::camera::Camera;
+use crate::camera_path::catmull_rom;
+use crate::math::Vec3f;
+
+/// Easing curve applied to the segment that starts at a keyframe
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Ease {
+    Linear,
+    EaseIn,
+    EaseOut,
+    EaseInOut,
+}
+
+impl Ease {
+    pub fn apply(&self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Ease::Linear => t,
+            Ease::EaseIn => t * t,
+            Ease::EaseOut => t * (2.0 - t),
+            Ease::EaseInOut => t * t * (3.0 - 2.0 * t), // smoothstep
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Ease::Linear => "linear",
+            Ease::EaseIn => "ease_in",
+            Ease::EaseOut => "ease_out",
+            Ease::EaseInOut => "ease_in_out",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Ease> {
+        [Ease::Linear, Ease::EaseIn, Ease::EaseOut, Ease::EaseInOut]
+            .into_iter()
+            .find(|ease| ease.name() == name)
+    }
+}
+
+/// One authored camera shot at a point in time
+#[derive(Copy, Clone, Debug)]
+pub struct CameraKeyframe {
+    pub time: f32,     // Seconds from the start of the sequence
+    pub position: Vec3f,
+    pub look_at: Vec3f,
+    pub fov: f32,      // Radians
+    pub ease: Ease,    // Easing towards the next keyframe
+}
+
+impl CameraKeyframe {
+    pub fn new(time: f32, position: Vec3f, look_at: Vec3f, fov: f32) -> Self {
+        Self { time, position, look_at, fov, ease: Ease::EaseInOut }
+    }
+
+    pub fn with_ease(mut self, ease: Ease) -> Self {
+        self.ease = ease;
+        self
+    }
+}
+
+///
+/// Keyframed camera playback for demo recordings.
+/// Position follows a Catmull-Rom spline through the keyframes, the view direction is
+/// slerped so the camera turns at a steady rate, and fov is blended smoothly.
+///
+pub struct CameraSequence {
+    pub keyframes: Vec<CameraKeyframe>, // Kept sorted by time
+    pub looping: bool,
+    time: f32,
+    playing: bool,
+}
+
+impl CameraSequence {
+    pub fn new() -> Self {
+        Self {
+            keyframes: Vec::new(),
+            looping: false,
+            time: 0.0,
+            playing: false,
+        }
+    }
+
+    pub fn with_keyframe(mut self, keyframe: CameraKeyframe) -> Self {
+        self.add_keyframe(keyframe);
+        self
+    }
+
+    pub fn with_looping(mut self, looping: bool) -> Self {
+        self.looping = looping;
+        self
+    }
+
+    pub fn add_keyframe(&mut self, keyframe: CameraKeyframe) {
+        let index = self.keyframes.partition_point(|k| k.time <= keyframe.time);
+        self.keyframes.insert(index, keyframe);
+    }
+
+    /// Length of the sequence, which is the time of the last keyframe
+    pub fn duration(&self) -> f32 {
+        self.keyframes.last().map_or(0.0, |k| k.time)
+    }
+
+    pub fn current_time(&self) -> f32 {
+        self.time
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.playing
+    }
+
+    /// Starts playing, restarting from the beginning if the sequence already finished
+    pub fn play(&mut self) {
+        if self.time >= self.duration() {
+            self.time = 0.0;
+        }
+        self.playing = !self.keyframes.is_empty();
+    }
+
+    pub fn pause(&mut self) {
+        self.playing = false;
+    }
+
+    pub fn seek(&mut self, time: f32) {
+        self.time = time.clamp(0.0, self.duration());
+    }
+
+    /// Moves the playhead forward, wrapping or stopping at the end
+    pub fn advance(&mut self, delta_time: f32) {
+        if !self.playing {
+            return;
+        }
+
+        let duration = self.duration();
+        self.time += delta_time;
+
+        if self.time >= duration {
+            if self.looping && duration > 0.0 {
+                self.time %= duration;
+            } else {
+                self.time = duration;
+                self.playing = false;
+            }
+        }
+    }
+
+    /// Returns (position, look_at, fov) at the given time
+    pub fn sample(&self, time: f32) -> (Vec3f, Vec3f, f32) {
+        let count = self.keyframes.len();
+        if count == 0 {
+            return (Vec3f::zero(), Vec3f::forward(), std::f32::consts::PI / 4.0);
+        }
+
+        let first = &self.keyframes[0];
+        let last = &self.keyframes[count - 1];
+        if count == 1 || time <= first.time {
+            return (first.position, first.look_at, first.fov);
+        }
+        if time >= last.time {
+            return (last.position, last.look_at, last.fov);
+        }
+
+        // Segment i runs from keyframe i to keyframe i + 1
+        let i = self.keyframes.partition_point(|k| k.time <= time) - 1;
+        let k1 = &self.keyframes[i];
+        let k2 = &self.keyframes[i + 1];
+        let k0 = &self.keyframes[i.saturating_sub(1)];
+        let k3 = &self.keyframes[(i + 2).min(count - 1)];
+
+        let span = k2.time - k1.time;
+        let local_t = if span > 0.0 { (time - k1.time) / span } else { 1.0 };
+        let t = k1.ease.apply(local_t);
+
+        let position = catmull_rom(k0.position, k1.position, k2.position, k3.position, t);
+
+        // Orientation: slerp the view directions, and lerp how far away the look-at point is
+        let offset1 = k1.look_at - k1.position;
+        let offset2 = k2.look_at - k2.position;
+        let direction = slerp(offset1.normalize(), offset2.normalize(), t);
+        let distance = offset1.length() + (offset2.length() - offset1.length()) * t;
+        let look_at = position + direction * distance;
+
+        let fov_t = t * t * (3.0 - 2.0 * t);
+        let fov = k1.fov + (k2.fov - k1.fov) * fov_t;
+
+        (position, look_at, fov)
+    }
+
+    /// Writes the current sample into the camera
+    pub fn apply_to(&self, camera: &mut Camera) {
+        let (position, look_at, fov) = self.sample(self.time);
+        camera.position = position;
+        camera.target = look_at;
+        if fov > 0.0 && fov < std::f32::consts::PI {
+            camera.fov = fov;
+        }
+    }
+
+    ///
+    /// Writes the keyframes to `path`, one per line as
+    /// "keyframe time px py pz lx ly lz fov ease", after a "looping true|false" line.
+    /// Numbers are written exactly, so load gives back the same sequence (stopped at its start).
+    ///
+    pub fn save(&self, path: &str) -> std::io::Result<()> {
+        let mut text = format!("looping {}\n", self.looping);
+        for k in &self.keyframes {
+            text.push_str(&format!(
+                "keyframe {} {} {} {} {} {} {} {} {}\n",
+                k.time, k.position.x, k.position.y, k.position.z,
+                k.look_at.x, k.look_at.y, k.look_at.z, k.fov, k.ease.name()
+            ));
+        }
+        std::fs::write(path, text)
+    }
+
+    /// Reads a file written by save
+    pub fn load(path: &str) -> std::io::Result<CameraSequence> {
+        let text = std::fs::read_to_string(path)?;
+        let mut sequence = CameraSequence::new();
+        for line in text.lines().filter(|line| !line.trim().is_empty()) {
+            let words: Vec<&str> = line.split_whitespace().collect();
+            match words[..] {
+                ["looping", looping] if looping.parse::<bool>().is_ok() => sequence.looping = looping == "true",
+                ["keyframe", ref numbers @ .., ease] if numbers.len() == 8 => {
+                    let numbers: Option<Vec<f32>> = numbers.iter().map(|number| number.parse().ok()).collect();
+                    let (Some(n), Some(ease)) = (numbers, Ease::from_name(ease)) else {
+                        return Err(bad_line(line));
+                    };
+                    sequence.add_keyframe(
+                        CameraKeyframe::new(n[0], Vec3f::new(n[1], n[2], n[3]), Vec3f::new(n[4], n[5], n[6]), n[7])
+                            .with_ease(ease),
+                    );
+                }
+                _ => return Err(bad_line(line)),
+            }
+        }
+        Ok(sequence)
+    }
+}
+
+fn bad_line(line: &str) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, format!("bad camera sequence line: {}", line))
+}
+
+impl Default for CameraSequence {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+///
+/// Spherical interpolation between two unit vectors, so the rotation speed stays constant.
+/// Falls back to a normalized lerp when the vectors are almost parallel.
+///
+fn slerp(from: Vec3f, to: Vec3f, t: f32) -> Vec3f {
+    let cos_angle = from.dot(&to).clamp(-1.0, 1.0);
+    if cos_angle > 0.9995 {
+        return (from + (to - from) * t).normalize();
+    }
+
+    let angle = cos_angle.acos();
+    let sin_angle = angle.sin();
+    if sin_angle.abs() < 1e-6 {
+        // Opposite directions: no unique rotation, so just switch halfway
+        return if t < 0.5 { from } else { to };
+    }
+
+    let a = ((1.0 - t) * angle).sin() / sin_angle;
+    let b = (t * angle).sin() / sin_angle;
+    (from * a + to * b).normalize()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sequence() -> CameraSequence {
+        CameraSequence::new()
+            .with_keyframe(CameraKeyframe::new(0.0, Vec3f::new(0.0, 2.0, 10.0), Vec3f::zero(), 0.8))
+            .with_keyframe(CameraKeyframe::new(1.5, Vec3f::new(6.0, 3.0, 4.0), Vec3f::new(0.0, 1.0, 0.0), 0.6)
+                .with_ease(Ease::Linear))
+            .with_keyframe(CameraKeyframe::new(4.0, Vec3f::new(2.0, 1.0, -6.0), Vec3f::new(1.0, 0.0, 0.0), 1.0)
+                .with_ease(Ease::EaseIn))
+            .with_keyframe(CameraKeyframe::new(5.25, Vec3f::new(-5.0, 4.0, 0.0), Vec3f::zero(), 0.7))
+    }
+
+    #[test]
+    fn seeking_to_a_keyframe_reproduces_it() {
+        let mut sequence = sequence();
+        let mut camera = Camera::new(Vec3f::new(9.0, 9.0, 9.0), Vec3f::zero(), Vec3f::up());
+
+        for keyframe in sequence.keyframes.clone() {
+            sequence.seek(keyframe.time);
+            sequence.apply_to(&mut camera);
+            assert!((camera.position - keyframe.position).length() < 1e-5, "at {}: {:?}", keyframe.time, camera.position);
+            assert!((camera.target - keyframe.look_at).length() < 1e-5, "at {}: {:?}", keyframe.time, camera.target);
+            assert!((camera.fov - keyframe.fov).abs() < 1e-6, "at {}: {}", keyframe.time, camera.fov);
+        }
+    }
+
+    #[test]
+    fn playback_lasts_until_the_last_keyframe() {
+        let mut sequence = sequence();
+        assert_eq!(sequence.duration(), 5.25);
+
+        sequence.play();
+        let (step, mut elapsed) = (1.0 / 64.0, 0.0);
+        while sequence.is_playing() {
+            sequence.advance(step);
+            elapsed += step;
+            assert!(elapsed < 10.0, "playback never finished");
+        }
+        assert_eq!(elapsed, 5.25);
+        assert_eq!(sequence.current_time(), 5.25);
+
+        // Looping keeps going, wrapped back into the sequence
+        let mut looping = self::sequence().with_looping(true);
+        looping.play();
+        for _ in 0..1000 {
+            looping.advance(step);
+        }
+        assert!(looping.is_playing());
+        assert!((looping.current_time() - (1000.0 * step) % 5.25).abs() < 1e-3, "{}", looping.current_time());
+    }
+
+    #[test]
+    fn saved_sequence_loads_back_the_same() {
+        let original = sequence().with_looping(true);
+        let path = std::env::temp_dir().join(format!("camera_sequence_{}.txt", std::process::id()));
+        let path = path.to_str().unwrap();
+        original.save(path).unwrap();
+        let loaded = CameraSequence::load(path);
+        let _ = std::fs::remove_file(path);
+
+        let loaded = loaded.unwrap();
+        assert!(loaded.looping);
+        assert_eq!(loaded.keyframes.len(), original.keyframes.len());
+        for (loaded, original) in loaded.keyframes.iter().zip(&original.keyframes) {
+            assert_eq!((loaded.time, loaded.fov, loaded.ease), (original.time, original.fov, original.ease));
+            let as_tuple = |v: Vec3f| (v.x, v.y, v.z);
+            assert_eq!(as_tuple(loaded.position), as_tuple(original.position));
+            assert_eq!(as_tuple(loaded.look_at), as_tuple(original.look_at));
+        }
+    }
+}
@@ -0,0 +1,60 @@
+use crate::math::Vec3f;
+
+///
+/// Which way is up, and the handedness, of the coordinates a scene or an asset uses.
+/// Meshes built by Mesh::create_* are Y-up right-handed (+Z towards the viewer); convert
+/// them with Mesh::with_coordinate_system for scenes that use something else.
+///
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum CoordinateSystem {
+    #[default]
+    YUpRightHanded,
+    /// Blender and most CAD tools: +Z up, -Y towards the viewer
+    ZUpRightHanded,
+    /// Y up with +Z going away from the viewer, as in Unity and Direct3D
+    YUpLeftHanded,
+}
+
+impl CoordinateSystem {
+    pub fn up(self) -> Vec3f {
+        match self {
+            CoordinateSystem::ZUpRightHanded => Vec3f::z_axis(),
+            CoordinateSystem::YUpRightHanded | CoordinateSystem::YUpLeftHanded => Vec3f::y_axis(),
+        }
+    }
+
+    pub fn is_left_handed(self) -> bool {
+        self == CoordinateSystem::YUpLeftHanded
+    }
+
+    ///
+    /// A point or direction given in Y-up right-handed coordinates, in this system.
+    /// Z-up is a quarter turn about X, so it keeps handedness; left-handed mirrors Z.
+    ///
+    pub fn from_y_up(self, v: Vec3f) -> Vec3f {
+        match self {
+            CoordinateSystem::YUpRightHanded => v,
+            CoordinateSystem::ZUpRightHanded => Vec3f::new(v.x, -v.z, v.y),
+            CoordinateSystem::YUpLeftHanded => Vec3f::new(v.x, v.y, -v.z),
+        }
+    }
+
+    /// The inverse of from_y_up
+    pub fn to_y_up(self, v: Vec3f) -> Vec3f {
+        match self {
+            CoordinateSystem::YUpRightHanded => v,
+            CoordinateSystem::ZUpRightHanded => Vec3f::new(v.x, v.z, -v.y),
+            CoordinateSystem::YUpLeftHanded => Vec3f::new(v.x, v.y, -v.z),
+        }
+    }
+
+    /// A point or direction given in `from`'s coordinates, in this system
+    pub fn convert_from(self, from: CoordinateSystem, v: Vec3f) -> Vec3f {
+        self.from_y_up(from.to_y_up(v))
+    }
+
+    /// Whether going between the two systems mirrors, which turns triangle winding inside out
+    pub fn mirrors(self, other: CoordinateSystem) -> bool {
+        self.is_left_handed() != other.is_left_handed()
+    }
+}
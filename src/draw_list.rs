@@ -0,0 +1,153 @@
+use std::cmp::Ordering;
+use crate::lighting::{LightReceiver, LightingSystem, Material};
+use crate::lightmap::Lightmap;
+use crate::math::{Vec2f, Vec3f, Vec4f};
+use crate::renderer::Renderer;
+use crate::scene::GameObject;
+
+/// How a projected triangle gets its color
+#[derive(Copy, Clone)]
+pub enum TriangleFill<'a> {
+    Color(u32), // Lit ARGB; an alpha byte below 0xFF blends
+    Hdr(Vec4f), // Lit linear RGB for the HDR buffer, opacity in w
+    /// Lit per pixel when drawn, sampling the material's texture maps and the object's lightmap
+    Textured {
+        world: [Vec3f; 3],
+        uvs: Option<[Vec2f; 3]>, // None when the material has no maps to sample
+        normals: [Vec3f; 3], // Per corner, all the face normal for flat shading
+        occlusion: [f32; 3], // Baked ambient occlusion per corner
+        vertex_colors: Option<[&'a Vec3f; 3]>, // Into the mesh's vertex_colors; None when the mesh has none or the material ignores them
+        material: &'a Material,
+        object: &'a GameObject, // For its light receiver (layers and light masks); occlusion comes from above
+        lightmap: Option<(&'a Lightmap, [Vec2f; 3])>, // With this triangle's corners in it
+    },
+}
+
+impl TriangleFill<'_> {
+    pub fn is_transparent(&self) -> bool {
+        match self {
+            TriangleFill::Color(argb) => argb >> 24 != 0xFF,
+            TriangleFill::Hdr(color) => color.w < 1.0,
+            TriangleFill::Textured { material, .. } => material.is_transparent(),
+        }
+    }
+}
+
+///
+/// A triangle that has been culled, lit and projected, waiting to be rasterized.
+/// Ordered by z_center, so sorting puts the nearest first.
+///
+#[derive(Copy, Clone)]
+pub struct ScreenTriangle<'a> {
+    pub screen: [Vec2f; 3],
+    pub depth: [f32; 3], // Same units as the depth buffer (view distance / camera.far)
+    pub fill: TriangleFill<'a>,
+}
+
+impl ScreenTriangle<'_> {
+    pub fn z_center(&self) -> f32 {
+        (self.depth[0] + self.depth[1] + self.depth[2]) / 3.0
+    }
+}
+
+impl Ord for ScreenTriangle<'_> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.z_center().total_cmp(&other.z_center())
+    }
+}
+
+impl PartialOrd for ScreenTriangle<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for ScreenTriangle<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for ScreenTriangle<'_> {}
+
+///
+/// Triangles collected for one view, split by whether they blend.
+/// submit draws opaque ones front to back (so the z-buffer rejects hidden pixels early),
+/// then transparent ones back to front (so each blends over what's behind it).
+///
+#[derive(Default)]
+pub struct DrawList<'a> {
+    pub opaque: Vec<ScreenTriangle<'a>>,
+    pub transparent: Vec<ScreenTriangle<'a>>,
+}
+
+impl<'a> DrawList<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, triangle: ScreenTriangle<'a>) {
+        if triangle.fill.is_transparent() {
+            self.transparent.push(triangle);
+        } else {
+            self.opaque.push(triangle);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.opaque.len() + self.transparent.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Puts both lists in drawing order; equal depths keep the order they were pushed in
+    pub fn sort(&mut self) {
+        self.opaque.sort();
+        self.transparent.sort_by(|a, b| b.cmp(a));
+    }
+
+    /// Sorts and rasterizes everything, returning how many triangles were drawn.
+    /// Textured triangles are lit here, per pixel, with `lighting` as seen from `camera_position`.
+    pub fn submit(mut self, renderer: &mut Renderer, lighting: &LightingSystem, camera_position: Vec3f) -> usize {
+        self.sort();
+
+        let count = self.len();
+        for triangle in self.opaque.iter().chain(&self.transparent) {
+            let [v0, v1, v2] = triangle.screen;
+            let [z0, z1, z2] = triangle.depth;
+            match triangle.fill {
+                TriangleFill::Color(argb) => renderer.draw_triangle(v0, v1, v2, z0, z1, z2, argb),
+                TriangleFill::Hdr(color) if color.w < 1.0 => {
+                    renderer.draw_triangle_hdr_alpha(triangle.screen, triangle.depth, color);
+                }
+                TriangleFill::Hdr(color) => {
+                    renderer.draw_triangle_hdr(triangle.screen, triangle.depth, Vec3f::new(color.x, color.y, color.z));
+                }
+                TriangleFill::Textured { world, uvs, normals, occlusion, vertex_colors, material, object, lightmap } => {
+                    let interpolate = |corners: [Vec2f; 3], weights: [f32; 3]| {
+                        corners[0] * weights[0] + corners[1] * weights[1] + corners[2] * weights[2]
+                    };
+                    renderer.draw_triangle_shaded(triangle.screen, triangle.depth, |weights| {
+                        let uv = uvs.map(|uvs| interpolate(uvs, weights));
+                        let point = Vec3f::barycentric_interpolate(weights[0], weights[1], weights[2], world[0], world[1], world[2]);
+                        let normal = Vec3f::barycentric_interpolate(weights[0], weights[1], weights[2], normals[0], normals[1], normals[2])
+                            .normalize();
+                        let ao = occlusion[0] * weights[0] + occlusion[1] * weights[1] + occlusion[2] * weights[2];
+                        let baked_light = lightmap.map_or(Vec3f::zero(), |(lightmap, corners)| {
+                            lightmap.sample(interpolate(corners, weights))
+                        });
+                        let vertex_color = vertex_colors.map(|colors| {
+                            *colors[0] * weights[0] + *colors[1] * weights[1] + *colors[2] * weights[2]
+                        });
+                        let receiver = LightReceiver { occlusion: ao, baked_light, vertex_color, uv, ..object.light_receiver() };
+                        let color = lighting.calculate_lighting(&point, &normal, &camera_position, material, &receiver);
+                        Vec4f::new(color.x, color.y, color.z, material.alpha)
+                    });
+                }
+            }
+        }
+        count
+    }
+}
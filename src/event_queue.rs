@@ -0,0 +1,93 @@
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use crate::scene::Scene;
+
+/// A scripted action that runs once the scene clock reaches `trigger_time`
+pub struct SceneEvent {
+    pub trigger_time: f32,
+    pub action: Box<dyn FnOnce(&mut Scene)>,
+    sequence: u64, // Keeps events with the same trigger time in scheduling order
+}
+
+// BinaryHeap is a max-heap, so the ordering is reversed to pop the earliest event first
+impl Ord for SceneEvent {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.trigger_time
+            .total_cmp(&self.trigger_time)
+            .then_with(|| other.sequence.cmp(&self.sequence))
+    }
+}
+
+impl PartialOrd for SceneEvent {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for SceneEvent {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for SceneEvent {}
+
+///
+/// Timed events for cutscenes (lights changing, objects spawning, camera cuts...).
+/// The queue keeps its own clock, which the scene advances every update.
+///
+pub struct EventQueue {
+    pub events: BinaryHeap<SceneEvent>,
+    pub current_time: f32,
+    next_sequence: u64,
+}
+
+impl EventQueue {
+    pub fn new() -> Self {
+        Self {
+            events: BinaryHeap::new(),
+            current_time: 0.0,
+            next_sequence: 0,
+        }
+    }
+
+    /// Schedules an action to run `delay` seconds from now
+    pub fn schedule(&mut self, delay: f32, action: impl FnOnce(&mut Scene) + 'static) {
+        self.events.push(SceneEvent {
+            trigger_time: self.current_time + delay.max(0.0),
+            action: Box::new(action),
+            sequence: self.next_sequence,
+        });
+        self.next_sequence += 1;
+    }
+
+    pub fn advance(&mut self, delta_time: f32) {
+        self.current_time += delta_time;
+    }
+
+    /// Removes and returns the earliest event if it is due
+    pub fn pop_due(&mut self) -> Option<SceneEvent> {
+        match self.events.peek() {
+            Some(event) if event.trigger_time <= self.current_time => self.events.pop(),
+            _ => None,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.events.clear();
+    }
+}
+
+impl Default for EventQueue {
+    fn default() -> Self {
+        Self::new()
+    }
+}
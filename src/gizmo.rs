@@ -0,0 +1,207 @@
+use crate::math::{Vec2f, Vec3f};
+use crate::renderer::Renderer;
+use crate::scene::Scene;
+use crate::undo::MoveObjectCommand;
+
+// Arrow length as a fraction of the viewport height, whatever the object's distance
+const GIZMO_SCREEN_SIZE: f32 = 0.12;
+// How close (in pixels) a click has to land to an arrow to grab it
+const GIZMO_PICK_RADIUS: f32 = 6.0;
+// Arrowhead length and half-width in pixels
+const GIZMO_HEAD_LENGTH: f32 = 10.0;
+const GIZMO_HEAD_WIDTH: f32 = 4.0;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum GizmoAxis {
+    X,
+    Y,
+    Z,
+}
+
+impl GizmoAxis {
+    pub const ALL: [GizmoAxis; 3] = [GizmoAxis::X, GizmoAxis::Y, GizmoAxis::Z];
+
+    pub fn direction(self) -> Vec3f {
+        match self {
+            GizmoAxis::X => Vec3f::x_axis(),
+            GizmoAxis::Y => Vec3f::y_axis(),
+            GizmoAxis::Z => Vec3f::z_axis(),
+        }
+    }
+
+    pub fn color(self) -> u32 {
+        match self {
+            GizmoAxis::X => 0xFFFF3030,
+            GizmoAxis::Y => 0xFF30FF30,
+            GizmoAxis::Z => 0xFF3060FF,
+        }
+    }
+}
+
+/// Translate gizmo drawn on the selected object: whether it shows, and the drag in progress
+pub struct TransformGizmo {
+    pub visible: bool,
+    drag: Option<GizmoDrag>,
+}
+
+#[derive(Copy, Clone)]
+struct GizmoDrag {
+    axis: GizmoAxis,
+    last_pixel: Vec2f,
+    object: usize,         // Index of the object being dragged
+    start_position: Vec3f, // Where it was when the drag began
+}
+
+impl TransformGizmo {
+    pub fn new() -> Self {
+        Self { visible: true, drag: None }
+    }
+
+    pub fn dragged_axis(&self) -> Option<GizmoAxis> {
+        self.drag.map(|drag| drag.axis)
+    }
+}
+
+impl Default for TransformGizmo {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// One arrow as it appears on screen
+struct GizmoArrow {
+    axis: GizmoAxis,
+    start: Vec2f,
+    end: Vec2f,
+    world_length: f32,
+}
+
+impl Scene {
+    ///
+    /// Starts dragging the selected object if (x, y) lands on one of its gizmo arrows.
+    /// Returns false when it doesn't, so the click can go to selection instead.
+    ///
+    pub fn begin_gizmo_drag(&mut self, x: f32, y: f32) -> bool {
+        let pixel = Vec2f::new(x, y);
+        let grabbed = self.gizmo_arrows()
+            .into_iter()
+            .map(|arrow| (arrow.axis, distance_to_segment(pixel, arrow.start, arrow.end)))
+            .filter(|(_, distance)| *distance <= GIZMO_PICK_RADIUS)
+            .min_by(|a, b| a.1.total_cmp(&b.1));
+
+        let selected = self.selected_object.and_then(|index| Some((index, self.game_objects.get(index)?.position)));
+        self.gizmo.drag = grabbed
+            .zip(selected)
+            .map(|((axis, _), (object, start_position))| GizmoDrag { axis, last_pixel: pixel, object, start_position });
+        self.gizmo.drag.is_some()
+    }
+
+    ///
+    /// Moves the selected object along the grabbed axis by the mouse movement since the last
+    /// call, projected onto that axis's arrow on screen, so the object follows the cursor.
+    ///
+    pub fn drag_gizmo(&mut self, x: f32, y: f32) {
+        let Some(drag) = self.gizmo.drag else {
+            return;
+        };
+        let pixel = Vec2f::new(x, y);
+        let Some(arrow) = self.gizmo_arrows().into_iter().find(|arrow| arrow.axis == drag.axis) else {
+            return;
+        };
+        let Some(game_object) = self.selected_object.and_then(|index| self.game_objects.get_mut(index)) else {
+            self.gizmo.drag = None;
+            return;
+        };
+
+        let on_screen = arrow.end - arrow.start;
+        let pixels_per_unit = on_screen.length() / arrow.world_length;
+        // Arrows pointing straight at the camera have no screen direction to drag along
+        if pixels_per_unit > 1e-3 {
+            let moved = (pixel - drag.last_pixel).dot(&on_screen.normalize()) / pixels_per_unit;
+            let position = game_object.position + drag.axis.direction() * moved;
+            game_object.set_position(position);
+        }
+        self.gizmo.drag = Some(GizmoDrag { last_pixel: pixel, ..drag });
+    }
+
+    ///
+    /// Lets go of the arrow. Returns the move the drag made, already applied, for an
+    /// UndoStack to record; None if nothing was being dragged or the object never moved.
+    ///
+    pub fn end_gizmo_drag(&mut self) -> Option<MoveObjectCommand> {
+        let drag = self.gizmo.drag.take()?;
+        let position = self.game_objects.get(drag.object)?.position;
+        ((position - drag.start_position).length() > 0.0).then(|| MoveObjectCommand::new(drag.object, drag.start_position, position))
+    }
+
+    pub fn is_dragging_gizmo(&self) -> bool {
+        self.gizmo.drag.is_some()
+    }
+
+    /// Draws the arrows over everything else; the grabbed one is drawn white
+    pub(crate) fn draw_gizmo(&self, renderer: &mut Renderer) {
+        for arrow in self.gizmo_arrows() {
+            let color = if self.gizmo.dragged_axis() == Some(arrow.axis) { 0xFFFFFFFF } else { arrow.axis.color() };
+            renderer.draw_line_clipped(arrow.start, arrow.end, color);
+
+            let along = arrow.end - arrow.start;
+            if along.length() < GIZMO_HEAD_LENGTH {
+                continue; // Too foreshortened for a head to read
+            }
+            let back = along.normalize() * GIZMO_HEAD_LENGTH;
+            let side = Vec2f::new(-back.y, back.x) * (GIZMO_HEAD_WIDTH / GIZMO_HEAD_LENGTH);
+            renderer.draw_line_clipped(arrow.end, arrow.end - back + side, color);
+            renderer.draw_line_clipped(arrow.end, arrow.end - back - side, color);
+        }
+    }
+
+    ///
+    /// Screen positions of the selected object's arrows, sized to stay GIZMO_SCREEN_SIZE of the
+    /// viewport tall at any distance. Arrows with an end behind the camera are left out.
+    ///
+    fn gizmo_arrows(&self) -> Vec<GizmoArrow> {
+        let Some(game_object) = self.selected_object.and_then(|index| self.game_objects.get(index)) else {
+            return Vec::new();
+        };
+        if !self.gizmo.visible {
+            return Vec::new();
+        }
+
+        let origin = game_object.position;
+        let depth = -self.camera.get_view_matrix().multiply_point(&origin).z;
+        if depth <= self.camera.near {
+            return Vec::new();
+        }
+        let world_length = 2.0 * depth * (self.camera.fov * 0.5).tan() * GIZMO_SCREEN_SIZE;
+
+        let (width, height) = self.frame_size;
+        let viewport = self.camera.compute_viewport(width, height);
+        let vp_matrix = self.camera.get_view_projection_matrix();
+        let project = |point: Vec3f| {
+            let clip = vp_matrix.multiply_point_4d(&point);
+            (clip.w > 0.0).then(|| viewport.ndc_to_screen(clip.x / clip.w, clip.y / clip.w))
+        };
+
+        let Some(start) = project(origin) else {
+            return Vec::new();
+        };
+        GizmoAxis::ALL
+            .into_iter()
+            .filter_map(|axis| {
+                let end = project(origin + axis.direction() * world_length)?;
+                Some(GizmoArrow { axis, start, end, world_length })
+            })
+            .collect()
+    }
+}
+
+fn distance_to_segment(point: Vec2f, start: Vec2f, end: Vec2f) -> f32 {
+    let segment = end - start;
+    let length_squared = segment.dot(&segment);
+    let t = if length_squared > 0.0 {
+        ((point - start).dot(&segment) / length_squared).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    (point - (start + segment * t)).length()
+}
@@ -0,0 +1,92 @@
+use std::ops::Deref;
+use crate::math::{Vec2f, Vec3f};
+use crate::mesh::Mesh;
+
+/// Per-vertex data laid out the way a GPU vertex buffer would take it
+#[derive(Clone, Debug, Default)]
+pub struct VertexBuffer<T> {
+    pub data: Vec<T>,
+}
+
+impl<T> VertexBuffer<T> {
+    pub fn new(data: Vec<T>) -> Self {
+        Self { data }
+    }
+}
+
+impl<T> Deref for VertexBuffer<T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        &self.data
+    }
+}
+
+/// Triangle list indices, three per triangle, into a VertexBuffer
+#[derive(Clone, Debug, Default)]
+pub struct IndexBuffer {
+    pub data: Vec<u32>,
+}
+
+impl IndexBuffer {
+    pub fn new(data: Vec<u32>) -> Self {
+        Self { data }
+    }
+
+    pub fn triangle_count(&self) -> usize {
+        self.data.len() / 3
+    }
+}
+
+impl Deref for IndexBuffer {
+    type Target = [u32];
+
+    fn deref(&self) -> &[u32] {
+        &self.data
+    }
+}
+
+// Floats per vertex in to_interleaved_f32: position XYZ, normal XYZ, UV ST
+pub const INTERLEAVED_STRIDE: usize = 8;
+
+impl Mesh {
+    /// Copy of the vertex positions
+    pub fn get_vertex_buffer(&self) -> VertexBuffer<Vec3f> {
+        VertexBuffer::new(self.vertices.clone())
+    }
+
+    /// Every triangle's corners in order, indexing get_vertex_buffer and to_interleaved_f32
+    pub fn get_index_buffer(&self) -> IndexBuffer {
+        IndexBuffer::new(
+            self.triangles
+                .iter()
+                .flat_map(|triangle| triangle.indices.map(|index| index as u32))
+                .collect(),
+        )
+    }
+
+    ///
+    /// One vertex after another, INTERLEAVED_STRIDE floats each: position, normal, UV.
+    /// Normals are the stored ones, or compute_vertex_normals when there aren't any; UVs are
+    /// the per-vertex ones, or zero, since UVs that triangles carry themselves can't be
+    /// shared through an index buffer.
+    ///
+    pub fn to_interleaved_f32(&self) -> Vec<f32> {
+        let computed_normals;
+        let normals = match self.vertex_normals() {
+            Some(normals) => normals,
+            None => {
+                computed_normals = self.compute_vertex_normals();
+                &computed_normals[..]
+            }
+        };
+        let has_uvs = self.uvs.len() == self.vertices.len();
+
+        let mut interleaved = Vec::with_capacity(self.vertices.len() * INTERLEAVED_STRIDE);
+        for (index, (position, normal)) in self.vertices.iter().zip(normals).enumerate() {
+            let uv = if has_uvs { self.uvs[index] } else { Vec2f::zero() };
+            interleaved.extend_from_slice(&[position.x, position.y, position.z, normal.x, normal.y, normal.z, uv.x, uv.y]);
+        }
+        interleaved
+    }
+}
@@ -0,0 +1,167 @@
+use crate::math::{Vec2f, Vec3f};
+use crate::mesh::{Mesh, Triangle};
+
+const TERRAIN_COLOR: u32 = 0xFFFFFFFF;
+
+impl Mesh {
+    ///
+    /// Terrain from a grid of heights: `heights` holds `width` samples along X for each of
+    /// `depth` rows along Z (row-major, row 0 at -Z), `cell_size` apart and scaled by
+    /// `height_scale`, centred on the origin in X and Z. Vertices are shared, with smooth
+    /// normals from the neighbouring heights in `normals` and UVs repeating once per cell.
+    /// None when `heights.len()` isn't `width * depth` or either side has fewer than 2 samples.
+    ///
+    pub fn from_heightmap(heights: &[f32], width: usize, depth: usize, cell_size: f32, height_scale: f32) -> Option<Self> {
+        Self::from_heightmap_with_skirt(heights, width, depth, cell_size, height_scale, None)
+    }
+
+    ///
+    /// from_heightmap plus, when `skirt_depth` is given, a vertical strip hanging down from
+    /// each border to `skirt_depth` below the lowest height, so chunks laid side by side
+    /// don't show gaps where their edges' level of detail differs. Skirts face outward.
+    ///
+    pub fn from_heightmap_with_skirt(heights: &[f32], width: usize, depth: usize, cell_size: f32, height_scale: f32,
+                                     skirt_depth: Option<f32>) -> Option<Self> {
+        if width < 2 || depth < 2 || width.checked_mul(depth) != Some(heights.len()) {
+            return None;
+        }
+
+        let mut mesh = Self::new();
+        let height = |column: usize, row: usize| heights[row * width + column] * height_scale;
+        let position = |column: usize, row: usize| {
+            Vec3f::new(
+                (column as f32 - (width - 1) as f32 * 0.5) * cell_size,
+                height(column, row),
+                (row as f32 - (depth - 1) as f32 * 0.5) * cell_size,
+            )
+        };
+        let uv = |column: usize, row: usize| Vec2f::new(column as f32, row as f32);
+
+        for row in 0..depth {
+            for column in 0..width {
+                // Central differences inside, one-sided along the border
+                let (left, right) = (column.saturating_sub(1), (column + 1).min(width - 1));
+                let (back, front) = (row.saturating_sub(1), (row + 1).min(depth - 1));
+                let slope_x = (height(right, row) - height(left, row)) / ((right - left) as f32 * cell_size);
+                let slope_z = (height(column, front) - height(column, back)) / ((front - back) as f32 * cell_size);
+
+                mesh.add_vertex(position(column, row));
+                mesh.normals.push(Vec3f::new(-slope_x, 1.0, -slope_z).normalize());
+                mesh.uvs.push(uv(column, row));
+            }
+        }
+
+        let index = |column: usize, row: usize| row * width + column;
+        for row in 0..depth - 1 {
+            for column in 0..width - 1 {
+                // Same split as create_plane: seen from above with -Z at the top, anticlockwise
+                let (a, b, c, d) = (
+                    index(column, row + 1),
+                    index(column + 1, row + 1),
+                    index(column + 1, row),
+                    index(column, row),
+                );
+                mesh.add_triangle(Triangle::new(a, b, c, TERRAIN_COLOR));
+                mesh.add_triangle(Triangle::new(c, d, a, TERRAIN_COLOR));
+            }
+        }
+
+        if let Some(skirt_depth) = skirt_depth {
+            let lowest = heights.iter().map(|&h| h * height_scale).fold(f32::INFINITY, f32::min);
+            let bottom = lowest - skirt_depth.abs();
+            let sides: [(Vec<usize>, Vec3f); 4] = [
+                ((0..width).map(|column| index(column, 0)).collect(), Vec3f::forward()),
+                ((0..width).map(|column| index(column, depth - 1)).collect(), Vec3f::z_axis()),
+                ((0..depth).map(|row| index(0, row)).collect(), -Vec3f::right()),
+                ((0..depth).map(|row| index(width - 1, row)).collect(), Vec3f::right()),
+            ];
+            for (border, outward) in sides {
+                mesh.add_skirt(&border, outward, bottom);
+            }
+        }
+
+        Some(mesh)
+    }
+
+    // A strip from the `border` vertices straight down to height `bottom`, wound to face `outward`.
+    // Skirt vertices are this side's own so corners don't share a normal between two sides.
+    fn add_skirt(&mut self, border: &[usize], outward: Vec3f, bottom: f32) {
+        let hanging: Vec<usize> = border
+            .iter()
+            .map(|&top| {
+                let position = Vec3f::new(self.vertices[top].x, bottom, self.vertices[top].z);
+                let uv = self.uvs[top];
+                self.normals.push(outward);
+                self.uvs.push(uv);
+                self.add_vertex(position)
+            })
+            .collect();
+
+        for i in 0..border.len() - 1 {
+            let (top0, top1, low0, low1) = (border[i], border[i + 1], hanging[i], hanging[i + 1]);
+            let (v0, v1, v2) = (self.vertices[top0], self.vertices[low0], self.vertices[low1]);
+            let faces_out = Vec3f::calculate_triangle_normal(v0, v1, v2).dot(&outward) >= 0.0;
+            let (first, second) = if faces_out {
+                (Triangle::new(top0, low0, low1, TERRAIN_COLOR), Triangle::new(low1, top1, top0, TERRAIN_COLOR))
+            } else {
+                (Triangle::new(top0, low1, low0, TERRAIN_COLOR), Triangle::new(low1, top0, top1, TERRAIN_COLOR))
+            };
+            self.add_triangle(first);
+            self.add_triangle(second);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mismatched_sizes_give_nothing() {
+        assert!(Mesh::from_heightmap(&[0.0; 5], 2, 3, 1.0, 1.0).is_none());
+        assert!(Mesh::from_heightmap(&[0.0; 3], 1, 3, 1.0, 1.0).is_none());
+    }
+
+    #[test]
+    fn flat_map_faces_straight_up() {
+        let terrain = Mesh::from_heightmap(&[2.0; 12], 4, 3, 1.0, 0.5).unwrap();
+        assert_eq!(terrain.vertices.len(), 12);
+        assert_eq!(terrain.triangles.len(), 3 * 2 * 2);
+        assert!(terrain.normals.iter().all(|normal| (normal.x, normal.y, normal.z) == (0.0, 1.0, 0.0)));
+        // Wound to face the same way as the normals
+        for triangle in &terrain.triangles {
+            assert!((triangle.calculate_normal(&terrain) - Vec3f::up()).length() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn ramp_gets_its_slope_normal_everywhere() {
+        // Rising half a unit per sample along X, samples 2 apart, scaled by 3: slope 0.75
+        let (width, depth) = (5, 3);
+        let heights: Vec<f32> = (0..width * depth).map(|i| (i % width) as f32 * 0.5).collect();
+        let terrain = Mesh::from_heightmap(&heights, width, depth, 2.0, 3.0).unwrap();
+
+        let expected = Vec3f::new(-0.75, 1.0, 0.0).normalize();
+        for normal in &terrain.normals {
+            assert!((*normal - expected).length() < 1e-6);
+        }
+        for triangle in &terrain.triangles {
+            assert!((triangle.calculate_normal(&terrain) - expected).length() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn skirt_hangs_below_the_lowest_height() {
+        let (width, depth) = (5, 3);
+        let heights: Vec<f32> = (0..width * depth).map(|i| (i % width) as f32 * 0.5).collect();
+        let terrain = Mesh::from_heightmap_with_skirt(&heights, width, depth, 2.0, 3.0, Some(1.0)).unwrap();
+
+        // One skirt vertex under each border vertex, corners counted once per side
+        let grid = width * depth;
+        assert_eq!(terrain.vertices.len(), grid + 2 * (width + depth));
+        for vertex in &terrain.vertices[grid..] {
+            assert_eq!(vertex.y, -1.0);
+        }
+        assert!(terrain.validate().non_manifold_edges.is_empty());
+    }
+}
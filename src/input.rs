@@ -2,6 +2,7 @@ use crate::math::Vec2f;
 use windows::Win32::UI::WindowsAndMessaging::{GetClientRect, SetCursorPos, ShowCursor};
 use windows::Win32::Foundation::{HWND, POINT, };
 use windows::Win32::UI::Input::KeyboardAndMouse::{SetCapture, ReleaseCapture};
+use windows::Win32::UI::Input::{RegisterRawInputDevices, RAWINPUTDEVICE, RAWINPUTDEVICE_FLAGS, RIDEV_REMOVE};
 use windows::Win32::Graphics::Gdi::ClientToScreen;
 pub const VK_W: u32 = 0x57;
 pub const VK_A: u32 = 0x41;
@@ -10,32 +11,147 @@ pub const VK_D: u32 = 0x44;
 pub const VK_SPACE: u32 = 0x20;
 pub const VK_LSHIFT: u32 = 0xA0;
 pub const VK_ESCAPE: u32 = 0x1B;
+pub const VK_CONTROL: u32 = 0x11;
+pub const VK_DELETE: u32 = 0x2E;
+pub const VK_P: u32 = 0x50;
+pub const VK_B: u32 = 0x42;
+pub const VK_C: u32 = 0x43;
+pub const VK_O: u32 = 0x4F;
+pub const VK_L: u32 = 0x4C;
+pub const VK_F: u32 = 0x46;
+pub const VK_G: u32 = 0x47;
+pub const VK_H: u32 = 0x48;
+pub const VK_T: u32 = 0x54;
+pub const VK_V: u32 = 0x56;
+pub const VK_X: u32 = 0x58;
+pub const VK_Y: u32 = 0x59;
+pub const VK_Z: u32 = 0x5A;
+pub const VK_K: u32 = 0x4B;
+pub const VK_M: u32 = 0x4D;
+pub const VK_F10: u32 = 0x79;
+pub const VK_LEFT: u32 = 0x25;
+pub const VK_UP: u32 = 0x26;
+pub const VK_RIGHT: u32 = 0x27;
+pub const VK_DOWN: u32 = 0x28;
+pub const VK_NUMPAD0: u32 = 0x60;
+pub const VK_NUMPAD9: u32 = 0x69;
+pub const VK_RETURN: u32 = 0x0D;
+pub const VK_TAB: u32 = 0x09;
+pub const VK_OEM_3: u32 = 0xC0; // ` ~ on US layouts
+
+// Fraction of a frame interval a frame may start early, see InputManager::is_frame_due
+const FRAME_DUE_TOLERANCE: f32 = 0.1;
+
+// HID usage for a mouse (from hidusage.h)
+const HID_USAGE_PAGE_GENERIC: u16 = 0x01;
+const HID_USAGE_GENERIC_MOUSE: u16 = 0x02;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum MouseButton {
+    Left,
+    Right,
+    Middle,
+}
+
+/// One call into InputManager's message handlers, as captured by start_recording
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum InputEvent {
+    KeyDown(u32),
+    KeyUp(u32),
+    MouseMove(i32, i32), // Cooked delta, from WM_MOUSEMOVE
+    RawMouse(i32, i32),  // Device delta, from WM_INPUT
+    MouseButtonDown(MouseButton),
+    MouseButtonUp(MouseButton),
+}
+
+impl InputEvent {
+    fn to_line(self) -> String {
+        match self {
+            InputEvent::KeyDown(vk_code) => format!("key_down {}", vk_code),
+            InputEvent::KeyUp(vk_code) => format!("key_up {}", vk_code),
+            InputEvent::MouseMove(dx, dy) => format!("mouse_move {} {}", dx, dy),
+            InputEvent::RawMouse(dx, dy) => format!("raw_mouse {} {}", dx, dy),
+            InputEvent::MouseButtonDown(button) => format!("button_down {:?}", button),
+            InputEvent::MouseButtonUp(button) => format!("button_up {:?}", button),
+        }
+    }
+
+    fn from_words(words: &[&str]) -> Option<Self> {
+        let button = |name: &str| match name {
+            "Left" => Some(MouseButton::Left),
+            "Right" => Some(MouseButton::Right),
+            "Middle" => Some(MouseButton::Middle),
+            _ => None,
+        };
+        match *words {
+            ["key_down", vk_code] => Some(InputEvent::KeyDown(vk_code.parse().ok()?)),
+            ["key_up", vk_code] => Some(InputEvent::KeyUp(vk_code.parse().ok()?)),
+            ["mouse_move", dx, dy] => Some(InputEvent::MouseMove(dx.parse().ok()?, dy.parse().ok()?)),
+            ["raw_mouse", dx, dy] => Some(InputEvent::RawMouse(dx.parse().ok()?, dy.parse().ok()?)),
+            ["button_down", name] => Some(InputEvent::MouseButtonDown(button(name)?)),
+            ["button_up", name] => Some(InputEvent::MouseButtonUp(button(name)?)),
+            _ => None,
+        }
+    }
+}
+
+///
+/// What start_recording captured, frame by frame: the delta time of each update while
+/// recording, and each event with the number of updates that had run when it arrived.
+///
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct InputRecording {
+    pub frame_times: Vec<f32>,
+    pub events: Vec<(usize, InputEvent)>,
+}
+
+struct Replay {
+    recording: InputRecording,
+    frame: usize, // Updates replayed so far
+    next: usize,  // First event not yet applied
+}
 
 pub struct InputManager {
     // Keyboard state - track what's currently pressed
     keys_pressed: [bool; 256],      // Win32 virtual key codes 0-255
+    mouse_buttons_pressed: [bool; 3], // Indexed by MouseButton
 
     // Mouse state
     mouse_delta: Vec2f,             // Movement since last frame
     mouse_sensitivity: f32,
     mouse_captured: bool,
+    raw_input_active: bool,         // Raw mouse registered; WM_INPUT deltas replace WM_MOUSEMOVE ones
     window_handle: Option<HWND>,    // Need this for mouse capture
 
     // Timing
     last_frame_time: std::time::Instant,
     delta_time: f32,
+    target_fps: u32,                // Frames closer together than 1 / this are skipped; 0 = no limit
+
+    // Keys pressed since take_key_presses, each with whether Ctrl was down at the time
+    key_presses: Vec<(u32, bool)>,
+
+    // Recording and replay
+    recording: Option<InputRecording>,
+    replay: Option<Replay>,         // While set, the message handlers ignore real input
 }
 
 impl InputManager {
     pub fn new() -> Self {
         Self {
             keys_pressed: [false; 256],
+            mouse_buttons_pressed: [false; 3],
             mouse_delta: Vec2f::zero(),
             mouse_sensitivity: 1.0,
             mouse_captured: false,
+            raw_input_active: false,
             window_handle: None,
             last_frame_time: std::time::Instant::now(),
             delta_time: 0.0,
+            target_fps: 0,
+            key_presses: Vec::new(),
+            recording: None,
+            replay: None,
         }
     }
 
@@ -49,6 +165,59 @@ impl InputManager {
 
     // Win32 message handlers - call these from window procedure
     pub fn on_key_down(&mut self, vk_code: u32) {
+        self.on_event(InputEvent::KeyDown(vk_code));
+    }
+
+    pub fn on_key_up(&mut self, vk_code: u32) {
+        self.on_event(InputEvent::KeyUp(vk_code));
+    }
+
+    /// Cooked cursor movement from WM_MOUSEMOVE; ignored while raw input is delivering deltas
+    pub fn on_mouse_move(&mut self, x_delta: i32, y_delta: i32) {
+        self.on_event(InputEvent::MouseMove(x_delta, y_delta));
+    }
+
+    /// Relative movement from WM_INPUT: device counts, before pointer acceleration
+    pub fn on_raw_input(&mut self, x_delta: i32, y_delta: i32) {
+        self.on_event(InputEvent::RawMouse(x_delta, y_delta));
+    }
+
+    pub fn on_mouse_button_down(&mut self, button: MouseButton) {
+        self.on_event(InputEvent::MouseButtonDown(button));
+    }
+
+    pub fn on_mouse_button_up(&mut self, button: MouseButton) {
+        self.on_event(InputEvent::MouseButtonUp(button));
+    }
+
+    // Real input: recorded if a recording is running, dropped while a replay is
+    fn on_event(&mut self, event: InputEvent) {
+        if self.replay.is_some() {
+            return;
+        }
+        if let Some(recording) = &mut self.recording {
+            recording.events.push((recording.frame_times.len(), event));
+        }
+        self.apply_event(event);
+    }
+
+    fn apply_event(&mut self, event: InputEvent) {
+        match event {
+            InputEvent::KeyDown(vk_code) => self.key_down(vk_code),
+            InputEvent::KeyUp(vk_code) => self.key_up(vk_code),
+            InputEvent::MouseMove(x_delta, y_delta) => self.mouse_move(x_delta, y_delta),
+            InputEvent::RawMouse(x_delta, y_delta) => self.raw_mouse(x_delta, y_delta),
+            InputEvent::MouseButtonDown(button) => self.mouse_buttons_pressed[button as usize] = true,
+            InputEvent::MouseButtonUp(button) => self.mouse_buttons_pressed[button as usize] = false,
+        }
+    }
+
+    fn key_down(&mut self, vk_code: u32) {
+        // Held keys repeat WM_KEYDOWN; only the first one is a press
+        if vk_code < 256 && self.keys_pressed[vk_code as usize] {
+            return;
+        }
+        self.key_presses.push((vk_code, self.is_key_pressed(VK_CONTROL)));
         if vk_code < 256 {
             self.keys_pressed[vk_code as usize] = true;
         }
@@ -59,20 +228,27 @@ impl InputManager {
         }
     }
 
-    pub fn on_key_up(&mut self, vk_code: u32) {
+    fn key_up(&mut self, vk_code: u32) {
         if vk_code < 256 {
             self.keys_pressed[vk_code as usize] = false;
         }
     }
 
-    pub fn on_mouse_move(&mut self, x_delta: i32, y_delta: i32) {
-        if self.mouse_captured {
+    fn mouse_move(&mut self, x_delta: i32, y_delta: i32) {
+        if self.mouse_captured && !self.raw_input_active {
             // Accumulate mouse movement
             self.mouse_delta.x += x_delta as f32;
             self.mouse_delta.y += y_delta as f32;
         }
     }
 
+    fn raw_mouse(&mut self, x_delta: i32, y_delta: i32) {
+        if self.mouse_captured {
+            self.mouse_delta.x += x_delta as f32;
+            self.mouse_delta.y += y_delta as f32;
+        }
+    }
+
     // Query methods for game logic
     pub fn is_key_pressed(&self, vk_code: u32) -> bool {
         if vk_code < 256 {
@@ -82,10 +258,39 @@ impl InputManager {
         }
     }
 
+    ///
+    /// Every key press since the last call, oldest first, with whether Ctrl was held. One-shot
+    /// toggles read these rather than the window messages so that a replay presses them too.
+    ///
+    pub fn take_key_presses(&mut self) -> Vec<(u32, bool)> {
+        std::mem::take(&mut self.key_presses)
+    }
+
+    pub fn is_mouse_button_pressed(&self, button: MouseButton) -> bool {
+        self.mouse_buttons_pressed[button as usize]
+    }
+
     pub fn is_mouse_captured(&self) -> bool {
         self.mouse_captured
     }
 
+    pub fn is_raw_input_active(&self) -> bool {
+        self.raw_input_active
+    }
+
+    // Asks Windows for WM_INPUT mouse messages (or stops them); false if registration failed
+    fn register_raw_mouse(hwnd: HWND, enable: bool) -> bool {
+        let device = RAWINPUTDEVICE {
+            usUsagePage: HID_USAGE_PAGE_GENERIC,
+            usUsage: HID_USAGE_GENERIC_MOUSE,
+            dwFlags: if enable { RAWINPUTDEVICE_FLAGS(0) } else { RIDEV_REMOVE },
+            hwndTarget: if enable { hwnd } else { HWND::default() }, // Removal requires a null target
+        };
+        unsafe {
+            RegisterRawInputDevices(&[device], size_of::<RAWINPUTDEVICE>() as u32).is_ok()
+        }
+    }
+
     // Win32-specific mouse capture implementation
     pub fn toggle_mouse_capture(&mut self) {
         if let Some(hwnd) = self.window_handle {
@@ -95,12 +300,19 @@ impl InputManager {
                     ReleaseCapture();
                     ShowCursor(true);
                     self.mouse_captured = false;
+                    if self.raw_input_active {
+                        Self::register_raw_mouse(hwnd, false);
+                        self.raw_input_active = false;
+                    }
                 } else {
                     // Capture mouse
                     SetCapture(hwnd);
                     ShowCursor(false);
                     self.mouse_captured = true;
 
+                    // Raw input gives unaccelerated, unclamped movement; fall back to WM_MOUSEMOVE without it
+                    self.raw_input_active = Self::register_raw_mouse(hwnd, true);
+
                     // Center cursor in window and reset delta
                     let mut rect = Default::default();
                     if GetClientRect(hwnd, &mut rect).is_ok() {
@@ -131,6 +343,12 @@ impl InputManager {
                 ShowCursor(true);
             }
             self.mouse_captured = false;
+            if self.raw_input_active {
+                if let Some(hwnd) = self.window_handle {
+                    Self::register_raw_mouse(hwnd, false);
+                }
+                self.raw_input_active = false;
+            }
         }
     }
 
@@ -146,10 +364,213 @@ impl InputManager {
         self.delta_time
     }
 
+    /// Caps the frame rate is_frame_due allows; 0 lifts the cap
+    pub fn set_target_fps(&mut self, fps: u32) {
+        self.target_fps = fps;
+    }
+
+    pub fn target_fps(&self) -> u32 {
+        self.target_fps
+    }
+
+    ///
+    /// Whether a frame interval (1 / target_fps) has passed since the last update. Ticks up
+    /// to FRAME_DUE_TOLERANCE early still count: Windows timers fire on a ~15.6 ms clock, so
+    /// at 60 fps a strict check would skip every other tick and halve the frame rate.
+    ///
+    pub fn is_frame_due(&self) -> bool {
+        self.target_fps == 0
+            || self.last_frame_time.elapsed().as_secs_f32() >= (1.0 - FRAME_DUE_TOLERANCE) / self.target_fps as f32
+    }
+
+    ///
+    /// Starts a frame: measures the delta time since the last one, or during a replay uses
+    /// the recorded one and applies the events that arrived before that frame.
+    ///
     pub fn update(&mut self) {
         // compute frame delta in seconds
         let now = std::time::Instant::now();
         self.delta_time = (now - self.last_frame_time).as_secs_f32();
         self.last_frame_time = now;
+
+        self.advance_replay();
+        if let Some(recording) = &mut self.recording {
+            recording.frame_times.push(self.delta_time);
+        }
+    }
+
+    /// Starts capturing every event the message handlers get, throwing away any recording in progress
+    pub fn start_recording(&mut self) {
+        self.recording = Some(InputRecording::default());
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.recording.is_some()
+    }
+
+    /// Everything since start_recording; empty if nothing was recording
+    pub fn stop_recording(&mut self) -> InputRecording {
+        self.recording.take().unwrap_or_default()
+    }
+
+    ///
+    /// Writes `recording` to `path` as a timeline: one line per event (e.g. "key_down 87"),
+    /// with a "frame 0.016667" line for each update and its delta time, so every event sits
+    /// after the frames that ran before it. load_recording reads it back.
+    ///
+    pub fn save_recording(recording: &InputRecording, path: &str) -> std::io::Result<()> {
+        let mut text = String::new();
+        let mut events = recording.events.iter().peekable();
+        for frame in 0..=recording.frame_times.len() {
+            while let Some((_, event)) = events.next_if(|&&(event_frame, _)| event_frame <= frame) {
+                text.push_str(&event.to_line());
+                text.push('\n');
+            }
+            if let Some(delta_time) = recording.frame_times.get(frame) {
+                text.push_str(&format!("frame {:.6}\n", delta_time));
+            }
+        }
+        std::fs::write(path, text)
+    }
+
+    /// Reads a file written by save_recording
+    pub fn load_recording(path: &str) -> std::io::Result<InputRecording> {
+        let text = std::fs::read_to_string(path)?;
+        let mut recording = InputRecording::default();
+        for line in text.lines().filter(|line| !line.trim().is_empty()) {
+            let words: Vec<&str> = line.split_whitespace().collect();
+            if let ["frame", delta_time] = words[..]
+                && let Ok(delta_time) = delta_time.parse() {
+                recording.frame_times.push(delta_time);
+            } else if let Some(event) = InputEvent::from_words(&words) {
+                recording.events.push((recording.frame_times.len(), event));
+            } else {
+                return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, format!("bad input event line: {}", line)));
+            }
+        }
+        Ok(recording)
+    }
+
+    ///
+    /// Plays `recording` back frame by frame: each update applies the events that arrived
+    /// before the matching recorded frame, through the same handling as real input, and
+    /// reports that frame's delta time, so the same scene renders the same frames. Key
+    /// presses reach take_key_presses as well. Real input is ignored until the last frame.
+    ///
+    pub fn start_replay(&mut self, recording: InputRecording) {
+        self.replay = Some(Replay { recording, frame: 0, next: 0 });
+    }
+
+    pub fn is_replaying(&self) -> bool {
+        self.replay.is_some()
+    }
+
+    // Applies the events due before the replay's next frame and takes its delta time; ends it after the last
+    fn advance_replay(&mut self) {
+        let Some(mut replay) = self.replay.take() else {
+            return;
+        };
+        while let Some(&(frame, event)) = replay.recording.events.get(replay.next)
+            && frame <= replay.frame {
+            self.apply_event(event);
+            replay.next += 1;
+        }
+        if let Some(&delta_time) = replay.recording.frame_times.get(replay.frame) {
+            self.delta_time = delta_time;
+        }
+        replay.frame += 1;
+        if replay.frame < replay.recording.frame_times.len() || replay.next < replay.recording.events.len() {
+            self.replay = Some(replay);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_event_reads_back_from_its_line() {
+        let events = [
+            InputEvent::KeyDown(VK_W),
+            InputEvent::KeyUp(VK_W),
+            InputEvent::MouseMove(-3, 12),
+            InputEvent::RawMouse(7, -1),
+            InputEvent::MouseButtonDown(MouseButton::Middle),
+            InputEvent::MouseButtonUp(MouseButton::Right),
+        ];
+        for event in events {
+            let line = event.to_line();
+            let words: Vec<&str> = line.split_whitespace().collect();
+            assert_eq!(InputEvent::from_words(&words), Some(event), "{line}");
+        }
+        assert_eq!(InputEvent::from_words(&["button_down", "Fourth"]), None);
+        assert_eq!(InputEvent::from_words(&["key_down"]), None);
+    }
+
+    #[test]
+    fn saved_recording_loads_back_the_same() {
+        let mut input = InputManager::new();
+        input.start_recording();
+        input.on_key_down(VK_W);
+        input.update();
+        input.update();
+        input.on_mouse_move(4, -2);
+        input.on_key_up(VK_W);
+        input.update();
+        input.on_mouse_button_down(MouseButton::Left);
+        let recording = input.stop_recording();
+        assert_eq!(recording.frame_times.len(), 3);
+        assert_eq!(recording.events.iter().map(|&(frame, _)| frame).collect::<Vec<_>>(), vec![0, 2, 2, 3]);
+
+        let path = std::env::temp_dir().join(format!("input_recording_{}.txt", std::process::id()));
+        let path = path.to_str().unwrap();
+        InputManager::save_recording(&recording, path).unwrap();
+        let loaded = InputManager::load_recording(path);
+        let _ = std::fs::remove_file(path);
+
+        // Frame times are written to the microsecond
+        let loaded = loaded.unwrap();
+        assert_eq!(loaded.events, recording.events);
+        assert_eq!(loaded.frame_times.len(), recording.frame_times.len());
+        for (loaded, recorded) in loaded.frame_times.iter().zip(&recording.frame_times) {
+            assert!((loaded - recorded).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn replay_applies_events_on_their_frames_with_the_recorded_times() {
+        let recording = InputRecording {
+            frame_times: vec![0.25, 0.5],
+            events: vec![(0, InputEvent::KeyDown(VK_W)), (1, InputEvent::KeyDown(VK_P)), (1, InputEvent::KeyUp(VK_W))],
+        };
+        let mut input = InputManager::new();
+        input.start_replay(recording);
+
+        // Real input is ignored while replaying
+        input.on_key_down(VK_S);
+        input.update();
+        assert_eq!(input.get_delta_time(), 0.25);
+        assert!(input.is_key_pressed(VK_W) && !input.is_key_pressed(VK_S));
+        assert_eq!(input.take_key_presses(), vec![(VK_W, false)]);
+
+        input.update();
+        assert_eq!(input.get_delta_time(), 0.5);
+        assert!(!input.is_key_pressed(VK_W));
+        assert_eq!(input.take_key_presses(), vec![(VK_P, false)]);
+        assert!(!input.is_replaying());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn auto_repeat_is_a_single_press() {
+        let mut input = InputManager::new();
+        for _ in 0..3 {
+            input.on_key_down(VK_F10);
+        }
+        assert_eq!(input.take_key_presses(), vec![(VK_F10, false)]);
+        input.on_key_up(VK_F10);
+        input.on_key_down(VK_F10);
+        assert_eq!(input.take_key_presses(), vec![(VK_F10, false)]);
+        assert!(input.is_key_pressed(VK_F10));
+    }
+}
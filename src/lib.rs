@@ -1,7 +1,43 @@
 pub mod renderer;
+pub mod font;
+pub mod post_process;
 pub mod math;
+pub mod coordinate_system;
 pub mod lighting;
+pub mod lighting_lod;
+pub mod material_registry;
+pub mod light_debug;
+pub mod light_gizmo;
+pub mod minimap;
+pub mod light_animation;
+pub mod texture;
 pub mod mesh;
+pub mod mesh_simplify;
+pub mod mesh_subdivide;
+pub mod mesh_repair;
+pub mod obj_export;
+pub mod obj_import;
+pub mod gpu_types;
+pub mod tangents;
+pub mod text_mesh;
+pub mod sdf;
+pub mod mesh_generators;
+pub mod heightmap;
 pub mod camera;
+pub mod camera_path;
+pub mod camera_sequence;
+pub mod camera_controller;
 pub mod scene;
+pub mod sampling;
+pub mod ambient_occlusion;
+pub mod lightmap;
+pub mod shadow;
+pub mod skybox;
+pub mod draw_list;
+pub mod render_pipeline;
+pub mod gizmo;
+pub mod undo;
+pub mod event_queue;
+pub mod spring;
+pub mod physics;
 pub mod input;
\ No newline at end of file
@@ -0,0 +1,149 @@
+use std::f32::consts::TAU;
+use crate::lighting::Light;
+use crate::math::Vec3f;
+
+///
+/// Drives a light's intensity or color over time, see LightingSystem::set_animation.
+/// Every animation is a pure function of the time since it was attached (and the seed,
+/// for Flicker), so replaying the same frame times gives the same lights.
+///
+#[derive(Clone, Debug)]
+pub enum LightAnimation {
+    /// Torch-like noise around `base`, never further than `amplitude` from it;
+    /// `speed` is how many new random targets per second
+    Flicker { base: f32, amplitude: f32, speed: f32, noise_seed: u32 },
+    /// Smooth swing between `min` and `max` intensity, starting at `min`, every `period` seconds
+    Pulse { min: f32, max: f32, period: f32 },
+    /// Blends through `colors` in order and back to the first, once every `period` seconds
+    ColorCycle { colors: Vec<Vec3f>, period: f32 },
+}
+
+impl LightAnimation {
+    /// Intensity at `time`, or None for animations that don't touch it
+    pub fn intensity_at(&self, time: f32) -> Option<f32> {
+        match *self {
+            LightAnimation::Flicker { base, amplitude, speed, noise_seed } => {
+                let noise = value_noise(time * speed, noise_seed);
+                Some((base + amplitude * (noise * 2.0 - 1.0)).max(0.0))
+            }
+            LightAnimation::Pulse { min, max, period } => {
+                if period <= 0.0 {
+                    return Some(max);
+                }
+                let phase = (time / period).rem_euclid(1.0);
+                Some(min + (max - min) * (0.5 - 0.5 * (TAU * phase).cos()))
+            }
+            LightAnimation::ColorCycle { .. } => None,
+        }
+    }
+
+    /// Color at `time`, or None for animations that don't touch it
+    pub fn color_at(&self, time: f32) -> Option<Vec3f> {
+        let LightAnimation::ColorCycle { colors, period } = self else {
+            return None;
+        };
+        match colors.len() {
+            0 => None,
+            1 => Some(colors[0]),
+            count => {
+                let phase = if *period > 0.0 { (time / period).rem_euclid(1.0) } else { 0.0 };
+                let position = phase * count as f32;
+                let index = (position as usize).min(count - 1);
+                let t = position - index as f32;
+                Some(colors[index] * (1.0 - t) + colors[(index + 1) % count] * t)
+            }
+        }
+    }
+
+    pub fn apply(&self, light: &mut Light, time: f32) {
+        if let Some(intensity) = self.intensity_at(time) {
+            light.intensity = intensity;
+        }
+        if let Some(color) = self.color_at(time) {
+            light.color = color;
+        }
+    }
+}
+
+// 1D value noise in [0, 1]: random values at whole numbers, smoothly blended in between
+fn value_noise(x: f32, seed: u32) -> f32 {
+    let cell = x.floor();
+    let t = x - cell;
+    let cell = cell as i32 as u32;
+    let a = hash_to_unit(cell, seed);
+    let b = hash_to_unit(cell.wrapping_add(1), seed);
+    let t = t * t * (3.0 - 2.0 * t);
+    a + (b - a) * t
+}
+
+// Integer hash of (value, seed) to [0, 1]
+fn hash_to_unit(value: u32, seed: u32) -> f32 {
+    let mut x = value.wrapping_mul(0x9E37_79B9) ^ seed.wrapping_mul(0x85EB_CA6B);
+    x ^= x >> 16;
+    x = x.wrapping_mul(0x7FEB_352D);
+    x ^= x >> 15;
+    x = x.wrapping_mul(0x846C_A68B);
+    x ^= x >> 16;
+    (x >> 8) as f32 / ((1 << 24) - 1) as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lighting::LightingSystem;
+
+    #[test]
+    fn pulse_stays_in_range_and_repeats_every_period() {
+        let pulse = LightAnimation::Pulse { min: 0.5, max: 2.0, period: 1.5 };
+        let intensity = |time: f32| pulse.intensity_at(time).unwrap();
+
+        assert!((intensity(0.0) - 0.5).abs() < 1e-6);
+        assert!((intensity(0.75) - 2.0).abs() < 1e-6);
+        for step in 0..100 {
+            let time = step as f32 * 1.5 / 100.0;
+            assert!((0.5..=2.0).contains(&intensity(time)), "{time}: {}", intensity(time));
+            assert!((intensity(time) - intensity(time + 1.5)).abs() < 1e-4, "not periodic at {time}");
+            assert!((intensity(time) - intensity(time + 4.5)).abs() < 1e-4, "not periodic at {time}");
+        }
+    }
+
+    #[test]
+    fn color_cycle_returns_to_its_first_color_every_period() {
+        let colors = vec![Vec3f::new(1.0, 0.0, 0.0), Vec3f::new(0.0, 1.0, 0.0), Vec3f::new(0.0, 0.0, 1.0)];
+        let cycle = LightAnimation::ColorCycle { colors, period: 3.0 };
+
+        assert!((cycle.color_at(0.0).unwrap() - Vec3f::new(1.0, 0.0, 0.0)).length() < 1e-6);
+        assert!((cycle.color_at(1.0).unwrap() - Vec3f::new(0.0, 1.0, 0.0)).length() < 1e-6);
+        for step in 0..60 {
+            let time = step as f32 * 0.05;
+            let color = cycle.color_at(time).unwrap();
+            assert!((color - cycle.color_at(time + 3.0).unwrap()).length() < 1e-4, "not periodic at {time}");
+            assert!((color.x + color.y + color.z - 1.0).abs() < 1e-5, "{time}: {color:?}");
+        }
+        assert_eq!(cycle.intensity_at(1.0), None);
+    }
+
+    #[test]
+    fn flicker_stays_within_its_amplitude_and_repeats_for_the_same_seed() {
+        let flicker = |noise_seed| LightAnimation::Flicker { base: 1.0, amplitude: 0.3, speed: 8.0, noise_seed };
+
+        // Two runs through LightingSystem::update with the same frame times
+        let run = |noise_seed: u32| {
+            let mut lighting = LightingSystem::new();
+            let torch = lighting.add_light(Light::point(Vec3f::zero(), Vec3f::one(), 1.0, 5.0));
+            lighting.set_animation(torch, flicker(noise_seed));
+            (0..120)
+                .map(|_| {
+                    lighting.update(1.0 / 60.0);
+                    lighting.get(torch).unwrap().intensity
+                })
+                .collect::<Vec<f32>>()
+        };
+
+        let first = run(7);
+        assert!(first.iter().all(|intensity| (0.7..=1.3).contains(intensity)), "{first:?}");
+        assert!(first.iter().any(|&intensity| (intensity - first[0]).abs() > 0.01), "it should flicker");
+        assert_eq!(run(7), first);
+        assert_ne!(run(8), first, "another seed should flicker differently");
+    }
+}
@@ -0,0 +1,144 @@
+use crate::lighting::{LightType, LightingSystem};
+use crate::math::{Mat4x4, Vec3f, Vec4f};
+use crate::mesh::Mesh;
+use crate::renderer::{Renderer, Viewport};
+
+// Lines closer to the camera than this (in clip-space w) are cut off
+const MIN_CLIP_W: f32 = 1e-3;
+// Lines from the apex to the rim of each spot cone
+const CONE_FAN_LINES: usize = 16;
+
+impl LightingSystem {
+    ///
+    /// Debug view of where point and spot lights reach: a wireframe sphere of radius `range`
+    /// around each one, plus line fans for the inner and outer cone of spots, in the light's
+    /// color at half brightness. Lines are depth tested against the scene, which needs the far
+    /// plane `vp_matrix` was built with; it's worked out from the matrix, with `camera_pos`
+    /// being where the camera the matrix belongs to sits.
+    ///
+    pub fn debug_draw_light_volumes(&self, renderer: &mut Renderer, vp_matrix: &Mat4x4, camera_pos: Vec3f) {
+        let viewport = renderer.get_viewport();
+        let camera_far = perspective_far_plane(vp_matrix, camera_pos);
+
+        for light in self.active_lights() {
+            let cones = match light.light_type {
+                LightType::Point => None,
+                LightType::Spot { inner_angle, outer_angle, .. } => Some([inner_angle, outer_angle]),
+                LightType::Directional | LightType::Area { .. } => continue,
+            };
+            if light.range <= 0.0 {
+                continue;
+            }
+
+            let half = light.color * 0.5;
+            let channel = |value: f32| (value.clamp(0.0, 1.0) * 255.0) as u32;
+            let color = 0xFF000000 | (channel(half.x) << 16) | (channel(half.y) << 8) | channel(half.z);
+
+            let mut line = |start: Vec3f, end: Vec3f| {
+                let a = vp_matrix.multiply_point_4d(&start);
+                let b = vp_matrix.multiply_point_4d(&end);
+                draw_clip_line(renderer, &viewport, camera_far, a, b, color);
+            };
+
+            for (start, end) in wire_sphere(light.position, light.range, 12, 16) {
+                line(start, end);
+            }
+
+            let Some(cone_angles) = cones else {
+                continue;
+            };
+            let axis = light.direction;
+            let helper = if axis.x.abs() < 0.9 { Vec3f::x_axis() } else { Vec3f::y_axis() };
+            let tangent = axis.cross(&helper).normalize();
+            let bitangent = axis.cross(&tangent);
+
+            for angle in cone_angles {
+                // Rim where the cone meets the range sphere
+                let rim_center = light.position + axis * (light.range * angle.cos());
+                let rim_radius = light.range * angle.sin();
+                let rim: Vec<Vec3f> = (0..CONE_FAN_LINES)
+                    .map(|i| {
+                        let phi = std::f32::consts::TAU * i as f32 / CONE_FAN_LINES as f32;
+                        rim_center + (tangent * phi.cos() + bitangent * phi.sin()) * rim_radius
+                    })
+                    .collect();
+
+                for (i, &point) in rim.iter().enumerate() {
+                    line(light.position, point);
+                    line(point, rim[(i + 1) % rim.len()]);
+                }
+            }
+        }
+    }
+}
+
+///
+/// The far plane of a perspective view-projection matrix whose camera is at `camera_pos`.
+/// Clip z is linear in clip w (z = B - A·w, with the far plane at w = B / (1 + A)): at the
+/// camera w is 0, giving B, and a point down the view axis (the direction w grows in) gives A.
+///
+fn perspective_far_plane(vp_matrix: &Mat4x4, camera_pos: Vec3f) -> f32 {
+    let clip = |point: Vec3f| vp_matrix.multiply_point_4d(&point);
+    let at_camera = clip(camera_pos);
+    let w_gradient = Vec3f::new(
+        clip(camera_pos + Vec3f::x_axis()).w,
+        clip(camera_pos + Vec3f::y_axis()).w,
+        clip(camera_pos + Vec3f::z_axis()).w,
+    ) - Vec3f::one() * at_camera.w;
+    let ahead = clip(camera_pos + w_gradient.normalize());
+    at_camera.z * ahead.w / (ahead.w - ahead.z + at_camera.z)
+}
+
+///
+/// Edges of a UV sphere around `center`, each once: every edge is shared by two triangles
+/// walking it in opposite directions, so only the low -> high one is kept.
+///
+pub(crate) fn wire_sphere(center: Vec3f, radius: f32, segments: usize, rings: usize) -> Vec<(Vec3f, Vec3f)> {
+    let sphere = Mesh::create_uv_sphere(radius, segments, rings);
+    let vertices: Vec<Vec3f> = sphere.vertices.iter().map(|vertex| *vertex + center).collect();
+    let mut edges = Vec::new();
+    for triangle in &sphere.triangles {
+        for corner in 0..3 {
+            let (a, b) = (triangle.indices[corner], triangle.indices[(corner + 1) % 3]);
+            if a < b {
+                edges.push((vertices[a], vertices[b]));
+            }
+        }
+    }
+    edges
+}
+
+/// Draws a clip-space segment, cutting it where it crosses the camera plane
+pub(crate) fn draw_clip_line(renderer: &mut Renderer, viewport: &Viewport, camera_far: f32, mut a: Vec4f, mut b: Vec4f, color: u32) {
+    if a.w < MIN_CLIP_W && b.w < MIN_CLIP_W {
+        return;
+    }
+    if a.w < MIN_CLIP_W {
+        a = b + (a - b) * ((MIN_CLIP_W - b.w) / (a.w - b.w));
+    } else if b.w < MIN_CLIP_W {
+        b = a + (b - a) * ((MIN_CLIP_W - a.w) / (b.w - a.w));
+    }
+
+    // For a perspective projection w is the view-space depth, which is what the z-buffer holds (over far)
+    let start = viewport.ndc_to_screen(a.x / a.w, a.y / a.w);
+    let end = viewport.ndc_to_screen(b.x / b.w, b.y / b.w);
+    renderer.draw_line_with_depth([start, end], [a.w / camera_far, b.w / camera_far], color);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::camera::Camera;
+
+    #[test]
+    fn far_plane_comes_back_out_of_the_matrix() {
+        for (position, target, near, far) in [
+            (Vec3f::new(0.0, 0.0, -5.0), Vec3f::zero(), 0.1, 100.0),
+            (Vec3f::new(3.0, 7.0, 2.0), Vec3f::new(-4.0, 0.5, 9.0), 0.5, 40.0),
+        ] {
+            let camera = Camera::builder().position(position).target(target).near(near).far(far).build().unwrap();
+            let found = perspective_far_plane(&camera.get_view_projection_matrix(), camera.position);
+            assert!((found - far).abs() < far * 1e-3, "far {far}, found {found}");
+        }
+    }
+}
@@ -0,0 +1,305 @@
+use crate::font::BitmapFont;
+use crate::light_debug::{draw_clip_line, wire_sphere};
+use crate::lighting::{Light, LightHandle, LightType};
+use crate::math::{Vec2f, Vec3f};
+use crate::renderer::Renderer;
+use crate::scene::Scene;
+use crate::undo::MoveLightCommand;
+
+// How close (in pixels) a click has to land to a light's position to pick it
+const LIGHT_PICK_RADIUS: f32 = 10.0;
+// Point light sphere radius and spot cone length as fractions of the light's range
+const POINT_GIZMO_SCALE: f32 = 0.05;
+const SPOT_GIZMO_SCALE: f32 = 0.2;
+// Smallest gizmo size in world units, so short-range lights stay visible
+const MIN_GIZMO_SIZE: f32 = 0.15;
+const DIRECTIONAL_ARROW_LENGTH: f32 = 1.0;
+// Lines from a spot gizmo's apex to its rim
+const SPOT_GIZMO_LINES: usize = 8;
+
+/// A light being dragged across the screen
+#[derive(Copy, Clone)]
+pub(crate) struct LightDrag {
+    light: LightHandle,
+    grab_offset: Vec3f,    // From the point under the cursor to the light, kept through the drag
+    start_position: Vec3f, // Where the light was when the drag began
+}
+
+impl Scene {
+    ///
+    /// Where the light's gizmo sits on screen as of the last rendered frame, or None when
+    /// it's behind the camera or the handle is stale. Directional lights have theirs at
+    /// Light::position too, which doesn't change how they light anything.
+    ///
+    pub fn light_screen_position(&self, handle: LightHandle) -> Option<Vec2f> {
+        let light = self.lighting.get(handle)?;
+        let (width, height) = self.frame_size;
+        let viewport = self.camera.compute_viewport(width, height);
+        let clip = self.camera.get_view_projection_matrix().multiply_point_4d(&light.position);
+        (clip.w > self.camera.near).then(|| viewport.ndc_to_screen(clip.x / clip.w, clip.y / clip.w))
+    }
+
+    /// The light whose gizmo is closest to (x, y), within LIGHT_PICK_RADIUS; None while gizmos are hidden
+    pub fn pick_light(&self, x: f32, y: f32) -> Option<LightHandle> {
+        if !self.show_light_gizmos {
+            return None;
+        }
+        let pixel = Vec2f::new(x, y);
+        self.lighting
+            .handles()
+            .filter_map(|handle| Some((handle, (self.light_screen_position(handle)? - pixel).length())))
+            .filter(|(_, distance)| *distance <= LIGHT_PICK_RADIUS)
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(handle, _)| handle)
+    }
+
+    ///
+    /// Selects the light under (x, y) and starts dragging it. Returns false when there's no
+    /// light there, so the click can go to the object gizmo or selection instead.
+    ///
+    pub fn begin_light_drag(&mut self, x: f32, y: f32) -> bool {
+        let Some(handle) = self.pick_light(x, y) else {
+            return false;
+        };
+        let Some(position) = self.lighting.get(handle).map(|light| light.position) else {
+            return false;
+        };
+        self.select_light(handle);
+
+        let grab_offset = self.point_in_camera_plane(x, y, position).map_or(Vec3f::zero(), |point| position - point);
+        self.light_drag = Some(LightDrag { light: handle, grab_offset, start_position: position });
+        true
+    }
+
+    ///
+    /// Moves the dragged light so it stays under the cursor, in the plane through it that
+    /// faces the camera: its distance from the camera along the view direction doesn't change.
+    ///
+    pub fn drag_light(&mut self, x: f32, y: f32) {
+        let Some(drag) = self.light_drag else {
+            return;
+        };
+        let Some(position) = self.lighting.get(drag.light).map(|light| light.position) else {
+            self.light_drag = None;
+            return;
+        };
+        if let Some(point) = self.point_in_camera_plane(x, y, position)
+            && let Some(light) = self.lighting.get_mut(drag.light) {
+            light.position = point + drag.grab_offset;
+        }
+    }
+
+    ///
+    /// Lets go of the light. Returns the move the drag made, already applied, for an
+    /// UndoStack to record; None if nothing was being dragged or the light never moved.
+    ///
+    pub fn end_light_drag(&mut self) -> Option<MoveLightCommand> {
+        let drag = self.light_drag.take()?;
+        let position = self.lighting.get(drag.light)?.position;
+        ((position - drag.start_position).length() > 0.0)
+            .then(|| MoveLightCommand::new(drag.light, drag.start_position, position))
+    }
+
+    pub fn is_dragging_light(&self) -> bool {
+        self.light_drag.is_some()
+    }
+
+    /// Moves the selected light along the camera's right and up axes, in world units
+    pub fn nudge_selected_light(&mut self, right: f32, up: f32) {
+        let offset = self.camera.get_right_vector() * right + self.camera.get_up_vector() * up;
+        if let Some(light) = self.selected_light.and_then(|handle| self.lighting.get_mut(handle)) {
+            light.position = light.position + offset;
+        }
+    }
+
+    // Where the ray through pixel (x, y) crosses the plane through `anchor` facing the camera
+    fn point_in_camera_plane(&self, x: f32, y: f32, anchor: Vec3f) -> Option<Vec3f> {
+        let (width, height) = self.frame_size;
+        let (origin, direction) = self.camera.screen_to_ray(x, y, width, height)?;
+        let forward = self.camera.get_forward_vector();
+        let facing = direction.dot(&forward);
+        if facing.abs() < 1e-6 {
+            return None;
+        }
+        let t = (anchor - origin).dot(&forward) / facing;
+        (t > 0.0).then(|| origin + direction * t)
+    }
+
+    ///
+    /// Draws a gizmo for every light, depth tested: a small sphere for point lights, a cone
+    /// for spots, an arrow for directional lights and an outline for area lights, sized by
+    /// range and angle. Drawn in the light's color at full brightness, grey when disabled and
+    /// white when selected.
+    ///
+    pub(crate) fn draw_light_gizmos(&self, renderer: &mut Renderer) {
+        let viewport = renderer.get_viewport();
+        let vp_matrix = self.camera.get_view_projection_matrix();
+        let far = self.camera.far;
+
+        for handle in self.lighting.handles() {
+            let Some(light) = self.lighting.get(handle) else {
+                continue;
+            };
+            let color = if self.selected_light == Some(handle) {
+                0xFFFFFFFF
+            } else if !self.lighting.is_enabled(handle) {
+                0xFF808080
+            } else {
+                gizmo_color(light.color)
+            };
+
+            for (start, end) in gizmo_lines(light) {
+                let a = vp_matrix.multiply_point_4d(&start);
+                let b = vp_matrix.multiply_point_4d(&end);
+                draw_clip_line(renderer, &viewport, far, a, b, color);
+            }
+        }
+    }
+
+    /// Top-right panel for the selected light, with its position in a form that can be pasted into code
+    pub(crate) fn render_light_inspector(renderer: &mut Renderer, font: &BitmapFont, light: &Light) {
+        const MARGIN: i32 = 6;
+
+        let kind = match light.light_type {
+            LightType::Directional => "DIRECTIONAL LIGHT",
+            LightType::Point => "POINT LIGHT",
+            LightType::Spot { .. } => "SPOT LIGHT",
+            LightType::Area { .. } => "AREA LIGHT",
+        };
+        let (position, direction) = (light.position, light.direction);
+        let lines = [
+            kind.to_string(),
+            format!("POS: {:.2}, {:.2}, {:.2}", position.x, position.y, position.z),
+            format!("DIR: {:.2}, {:.2}, {:.2}", direction.x, direction.y, direction.z),
+            format!("INTENSITY: {:.2}", light.intensity),
+        ];
+
+        let (screen_width, _) = renderer.get_dimension();
+        let width = lines.iter().map(|line| font.text_width(line)).max().unwrap_or(0) + 2 * MARGIN as u32;
+        let height = lines.len() as u32 * font.line_height() + 2 * MARGIN as u32;
+        let left = screen_width as i32 - width as i32;
+        renderer.draw_filled_rect(left, 0, width, height, 0x99000000);
+
+        let mut y = MARGIN;
+        for line in &lines {
+            renderer.draw_text(left + MARGIN, y, line, font, 0xFFFFFFFF);
+            y += font.line_height() as i32;
+        }
+    }
+}
+
+// The light's color scaled so its brightest channel is full, so dim lights still show up
+fn gizmo_color(color: Vec3f) -> u32 {
+    let brightest = color.x.max(color.y).max(color.z);
+    let color = if brightest > 0.0 { color * (1.0 / brightest) } else { Vec3f::one() };
+    let channel = |value: f32| (value.clamp(0.0, 1.0) * 255.0) as u32;
+    0xFF000000 | (channel(color.x) << 16) | (channel(color.y) << 8) | channel(color.z)
+}
+
+// World-space segments making up a light's gizmo
+fn gizmo_lines(light: &Light) -> Vec<(Vec3f, Vec3f)> {
+    let position = light.position;
+    match light.light_type {
+        LightType::Point => wire_sphere(position, (light.range * POINT_GIZMO_SCALE).max(MIN_GIZMO_SIZE), 8, 6),
+        LightType::Directional => {
+            let tip = position + light.direction * DIRECTIONAL_ARROW_LENGTH;
+            let (tangent, bitangent) = perpendiculars(light.direction);
+            let back = tip - light.direction * (DIRECTIONAL_ARROW_LENGTH * 0.25);
+            let head = DIRECTIONAL_ARROW_LENGTH * 0.1;
+            vec![
+                (position, tip),
+                (tip, back + tangent * head),
+                (tip, back - tangent * head),
+                (tip, back + bitangent * head),
+                (tip, back - bitangent * head),
+            ]
+        }
+        LightType::Spot { outer_angle, .. } => {
+            let length = (light.range * SPOT_GIZMO_SCALE).max(MIN_GIZMO_SIZE);
+            let (tangent, bitangent) = perpendiculars(light.direction);
+            let rim_center = position + light.direction * (length * outer_angle.cos());
+            let rim_radius = length * outer_angle.sin();
+            let rim: Vec<Vec3f> = (0..SPOT_GIZMO_LINES)
+                .map(|i| {
+                    let phi = std::f32::consts::TAU * i as f32 / SPOT_GIZMO_LINES as f32;
+                    rim_center + (tangent * phi.cos() + bitangent * phi.sin()) * rim_radius
+                })
+                .collect();
+            rim.iter()
+                .enumerate()
+                .flat_map(|(i, &point)| [(position, point), (point, rim[(i + 1) % rim.len()])])
+                .collect()
+        }
+        LightType::Area { right, up, width, height } => {
+            let (half_right, half_up) = (right * (width * 0.5), up * (height * 0.5));
+            let corners = [
+                position - half_right - half_up,
+                position + half_right - half_up,
+                position + half_right + half_up,
+                position - half_right + half_up,
+            ];
+            let emission = up.cross(&right) * MIN_GIZMO_SIZE.max(width.min(height) * 0.5);
+            let mut lines: Vec<(Vec3f, Vec3f)> = (0..4).map(|i| (corners[i], corners[(i + 1) % 4])).collect();
+            lines.push((position, position + emission));
+            lines
+        }
+    }
+}
+
+// Two unit vectors at right angles to `axis` and each other
+fn perpendiculars(axis: Vec3f) -> (Vec3f, Vec3f) {
+    let helper = if axis.x.abs() < 0.9 { Vec3f::x_axis() } else { Vec3f::y_axis() };
+    let tangent = axis.cross(&helper).normalize();
+    (tangent, axis.cross(&tangent))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scene_with_lights() -> (Scene, LightHandle, LightHandle) {
+        let mut scene = Scene::new();
+        scene.frame_size = (800, 600);
+        scene.show_light_gizmos = true;
+        let lamp = scene.lighting.add_light(Light::point(Vec3f::new(1.0, 0.5, 0.0), Vec3f::one(), 2.0, 5.0));
+        let other = scene.lighting.add_light(Light::point(Vec3f::new(-2.0, -1.0, 1.0), Vec3f::one(), 2.0, 5.0));
+        (scene, lamp, other)
+    }
+
+    #[test]
+    fn picking_at_a_lights_screen_position_returns_it() {
+        let (scene, lamp, other) = scene_with_lights();
+
+        for handle in [lamp, other] {
+            let screen = scene.light_screen_position(handle).expect("both lights are in front of the camera");
+            assert_eq!(scene.pick_light(screen.x, screen.y), Some(handle));
+            assert_eq!(scene.pick_light(screen.x + 4.0, screen.y - 4.0), Some(handle), "within the pick radius");
+        }
+        assert_eq!(scene.pick_light(5.0, 5.0), None, "nothing near the corner");
+    }
+
+    #[test]
+    fn dragging_a_light_moves_it_with_the_cursor() {
+        let (mut scene, lamp, _) = scene_with_lights();
+        let start = scene.lighting.get(lamp).unwrap().position;
+        let screen = scene.light_screen_position(lamp).unwrap();
+
+        assert!(scene.begin_light_drag(screen.x, screen.y));
+        assert_eq!(scene.selected_light, Some(lamp));
+        scene.drag_light(screen.x + 60.0, screen.y - 30.0);
+
+        let moved = scene.lighting.get(lamp).unwrap().position;
+        let moved_screen = scene.light_screen_position(lamp).unwrap();
+        assert!((moved_screen - Vec2f::new(screen.x + 60.0, screen.y - 30.0)).length() < 0.5, "{moved_screen:?}");
+        // It stays in the plane facing the camera
+        let forward = scene.camera.get_forward_vector();
+        assert!((moved - start).dot(&forward).abs() < 1e-4);
+
+        // Letting go leaves it there and hands back the move for the undo stack
+        let command = scene.end_light_drag().expect("the light moved");
+        assert!(!scene.is_dragging_light());
+        assert_eq!(command.light, lamp);
+        assert!((command.old_pos - start).length() < 1e-6 && (command.new_pos - moved).length() < 1e-6);
+        assert!((scene.lighting.get(lamp).unwrap().position - moved).length() < 1e-6);
+    }
+}
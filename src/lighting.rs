@@ -1,20 +1,167 @@
-use crate::math::Vec3f;
+use std::cell::RefCell;
+use std::sync::Arc;
+use crate::light_animation::LightAnimation;
+use crate::math::{Vec2f, Vec3f};
+use crate::scene::ObjectId;
+use crate::shadow::{DEFAULT_MAX_SHADOWED_POINT_LIGHTS, PointShadowMap};
+use crate::texture::Texture;
 
 #[derive(Copy, Clone)]
 pub enum LightType {
     Directional,
     Point,
-    Spot { inner_angle: f32, outer_angle: f32 },
+    /// Half-angles in radians, with their cosines precomputed for the cone test
+    Spot { inner_angle: f32, outer_angle: f32, cos_inner: f32, cos_outer: f32 },
+    /// Rectangle centered on the light's position, spanned by unit `right` and `up`;
+    /// emits only from the side facing -(right × up)
+    Area { right: Vec3f, up: Vec3f, width: f32, height: f32 },
 }
 
-#[derive(Copy, Clone)]
+/// How point/spot light intensity falls off with distance
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Attenuation {
+    /// Physically based 1/d², windowed so it fades to exactly zero at `radius`
+    InverseSquare { radius: f32 },
+    /// Straight line from full intensity at the light to zero at `range`
+    Linear { range: f32 },
+    /// 1 / (constant + linear*d + quadratic*d²), faded out linearly towards the light's range
+    Custom { constant: f32, linear: f32, quadratic: f32 },
+}
+
+impl Attenuation {
+    /// The falloff the renderer has always used
+    pub fn default_custom() -> Self {
+        Attenuation::Custom { constant: 1.0, linear: 0.1, quadratic: 0.01 }
+    }
+
+    ///
+    /// Attenuation factor at `distance`. `light_range` is only used by Custom,
+    /// the other modes carry their own cutoff.
+    /// Every mode reaches 0 at its cutoff without a jump, so lights don't pop at the edge.
+    ///
+    pub fn factor(&self, distance: f32, light_range: f32) -> f32 {
+        match *self {
+            Attenuation::InverseSquare { radius } => {
+                if radius <= 0.0 || distance >= radius {
+                    return 0.0;
+                }
+                // Window from Karis, "Real Shading in Unreal Engine 4": (1 - (d/r)⁴)²
+                let ratio = distance / radius;
+                let window = (1.0 - ratio * ratio * ratio * ratio).max(0.0);
+                window * window / (distance * distance + 1.0)
+            }
+            Attenuation::Linear { range } => {
+                if range <= 0.0 {
+                    return 0.0;
+                }
+                (1.0 - distance / range).max(0.0)
+            }
+            Attenuation::Custom { constant, linear, quadratic } => {
+                if light_range <= 0.0 || distance >= light_range {
+                    return 0.0;
+                }
+                let falloff = 1.0 / (constant + linear * distance + quadratic * distance * distance);
+                let range_attenuation = ((light_range - distance) / light_range).max(0.0);
+                falloff * range_attenuation
+            }
+        }
+    }
+
+    /// Distance at which the light stops contributing
+    pub fn cutoff(&self, light_range: f32) -> f32 {
+        match *self {
+            Attenuation::InverseSquare { radius } => radius,
+            Attenuation::Linear { range } => range,
+            Attenuation::Custom { .. } => light_range,
+        }
+    }
+}
+
+impl Default for Attenuation {
+    fn default() -> Self {
+        Self::default_custom()
+    }
+}
+
+///
+/// A light source. Colors are linear and intensities unbounded:
+/// - Directional: like sunlight, `intensity` is the light arriving on a surface that faces
+///   it, so 1.0 lights a white diffuse surface to full white and 5.0 is bright midday sun.
+/// - Point/spot: intensity at the light, like luminous intensity; with InverseSquare falloff
+///   it drops to intensity / (d² + 1) at distance d, so a lamp lighting things 3 units away
+///   needs around 10 to look as bright as a 1.0 directional light.
+/// - Area: as point, measured from the nearest spot on the panel.
+///
+/// These are the units of IntensityMode::Legacy; LightingSystem::intensity_mode can read
+/// them as physical-ish units instead.
+///
+/// Anything above 1.0 at a pixel only survives with HDR rendering; exposure then brings it back down.
+///
+/// A light only reaches objects whose layers share a bit with its mask, and never the objects
+/// it excludes; those still get the scene's ambient light.
+///
+/// Point lights with `shadow` set cast shadows, see ShadowSettings.
+///
+#[derive(Clone)]
 pub struct Light {
     pub light_type: LightType,
-    pub position: Vec3f,      // For point/spot lights
-    pub direction: Vec3f,     // For directional/spot lights
+    pub position: Vec3f,      // For point/spot/area lights
+    pub direction: Vec3f,     // For directional/spot lights, and the way an area light faces
     pub color: Vec3f,
-    pub intensity: f32,
-    pub range: f32,           // For point/spot lights
+    pub intensity: f32,       // Unbounded, see above
+    pub range: f32,           // For point/spot/area lights
+    pub attenuation: Attenuation,
+    pub mask: u32,               // Matched against GameObject::layers, all bits by default
+    pub excluded: Vec<ObjectId>, // Objects this light never reaches, see exclude
+    pub shadow: Option<ShadowSettings>, // Point lights only; None = no shadows
+}
+
+///
+/// Shadow cube settings for a point light. Each of the six faces is `resolution` texels square.
+///
+/// Both biases stop surfaces shadowing themselves (acne: stripes or speckles on lit
+/// surfaces). `depth_bias` is how far a surface may sit behind the nearest caster and still
+/// count as lit; `normal_bias` moves the point looked up off the surface along its normal
+/// first, which helps most on surfaces the light grazes. Raise them if lit surfaces show
+/// acne, lower them if shadows come loose from the objects casting them. Both are in world units.
+///
+/// `pcf_radius` softens edges by averaging (2r + 1)² texels: 0 is hard, 1 is 3x3, 2 is 5x5.
+/// `strength` scales how much light a shadow takes away: 1 blocks it all, 0.5 half, 0 none.
+///
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ShadowSettings {
+    pub resolution: usize,
+    pub depth_bias: f32,
+    pub normal_bias: f32,
+    pub pcf_radius: usize,
+    pub strength: f32,
+}
+
+impl ShadowSettings {
+    pub fn new(resolution: usize, depth_bias: f32) -> Self {
+        Self { resolution, depth_bias, normal_bias: 0.0, pcf_radius: 0, strength: 1.0 }
+    }
+
+    pub fn with_normal_bias(mut self, normal_bias: f32) -> Self {
+        self.normal_bias = normal_bias;
+        self
+    }
+
+    pub fn with_pcf_radius(mut self, pcf_radius: usize) -> Self {
+        self.pcf_radius = pcf_radius;
+        self
+    }
+
+    pub fn with_strength(mut self, strength: f32) -> Self {
+        self.strength = strength.clamp(0.0, 1.0);
+        self
+    }
+}
+
+impl Default for ShadowSettings {
+    fn default() -> Self {
+        Self::new(128, 0.05).with_normal_bias(0.1).with_pcf_radius(1)
+    }
 }
 
 impl Light {
@@ -26,6 +173,10 @@ impl Light {
             color,
             intensity,
             range: 0.0,
+            attenuation: Attenuation::default_custom(),
+            mask: u32::MAX,
+            excluded: Vec::new(),
+            shadow: None,
         }
     }
 
@@ -37,23 +188,173 @@ impl Light {
             color,
             intensity,
             range,
+            attenuation: Attenuation::default_custom(),
+            mask: u32::MAX,
+            excluded: Vec::new(),
+            shadow: None,
         }
     }
 
+    pub fn directional_kelvin(direction: Vec3f, kelvin: f32, intensity: f32) -> Self {
+        Self::directional(direction, Self::color_from_kelvin(kelvin), intensity)
+    }
+
+    pub fn point_kelvin(position: Vec3f, kelvin: f32, intensity: f32, range: f32) -> Self {
+        Self::point(position, Self::color_from_kelvin(kelvin), intensity, range)
+    }
+
+    ///
+    /// Color of a black body at `kelvin`, in [0, 1] with the brightest channel at about 1.
+    /// Uses Tanner Helland's fit to the Planckian locus, valid from 1000K to 40000K
+    /// (clamped outside). Candle ~1900K, tungsten bulb ~2700K, daylight ~6500K, clear sky ~10000K.
+    ///
+    pub fn color_from_kelvin(kelvin: f32) -> Vec3f {
+        let t = kelvin.clamp(1000.0, 40000.0) / 100.0;
+
+        let red = if t <= 66.0 {
+            255.0
+        } else {
+            329.69873 * (t - 60.0).powf(-0.13320476)
+        };
+        let green = if t <= 66.0 {
+            99.4708 * t.ln() - 161.11957
+        } else {
+            288.12217 * (t - 60.0).powf(-0.07551485)
+        };
+        let blue = if t >= 66.0 {
+            255.0
+        } else if t <= 19.0 {
+            0.0
+        } else {
+            138.51773 * (t - 10.0).ln() - 305.0448
+        };
+
+        Vec3f::new(
+            red.clamp(0.0, 255.0) / 255.0,
+            green.clamp(0.0, 255.0) / 255.0,
+            blue.clamp(0.0, 255.0) / 255.0,
+        )
+    }
+
+    /// Spot light; `inner_angle` and `outer_angle` are half-angles of the cone in radians
     pub fn spot(position: Vec3f, direction: Vec3f, color: Vec3f, intensity: f32,
                 range: f32, inner_angle: f32, outer_angle: f32) -> Self {
+        // Swapped angles give a hard-edged cone at outer_angle, with no falloff
+        debug_assert!(inner_angle <= outer_angle, "spot light inner_angle must not exceed outer_angle");
+        debug_assert!(
+            inner_angle > 0.0 && outer_angle < std::f32::consts::PI,
+            "spot light angles must be in (0, π) radians"
+        );
         Self {
-            light_type: LightType::Spot { inner_angle, outer_angle },
+            light_type: LightType::Spot {
+                inner_angle,
+                outer_angle,
+                cos_inner: inner_angle.cos(),
+                cos_outer: outer_angle.cos(),
+            },
             position,
             direction: direction.normalize(),
             color,
             intensity,
             range,
+            attenuation: Attenuation::default_custom(),
+            mask: u32::MAX,
+            excluded: Vec::new(),
+            shadow: None,
         }
     }
 
-    pub fn calculate_lighting(&self, surface_point: &Vec3f, surface_normal: &Vec3f,
-                              view_direction: &Vec3f) -> (f32, f32) {
+    /// Same as spot, with the cone half-angles in degrees
+    pub fn spot_degrees(position: Vec3f, direction: Vec3f, color: Vec3f, intensity: f32,
+                        range: f32, inner_degrees: f32, outer_degrees: f32) -> Self {
+        Self::spot(position, direction, color, intensity, range,
+                   inner_degrees.to_radians(), outer_degrees.to_radians())
+    }
+
+    ///
+    /// Rectangular panel light (a window, a screen) centered on `position`. `right` and `up`
+    /// span it, their lengths being its width and height, and it shines towards -(right × up)
+    /// with nothing coming out the back.
+    ///
+    pub fn area(position: Vec3f, right: Vec3f, up: Vec3f, color: Vec3f, intensity: f32, range: f32) -> Self {
+        let (right_axis, up_axis) = (right.normalize(), up.normalize());
+        Self {
+            light_type: LightType::Area {
+                right: right_axis,
+                up: up_axis,
+                width: right.length(),
+                height: up.length(),
+            },
+            position,
+            direction: up_axis.cross(&right_axis).normalize(),
+            color,
+            intensity,
+            range,
+            attenuation: Attenuation::default_custom(),
+            mask: u32::MAX,
+            excluded: Vec::new(),
+            shadow: None,
+        }
+    }
+
+    /// Outer cone half-angle in degrees, None for lights that aren't spots
+    pub fn get_outer_angle_degrees(&self) -> Option<f32> {
+        match self.light_type {
+            LightType::Spot { outer_angle, .. } => Some(outer_angle.to_degrees()),
+            _ => None,
+        }
+    }
+
+    pub fn with_shadows(mut self, settings: ShadowSettings) -> Self {
+        self.shadow = Some(settings);
+        self
+    }
+
+    pub fn with_mask(mut self, mask: u32) -> Self {
+        self.mask = mask;
+        self
+    }
+
+    /// Stops this light reaching `object`, e.g. a flashlight and the player holding it.
+    /// For a light already added, go through LightingSystem::get_mut.
+    pub fn exclude(&mut self, object: ObjectId) {
+        if !self.excluded.contains(&object) {
+            self.excluded.push(object);
+        }
+    }
+
+    /// Undoes exclude
+    pub fn include(&mut self, object: ObjectId) {
+        self.excluded.retain(|excluded| *excluded != object);
+    }
+
+    /// Whether the light's mask and exclusions let it reach `receiver` at all
+    pub fn reaches(&self, receiver: &LightReceiver) -> bool {
+        self.mask & receiver.layers != 0
+            && receiver.object.is_none_or(|object| !self.excluded.contains(&object))
+    }
+
+    pub fn with_attenuation(mut self, attenuation: Attenuation) -> Self {
+        self.attenuation = attenuation;
+        self.range = attenuation.cutoff(self.range);
+        self
+    }
+
+    pub fn with_inverse_square_falloff(self, radius: f32) -> Self {
+        self.with_attenuation(Attenuation::InverseSquare { radius })
+    }
+
+    pub fn with_linear_falloff(self, range: f32) -> Self {
+        self.with_attenuation(Attenuation::Linear { range })
+    }
+
+    pub fn with_custom_falloff(self, constant: f32, linear: f32, quadratic: f32) -> Self {
+        self.with_attenuation(Attenuation::Custom { constant, linear, quadratic })
+    }
+
+    /// Direction from the surface towards the light and the combined distance/cone attenuation,
+    /// or None when the point gets no light at all
+    pub fn incident(&self, surface_point: &Vec3f) -> Option<(Vec3f, f32)> {
         let (light_direction, attenuation) = match self.light_type {
             LightType::Directional => {
                 // For directional lights, direction is constant and no attenuation
@@ -64,70 +365,253 @@ impl Light {
                 let light_dir = self.position - *surface_point;
                 let distance = light_dir.length();
 
-                if distance > self.range {
-                    return (0.0, 0.0);
+                let attenuation = self.attenuation.factor(distance, self.range);
+                if attenuation <= 0.0 {
+                    return None;
                 }
 
-                let normalized_dir = light_dir.normalize();
-
-                // Distance attenuation (quadratic falloff)
-                let attenuation = 1.0 / (1.0 + 0.1 * distance + 0.01 * distance * distance);
-                let range_attenuation = ((self.range - distance) / self.range).max(0.0);
-
-                (normalized_dir, attenuation * range_attenuation)
+                (light_dir.normalize(), attenuation)
             },
-            LightType::Spot { inner_angle, outer_angle } => {
+            LightType::Spot { cos_inner, cos_outer, .. } => {
                 let light_to_surface = *surface_point - self.position;
                 let distance = light_to_surface.length();
 
-                if distance > self.range {
-                    return (0.0, 0.0);
+                let distance_attenuation = self.attenuation.factor(distance, self.range);
+                if distance_attenuation <= 0.0 {
+                    return None;
                 }
 
                 let light_direction = light_to_surface.normalize();
-                let spot_direction = self.direction;
-
-                let angle = light_direction.dot(&spot_direction).acos();
 
-                if angle > outer_angle {
-                    return (0.0, 0.0);
+                // Compare cosines rather than angles: no acos per pixel, and a dot product that
+                // rounds to just above 1.0 on the axis can't turn into a NaN angle
+                let cos_angle = light_direction.dot(&self.direction);
+                if cos_angle < cos_outer {
+                    return None;
                 }
 
-                // Smooth falloff between inner and outer angles
-                let spot_attenuation = if angle < inner_angle {
-                    1.0
-                } else {
-                    ((outer_angle - angle) / (outer_angle - inner_angle)).powf(2.0)
-                };
+                // Smooth falloff between the outer and inner cone
+                let spot_attenuation = smoothstep(cos_outer, cos_inner, cos_angle);
 
-                let distance_attenuation = 1.0 / (1.0 + 0.1 * distance + 0.01 * distance * distance);
-                let range_attenuation = ((self.range - distance) / self.range).max(0.0);
+                (-light_direction, distance_attenuation * spot_attenuation)
+            }
+            LightType::Area { right, up, width, height } => {
+                // Falloff is measured from the nearest spot on the panel, so a big panel
+                // lights what's close to it evenly instead of peaking at its center
+                let nearest = self.clamp_to_area(*surface_point, right, up, width, height);
+                let from_panel = *surface_point - nearest;
+                let distance = from_panel.length();
+                let emission = up.cross(&right);
+
+                // Single-sided: nothing reaches points behind the panel's plane. The last few
+                // degrees before the plane fade out so there's no seam where it stops.
+                let cos_emission = if distance > 0.0 { from_panel.dot(&emission) / distance } else { 0.0 };
+                if cos_emission <= 0.0 {
+                    return None;
+                }
+                let attenuation = self.attenuation.factor(distance, self.range)
+                    * smoothstep(0.0, AREA_LIGHT_EDGE_FADE, cos_emission);
 
-                (-light_direction, distance_attenuation * range_attenuation * spot_attenuation)
+                ((self.position - *surface_point).normalize(), attenuation)
             }
         };
 
         if attenuation <= 0.0 {
-            return (0.0, 0.0);
+            return None;
         }
+        Some((light_direction, attenuation))
+    }
+
+    ///
+    /// Everything the shading models need from this light at one surface point, or None when
+    /// it gets no light. For point-like lights both directions are the incident one.
+    ///
+    /// Area lights use the representative point approximation: diffuse N·L is taken towards
+    /// the center and widened by the panel's solid angle (as a sphere of the same area), so
+    /// surfaces turned partly away still catch the near side of a big panel; highlights aim at
+    /// where the reflected view ray meets the panel, clamped onto it. Not physically exact,
+    /// but it only gets dimmer with distance and becomes a point light as the panel shrinks.
+    ///
+    pub fn sample(&self, surface_point: &Vec3f, surface_normal: &Vec3f, view_direction: &Vec3f) -> Option<LightSample> {
+        let (direction, attenuation) = self.incident(surface_point)?;
+
+        let LightType::Area { right, up, width, height } = self.light_type else {
+            return Some(LightSample {
+                direction,
+                specular_direction: direction,
+                n_dot_l: surface_normal.dot(&direction),
+                attenuation,
+            });
+        };
+
+        let center_distance = (self.position - *surface_point).length();
+        let equivalent_radius = (width * height / std::f32::consts::PI).sqrt();
+        let sin_sigma = if center_distance > 0.0 { (equivalent_radius / center_distance).min(1.0) } else { 1.0 };
+        let n_dot_l = horizon_wrapped_cosine(surface_normal.dot(&direction), sin_sigma);
+
+        let reflected = *surface_normal * (2.0 * surface_normal.dot(view_direction)) - *view_direction;
+        let plane_normal = right.cross(&up);
+        let facing = reflected.dot(&plane_normal);
+        let hit_distance = if facing.abs() > 1e-6 {
+            (self.position - *surface_point).dot(&plane_normal) / facing
+        } else {
+            -1.0
+        };
+        // Reflection misses the plane: head for the panel's edge in the reflection's direction
+        let along_ray = if hit_distance > 0.0 { hit_distance } else { center_distance };
+        let representative =
+            self.clamp_to_area(*surface_point + reflected * along_ray, right, up, width, height);
+        let specular_direction = (representative - *surface_point).normalize();
+
+        Some(LightSample {
+            direction,
+            specular_direction: if specular_direction.length() > 0.0 { specular_direction } else { direction },
+            n_dot_l,
+            attenuation,
+        })
+    }
+
+    /// Nearest point of an area light's rectangle to where `point` falls on its plane
+    fn clamp_to_area(&self, point: Vec3f, right: Vec3f, up: Vec3f, width: f32, height: f32) -> Vec3f {
+        let offset = point - self.position;
+        let u = offset.dot(&right).clamp(-width * 0.5, width * 0.5);
+        let v = offset.dot(&up).clamp(-height * 0.5, height * 0.5);
+        self.position + right * u + up * v
+    }
+
+    /// Returns (diffuse, specular) intensity for one surface point.
+    /// `specular_power` is the material's shininess: higher gives a tighter highlight.
+    pub fn calculate_lighting(&self, surface_point: &Vec3f, surface_normal: &Vec3f,
+                              view_direction: &Vec3f, specular_power: f32) -> (f32, f32) {
+        let Some(sample) = self.sample(surface_point, surface_normal, view_direction) else {
+            return (0.0, 0.0);
+        };
 
         // Diffuse lighting (Lambert)
-        let diffuse = surface_normal.dot(&light_direction).max(0.0);
+        let diffuse = sample.n_dot_l.max(0.0);
 
         // Specular lighting (Blinn-Phong)
-        let half_vector = (light_direction + *view_direction).normalize();
-        let specular_power = 32.0; // Shininess
+        let half_vector = (sample.specular_direction + *view_direction).normalize();
         let specular = surface_normal.dot(&half_vector).max(0.0).powf(specular_power);
 
-        (diffuse * attenuation, specular * attenuation)
+        (diffuse * sample.attenuation, specular * sample.attenuation)
+    }
+}
+
+///
+/// The surface being lit: the layers and object light masks and exclusions are checked
+/// against, how much ambient light its baked occlusion lets through, any light baked into
+/// a lightmap there, and where the material's texture maps are sampled. The default is
+/// reached by every light, fully open, unbaked and untextured.
+///
+#[derive(Copy, Clone, Debug)]
+pub struct LightReceiver {
+    pub layers: u32,
+    pub object: Option<ObjectId>,
+    pub occlusion: f32,     // Ambient scale, 1 = open
+    pub baked_light: Vec3f, // Diffuse light from a Lightmap, lit like the ambient term
+    pub vertex_color: Option<Vec3f>, // The mesh's vertex color here, if it has them
+    pub uv: Option<Vec2f>,  // None leaves the material's colors unmapped
+}
+
+impl Default for LightReceiver {
+    fn default() -> Self {
+        Self { layers: u32::MAX, object: None, occlusion: 1.0, baked_light: Vec3f::zero(), vertex_color: None, uv: None }
     }
 }
 
+/// How one light reaches a surface point, see Light::sample
+#[derive(Copy, Clone, Debug)]
+pub struct LightSample {
+    pub direction: Vec3f,          // Towards the light
+    pub specular_direction: Vec3f, // Towards the light as seen in reflections
+    pub n_dot_l: f32,              // Diffuse cosine term, not clamped
+    pub attenuation: f32,          // Distance, cone and emission falloff combined
+}
+
+/// How a material turns light into color
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ShadingModel {
+    /// The original look: Lambert plus an unnormalized Blinn-Phong highlight
+    LegacyBlinnPhong,
+    ///
+    /// Energy-conserving Blinn-Phong: the highlight is normalized by (n + 8) / 8π so it keeps
+    /// its brightness as it tightens, Schlick's Fresnel (from Material::f0) brightens it at
+    /// grazing angles, and diffuse only gets the light that wasn't reflected (1 - F).
+    ///
+    BlinnPhong,
+    ///
+    /// Metal/roughness PBR: GGX distribution, height-correlated Smith visibility and Schlick
+    /// Fresnel, with f0 blended from 0.04 to the albedo by `metallic`. Metals get no diffuse.
+    ///
+    Pbr { roughness: f32, metallic: f32 },
+    ///
+    /// Cel shading: diffuse snaps to `bands` flat steps, specular becomes a hard-edged spot,
+    /// and faces seen at a grazing angle (N·V below `rim_threshold`) get a dark rim.
+    /// A rim_threshold of 0 turns the rim off.
+    ///
+    Toon { bands: u32, rim_threshold: f32 },
+}
+
 pub struct Material {
     pub diffuse_color: Vec3f,
     pub specular_color: Vec3f,
     pub specular_power: f32,
     pub ambient_factor: f32,
+    pub shading_model: ShadingModel,
+    pub f0: Vec3f, // Reflectance at normal incidence, used by ShadingModel::BlinnPhong
+    pub alpha: f32, // Opacity, below 1.0 the surface is blended and drawn back to front
+    pub emissive_color: Vec3f, // Light given off by the surface itself, unaffected by lights
+    pub two_sided: bool, // Drawn from behind too, lit as if its normal faced the viewer (leaves, paper)
+    pub rim: Option<RimLight>, // Glow where the surface turns away from the viewer
+    pub use_vertex_color: VertexColorMode,
+    // Texture maps, multiplied with the matching color above at the surface's UV
+    pub diffuse_map: Option<Arc<Texture>>,
+    pub specular_map: Option<Arc<Texture>>,
+    pub emissive_map: Option<Arc<Texture>>,
+}
+
+///
+/// Rim light: intensity * color * (1 - max(N·V, 0))^power, added on top of the lit color.
+/// Zero where the surface faces the viewer and strongest on the silhouette; a higher
+/// power makes the rim thinner. Independent of the scene's lights.
+///
+#[derive(Copy, Clone, Debug)]
+pub struct RimLight {
+    pub color: Vec3f,
+    pub power: f32,
+    pub intensity: f32,
+}
+
+impl RimLight {
+    pub fn new(color: Vec3f, power: f32, intensity: f32) -> Self {
+        Self { color, power, intensity }
+    }
+
+    /// The rim color for a surface whose normal and view direction have dot product `n_dot_v`
+    pub fn term(&self, n_dot_v: f32) -> Vec3f {
+        // Clamped both ways so grazing and back-facing normals can't feed powf a negative base
+        let facing_away = (1.0 - n_dot_v.max(0.0)).clamp(0.0, 1.0);
+        self.color * (self.intensity * facing_away.powf(self.power.max(0.0)))
+    }
+}
+
+/// What a material does with the vertex colors of the mesh it's drawn on
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Default)]
+pub enum VertexColorMode {
+    #[default]
+    Ignore,   // Diffuse comes from the material alone
+    Multiply, // Vertex color tints the diffuse (terrain blends, AO baked into colors)
+    Replace,  // Vertex color stands in for diffuse_color; a diffuse map still multiplies it
+}
+
+/// A material's colors at one point on a surface, with texture maps applied
+#[derive(Copy, Clone, Debug)]
+pub struct SurfaceColors {
+    pub diffuse: Vec3f,
+    pub specular: Vec3f,
+    pub emissive: Vec3f,
+    pub f0: Vec3f,
 }
 
 impl Material {
@@ -137,9 +621,119 @@ impl Material {
             specular_color: specular,
             specular_power: shininess,
             ambient_factor: 0.1,
+            shading_model: ShadingModel::LegacyBlinnPhong,
+            f0: Vec3f::splat(0.04), // Typical for plastics and other dielectrics
+            alpha: 1.0,
+            emissive_color: Vec3f::zero(),
+            two_sided: false,
+            rim: None,
+            use_vertex_color: VertexColorMode::Ignore,
+            diffuse_map: None,
+            specular_map: None,
+            emissive_map: None,
         }
     }
 
+    pub fn with_two_sided(mut self, two_sided: bool) -> Self {
+        self.two_sided = two_sided;
+        self
+    }
+
+    pub fn with_rim(mut self, color: Vec3f, power: f32, intensity: f32) -> Self {
+        self.rim = Some(RimLight::new(color, power, intensity));
+        self
+    }
+
+    pub fn with_vertex_color_mode(mut self, mode: VertexColorMode) -> Self {
+        self.use_vertex_color = mode;
+        self
+    }
+
+    pub fn with_emissive(mut self, emissive_color: Vec3f) -> Self {
+        self.emissive_color = emissive_color;
+        self
+    }
+
+    pub fn with_diffuse_map(mut self, texture: Arc<Texture>) -> Self {
+        self.diffuse_map = Some(texture);
+        self
+    }
+
+    pub fn with_specular_map(mut self, texture: Arc<Texture>) -> Self {
+        self.specular_map = Some(texture);
+        self
+    }
+
+    /// The map is scaled by emissive_color, so set that too (Vec3f::one() for the map as-is)
+    pub fn with_emissive_map(mut self, texture: Arc<Texture>) -> Self {
+        self.emissive_map = Some(texture);
+        self
+    }
+
+    pub fn has_texture_maps(&self) -> bool {
+        self.diffuse_map.is_some() || self.specular_map.is_some() || self.emissive_map.is_some()
+    }
+
+    ///
+    /// Colors at texture coordinate `uv`: each map sample times its color factor.
+    /// Without a UV, or without a map, the plain color is used. `vertex_color` goes into the
+    /// diffuse as use_vertex_color says; without one the diffuse is left as it is.
+    /// For PBR materials f0 follows the (textured) albedo, so metals pick up the map's tint.
+    ///
+    pub fn colors_at(&self, uv: Option<Vec2f>, vertex_color: Option<Vec3f>) -> SurfaceColors {
+        let apply = |color: Vec3f, map: &Option<Arc<Texture>>| match (map, uv) {
+            (Some(texture), Some(uv)) => color * texture.sample(uv),
+            _ => color,
+        };
+
+        let base_diffuse = match (self.use_vertex_color, vertex_color) {
+            (VertexColorMode::Multiply, Some(vertex_color)) => self.diffuse_color * vertex_color,
+            (VertexColorMode::Replace, Some(vertex_color)) => vertex_color,
+            _ => self.diffuse_color,
+        };
+        let diffuse = apply(base_diffuse, &self.diffuse_map);
+        let f0 = match self.shading_model {
+            ShadingModel::Pbr { metallic, .. } => Vec3f::splat(0.04) + (diffuse - Vec3f::splat(0.04)) * metallic,
+            _ => self.f0,
+        };
+
+        SurfaceColors {
+            diffuse,
+            specular: apply(self.specular_color, &self.specular_map),
+            emissive: apply(self.emissive_color, &self.emissive_map),
+            f0,
+        }
+    }
+
+    pub fn with_alpha(mut self, alpha: f32) -> Self {
+        self.alpha = alpha;
+        self
+    }
+
+    pub fn is_transparent(&self) -> bool {
+        self.alpha < 1.0
+    }
+
+    pub fn with_f0(mut self, f0: Vec3f) -> Self {
+        self.f0 = f0;
+        self
+    }
+
+    /// Physically based material; albedo is the base color (tint of reflections for metals)
+    pub fn pbr(albedo: Vec3f, roughness: f32, metallic: f32) -> Self {
+        let roughness = roughness.clamp(0.0, 1.0);
+        let metallic = metallic.clamp(0.0, 1.0);
+        let mut material = Self::new(albedo, Vec3f::one(), 1.0)
+            .with_shading_model(ShadingModel::Pbr { roughness, metallic });
+        material.f0 = Vec3f::splat(0.04) + (albedo - Vec3f::splat(0.04)) * metallic;
+        material
+    }
+
+    pub fn with_shading_model(mut self, shading_model: ShadingModel) -> Self {
+        self.shading_model = shading_model;
+        self
+    }
+
     pub fn default() -> Self {
         Self::new(
             Vec3f::new(1.0, 1.0, 1.0),  // White diffuse
@@ -147,25 +741,324 @@ impl Material {
             32.0                        // Medium shininess
         )
     }
+
+    /// Diffuse from a 0xRRGGBB color (bits above those are ignored), specular and shininess as in default()
+    pub fn from_hex(hex: u32) -> Self {
+        let channel = |shift: u32| ((hex >> shift) & 0xFF) as f32 / 255.0;
+        Self {
+            diffuse_color: Vec3f::new(channel(16), channel(8), channel(0)),
+            ..Self::default()
+        }
+    }
+
+    /// from_hex for a basic color name ("red", "cyan", ...), any case; None for names it doesn't know
+    pub fn from_name(name: &str) -> Option<Self> {
+        let hex = match name.to_ascii_lowercase().as_str() {
+            "red" => 0xFF0000,
+            "green" => 0x00FF00,
+            "blue" => 0x0000FF,
+            "white" => 0xFFFFFF,
+            "black" => 0x000000,
+            "yellow" => 0xFFFF00,
+            "cyan" => 0x00FFFF,
+            "magenta" => 0xFF00FF,
+            _ => return None,
+        };
+        Some(Self::from_hex(hex))
+    }
+}
+
+///
+/// Refers to a light added to a LightingSystem.
+/// The generation changes every time a slot is reused, so a handle to a removed light
+/// never ends up pointing at whichever light took its place.
+///
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct LightHandle {
+    index: usize,
+    generation: u32,
+}
+
+struct LightSlot {
+    light: Option<Light>, // None = free slot
+    generation: u32,
+    enabled: bool,
+    animation: Option<LightAnimation>,
+    animation_time: f32, // Seconds since the animation was attached
 }
 
 pub struct LightingSystem {
-    pub lights: Vec<Light>,
+    slots: Vec<LightSlot>,
+    free_slots: Vec<usize>,
     pub ambient_color: Vec3f,
     pub ambient_intensity: f32,
+    pub exposure: f32, // Multiplies all lit colors before tonemapping
+    pub auto_exposure: Option<AutoExposure>, // Drives exposure from the rendered frame when set
+    pub debug_mode: Option<LightingDebug>, // Replaces lit colors with a diagnostic view
+    pub max_shadowed_point_lights: usize, // The rest light as if nothing were in the way
+    pub intensity_mode: IntensityMode, // What Light::intensity is measured in
+    point_shadows: RefCell<Vec<PointShadowMap>>, // Rendered by the ShadowPass each frame
+}
+
+///
+/// How LightingSystem reads Light::intensity.
+///
+/// Legacy is the light as documented on Light: every kind is scaled by its intensity as is,
+/// so a point light's number says how bright it is right at the light.
+///
+/// Physical treats point, spot and area intensities as the total power the light gives off,
+/// spread over the whole sphere (divided by 4π), and directional intensity as illuminance,
+/// the light arriving on a surface facing it. Point lights then need numbers in the tens to
+/// thousands, and a bright scene is brought down with exposure (or auto_exposure) rather than
+/// by dimming every light. Spot lights aren't brightened for their narrower cone, so
+/// resizing one keeps its brightness. See LightingSystem::set_intensity_mode to switch a
+/// scene over without changing its look.
+///
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Default)]
+pub enum IntensityMode {
+    #[default]
+    Legacy,
+    Physical,
+}
+
+impl IntensityMode {
+    /// What `light`'s intensity is multiplied by when shading in this mode
+    pub fn scale(self, light: &Light) -> f32 {
+        match (self, light.light_type) {
+            (IntensityMode::Legacy, _) | (IntensityMode::Physical, LightType::Directional) => 1.0,
+            (IntensityMode::Physical, _) => 1.0 / (4.0 * std::f32::consts::PI),
+        }
+    }
+}
+
+/// What LightingSystem::debug_mode shows instead of the lit color
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum LightingDebug {
+    /// One light's diffuse + specular as grey luminance, nothing else
+    LightContribution(LightHandle),
+    /// Sum of every light's distance/cone attenuation at the surface, as grey
+    Attenuation,
+    /// World normal mapped from [-1, 1] to [0, 1] per channel: +X red, +Y green, +Z blue
+    NormalColor,
+}
+
+impl LightingDebug {
+    /// Label for the HUD
+    pub fn name(&self) -> String {
+        match self {
+            LightingDebug::LightContribution(handle) => format!("LIGHT {}", handle.index),
+            LightingDebug::Attenuation => "ATTENUATION".to_string(),
+            LightingDebug::NormalColor => "NORMALS".to_string(),
+        }
+    }
+}
+
+///
+/// Settings for automatic exposure (eye adaptation). Each frame the scene's log-average
+/// luminance is measured and exposure eases toward the value that maps it to `key`.
+/// Needs HDR rendering, since clipped LDR colors can't be measured.
+///
+#[derive(Copy, Clone, Debug)]
+pub struct AutoExposure {
+    pub key: f32,             // Brightness the average should end up at; 0.18 is middle grey
+    pub adaptation_time: f32, // Time constant in seconds: ~63% of the way to the target after this long
+    pub min_exposure: f32,
+    pub max_exposure: f32,
+}
+
+impl AutoExposure {
+    pub fn new(key: f32, adaptation_time: f32) -> Self {
+        Self { key, adaptation_time, min_exposure: 0.05, max_exposure: 20.0 }
+    }
+}
+
+impl Default for AutoExposure {
+    fn default() -> Self {
+        Self::new(0.18, 0.5)
+    }
 }
 
 impl LightingSystem {
     pub fn new() -> Self {
         Self {
-            lights: Vec::new(),
+            slots: Vec::new(),
+            free_slots: Vec::new(),
             ambient_color: Vec3f::new(1.0, 1.0, 1.0),
             ambient_intensity: 0.1,
+            exposure: 1.0,
+            auto_exposure: None,
+            debug_mode: None,
+            max_shadowed_point_lights: DEFAULT_MAX_SHADOWED_POINT_LIGHTS,
+            intensity_mode: IntensityMode::Legacy,
+            point_shadows: RefCell::new(Vec::new()),
+        }
+    }
+
+    /// Adds an enabled light, reusing a free slot if there is one
+    pub fn add_light(&mut self, light: Light) -> LightHandle {
+        if let Some(index) = self.free_slots.pop() {
+            let slot = &mut self.slots[index];
+            slot.light = Some(light);
+            slot.enabled = true;
+            slot.animation = None;
+            return LightHandle { index, generation: slot.generation };
+        }
+
+        self.slots.push(LightSlot {
+            light: Some(light),
+            generation: 0,
+            enabled: true,
+            animation: None,
+            animation_time: 0.0,
+        });
+        LightHandle { index: self.slots.len() - 1, generation: 0 }
+    }
+
+    /// Removes the light and returns it, or None if the handle is stale
+    pub fn remove_light(&mut self, handle: LightHandle) -> Option<Light> {
+        let slot = self.slot_mut(handle)?;
+        let light = slot.light.take();
+        slot.generation = slot.generation.wrapping_add(1);
+        self.free_slots.push(handle.index);
+        light
+    }
+
+    /// Returns false if the handle is stale
+    pub fn set_enabled(&mut self, handle: LightHandle, enabled: bool) -> bool {
+        match self.slot_mut(handle) {
+            Some(slot) => {
+                slot.enabled = enabled;
+                true
+            }
+            None => false,
         }
     }
 
-    pub fn add_light(&mut self, light: Light) {
-        self.lights.push(light);
+    pub fn is_enabled(&self, handle: LightHandle) -> bool {
+        self.slot(handle).is_some_and(|slot| slot.enabled)
+    }
+
+    pub fn get(&self, handle: LightHandle) -> Option<&Light> {
+        self.slot(handle)?.light.as_ref()
+    }
+
+    pub fn get_mut(&mut self, handle: LightHandle) -> Option<&mut Light> {
+        self.slot_mut(handle)?.light.as_mut()
+    }
+
+    pub fn contains(&self, handle: LightHandle) -> bool {
+        self.slot(handle).is_some()
+    }
+
+    ///
+    /// Attaches an animation to the light, replacing any it had, and applies its first frame.
+    /// Returns false if the handle is stale.
+    ///
+    pub fn set_animation(&mut self, handle: LightHandle, animation: LightAnimation) -> bool {
+        let Some(slot) = self.slot_mut(handle) else {
+            return false;
+        };
+        if let Some(light) = &mut slot.light {
+            animation.apply(light, 0.0);
+        }
+        slot.animation = Some(animation);
+        slot.animation_time = 0.0;
+        true
+    }
+
+    /// Detaches the light's animation, leaving it as the last frame set it
+    pub fn clear_animation(&mut self, handle: LightHandle) -> Option<LightAnimation> {
+        self.slot_mut(handle)?.animation.take()
+    }
+
+    /// Advances every light animation, disabled lights included, by `delta_time` seconds
+    pub fn update(&mut self, delta_time: f32) {
+        for slot in &mut self.slots {
+            if let (Some(light), Some(animation)) = (&mut slot.light, &slot.animation) {
+                slot.animation_time += delta_time;
+                animation.apply(light, slot.animation_time);
+            }
+        }
+    }
+
+    ///
+    /// Steps debug_mode through: off, each light's contribution in slot order, attenuation,
+    /// normals, and back to off.
+    ///
+    pub fn cycle_debug_mode(&mut self) {
+        let handles: Vec<LightHandle> = self.handles().collect();
+
+        self.debug_mode = match self.debug_mode {
+            None => handles.first().map(|&handle| LightingDebug::LightContribution(handle))
+                .or(Some(LightingDebug::Attenuation)),
+            Some(LightingDebug::LightContribution(current)) => handles
+                .iter()
+                .position(|&handle| handle == current)
+                .and_then(|position| handles.get(position + 1))
+                .map(|&handle| LightingDebug::LightContribution(handle))
+                .or(Some(LightingDebug::Attenuation)),
+            Some(LightingDebug::Attenuation) => Some(LightingDebug::NormalColor),
+            Some(LightingDebug::NormalColor) => None,
+        };
+    }
+
+    /// Handles of every light, enabled or not, in slot order
+    pub fn handles(&self) -> impl Iterator<Item = LightHandle> + '_ {
+        self.slots
+            .iter()
+            .enumerate()
+            .filter(|(_, slot)| slot.light.is_some())
+            .map(|(index, slot)| LightHandle { index, generation: slot.generation })
+    }
+
+    ///
+    /// Switches intensity_mode and rescales every light's intensity to match, so the scene
+    /// looks the same as before: converting to Physical multiplies point, spot and area
+    /// intensities by 4π. Light animations that set intensity still use their old values.
+    ///
+    pub fn set_intensity_mode(&mut self, mode: IntensityMode) {
+        let old_mode = self.intensity_mode;
+        for light in self.slots.iter_mut().filter_map(|slot| slot.light.as_mut()) {
+            light.intensity *= old_mode.scale(light) / mode.scale(light);
+        }
+        self.intensity_mode = mode;
+    }
+
+    /// Replaces the shadow cubes used for shading; lights without one aren't shadowed
+    pub(crate) fn set_point_shadows(&self, maps: Vec<PointShadowMap>) {
+        *self.point_shadows.borrow_mut() = maps;
+    }
+
+    // Enabled lights with the handles they can be looked up by
+    fn active_lights_with_handles(&self) -> impl Iterator<Item = (LightHandle, &Light)> {
+        self.slots
+            .iter()
+            .enumerate()
+            .filter(|(_, slot)| slot.enabled)
+            .filter_map(|(index, slot)| {
+                Some((LightHandle { index, generation: slot.generation }, slot.light.as_ref()?))
+            })
+    }
+
+    /// All lights that are currently enabled
+    pub fn active_lights(&self) -> impl Iterator<Item = &Light> {
+        self.slots
+            .iter()
+            .filter(|slot| slot.enabled)
+            .filter_map(|slot| slot.light.as_ref())
+    }
+
+    // A slot that still holds the light this handle was created for
+    fn slot(&self, handle: LightHandle) -> Option<&LightSlot> {
+        self.slots
+            .get(handle.index)
+            .filter(|slot| slot.generation == handle.generation && slot.light.is_some())
+    }
+
+    fn slot_mut(&mut self, handle: LightHandle) -> Option<&mut LightSlot> {
+        self.slots
+            .get_mut(handle.index)
+            .filter(|slot| slot.generation == handle.generation && slot.light.is_some())
     }
 
     pub fn set_ambient(&mut self, color: Vec3f, intensity: f32) {
@@ -173,41 +1066,111 @@ impl LightingSystem {
         self.ambient_intensity = intensity;
     }
 
-    pub fn calculate_lighting(&self, surface_point: &Vec3f, surface_normal: &Vec3f,
-                              camera_position: &Vec3f, material: &Material) -> Vec3f {
+    ///
+    /// Lit color in linear RGB, scaled by `exposure` and not clamped: bright lights can push
+    /// channels well above 1.0. HDR rendering keeps that range for tonemapping; the LDR path
+    /// clips it when converting to a framebuffer color. Lights whose mask or exclusions leave
+    /// out `receiver` are skipped; LightReceiver::default() is a plain, untextured surface.
+    ///
+    pub fn calculate_lighting(&self, surface_point: &Vec3f, surface_normal: &Vec3f, camera_position: &Vec3f,
+                              material: &Material, receiver: &LightReceiver) -> Vec3f {
+        let colors = material.colors_at(receiver.uv, receiver.vertex_color);
+
         // Ambient component
-        let ambient = self.ambient_color * self.ambient_intensity * material.ambient_factor;
+        let ambient = self.ambient_color * self.ambient_intensity * material.ambient_factor * receiver.occlusion;
 
-        let mut final_color = ambient * material.diffuse_color;
+        let mut final_color = (ambient + receiver.baked_light) * colors.diffuse + colors.emissive;
 
         if surface_normal.length() == 0.0 {
-            return final_color;
+            return final_color * self.exposure;
         }
 
         let view_direction = (*camera_position - *surface_point).normalize();
 
-        // Accumulate lighting from all lights
-        for light in &self.lights {
-            let (diffuse_intensity, specular_intensity) =
-                light.calculate_lighting(surface_point, surface_normal, &view_direction);
+        // Seen from behind, a two-sided surface is lit as its back face
+        let surface_normal = &if material.two_sided && surface_normal.dot(&view_direction) < 0.0 {
+            -*surface_normal
+        } else {
+            *surface_normal
+        };
 
-            if diffuse_intensity > 0.0 || specular_intensity > 0.0 {
-                // Diffuse contribution
-                let diffuse_contribution = light.color * light.intensity * diffuse_intensity;
-                final_color = final_color + (diffuse_contribution * material.diffuse_color);
+        let point_shadows = self.point_shadows.borrow();
+        let visibility = |handle: LightHandle| {
+            point_shadows
+                .iter()
+                .find(|map| map.light == handle)
+                .map_or(1.0, |map| map.visibility(surface_point, surface_normal))
+        };
 
-                // Specular contribution
-                let specular_contribution = light.color * light.intensity * specular_intensity;
-                final_color = final_color + (specular_contribution * material.specular_color);
+        if let Some(debug) = self.debug_mode {
+            return match debug {
+                // Raw values, not exposed, so they read the same whatever the scene's brightness
+                LightingDebug::NormalColor => (*surface_normal + Vec3f::one()) * 0.5,
+                LightingDebug::Attenuation => Vec3f::splat(
+                    self.active_lights()
+                        .filter(|light| light.reaches(receiver))
+                        .filter_map(|light| light.incident(surface_point))
+                        .map(|(_, attenuation)| attenuation)
+                        .sum(),
+                ),
+                LightingDebug::LightContribution(handle) => {
+                    let contribution = self.get(handle)
+                        .filter(|light| self.is_enabled(handle) && light.reaches(receiver))
+                        .map_or(Vec3f::zero(), |light| {
+                            light_contribution(light, surface_point, surface_normal, &view_direction, material, &colors)
+                                * (self.intensity_mode.scale(light) * visibility(handle))
+                        }) * self.exposure;
+                    Vec3f::splat(0.2126 * contribution.x + 0.7152 * contribution.y + 0.0722 * contribution.z)
+                }
+            };
+        }
+
+        // Accumulate lighting from all enabled lights
+        for (handle, light) in self.active_lights_with_handles().filter(|(_, light)| light.reaches(receiver)) {
+            let shadow = visibility(handle);
+            if shadow > 0.0 {
+                final_color = final_color
+                    + light_contribution(light, surface_point, surface_normal, &view_direction, material, &colors)
+                        * (self.intensity_mode.scale(light) * shadow);
             }
         }
 
-        // Clamp to [0, 1] range
-        Vec3f::new(
-            final_color.x.min(1.0).max(0.0),
-            final_color.y.min(1.0).max(0.0),
-            final_color.z.min(1.0).max(0.0),
-        )
+        // Toon rim: darken the silhouette where the surface turns away from the viewer
+        if let ShadingModel::Toon { rim_threshold, .. } = material.shading_model
+            && surface_normal.dot(&view_direction) < rim_threshold {
+            final_color = final_color * TOON_RIM_DARKENING;
+        }
+
+        // After the toon darkening, which would otherwise eat the rim it overlaps
+        if let Some(rim) = &material.rim {
+            final_color = final_color + rim.term(surface_normal.dot(&view_direction));
+        }
+
+        final_color * self.exposure
+    }
+
+    ///
+    /// Eye adaptation step, does nothing unless auto_exposure is set.
+    /// `log_average_luminance` is what the last frame measured (already exposed), so the scene's
+    /// own luminance is that divided by the current exposure. Exposure then moves toward
+    /// key / scene luminance, closing 1 - e^(-dt/τ) of the gap so the speed doesn't depend on frame rate.
+    ///
+    pub fn adapt_exposure(&mut self, log_average_luminance: f32, delta_time: f32) {
+        let Some(auto) = self.auto_exposure else {
+            return;
+        };
+        if log_average_luminance <= 0.0 || delta_time <= 0.0 {
+            return;
+        }
+
+        let scene_luminance = log_average_luminance / self.exposure;
+        let target = (auto.key / scene_luminance).clamp(auto.min_exposure, auto.max_exposure);
+        let blend = if auto.adaptation_time > 0.0 {
+            1.0 - (-delta_time / auto.adaptation_time).exp()
+        } else {
+            1.0
+        };
+        self.exposure += (target - self.exposure) * blend;
     }
 
     pub fn calculate_lighting_u32(&self, surface_point: &Vec3f, surface_normal: &Vec3f,
@@ -223,12 +1186,747 @@ impl LightingSystem {
             32.0
         );
 
-        let lit_color = self.calculate_lighting(surface_point, surface_normal, camera_position, &material);
+        let lit_color = self.calculate_lighting(surface_point, surface_normal, camera_position, &material, &LightReceiver::default());
 
-        let r = (lit_color.x * 255.0) as u32;
-        let g = (lit_color.y * 255.0) as u32;
-        let b = (lit_color.z * 255.0) as u32;
+        let r = (lit_color.x.clamp(0.0, 1.0) * 255.0) as u32;
+        let g = (lit_color.y.clamp(0.0, 1.0) * 255.0) as u32;
+        let b = (lit_color.z.clamp(0.0, 1.0) * 255.0) as u32;
 
         0xFF000000 | (r << 16) | (g << 8) | b
     }
-}
\ No newline at end of file
+}
+
+/// Diffuse plus specular from one light under the material's shading model, before exposure
+/// and taking the light's intensity as is (see IntensityMode::scale)
+fn light_contribution(light: &Light, surface_point: &Vec3f, surface_normal: &Vec3f, view_direction: &Vec3f,
+                      material: &Material, colors: &SurfaceColors) -> Vec3f {
+    match material.shading_model {
+        ShadingModel::BlinnPhong => {
+            return energy_conserving_blinn_phong(light, surface_point, surface_normal, view_direction,
+                                                 material.specular_power, colors);
+        }
+        ShadingModel::Pbr { roughness, metallic } => {
+            return cook_torrance(light, surface_point, surface_normal, view_direction, colors, roughness, metallic);
+        }
+        ShadingModel::LegacyBlinnPhong | ShadingModel::Toon { .. } => {}
+    }
+
+    let (mut diffuse_intensity, mut specular_intensity) =
+        light.calculate_lighting(surface_point, surface_normal, view_direction, material.specular_power);
+
+    if let ShadingModel::Toon { bands, .. } = material.shading_model {
+        diffuse_intensity = quantize(diffuse_intensity, bands);
+        specular_intensity = if specular_intensity > TOON_SPECULAR_THRESHOLD { 1.0 } else { 0.0 };
+    }
+
+    if diffuse_intensity <= 0.0 && specular_intensity <= 0.0 {
+        return Vec3f::zero();
+    }
+
+    // Diffuse contribution
+    let diffuse_contribution = light.color * light.intensity * diffuse_intensity;
+
+    // Specular contribution
+    let specular_contribution = light.color * light.intensity * specular_intensity;
+
+    diffuse_contribution * colors.diffuse + specular_contribution * colors.specular
+}
+
+// Specular above this becomes a full-strength highlight, below it nothing
+const TOON_SPECULAR_THRESHOLD: f32 = 0.5;
+// Color multiplier for the toon rim
+const TOON_RIM_DARKENING: f32 = 0.2;
+
+// Cosine away from an area light's plane below which its emission fades to nothing (about 6°)
+const AREA_LIGHT_EDGE_FADE: f32 = 0.1;
+
+/// Hermite 0 -> 1 as x goes from edge0 to edge1, clamped outside
+fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+    if edge1 <= edge0 {
+        return if x >= edge1 { 1.0 } else { 0.0 };
+    }
+    let t = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+///
+/// N·L for a light of angular radius σ instead of a point: unchanged while the whole light is
+/// above the horizon (cosθ ≥ sinσ), then easing to zero once it has fully set (cosθ = -sinσ).
+/// The quadratic in between meets both ends with matching slope.
+///
+fn horizon_wrapped_cosine(cos_theta: f32, sin_sigma: f32) -> f32 {
+    if cos_theta >= sin_sigma {
+        cos_theta
+    } else if cos_theta <= -sin_sigma {
+        0.0
+    } else {
+        (cos_theta + sin_sigma).powi(2) / (4.0 * sin_sigma)
+    }
+}
+
+///
+/// Snaps a [0, 1] light intensity to `bands` evenly spaced levels, 0 and 1 included
+/// (3 bands gives 0, 0.5 and 1). Intensities above 1 (bright lights) keep their
+/// whole-number part so HDR still gets its range.
+///
+fn quantize(intensity: f32, bands: u32) -> f32 {
+    if intensity >= 1.0 {
+        return intensity.floor();
+    }
+    if bands <= 1 {
+        return if intensity > 0.0 { 1.0 } else { 0.0 };
+    }
+    let level = (intensity * bands as f32).floor();
+    level / (bands - 1) as f32
+}
+
+/// Schlick's approximation: F = f0 + (1 - f0)(1 - cosθ)⁵
+pub fn fresnel_schlick(f0: Vec3f, cos_theta: f32) -> Vec3f {
+    let factor = (1.0 - cos_theta.clamp(0.0, 1.0)).powi(5);
+    f0 + (Vec3f::one() - f0) * factor
+}
+
+///
+/// One light's contribution under ShadingModel::BlinnPhong.
+/// The BRDF is (1 - F) * albedo / π + F * (n + 8) / 8π * (N·H)ⁿ. Light intensity is taken
+/// as irradiance / π, matching the legacy model where a white surface facing a light of
+/// intensity 1 comes out at exactly 1.
+///
+fn energy_conserving_blinn_phong(light: &Light, surface_point: &Vec3f, surface_normal: &Vec3f,
+                                 view_direction: &Vec3f, specular_power: f32, colors: &SurfaceColors) -> Vec3f {
+    let Some(sample) = light.sample(surface_point, surface_normal, view_direction) else {
+        return Vec3f::zero();
+    };
+
+    let n_dot_l = sample.n_dot_l;
+    if n_dot_l <= 0.0 {
+        return Vec3f::zero();
+    }
+    let attenuation = sample.attenuation;
+
+    let half_vector = (sample.specular_direction + *view_direction).normalize();
+    let n_dot_h = surface_normal.dot(&half_vector).max(0.0);
+    let v_dot_h = view_direction.dot(&half_vector).max(0.0);
+
+    let fresnel = fresnel_schlick(colors.f0, v_dot_h);
+    let power = specular_power;
+    let specular_lobe = (power + 8.0) / 8.0 * n_dot_h.powf(power);
+    let diffuse = (Vec3f::one() - fresnel) * colors.diffuse;
+    let specular = fresnel * specular_lobe;
+
+    // The π from the BRDF cancels against intensity = irradiance / π
+    (diffuse + specular) * light.color * (light.intensity * attenuation * n_dot_l)
+}
+
+// Keeps the GGX highlight from collapsing to an infinitely thin spike at roughness 0
+const MIN_ROUGHNESS: f32 = 0.045;
+
+///
+/// GGX / Trowbridge-Reitz normal distribution with alpha = roughness².
+/// D(h) = α² / (π ((N·H)² (α² - 1) + 1)²); integrating D(h)(N·H) over the hemisphere gives 1.
+///
+pub fn ggx_distribution(n_dot_h: f32, roughness: f32) -> f32 {
+    let alpha = roughness.max(MIN_ROUGHNESS).powi(2);
+    let alpha2 = alpha * alpha;
+    let denominator = n_dot_h * n_dot_h * (alpha2 - 1.0) + 1.0;
+    alpha2 / (std::f32::consts::PI * denominator * denominator)
+}
+
+/// Height-correlated Smith visibility term, G / (4 (N·L)(N·V)) folded into one
+pub fn smith_ggx_visibility(n_dot_l: f32, n_dot_v: f32, roughness: f32) -> f32 {
+    let alpha = roughness.max(MIN_ROUGHNESS).powi(2);
+    let alpha2 = alpha * alpha;
+    let lambda_v = n_dot_l * (n_dot_v * n_dot_v * (1.0 - alpha2) + alpha2).sqrt();
+    let lambda_l = n_dot_v * (n_dot_l * n_dot_l * (1.0 - alpha2) + alpha2).sqrt();
+    0.5 / (lambda_v + lambda_l).max(1e-6)
+}
+
+/// One light's contribution under ShadingModel::Pbr, with the same intensity convention
+/// as energy_conserving_blinn_phong
+fn cook_torrance(light: &Light, surface_point: &Vec3f, surface_normal: &Vec3f, view_direction: &Vec3f,
+                 colors: &SurfaceColors, roughness: f32, metallic: f32) -> Vec3f {
+    let Some(sample) = light.sample(surface_point, surface_normal, view_direction) else {
+        return Vec3f::zero();
+    };
+
+    let n_dot_l = sample.n_dot_l;
+    if n_dot_l <= 0.0 {
+        return Vec3f::zero();
+    }
+    let n_dot_v = surface_normal.dot(view_direction).max(1e-4);
+
+    // Same as n_dot_l except for area lights, whose highlight comes from a different spot
+    let specular_n_dot_l = surface_normal.dot(&sample.specular_direction).max(0.0);
+    let half_vector = (sample.specular_direction + *view_direction).normalize();
+    let n_dot_h = surface_normal.dot(&half_vector).max(0.0);
+    let v_dot_h = view_direction.dot(&half_vector).max(0.0);
+
+    let fresnel = fresnel_schlick(colors.f0, v_dot_h);
+    let specular = fresnel
+        * (ggx_distribution(n_dot_h, roughness) * smith_ggx_visibility(specular_n_dot_l, n_dot_v, roughness))
+        * specular_n_dot_l;
+    let diffuse = (Vec3f::one() - fresnel) * colors.diffuse
+        * ((1.0 - metallic) / std::f32::consts::PI * n_dot_l);
+
+    (diffuse + specular) * light.color * (std::f32::consts::PI * light.intensity * sample.attenuation)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn higher_specular_power_tightens_the_highlight() {
+        let light = Light::directional(Vec3f::new(0.0, -1.0, 0.0), Vec3f::one(), 1.0);
+        let point = Vec3f::zero();
+        let normal = Vec3f::up();
+
+        // Viewing straight down the normal puts the half vector on it: the peak
+        let (_, peak_sharp) = light.calculate_lighting(&point, &normal, &Vec3f::up(), 256.0);
+        let (_, peak_broad) = light.calculate_lighting(&point, &normal, &Vec3f::up(), 4.0);
+        assert!((peak_sharp - peak_broad).abs() < 1e-6);
+        assert!((peak_sharp - 1.0).abs() < 1e-6);
+
+        // Tilting the view swings the half vector off the normal
+        let off_axis = Vec3f::new(0.5, 1.0, 0.0).normalize();
+        let (_, sharp) = light.calculate_lighting(&point, &normal, &off_axis, 256.0);
+        let (_, broad) = light.calculate_lighting(&point, &normal, &off_axis, 4.0);
+        assert!(sharp < broad, "256 gave {sharp}, 4 gave {broad}");
+        assert!(broad < 1.0);
+    }
+
+    #[test]
+    fn spot_light_is_full_strength_exactly_on_its_axis() {
+        let direction = Vec3f::new(0.1, -1.0, 0.9);
+        let light = Light::spot_degrees(Vec3f::zero(), direction, Vec3f::one(), 1.0, 10.0, 20.0, 30.0);
+        let on_axis = light.direction * 3.0;
+        // Rounding puts the cosine just past 1, where an acos would have given NaN
+        let cos_angle = on_axis.normalize().dot(&light.direction);
+        assert!(cos_angle > 1.0, "setup should round past 1, got {cos_angle}");
+
+        let (towards_light, attenuation) = light.incident(&on_axis).expect("on-axis point should be lit");
+        let distance_only = light.attenuation.factor(3.0, light.range);
+        assert!(towards_light.x.is_finite() && towards_light.y.is_finite() && towards_light.z.is_finite());
+        assert!(attenuation.is_finite());
+        assert!((attenuation - distance_only).abs() < 1e-5, "cone should not dim the axis: {attenuation} vs {distance_only}");
+    }
+
+    #[test]
+    fn stale_handle_is_rejected_once_its_slot_is_reused() {
+        let mut lighting = LightingSystem::new();
+        let first = lighting.add_light(Light::directional(Vec3f::new(0.0, -1.0, 0.0), Vec3f::one(), 1.0));
+        assert!(lighting.remove_light(first).is_some());
+
+        let second = lighting.add_light(Light::point(Vec3f::zero(), Vec3f::one(), 2.0, 5.0));
+        assert_eq!(second.index, first.index, "the freed slot should be reused");
+
+        assert!(lighting.get(first).is_none());
+        assert!(lighting.get_mut(first).is_none());
+        assert!(!lighting.set_enabled(first, false));
+        assert!(lighting.remove_light(first).is_none());
+        assert!(lighting.is_enabled(second), "the stale handle must not have touched the new light");
+        assert_eq!(lighting.get(second).map(|light| light.intensity), Some(2.0));
+    }
+
+    #[test]
+    fn disabling_the_only_light_leaves_ambient_only() {
+        let material = Material::new(Vec3f::new(0.8, 0.6, 0.4), Vec3f::splat(0.5), 32.0);
+        let (point, normal, camera) = (Vec3f::zero(), Vec3f::up(), Vec3f::new(0.0, 5.0, 0.0));
+
+        let unlit = LightingSystem::new().calculate_lighting(&point, &normal, &camera, &material, &LightReceiver::default());
+
+        let mut lighting = LightingSystem::new();
+        let sun = lighting.add_light(Light::directional(Vec3f::new(0.0, -1.0, 0.0), Vec3f::one(), 1.0));
+        let lit = lighting.calculate_lighting(&point, &normal, &camera, &material, &LightReceiver::default());
+        assert!(lit.x > unlit.x);
+
+        assert!(lighting.set_enabled(sun, false));
+        let disabled = lighting.calculate_lighting(&point, &normal, &camera, &material, &LightReceiver::default());
+        assert!((disabled - unlit).length() < 1e-6, "{disabled:?} vs ambient-only {unlit:?}");
+    }
+
+    #[test]
+    fn texture_maps_multiply_their_colors_only_where_there_is_a_uv() {
+        let mut lighting = LightingSystem::new();
+        lighting.set_ambient(Vec3f::one(), 1.0);
+        lighting.exposure = 1.0;
+        let (point, normal, camera) = (Vec3f::zero(), Vec3f::up(), Vec3f::new(0.0, 5.0, 0.0));
+
+        let (diffuse, emissive) = (Vec3f::new(0.5, 0.5, 0.5), Vec3f::new(0.2, 0.4, 0.6));
+        let (diffuse_texel, emissive_texel) = (Vec3f::new(0.5, 1.0, 0.0), Vec3f::new(1.0, 0.5, 0.25));
+        let plain = Material::new(diffuse, Vec3f::zero(), 32.0).with_emissive(emissive);
+        let mapped = Material::new(diffuse, Vec3f::zero(), 32.0)
+            .with_emissive(emissive)
+            .with_diffuse_map(Arc::new(Texture::solid(diffuse_texel)))
+            .with_emissive_map(Arc::new(Texture::solid(emissive_texel)));
+
+        // No lights, so just ambient * ambient_factor * diffuse + emissive
+        let shade = |material: &Material, uv: Option<Vec2f>| {
+            lighting.calculate_lighting(&point, &normal, &camera, material, &LightReceiver { uv, ..LightReceiver::default() })
+        };
+        let unmapped = diffuse * plain.ambient_factor + emissive;
+        let expected = diffuse * diffuse_texel * mapped.ambient_factor + emissive * emissive_texel;
+
+        assert!((shade(&mapped, Some(Vec2f::new(0.3, 0.7))) - expected).length() < 1e-6);
+        // Without a UV the maps have nowhere to be sampled and the material's own colors stand
+        assert!((shade(&mapped, None) - unmapped).length() < 1e-6);
+        assert!((shade(&plain, Some(Vec2f::new(0.3, 0.7))) - unmapped).length() < 1e-6);
+    }
+
+    #[test]
+    fn ggx_distribution_integrates_to_one_over_the_hemisphere() {
+        // ∫ D(h) (N·H) dω, with dω = 2π sinθ dθ around the normal
+        let steps = 200_000;
+        let step = std::f64::consts::FRAC_PI_2 / steps as f64;
+        for roughness in [0.3, 0.6, 1.0] {
+            let integral: f64 = (0..steps)
+                .map(|i| {
+                    let theta = (i as f64 + 0.5) * step;
+                    let d = ggx_distribution(theta.cos() as f32, roughness) as f64;
+                    d * theta.cos() * theta.sin() * 2.0 * std::f64::consts::PI * step
+                })
+                .sum();
+            assert!((integral - 1.0).abs() < 1e-2, "roughness {roughness} integrated to {integral}");
+        }
+    }
+
+    #[test]
+    fn fully_rough_dielectric_is_close_to_lambert() {
+        let albedo = Vec3f::splat(0.5);
+        let pbr = Material::pbr(albedo, 1.0, 0.0);
+        let lambert = Material::new(albedo, Vec3f::zero(), 1.0);
+        let (point, normal, camera) = (Vec3f::zero(), Vec3f::up(), Vec3f::new(0.0, 5.0, 0.0));
+
+        for degrees in [0.0f32, 30.0, 60.0] {
+            let mut lighting = LightingSystem::new();
+            lighting.set_ambient(Vec3f::zero(), 0.0);
+            let towards_light = Vec3f::new(degrees.to_radians().sin(), degrees.to_radians().cos(), 0.0);
+            lighting.add_light(Light::directional(-towards_light, Vec3f::one(), 1.0));
+
+            let expected = lighting.calculate_lighting(&point, &normal, &camera, &lambert, &LightReceiver::default());
+            let shaded = lighting.calculate_lighting(&point, &normal, &camera, &pbr, &LightReceiver::default());
+            assert!((expected.x - towards_light.y * 0.5).abs() < 1e-5);
+            assert!((shaded.x - expected.x).abs() < 0.05 * expected.x,
+                    "{degrees}°: PBR gave {shaded:?}, Lambert {expected:?}");
+        }
+    }
+
+    #[test]
+    fn fresnel_is_f0_head_on_and_approaches_one_at_grazing_angles() {
+        for f0 in [Vec3f::splat(0.04), Vec3f::new(0.95, 0.64, 0.54), Vec3f::zero()] {
+            let head_on = fresnel_schlick(f0, 1.0);
+            assert!((head_on - f0).length() < 1e-6, "{head_on:?} vs {f0:?}");
+
+            let grazing = fresnel_schlick(f0, 1e-3);
+            assert!((grazing - Vec3f::one()).length() < 0.01, "f0 {f0:?} gave {grazing:?} at grazing");
+            assert!(fresnel_schlick(f0, 0.3).x < grazing.x + 1e-6);
+        }
+    }
+
+    #[test]
+    fn energy_conserving_blinn_phong_never_reflects_more_than_arrives() {
+        // White furnace: a white surface lit by one light of intensity 1, with the reflected
+        // light summed over every view direction in the hemisphere. The output is the BRDF * π
+        // * N·L, so the reflected fraction is ∫ output / (π N·L) cosθ dω. (n + 8) / 8π is an
+        // approximation that runs a few percent over the exact lobe normalization at middling
+        // shininess, which adds up to a fraction of a percent over 1 head on; hence the 1% slack.
+        let (theta_steps, phi_steps) = (256, 256);
+        let (d_theta, d_phi) = (std::f32::consts::FRAC_PI_2 / theta_steps as f32,
+                                2.0 * std::f32::consts::PI / phi_steps as f32);
+        let (point, normal) = (Vec3f::zero(), Vec3f::up());
+
+        for shininess in [1.0, 16.0, 256.0] {
+            let material = Material::new(Vec3f::one(), Vec3f::one(), shininess)
+                .with_shading_model(ShadingModel::BlinnPhong);
+            for light_degrees in [0.0f32, 45.0, 75.0] {
+                let mut lighting = LightingSystem::new();
+                lighting.set_ambient(Vec3f::zero(), 0.0);
+                let towards_light = Vec3f::new(light_degrees.to_radians().sin(), light_degrees.to_radians().cos(), 0.0);
+                lighting.add_light(Light::directional(-towards_light, Vec3f::one(), 1.0));
+
+                let mut reflected = 0.0;
+                for i in 0..theta_steps {
+                    let theta = (i as f32 + 0.5) * d_theta;
+                    for j in 0..phi_steps {
+                        let phi = (j as f32 + 0.5) * d_phi;
+                        let view = Vec3f::new(theta.sin() * phi.cos(), theta.cos(), theta.sin() * phi.sin());
+                        let shaded = lighting.calculate_lighting(&point, &normal, &view, &material, &LightReceiver::default());
+                        reflected += shaded.x / (std::f32::consts::PI * towards_light.y)
+                            * theta.cos() * theta.sin() * d_theta * d_phi;
+                    }
+                }
+                assert!(reflected <= 1.01,
+                        "shininess {shininess}, light at {light_degrees}°: reflected {reflected}");
+            }
+        }
+    }
+
+    #[test]
+    fn each_attenuation_mode_follows_its_curve() {
+        let range = 10.0;
+        let curve = |attenuation: Attenuation, d: f32| match attenuation {
+            Attenuation::InverseSquare { .. } => (1.0 - (d / range).powi(4)).powi(2) / (d * d + 1.0),
+            Attenuation::Linear { .. } => 1.0 - d / range,
+            Attenuation::Custom { .. } => (range - d) / range / (1.0 + 0.1 * d + 0.01 * d * d),
+        };
+
+        for attenuation in [
+            Attenuation::InverseSquare { radius: range },
+            Attenuation::Linear { range },
+            Attenuation::Custom { constant: 1.0, linear: 0.1, quadratic: 0.01 },
+        ] {
+            let mut previous = f32::INFINITY;
+            for distance in [0.0, 0.5, 1.0, 2.0, 5.0, 8.0, 9.5] {
+                let factor = attenuation.factor(distance, range);
+                let expected = curve(attenuation, distance);
+                assert!((factor - expected).abs() < 1e-5, "{attenuation:?} at {distance}: {factor} vs {expected}");
+                assert!(factor < previous, "{attenuation:?} should keep falling, rose at {distance}");
+                previous = factor;
+            }
+            assert!((attenuation.factor(0.0, range) - 1.0).abs() < 1e-6, "{attenuation:?} should start at 1");
+        }
+    }
+
+    #[test]
+    fn each_attenuation_mode_reaches_zero_at_its_range_without_a_jump() {
+        let range = 10.0;
+        for attenuation in [
+            Attenuation::InverseSquare { radius: range },
+            Attenuation::Linear { range },
+            Attenuation::default_custom(),
+        ] {
+            assert_eq!(attenuation.cutoff(range), range);
+            assert_eq!(attenuation.factor(range, range), 0.0, "{attenuation:?} at its range");
+            assert_eq!(attenuation.factor(range + 1.0, range), 0.0, "{attenuation:?} past its range");
+
+            // Just inside the range the light is already all but gone: no pop at the edge
+            let just_inside = attenuation.factor(range - 1e-3, range);
+            assert!(just_inside > 0.0 && just_inside < 1e-3, "{attenuation:?} just inside: {just_inside}");
+        }
+    }
+
+    #[test]
+    fn toon_diffuse_takes_exactly_one_value_per_band() {
+        let mut lighting = LightingSystem::new();
+        lighting.set_ambient(Vec3f::zero(), 0.0);
+        lighting.add_light(Light::directional(Vec3f::new(0.0, -1.0, 0.0), Vec3f::one(), 1.0));
+        // No specular and no rim, so only the banded diffuse is left
+        let material = Material::new(Vec3f::one(), Vec3f::zero(), 32.0)
+            .with_shading_model(ShadingModel::Toon { bands: 3, rim_threshold: 0.0 });
+
+        let mut levels: Vec<f32> = Vec::new();
+        for theta_degrees in 0..=90 {
+            for phi_degrees in (0..360).step_by(30) {
+                let (theta, phi) = ((theta_degrees as f32).to_radians(), (phi_degrees as f32).to_radians());
+                let normal = Vec3f::new(theta.sin() * phi.cos(), theta.cos(), theta.sin() * phi.sin());
+                let shaded = lighting.calculate_lighting(&Vec3f::zero(), &normal, &(normal * 5.0), &material,
+                                                         &LightReceiver::default());
+                if !levels.iter().any(|level| (level - shaded.x).abs() < 1e-6) {
+                    levels.push(shaded.x);
+                }
+            }
+        }
+
+        levels.sort_by(f32::total_cmp);
+        assert_eq!(levels, vec![0.0, 0.5, 1.0]);
+    }
+
+    #[test]
+    fn toon_rim_darkens_only_below_its_threshold() {
+        let mut lighting = LightingSystem::new();
+        lighting.add_light(Light::directional(Vec3f::new(0.0, -1.0, 0.0), Vec3f::one(), 1.0));
+        let toon = |rim_threshold| Material::new(Vec3f::one(), Vec3f::zero(), 32.0)
+            .with_shading_model(ShadingModel::Toon { bands: 3, rim_threshold });
+        let (with_rim, without_rim) = (toon(0.3), toon(0.0));
+
+        for view_degrees in [0.0f32, 45.0, 70.0, 75.0, 80.0, 89.0] {
+            let view = Vec3f::new(view_degrees.to_radians().sin(), view_degrees.to_radians().cos(), 0.0);
+            let shade = |material: &Material| {
+                lighting.calculate_lighting(&Vec3f::zero(), &Vec3f::up(), &(view * 5.0), material,
+                                            &LightReceiver::default())
+            };
+            let (rimmed, plain) = (shade(&with_rim), shade(&without_rim));
+
+            if view.y < 0.3 {
+                assert!((rimmed.x - plain.x * TOON_RIM_DARKENING).abs() < 1e-6, "N·V {}: {rimmed:?}", view.y);
+            } else {
+                assert!((rimmed - plain).length() < 1e-6, "N·V {} should have no rim: {rimmed:?}", view.y);
+            }
+        }
+    }
+
+    #[test]
+    fn doubling_every_light_and_halving_exposure_tonemaps_the_same() {
+        let scene = |scale: f32| {
+            let mut lighting = LightingSystem::new();
+            lighting.set_ambient(Vec3f::new(0.6, 0.7, 1.0), 0.2 * scale);
+            lighting.add_light(Light::directional(Vec3f::new(0.3, -1.0, 0.2), Vec3f::new(1.0, 0.9, 0.8), 3.0 * scale));
+            lighting.add_light(Light::point(Vec3f::new(1.0, 2.0, 0.0), Vec3f::new(1.0, 0.5, 0.2), 8.0 * scale, 10.0));
+            lighting.add_light(Light::spot_degrees(Vec3f::new(-1.0, 3.0, 1.0), Vec3f::new(0.0, -1.0, 0.0),
+                                                   Vec3f::one(), 5.0 * scale, 8.0, 20.0, 35.0));
+            lighting.exposure = 1.0 / scale;
+            lighting
+        };
+        let material = Material::new(Vec3f::new(0.8, 0.6, 0.4), Vec3f::splat(0.5), 32.0);
+        let camera = Vec3f::new(0.0, 4.0, 6.0);
+
+        let render = |lighting: &LightingSystem| {
+            let mut renderer = crate::renderer::Renderer::new(16, 16);
+            renderer.enable_hdr_mode();
+            for y in 0..16 {
+                for x in 0..16 {
+                    let point = Vec3f::new(x as f32 * 0.25 - 2.0, 0.0, y as f32 * 0.25 - 2.0);
+                    let lit = lighting.calculate_lighting(&point, &Vec3f::up(), &camera, &material,
+                                                          &LightReceiver::default());
+                    renderer.set_pixel_hdr(x, y, lit.x, lit.y, lit.z);
+                }
+            }
+            renderer.tonemap_hdr();
+            renderer.get_framebuffer().to_vec()
+        };
+
+        let original = render(&scene(1.0));
+        assert!(original.iter().any(|&pixel| pixel & 0x00FFFFFF != 0), "the scene should be lit");
+        assert_eq!(render(&scene(2.0)), original);
+    }
+
+    #[test]
+    fn kelvin_colors_go_from_warm_through_white_to_blue() {
+        let close = |kelvin: f32, expected: Vec3f| {
+            let color = Light::color_from_kelvin(kelvin);
+            assert!((color - expected).length() < 0.02, "{kelvin}K gave {color:?}, expected about {expected:?}");
+            color
+        };
+
+        let tungsten = close(2700.0, Vec3f::new(1.0, 0.654, 0.343));
+        assert!(tungsten.x > tungsten.y && tungsten.y > tungsten.z, "2700K should be warm: {tungsten:?}");
+
+        let daylight = close(6500.0, Vec3f::new(1.0, 0.996, 0.981));
+        assert!(daylight.x.min(daylight.y).min(daylight.z) > 0.95, "6500K should be near white: {daylight:?}");
+
+        let sky = close(10000.0, Vec3f::new(0.791, 0.855, 1.0));
+        assert!(sky.z > sky.y && sky.y > sky.x, "10000K should be blue-ish: {sky:?}");
+    }
+
+    #[test]
+    fn shrinking_area_light_converges_to_a_point_light() {
+        // Panel two units up facing down (-(right × up) with right = Z, up = X is -Y)
+        let position = Vec3f::new(0.0, 2.0, 0.0);
+        let point_light = Light::point(position, Vec3f::one(), 4.0, 10.0);
+        let mut lighting_point = LightingSystem::new();
+        lighting_point.add_light(point_light);
+        let material = Material::new(Vec3f::splat(0.8), Vec3f::splat(0.5), 16.0);
+        let camera = Vec3f::new(1.0, 3.0, 2.0);
+        let floor = [Vec3f::zero(), Vec3f::new(0.7, 0.0, 0.3), Vec3f::new(-1.0, 0.0, -0.5)];
+
+        let mut previous_error = f32::INFINITY;
+        for size in [1.0, 0.1, 1e-2, 1e-3] {
+            let mut lighting_area = LightingSystem::new();
+            lighting_area.add_light(Light::area(position, Vec3f::new(0.0, 0.0, size), Vec3f::new(size, 0.0, 0.0),
+                                                Vec3f::one(), 4.0, 10.0));
+            let error = floor
+                .iter()
+                .map(|point| {
+                    let shade = |lighting: &LightingSystem| {
+                        lighting.calculate_lighting(point, &Vec3f::up(), &camera, &material, &LightReceiver::default())
+                    };
+                    (shade(&lighting_area) - shade(&lighting_point)).length()
+                })
+                .fold(0.0, f32::max);
+            assert!(error < previous_error, "size {size}: {error} after {previous_error}");
+            previous_error = error;
+        }
+        assert!(previous_error < 2e-3, "a tiny panel should light like a point, off by {previous_error}");
+    }
+
+    #[test]
+    fn nothing_behind_an_area_light_is_lit() {
+        let position = Vec3f::new(0.0, 2.0, 0.0);
+        let area = Light::area(position, Vec3f::new(0.0, 0.0, 1.0), Vec3f::new(1.0, 0.0, 0.0), Vec3f::one(), 4.0, 10.0);
+        let point = Light::point(position, Vec3f::one(), 4.0, 10.0);
+        assert!((area.direction - Vec3f::new(0.0, -1.0, 0.0)).length() < 1e-6);
+
+        // Above the panel, facing down at it: a point light there would light these
+        let ceiling_normal = Vec3f::new(0.0, -1.0, 0.0);
+        for above in [Vec3f::new(0.0, 3.0, 0.0), Vec3f::new(0.4, 2.5, -0.3), Vec3f::new(2.0, 2.01, 0.0)] {
+            let view = (-above).normalize();
+            assert!(point.calculate_lighting(&above, &ceiling_normal, &view, 16.0).0 > 0.0);
+            assert_eq!(area.calculate_lighting(&above, &ceiling_normal, &view, 16.0), (0.0, 0.0), "{above:?}");
+            assert!(area.incident(&above).is_none());
+        }
+    }
+
+    #[test]
+    fn rim_is_zero_facing_the_viewer_and_strongest_side_on() {
+        let rim = RimLight::new(Vec3f::new(0.2, 0.6, 1.0), 2.0, 1.5);
+        assert!(rim.term(1.0).length() < 1e-6, "facing the viewer: {:?}", rim.term(1.0));
+
+        let side_on = rim.term(0.0);
+        assert!((side_on - Vec3f::new(0.2, 0.6, 1.0) * 1.5).length() < 1e-6, "{side_on:?}");
+
+        let mut previous = -1.0;
+        for degrees in (0..=90).step_by(10) {
+            let strength = rim.term((degrees as f32).to_radians().cos()).z;
+            assert!(strength > previous && strength <= side_on.z + 1e-6, "{degrees}°: {strength}");
+            previous = strength;
+        }
+        // Turned further away still counts as the silhouette, never more
+        assert!((rim.term(-0.5) - side_on).length() < 1e-6);
+    }
+
+    #[test]
+    fn excluded_object_gets_only_ambient_from_the_light() {
+        use crate::mesh::Mesh;
+        use crate::scene::GameObject;
+
+        let (player, neighbour) = (GameObject::new(Mesh::create_cube()), GameObject::new(Mesh::create_cube()));
+        let material = Material::new(Vec3f::new(0.8, 0.6, 0.4), Vec3f::splat(0.5), 32.0);
+        let (point, normal, camera) = (Vec3f::zero(), Vec3f::up(), Vec3f::new(0.0, 5.0, 1.0));
+
+        let mut flashlight = Light::point(Vec3f::new(0.0, 2.0, 0.0), Vec3f::one(), 3.0, 10.0);
+        flashlight.exclude(player.id());
+        let mut lighting = LightingSystem::new();
+        lighting.add_light(flashlight);
+
+        let ambient_only = LightingSystem::new().calculate_lighting(&point, &normal, &camera, &material,
+                                                                    &player.light_receiver());
+        let excluded = lighting.calculate_lighting(&point, &normal, &camera, &material, &player.light_receiver());
+        let lit = lighting.calculate_lighting(&point, &normal, &camera, &material, &neighbour.light_receiver());
+
+        assert!((excluded - ambient_only).length() < 1e-6, "{excluded:?} vs ambient {ambient_only:?}");
+        assert!(ambient_only.x > 0.0, "ambient should still reach the excluded object");
+        assert!(lit.x > ambient_only.x + 0.5, "the neighbour should be lit normally: {lit:?}");
+    }
+
+    #[test]
+    fn two_sided_quad_is_lit_the_same_from_either_side() {
+        // A quad in the XY plane facing +Z, lit and seen from the front, then with the light
+        // and camera mirrored through its plane to the back
+        let mirror = |v: Vec3f| Vec3f::new(v.x, v.y, -v.z);
+        let normal = Vec3f::z_axis();
+        let sun_direction = Vec3f::new(0.3, -0.4, -1.0);
+        let camera = Vec3f::new(0.5, 1.0, 4.0);
+        let quad = [Vec3f::new(-1.0, -1.0, 0.0), Vec3f::new(1.0, -1.0, 0.0), Vec3f::new(0.2, 0.7, 0.0)];
+
+        let lit_from = |direction: Vec3f| {
+            let mut lighting = LightingSystem::new();
+            lighting.add_light(Light::directional(direction, Vec3f::one(), 1.0));
+            lighting
+        };
+        let (front, back) = (lit_from(sun_direction), lit_from(mirror(sun_direction)));
+        let two_sided = Material::new(Vec3f::new(0.3, 0.8, 0.2), Vec3f::splat(0.5), 16.0).with_two_sided(true);
+        let one_sided = Material::new(Vec3f::new(0.3, 0.8, 0.2), Vec3f::splat(0.5), 16.0);
+        let ambient_only = LightingSystem::new()
+            .calculate_lighting(&quad[0], &normal, &camera, &two_sided, &LightReceiver::default());
+
+        for point in quad {
+            let seen_from_front = front.calculate_lighting(&point, &normal, &camera, &two_sided, &LightReceiver::default());
+            let seen_from_back = back.calculate_lighting(&point, &normal, &mirror(camera), &two_sided,
+                                                         &LightReceiver::default());
+            assert!(seen_from_front.y > ambient_only.y + 0.1, "front at {point:?}: {seen_from_front:?}");
+            assert!((seen_from_back - seen_from_front).length() < 1e-5,
+                    "back {seen_from_back:?} should mirror front {seen_from_front:?} at {point:?}");
+
+            // Without two_sided the back keeps its front-facing normal and the light misses it
+            let single = back.calculate_lighting(&point, &normal, &mirror(camera), &one_sided, &LightReceiver::default());
+            assert!((single - ambient_only).length() < 1e-6, "one-sided back at {point:?}: {single:?}");
+        }
+    }
+
+    #[test]
+    fn normal_debug_colors_put_each_cube_face_at_a_channel_extreme() {
+        use crate::mesh::Mesh;
+
+        let mut lighting = LightingSystem::new();
+        lighting.add_light(Light::directional(Vec3f::new(0.3, -1.0, 0.2), Vec3f::one(), 2.0));
+        lighting.debug_mode = Some(LightingDebug::NormalColor);
+        let material = Material::default();
+        let cube = Mesh::create_cube();
+
+        let mut extremes = [[false; 2]; 3];
+        for triangle in &cube.triangles {
+            let (center, normal) = (triangle.get_center(&cube), triangle.calculate_normal(&cube));
+            let color = lighting.calculate_lighting(&center, &normal, &(center * 3.0), &material, &LightReceiver::default());
+            let (channels, components) = ([color.x, color.y, color.z], [normal.x, normal.y, normal.z]);
+
+            for axis in 0..3 {
+                if components[axis].abs() > 0.999 {
+                    // The face's own axis: 1 facing +, 0 facing -
+                    let expected = if components[axis] > 0.0 { 1.0 } else { 0.0 };
+                    assert!((channels[axis] - expected).abs() < 1e-5, "normal {normal:?} gave {color:?}");
+                    extremes[axis][expected as usize] = true;
+                } else {
+                    assert!((channels[axis] - 0.5).abs() < 1e-5, "normal {normal:?} gave {color:?}");
+                }
+            }
+        }
+        assert_eq!(extremes, [[true; 2]; 3], "every face direction should show up: ±X red, ±Y green, ±Z blue");
+    }
+
+    #[test]
+    fn vertex_color_modes_ignore_multiply_or_replace_the_diffuse() {
+        let mut lighting = LightingSystem::new();
+        lighting.set_ambient(Vec3f::one(), 1.0);
+        let (point, normal, camera) = (Vec3f::zero(), Vec3f::up(), Vec3f::new(0.0, 5.0, 0.0));
+        let (diffuse, vertex_color) = (Vec3f::new(0.5, 1.0, 0.25), Vec3f::new(1.0, 0.5, 0.2));
+        let receiver = LightReceiver { vertex_color: Some(vertex_color), ..LightReceiver::default() };
+
+        for (mode, expected) in [
+            (VertexColorMode::Ignore, diffuse),
+            (VertexColorMode::Multiply, Vec3f::new(0.5, 0.5, 0.05)),
+            (VertexColorMode::Replace, vertex_color),
+        ] {
+            let material = Material::new(diffuse, Vec3f::zero(), 32.0).with_vertex_color_mode(mode);
+            let colors = material.colors_at(None, Some(vertex_color));
+            assert!((colors.diffuse - expected).length() < 1e-6, "{mode:?}: {:?}", colors.diffuse);
+
+            // No lights, so the output is ambient * ambient_factor * diffuse
+            let shaded = lighting.calculate_lighting(&point, &normal, &camera, &material, &receiver);
+            assert!((shaded - expected * material.ambient_factor).length() < 1e-6, "{mode:?}: {shaded:?}");
+
+            // A mesh without vertex colors leaves every mode on the material's diffuse
+            assert!((material.colors_at(None, None).diffuse - diffuse).length() < 1e-6, "{mode:?} without colors");
+        }
+    }
+
+    #[test]
+    fn point_light_at_distance_one_in_legacy_and_physical_modes() {
+        let material = Material::new(Vec3f::one(), Vec3f::zero(), 32.0);
+        let (point, normal, camera) = (Vec3f::zero(), Vec3f::up(), Vec3f::new(0.0, 5.0, 0.0));
+        let lamp = Light::point(Vec3f::new(0.0, 1.0, 0.0), Vec3f::one(), 2.0, 10.0);
+        let shade = |lighting: &LightingSystem| {
+            lighting.calculate_lighting(&point, &normal, &camera, &material, &LightReceiver::default())
+        };
+        let lit_by = |light: Light, mode: IntensityMode| {
+            let mut lighting = LightingSystem::new();
+            lighting.set_ambient(Vec3f::zero(), 0.0);
+            lighting.intensity_mode = mode;
+            lighting.add_light(light);
+            shade(&lighting)
+        };
+
+        // Legacy: the intensity as is, times the falloff at distance 1, on a surface facing the lamp
+        let legacy = lit_by(lamp.clone(), IntensityMode::Legacy);
+        let falloff = lamp.attenuation.factor(1.0, lamp.range);
+        assert!((legacy.x - 2.0 * falloff).abs() < 1e-5, "{legacy:?}");
+
+        // Physical: the same number is total power, spread over 4π
+        let physical = lit_by(lamp.clone(), IntensityMode::Physical);
+        let four_pi = 4.0 * std::f32::consts::PI;
+        assert!((physical.x - legacy.x / four_pi).abs() < 1e-6, "{physical:?} vs {legacy:?} / 4π");
+        let matched = Light { intensity: 2.0 * four_pi, ..lamp.clone() };
+        assert!((lit_by(matched, IntensityMode::Physical) - legacy).length() < 1e-5);
+
+        // Directional intensity means the same in both
+        let sun = Light::directional(Vec3f::new(0.0, -1.0, 0.0), Vec3f::one(), 0.8);
+        assert!((lit_by(sun.clone(), IntensityMode::Physical) - lit_by(sun, IntensityMode::Legacy)).length() < 1e-6);
+
+        // Converting a scene rescales its lights so it keeps its look
+        let mut lighting = LightingSystem::new();
+        let handle = lighting.add_light(lamp);
+        let before = shade(&lighting);
+        lighting.set_intensity_mode(IntensityMode::Physical);
+        assert!((lighting.get(handle).unwrap().intensity - 2.0 * four_pi).abs() < 1e-4);
+        assert!((shade(&lighting) - before).length() < 1e-5);
+    }
+}
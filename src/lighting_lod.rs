@@ -0,0 +1,188 @@
+use std::collections::HashMap;
+use crate::lighting::{LightReceiver, Material};
+use crate::math::Vec3f;
+use crate::scene::Scene;
+
+/// How finely an object's lighting is evaluated, picked per object by distance
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum LightingLod {
+    /// Once at the object's center, shared by all its triangles
+    PerObject,
+    /// Once per triangle at its center (flat shading)
+    PerTriangle,
+    /// At every pixel, like textured triangles
+    PerPixel,
+}
+
+///
+/// Distances (from the camera to the nearest point of an object's bounding sphere) at which
+/// lighting gets coarser. An object only changes level once it is `hysteresis` past a
+/// threshold, so one sitting right on it doesn't flicker between levels from frame to frame.
+///
+#[derive(Copy, Clone, Debug)]
+pub struct LightingLodPolicy {
+    pub enabled: bool, // False lights everything per triangle
+    pub per_pixel_distance: f32,  // Closer than this is lit per pixel; 0 never is
+    pub per_object_distance: f32, // Further than this is lit once per object
+    pub hysteresis: f32,
+}
+
+impl LightingLodPolicy {
+    pub fn new(per_pixel_distance: f32, per_object_distance: f32) -> Self {
+        Self { per_pixel_distance, per_object_distance, ..Self::default() }
+    }
+
+    pub fn with_hysteresis(mut self, hysteresis: f32) -> Self {
+        self.hysteresis = hysteresis;
+        self
+    }
+
+    /// The level for an object `distance` away that was drawn at `previous` last frame
+    pub fn level(&self, distance: f32, previous: Option<LightingLod>) -> LightingLod {
+        if !self.enabled {
+            return LightingLod::PerTriangle;
+        }
+        // Leaving a level takes going `hysteresis` past its threshold
+        let pixel_edge = if previous == Some(LightingLod::PerPixel) {
+            self.per_pixel_distance + self.hysteresis
+        } else {
+            self.per_pixel_distance - self.hysteresis
+        };
+        let object_edge = if previous == Some(LightingLod::PerObject) {
+            self.per_object_distance - self.hysteresis
+        } else {
+            self.per_object_distance + self.hysteresis
+        };
+
+        if distance > object_edge {
+            LightingLod::PerObject
+        } else if distance < pixel_edge {
+            LightingLod::PerPixel
+        } else {
+            LightingLod::PerTriangle
+        }
+    }
+}
+
+impl Default for LightingLodPolicy {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            per_pixel_distance: 0.0,
+            per_object_distance: 40.0,
+            hysteresis: 1.0,
+        }
+    }
+}
+
+/// Lighting evaluations made during the last Scene::render, by level
+#[derive(Copy, Clone, Debug, Default)]
+pub struct LightingCounts {
+    pub per_object: usize,   // Evaluations shared by a whole object
+    pub per_triangle: usize, // Flat-shaded triangles lit
+    pub per_pixel: usize,    // Triangles handed to the rasterizer to light per pixel
+}
+
+///
+/// How one object is lit this frame. At LightingLod::PerObject lighting is evaluated at the
+/// object's center with its average normal the first time each of its materials comes up,
+/// then reused for every triangle.
+///
+pub(crate) struct ObjectLighting<'a> {
+    pub(crate) lod: LightingLod,
+    center: Vec3f,
+    normal: Vec3f,
+    results: Vec<(&'a Material, Vec3f)>,
+}
+
+impl<'a> ObjectLighting<'a> {
+    pub(crate) fn per_triangle() -> Self {
+        Self { lod: LightingLod::PerTriangle, center: Vec3f::zero(), normal: Vec3f::zero(), results: Vec::new() }
+    }
+
+    pub(crate) fn per_pixel() -> Self {
+        Self { lod: LightingLod::PerPixel, ..Self::per_triangle() }
+    }
+
+    pub(crate) fn per_object(center: Vec3f, normal: Vec3f) -> Self {
+        Self { lod: LightingLod::PerObject, center, normal, results: Vec::new() }
+    }
+
+    /// The shared color for `material`; only meaningful at LightingLod::PerObject
+    pub(crate) fn color_for(&mut self, scene: &Scene, material: &'a Material, receiver: &LightReceiver,
+                            camera_position: Vec3f) -> Vec3f {
+        if let Some((_, color)) = self.results.iter().find(|(seen, _)| std::ptr::eq(*seen, material)) {
+            return *color;
+        }
+        let color = scene.lighting.calculate_lighting(&self.center, &self.normal, &camera_position, material, receiver);
+        scene.count_lighting(|counts| counts.per_object += 1);
+        self.results.push((material, color));
+        color
+    }
+}
+
+impl Scene {
+    ///
+    /// Picks every visible object's lighting level for this frame from its distance to the
+    /// camera and the level it had last frame. Objects that dropped out of view forget theirs.
+    ///
+    pub(crate) fn update_lighting_lods(&mut self) {
+        let previous = std::mem::take(&mut self.lighting_lods);
+        let camera_position = self.camera.position;
+        self.lighting_lods = self.visible_objects
+            .iter()
+            .map(|&index| {
+                let game_object = &self.game_objects[index];
+                let (center, radius) = game_object.get_bounding_sphere();
+                let distance = ((center - camera_position).length() - radius).max(0.0);
+                let id = game_object.id();
+                (id, self.lighting_lod.level(distance, previous.get(&id).copied()))
+            })
+            .collect::<HashMap<_, _>>();
+    }
+
+    /// The level the object at `index` is lit at this frame; PerTriangle when it isn't being drawn
+    pub fn lighting_lod_of(&self, index: usize) -> LightingLod {
+        self.game_objects
+            .get(index)
+            .and_then(|game_object| self.lighting_lods.get(&game_object.id()))
+            .copied()
+            .unwrap_or(LightingLod::PerTriangle)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mesh::Mesh;
+    use crate::renderer::Renderer;
+    use crate::scene::GameObject;
+
+    #[test]
+    fn far_object_is_lit_exactly_once() {
+        let mut scene = Scene::new();
+        scene.camera.far = 500.0;
+        scene.add_game_object(GameObject::new(Mesh::create_cube()).with_position(Vec3f::new(0.0, 0.0, -60.0)));
+        let mut renderer = Renderer::new(160, 120);
+
+        scene.render(&mut renderer);
+
+        assert_eq!(scene.lighting_lod_of(0), LightingLod::PerObject);
+        let counts = scene.stats.lighting;
+        assert!(scene.stats.triangles_drawn > 0, "the cube should be in view");
+        assert_eq!(counts.per_object, 1, "one material, so one evaluation for all its triangles");
+        assert_eq!((counts.per_triangle, counts.per_pixel), (0, 0));
+    }
+
+    #[test]
+    fn level_switches_only_past_the_hysteresis_band() {
+        let policy = LightingLodPolicy::new(5.0, 40.0).with_hysteresis(1.0);
+
+        assert_eq!(policy.level(40.5, None), LightingLod::PerTriangle);
+        assert_eq!(policy.level(41.5, None), LightingLod::PerObject);
+        assert_eq!(policy.level(39.5, Some(LightingLod::PerObject)), LightingLod::PerObject);
+        assert_eq!(policy.level(38.5, Some(LightingLod::PerObject)), LightingLod::PerTriangle);
+        assert_eq!(policy.level(4.5, Some(LightingLod::PerTriangle)), LightingLod::PerTriangle);
+        assert_eq!(policy.level(3.5, None), LightingLod::PerPixel);
+    }
+}
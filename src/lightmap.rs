@@ -0,0 +1,249 @@
+use crate::lighting::{LightHandle, LightReceiver};
+use crate::math::{Vec2f, Vec3f};
+use crate::scene::Scene;
+
+// Lightmap cell size limits per triangle, in texels
+const MIN_CELL_TEXELS: usize = 4;
+const MAX_CELL_TEXELS: usize = 64;
+
+///
+/// Diffuse light baked for one static object: linear RGB irradiance in the same units as
+/// a light's color * intensity * N·L, so shading multiplies it by the surface's diffuse color.
+/// Every triangle gets its own square cell of the atlas; `triangle_uvs` says where its
+/// corners landed.
+///
+pub struct Lightmap {
+    pub width: usize,
+    pub height: usize,
+    pub texels: Vec<Vec3f>,
+    pub triangle_uvs: Vec<[Vec2f; 3]>,   // Indexed like Mesh::triangles
+    pub baked_lights: Vec<LightHandle>, // Excluded from the object at runtime, their light is in here
+}
+
+impl Lightmap {
+    /// Bilinear lookup, clamped to the edges
+    pub fn sample(&self, uv: Vec2f) -> Vec3f {
+        let x = (uv.x * self.width as f32 - 0.5).clamp(0.0, (self.width - 1) as f32);
+        let y = (uv.y * self.height as f32 - 0.5).clamp(0.0, (self.height - 1) as f32);
+        let (x0, y0) = (x as usize, y as usize);
+        let (x1, y1) = ((x0 + 1).min(self.width - 1), (y0 + 1).min(self.height - 1));
+        let (tx, ty) = (x - x0 as f32, y - y0 as f32);
+
+        let texel = |x: usize, y: usize| self.texels[y * self.width + x];
+        let top = texel(x0, y0) * (1.0 - tx) + texel(x1, y0) * tx;
+        let bottom = texel(x0, y1) * (1.0 - tx) + texel(x1, y1) * tx;
+        top * (1.0 - ty) + bottom * ty
+    }
+}
+
+impl Scene {
+    ///
+    /// Bakes the enabled lights into a Lightmap for every object with static_lighting set,
+    /// about `texel_density` texels per world unit along each triangle's longest side.
+    /// Each baked light is then excluded from the object, so it's shaded from the lightmap
+    /// instead; lights added or enabled later still light it dynamically on top.
+    ///
+    /// Lightmaps hold diffuse light only (static objects lose baked lights' highlights) and
+    /// use the objects' current transforms, so bake once they're in place. Baking again
+    /// first gives back the lights the previous bake took.
+    ///
+    pub fn bake_lightmaps(&mut self, texel_density: f32) {
+        for index in 0..self.game_objects.len() {
+            if !self.game_objects[index].static_lighting {
+                continue;
+            }
+
+            let object_id = self.game_objects[index].id();
+            if let Some(previous) = self.game_objects[index].lightmap.take() {
+                for handle in previous.baked_lights {
+                    if let Some(light) = self.lighting.get_mut(handle) {
+                        light.include(object_id);
+                    }
+                }
+            }
+
+            let lightmap = self.bake_object_lightmap(index, texel_density);
+            for &handle in &lightmap.baked_lights {
+                if let Some(light) = self.lighting.get_mut(handle) {
+                    light.exclude(object_id);
+                }
+            }
+            self.game_objects[index].lightmap = Some(lightmap);
+        }
+    }
+
+    fn bake_object_lightmap(&self, index: usize, texel_density: f32) -> Lightmap {
+        let game_object = &self.game_objects[index];
+        let receiver = LightReceiver { occlusion: 1.0, ..game_object.light_receiver() };
+        let lights: Vec<LightHandle> = self.lighting
+            .handles()
+            .filter(|&handle| self.lighting.is_enabled(handle))
+            .filter(|&handle| self.lighting.get(handle).is_some_and(|light| light.reaches(&receiver)))
+            .collect();
+
+        let vertices = game_object.mesh.transform_vertices(&game_object.get_world_matrix());
+        let triangles: Vec<[Vec3f; 3]> = game_object.mesh.triangles
+            .iter()
+            .map(|triangle| triangle.indices.map(|index| vertices[index]))
+            .collect();
+
+        // One square cell per triangle, all the same size, in a near-square grid
+        let longest_edge = triangles
+            .iter()
+            .flat_map(|[v0, v1, v2]| [(*v1 - *v0).length(), (*v2 - *v1).length(), (*v0 - *v2).length()])
+            .fold(0.0f32, f32::max);
+        let cell = ((longest_edge * texel_density).ceil() as usize).clamp(MIN_CELL_TEXELS, MAX_CELL_TEXELS);
+        let columns = (triangles.len() as f32).sqrt().ceil().max(1.0) as usize;
+        let rows = triangles.len().div_ceil(columns).max(1);
+        let (width, height) = (columns * cell, rows * cell);
+
+        let mut texels = vec![Vec3f::zero(); width * height];
+        let mut triangle_uvs = Vec::with_capacity(triangles.len());
+        for (triangle_index, &[v0, v1, v2]) in triangles.iter().enumerate() {
+            let normal = Vec3f::calculate_triangle_normal(v0, v1, v2);
+            let chart = planar_chart(v0, v1, v2, cell);
+            let (cell_x, cell_y) = ((triangle_index % columns) * cell, (triangle_index / columns) * cell);
+
+            triangle_uvs.push(chart.map(|corner| {
+                Vec2f::new((cell_x as f32 + corner.x) / width as f32, (cell_y as f32 + corner.y) / height as f32)
+            }));
+
+            for y in 0..cell {
+                for x in 0..cell {
+                    // Texels past the edges take the nearest point on the triangle, so bilinear
+                    // filtering at the seams doesn't pull in black
+                    let weights = clamped_barycentric(Vec2f::new(x as f32 + 0.5, y as f32 + 0.5), chart);
+                    let point = Vec3f::barycentric_interpolate(weights[0], weights[1], weights[2], v0, v1, v2);
+
+                    let mut irradiance = Vec3f::zero();
+                    for light in lights.iter().filter_map(|&handle| self.lighting.get(handle)) {
+                        if let Some(sample) = light.sample(&point, &normal, &normal) {
+                            irradiance = irradiance
+                                + light.color * (light.intensity * self.lighting.intensity_mode.scale(light)
+                                    * sample.attenuation * sample.n_dot_l.max(0.0));
+                        }
+                    }
+                    texels[(cell_y + y) * width + cell_x + x] = irradiance;
+                }
+            }
+        }
+
+        Lightmap { width, height, texels, triangle_uvs, baked_lights: lights }
+    }
+}
+
+///
+/// Lays a triangle flat in its own plane and scales it to fit a cell of `cell` texels,
+/// keeping its shape and leaving a one-texel border. Returns the corners in texels.
+///
+fn planar_chart(v0: Vec3f, v1: Vec3f, v2: Vec3f, cell: usize) -> [Vec2f; 3] {
+    let edge = v1 - v0;
+    let normal = edge.cross(&(v2 - v0));
+    let axis_u = edge.normalize();
+    let axis_v = normal.cross(&axis_u).normalize();
+
+    let flat = [v0, v1, v2].map(|vertex| {
+        let offset = vertex - v0;
+        Vec2f::new(offset.dot(&axis_u), offset.dot(&axis_v))
+    });
+    let min = Vec2f::new(
+        flat.iter().map(|p| p.x).fold(f32::INFINITY, f32::min),
+        flat.iter().map(|p| p.y).fold(f32::INFINITY, f32::min),
+    );
+    let extent = flat
+        .iter()
+        .map(|p| (p.x - min.x).max(p.y - min.y))
+        .fold(0.0f32, f32::max);
+    let scale = if extent > 0.0 { (cell as f32 - 2.0) / extent } else { 0.0 };
+
+    flat.map(|p| Vec2f::new(1.0 + (p.x - min.x) * scale, 1.0 + (p.y - min.y) * scale))
+}
+
+// Barycentric weights of `point` in the 2D triangle, moved onto the triangle when outside it
+fn clamped_barycentric(point: Vec2f, [a, b, c]: [Vec2f; 3]) -> [f32; 3] {
+    let (ab, ac, ap) = (b - a, c - a, point - a);
+    let denominator = ab.x * ac.y - ab.y * ac.x;
+    if denominator.abs() < 1e-12 {
+        return [1.0, 0.0, 0.0]; // Degenerate, it has no area to light anyway
+    }
+    let wb = (ap.x * ac.y - ap.y * ac.x) / denominator;
+    let wc = (ab.x * ap.y - ab.y * ap.x) / denominator;
+    let weights = [1.0 - wb - wc, wb, wc];
+    if weights.iter().all(|&weight| weight >= 0.0) {
+        return weights;
+    }
+
+    // Outside: nearest point on the closest edge
+    let closest_on = |from: Vec2f, to: Vec2f| {
+        let edge = to - from;
+        let length_squared = edge.dot(&edge);
+        let t = if length_squared > 0.0 { ((point - from).dot(&edge) / length_squared).clamp(0.0, 1.0) } else { 0.0 };
+        (t, (point - (from + edge * t)).length())
+    };
+    let (t_ab, d_ab) = closest_on(a, b);
+    let (t_bc, d_bc) = closest_on(b, c);
+    let (t_ca, d_ca) = closest_on(c, a);
+    if d_ab <= d_bc && d_ab <= d_ca {
+        [1.0 - t_ab, t_ab, 0.0]
+    } else if d_bc <= d_ca {
+        [0.0, 1.0 - t_bc, t_bc]
+    } else {
+        [t_ca, 0.0, 1.0 - t_ca]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lighting::Light;
+    use crate::mesh::Mesh;
+    use crate::scene::GameObject;
+
+    #[test]
+    fn texel_nearest_a_point_light_is_the_brightest() {
+        let mut scene = Scene::new();
+        let mut floor = GameObject::new(Mesh::create_plane(4.0, 4.0, 1, 1));
+        floor.static_lighting = true;
+        assert_eq!(floor.mesh.triangles.len(), 2);
+        scene.add_game_object(floor);
+        let lamp = Vec3f::new(0.6, 1.0, -0.4);
+        scene.lighting.add_light(Light::point(lamp, Vec3f::one(), 3.0, 10.0));
+
+        scene.bake_lightmaps(8.0);
+
+        let floor = &scene.game_objects[0];
+        let lightmap = floor.lightmap.as_ref().expect("the floor is static and should have been baked");
+        let vertices = floor.mesh.transform_vertices(&floor.get_world_matrix());
+
+        // Every texel whose center lies inside a triangle's chart, with its world position
+        let mut texels = Vec::new();
+        for (triangle, uvs) in floor.mesh.triangles.iter().zip(&lightmap.triangle_uvs) {
+            let chart = uvs.map(|uv| Vec2f::new(uv.x * lightmap.width as f32, uv.y * lightmap.height as f32));
+            let [v0, v1, v2] = triangle.indices.map(|index| vertices[index]);
+            for y in 0..lightmap.height {
+                for x in 0..lightmap.width {
+                    let (ab, ac) = (chart[1] - chart[0], chart[2] - chart[0]);
+                    let ap = Vec2f::new(x as f32 + 0.5, y as f32 + 0.5) - chart[0];
+                    let denominator = ab.x * ac.y - ab.y * ac.x;
+                    let wb = (ap.x * ac.y - ap.y * ac.x) / denominator;
+                    let wc = (ab.x * ap.y - ab.y * ap.x) / denominator;
+                    if wb >= 0.0 && wc >= 0.0 && wb + wc <= 1.0 {
+                        let point = Vec3f::barycentric_interpolate(1.0 - wb - wc, wb, wc, v0, v1, v2);
+                        texels.push((point, lightmap.texels[y * lightmap.width + x]));
+                    }
+                }
+            }
+        }
+        assert!(texels.len() > 20, "the charts should cover plenty of texels, got {}", texels.len());
+
+        let brightness = |color: &Vec3f| color.x + color.y + color.z;
+        let (nearest_point, nearest_light) = texels
+            .iter()
+            .min_by(|a, b| (a.0 - lamp).length().total_cmp(&(b.0 - lamp).length()))
+            .unwrap();
+        let brightest = texels.iter().map(|(_, light)| brightness(light)).fold(0.0, f32::max);
+        assert!(brightest > 0.0);
+        assert!(brightness(nearest_light) >= brightest * (1.0 - 1e-5),
+                "texel at {nearest_point:?} got {}, the brightest {brightest}", brightness(nearest_light));
+    }
+}
@@ -6,17 +6,34 @@ use windows::{
     Win32::System::LibraryLoader::GetModuleHandleA,
     Win32::UI::WindowsAndMessaging::*,
 };
-use windows::Win32::Graphics::Gdi::ClientToScreen;
-use Rust_3D_Rasterizer::lighting::Light;
+use windows::Win32::Graphics::Gdi::{ClientToScreen, GetMonitorInfoA, MonitorFromWindow, MONITORINFO, MONITOR_DEFAULTTONEAREST};
+use windows::Win32::UI::Input::KeyboardAndMouse::{SetCapture, ReleaseCapture};
+use windows::Win32::UI::Input::{GetRawInputData, HRAWINPUT, MOUSE_MOVE_ABSOLUTE, RAWINPUT, RAWINPUTHEADER, RID_INPUT, RIM_TYPEMOUSE};
+use std::sync::Arc;
+use Rust_3D_Rasterizer::lighting::{Light, Material};
 use Rust_3D_Rasterizer::math::Vec3f;
 use Rust_3D_Rasterizer::renderer::Renderer;
-use Rust_3D_Rasterizer::scene::Scene;
-use Rust_3D_Rasterizer::input::{InputManager, VK_W, VK_A, VK_S, VK_D, VK_SPACE, VK_LSHIFT};
+use Rust_3D_Rasterizer::scene::{GameObject, Portal, Scene};
+use Rust_3D_Rasterizer::mesh::Mesh;
+use Rust_3D_Rasterizer::texture::Texture;
+use Rust_3D_Rasterizer::skybox::SkyboxRenderer;
+use Rust_3D_Rasterizer::post_process::DepthOfField;
+use Rust_3D_Rasterizer::input::{InputManager, MouseButton, VK_B, VK_P, VK_C, VK_O, VK_L, VK_F, VK_G, VK_H, VK_T, VK_V, VK_X, VK_Y, VK_Z, VK_F10, VK_TAB, VK_OEM_3, VK_DELETE, VK_K, VK_M, VK_LEFT, VK_UP, VK_RIGHT, VK_DOWN, VK_NUMPAD0, VK_NUMPAD9, VK_RETURN};
+use Rust_3D_Rasterizer::camera_controller::CameraController;
+use Rust_3D_Rasterizer::camera::{AspectPolicy, Camera};
+use Rust_3D_Rasterizer::camera_path::CameraPath;
+use Rust_3D_Rasterizer::camera_sequence::{CameraKeyframe, CameraSequence, Ease};
+use Rust_3D_Rasterizer::undo::{DeleteObjectCommand, UndoStack};
 
 struct WindowData {
     renderer: Renderer,
     scene: Scene,
     input: InputManager,
+    controller: CameraController,
+    undo_stack: UndoStack, // Gizmo moves and deletions, undone with Ctrl+Z
+    target_fps: u32,       // Frame timer rate and the input's frame limiter, changed with numpad 0 / 9
+    fullscreen: bool,      // Borderless over the whole monitor, toggled with Alt+Enter
+    windowed_rect: RECT,   // Where the window was before going fullscreen
 }
 
 // tiny helpers to extract x/y from LPARAM (avoids missing GET_X/Y_LPARAM)
@@ -29,9 +46,133 @@ fn lparam_get_y(lp: LPARAM) -> i32 {
     ((lp.0 as u32 >> 16) & 0xFFFF) as i16 as i32
 }
 
+// relative (dx, dy) from a WM_INPUT mouse packet; None for other devices or absolute
+// positions (tablets, remote desktop)
+fn raw_mouse_delta(lparam: LPARAM) -> Option<(i32, i32)> {
+    let mut raw = RAWINPUT::default();
+    let mut size = size_of::<RAWINPUT>() as u32;
+    let read = unsafe {
+        GetRawInputData(
+            HRAWINPUT(lparam.0 as *mut _),
+            RID_INPUT,
+            Some(&mut raw as *mut RAWINPUT as *mut _),
+            &mut size,
+            size_of::<RAWINPUTHEADER>() as u32,
+        )
+    };
+    if read == u32::MAX || raw.header.dwType != RIM_TYPEMOUSE.0 {
+        return None;
+    }
+
+    let mouse = unsafe { raw.data.mouse };
+    if mouse.usFlags.0 & MOUSE_MOVE_ABSOLUTE.0 != 0 {
+        return None;
+    }
+    Some((mouse.lLastX, mouse.lLastY))
+}
+
 // frame timer constants
 const FRAME_TIMER_ID: usize = 1;
-const FRAME_TIMER_MS: u32 = 1;
+const DEFAULT_TARGET_FPS: u32 = 60;
+// Numpad 0 / 9 change the target by this much, within these limits
+const TARGET_FPS_STEP: u32 = 10;
+const MIN_TARGET_FPS: u32 = 10;
+const MAX_TARGET_FPS: u32 = 240;
+
+// (Re)starts the frame timer at `fps` and limits frames to that rate
+unsafe fn apply_target_fps(window: HWND, wd: &mut WindowData, fps: u32) {
+    wd.target_fps = fps;
+    wd.input.set_target_fps(fps);
+    // Setting an existing timer id again just changes its interval
+    unsafe {
+        SetTimer(Option::from(window), FRAME_TIMER_ID, (1000 / fps.max(1)).max(1), None);
+    }
+}
+// Switches between the normal window and a borderless one covering its monitor
+unsafe fn toggle_fullscreen(window: HWND, wd: &mut WindowData) {
+    unsafe {
+        if wd.fullscreen {
+            SetWindowLongPtrA(window, GWL_STYLE, (WS_OVERLAPPEDWINDOW | WS_VISIBLE).0 as isize);
+            let rect = wd.windowed_rect;
+            let _ = SetWindowPos(
+                window, None, rect.left, rect.top, rect.right - rect.left, rect.bottom - rect.top,
+                SWP_NOZORDER | SWP_FRAMECHANGED,
+            );
+        } else {
+            let mut rect = RECT::default();
+            let mut monitor_info = MONITORINFO { cbSize: size_of::<MONITORINFO>() as u32, ..Default::default() };
+            let monitor = MonitorFromWindow(window, MONITOR_DEFAULTTONEAREST);
+            if GetWindowRect(window, &mut rect).is_err() || !GetMonitorInfoA(monitor, &mut monitor_info).as_bool() {
+                return;
+            }
+            wd.windowed_rect = rect;
+
+            let screen = monitor_info.rcMonitor;
+            let (width, height) = (screen.right - screen.left, screen.bottom - screen.top);
+            SetWindowLongPtrA(window, GWL_STYLE, (WS_POPUP | WS_VISIBLE).0 as isize);
+            let _ = SetWindowPos(window, Some(HWND_TOP), screen.left, screen.top, width, height, SWP_FRAMECHANGED);
+            // WM_SIZE does this too, but the next frame shouldn't wait for it
+            wd.renderer.resize(width.max(1) as u32, height.max(1) as u32);
+        }
+        wd.fullscreen = !wd.fullscreen;
+    }
+}
+
+// One-shot toggles, run each frame for the keys pressed since the last (real or replayed)
+fn handle_key_press(window: HWND, wd: &mut WindowData, vk_code: u32, ctrl: bool) {
+    match vk_code {
+        VK_Z if ctrl => { wd.undo_stack.undo(&mut wd.scene); }
+        VK_Y if ctrl => { wd.undo_stack.redo(&mut wd.scene); }
+        VK_NUMPAD0 => {
+            // lower / raise the frame rate cap
+            let fps = wd.target_fps.saturating_sub(TARGET_FPS_STEP).max(MIN_TARGET_FPS);
+            unsafe { apply_target_fps(window, wd, fps) };
+        }
+        VK_NUMPAD9 => {
+            let fps = (wd.target_fps + TARGET_FPS_STEP).min(MAX_TARGET_FPS);
+            unsafe { apply_target_fps(window, wd, fps) };
+        }
+        VK_DELETE => {
+            // delete the selection (undoable)
+            if let Some(index) = wd.scene.selected_object {
+                wd.undo_stack.execute(Box::new(DeleteObjectCommand::new(index)), &mut wd.scene);
+            }
+        }
+        VK_P => wd.scene.toggle_camera_path(),     // camera fly-through
+        VK_C => wd.scene.toggle_camera_sequence(), // keyframed camera shots
+        VK_O => wd.scene.cycle_outline_style(), // cartoon outlines: shells / silhouette edges / off
+        VK_X => wd.controller.toggle_mode(&mut wd.scene.camera), // free-fly / first-person
+        VK_G => wd.scene.show_grid = !wd.scene.show_grid, // XZ reference grid
+        VK_M => wd.scene.show_minimap = !wd.scene.show_minimap, // overhead map, bottom right
+        VK_B => wd.scene.lighting.cycle_debug_mode(), // single light / attenuation / normals
+        VK_T => wd.scene.gizmo.visible = !wd.scene.gizmo.visible, // translate arrows on the selection
+        VK_L => wd.scene.show_light_volumes = !wd.scene.show_light_volumes, // light ranges
+        VK_K => wd.scene.show_light_gizmos = !wd.scene.show_light_gizmos, // draggable light markers
+        VK_F => wd.scene.post_effects_enabled = !wd.scene.post_effects_enabled, // depth of field
+        VK_TAB | VK_OEM_3 => wd.scene.show_hud = !wd.scene.show_hud, // stats overlay
+        VK_F10 => wd.scene.debug_draw_vertices = !wd.scene.debug_draw_vertices, // vertex dots
+        VK_H => {
+            // HDR lighting with tonemapping, or the plain clamped colors
+            if wd.renderer.is_hdr_enabled() {
+                wd.renderer.disable_hdr_mode();
+            } else {
+                wd.renderer.enable_hdr_mode();
+            }
+        }
+        VK_V => {
+            // lock to 16:9 with black bars, or stretch to the window
+            let policy = match wd.scene.camera.aspect_policy {
+                AspectPolicy::Stretch => AspectPolicy::Fixed(16.0 / 9.0),
+                AspectPolicy::Fixed(_) => AspectPolicy::Stretch,
+            };
+            wd.scene.camera.set_aspect_policy(policy);
+        }
+        _ => {}
+    }
+}
+
+// How fast the arrow keys move the selected light, in units per second
+const LIGHT_NUDGE_SPEED: f32 = 2.0;
 
 fn main() -> Result<()> {
     unsafe {
@@ -75,11 +216,71 @@ fn main() -> Result<()> {
         // Create renderer and scene
         let renderer = Renderer::new(800, 600);
         let mut scene = Scene::new();
+        scene.camera = Camera::builder()
+            .position(Vec3f::new(0.0, 0.0, 8.0))
+            .target(Vec3f::zero())
+            .up(Vec3f::up())
+            .fov_degrees(45.0)
+            .near(0.1)
+            .far(100.0)
+            .aspect(800.0 / 600.0)
+            .build()
+            .expect("invalid camera parameters");
+
+        scene.skybox = Some(SkyboxRenderer::default());
 
         // Add multiple cubes with different positions
         scene.add_cube_at(Vec3f::new(-2.0, 0.0, 0.0));
         scene.add_cube_at(Vec3f::new(2.0, 0.0, 0.0));
-        scene.add_cube_at(Vec3f::new(0.0, 2.0, -2.0));
+
+        // Checkered cube up top to show off texture maps
+        let checker = Arc::new(Texture::checkerboard(64, 4, Vec3f::one(), Vec3f::splat(0.15)));
+        let mut textured_cube = GameObject::new(Mesh::create_cube())
+            .with_position(Vec3f::new(0.0, 2.0, -2.0));
+        textured_cube.materials[0] = Material::new(Vec3f::new(0.9, 0.8, 0.6), Vec3f::splat(0.3), 32.0)
+            .with_diffuse_map(checker);
+        scene.add_game_object(textured_cube);
+
+        // Cube with a glowing disc on its top face, from a gradient emissive map
+        let glow = Arc::new(Texture::radial_gradient(64, Vec3f::one(), Vec3f::zero()));
+        let mut glowing_cube = GameObject::new(Mesh::create_cube_with_materials(Some([0, 0, 0, 0, 1, 0])))
+            .with_position(Vec3f::new(4.5, -1.5, -3.0))
+            .with_scale(Vec3f::splat(0.6));
+        glowing_cube.materials = vec![
+            Material::new(Vec3f::splat(0.3), Vec3f::splat(0.2), 16.0),
+            Material::new(Vec3f::splat(0.3), Vec3f::splat(0.2), 16.0)
+                .with_emissive(Vec3f::new(0.2, 1.0, 0.9))
+                .with_emissive_map(glow),
+        ];
+        scene.add_game_object(glowing_cube);
+
+        // PBR sweep behind the cubes: roughness 0 -> 1, dielectric in front, metal behind
+        scene.add_pbr_test_spheres(Vec3f::new(-2.4, -1.5, -5.0));
+
+        // A flat portal below the cubes that shows them from above
+        let portal_frame = GameObject::new(Mesh::create_cube())
+            .with_position(Vec3f::new(0.0, -2.5, 0.0))
+            .with_scale(Vec3f::new(1.5, 1.0, 0.05));
+        let portal_view = Camera::look_at(
+            Vec3f::new(0.0, 9.0, 0.5),
+            Vec3f::zero(),
+            Vec3f::up(),
+        );
+        scene.set_portal(Portal::new(portal_frame, portal_view));
+        scene.debug_cameras.push(portal_view);
+
+        // Depth of field focused on the cubes (toggle with F)
+        scene.camera.focus_distance = 8.0;
+        scene.camera.focus_range = 4.0;
+        scene.add_post_effect(Box::new(DepthOfField::new(4)));
+        scene.post_effects_enabled = false;
+
+        // Scripted event: a sphere pops in behind the cubes after 5 seconds
+        scene.schedule_event(5.0, |scene| {
+            let sphere = GameObject::new(Mesh::create_uv_sphere(1.0, 16, 12))
+                .with_position(Vec3f::new(0.0, -1.0, -3.0));
+            scene.add_game_object(sphere);
+        });
 
         // Add multiple lights for dramatic effect
         scene.add_light(Light::directional(
@@ -98,16 +299,38 @@ fn main() -> Result<()> {
             2.0,
             10.0
         ));
-        scene.add_light(Light::spot(
+        scene.add_light(Light::spot_degrees(
             Vec3f::new(-4.0, 3.0, 4.0),
             Vec3f::new(1.0, -0.5, -1.0).normalize(),
             Vec3f::new(0.9, 0.2, 0.9),
             3.0,
             15.0,
-            std::f32::consts::PI / 6.0,
-            std::f32::consts::PI / 4.0
+            30.0,
+            45.0
         ));
 
+        // Fly-through path around the cubes (toggle with P)
+        scene.camera_path = Some(
+            CameraPath::new(12.0)
+                .with_control_point(Vec3f::new(0.0, 0.0, 8.0), Vec3f::zero())
+                .with_control_point(Vec3f::new(6.0, 2.0, 4.0), Vec3f::new(0.0, 0.5, 0.0))
+                .with_control_point(Vec3f::new(6.0, 4.0, -5.0), Vec3f::new(0.0, 1.0, -1.0))
+                .with_control_point(Vec3f::new(-5.0, 3.0, -5.0), Vec3f::new(0.0, 1.0, -1.0))
+                .with_control_point(Vec3f::new(-6.0, 1.0, 4.0), Vec3f::zero())
+                .with_control_point(Vec3f::new(0.0, 0.0, 8.0), Vec3f::zero())
+        );
+
+        // Keyframed shots with a zoom in the middle (toggle with C)
+        let fov = std::f32::consts::PI / 4.0;
+        scene.camera_sequence = Some(
+            CameraSequence::new()
+                .with_keyframe(CameraKeyframe::new(0.0, Vec3f::new(0.0, 0.0, 8.0), Vec3f::zero(), fov))
+                .with_keyframe(CameraKeyframe::new(3.0, Vec3f::new(5.0, 3.0, 5.0), Vec3f::new(2.0, 0.0, 0.0), fov * 0.5))
+                .with_keyframe(CameraKeyframe::new(6.0, Vec3f::new(-5.0, 4.0, 3.0), Vec3f::new(0.0, 2.0, -2.0), fov)
+                    .with_ease(Ease::Linear))
+                .with_keyframe(CameraKeyframe::new(9.0, Vec3f::new(0.0, 0.0, 8.0), Vec3f::zero(), fov))
+        );
+
         // set up input (attach window handle + sensitivity)
         let mut input = InputManager::new();
         input.set_window_handle(hwnd);
@@ -117,12 +340,18 @@ fn main() -> Result<()> {
             renderer,
             scene,
             input,
+            controller: CameraController::default(),
+            undo_stack: UndoStack::new(),
+            target_fps: DEFAULT_TARGET_FPS,
+            fullscreen: false,
+            windowed_rect: RECT::default(),
         });
 
-        SetWindowLongPtrA(hwnd, GWLP_USERDATA, Box::into_raw(window_data) as isize);
+        let window_data = Box::into_raw(window_data);
+        SetWindowLongPtrA(hwnd, GWLP_USERDATA, window_data as isize);
 
-        // start a fast frame timer to drive updates + repaints
-        SetTimer(Option::from(hwnd), FRAME_TIMER_ID, FRAME_TIMER_MS, None);
+        // start the frame timer that drives updates + repaints
+        apply_target_fps(hwnd, &mut *window_data, DEFAULT_TARGET_FPS);
 
         // Message loop
         let mut msg = MSG::default();
@@ -145,6 +374,31 @@ extern "system" fn wndproc(window: HWND, message: u32, wparam: WPARAM, lparam: L
                 }
                 LRESULT(0)
             }
+            // F10 arrives as a system key (it would otherwise open the window menu)
+            WM_SYSKEYDOWN if wparam.0 as u32 == VK_F10 => {
+                let window_data_ptr = GetWindowLongPtrA(window, GWLP_USERDATA) as *mut WindowData;
+                if !window_data_ptr.is_null() {
+                    (*window_data_ptr).input.on_key_down(VK_F10);
+                }
+                LRESULT(0)
+            }
+            WM_SYSKEYUP if wparam.0 as u32 == VK_F10 => {
+                let window_data_ptr = GetWindowLongPtrA(window, GWLP_USERDATA) as *mut WindowData;
+                if !window_data_ptr.is_null() {
+                    (*window_data_ptr).input.on_key_up(VK_F10);
+                }
+                LRESULT(0)
+            }
+            // Alt+Enter (bit 29 is the Alt flag); handled here so it doesn't flash the window menu
+            WM_SYSKEYDOWN if wparam.0 as u32 == VK_RETURN && lparam.0 & (1 << 29) != 0 => {
+                let window_data_ptr = GetWindowLongPtrA(window, GWLP_USERDATA) as *mut WindowData;
+                if !window_data_ptr.is_null() {
+                    toggle_fullscreen(window, &mut *window_data_ptr);
+                }
+                LRESULT(0)
+            }
+            // The Alt+Enter character that follows would otherwise beep
+            WM_SYSCHAR if wparam.0 as u32 == VK_RETURN => LRESULT(0),
             WM_KEYUP => {
                 let window_data_ptr = GetWindowLongPtrA(window, GWLP_USERDATA) as *mut WindowData;
                 if !window_data_ptr.is_null() {
@@ -153,6 +407,47 @@ extern "system" fn wndproc(window: HWND, message: u32, wparam: WPARAM, lparam: L
                 LRESULT(0)
             }
 
+            // click a light gizmo to drag the light, an arrow to drag the selection along it,
+            // anywhere else to select (only while the cursor is free)
+            WM_LBUTTONDOWN => {
+                let window_data_ptr = GetWindowLongPtrA(window, GWLP_USERDATA) as *mut WindowData;
+                if !window_data_ptr.is_null() {
+                    let wd = &mut *window_data_ptr;
+                    wd.input.on_mouse_button_down(MouseButton::Left);
+                    if !wd.input.is_mouse_captured() {
+                        let x = lparam_get_x(lparam);
+                        let y = lparam_get_y(lparam);
+                        if wd.scene.begin_light_drag(x as f32, y as f32) || wd.scene.begin_gizmo_drag(x as f32, y as f32) {
+                            // keep getting moves (and the button up) if the drag leaves the window
+                            SetCapture(window);
+                        } else {
+                            wd.scene.select_at_pixel(x.max(0) as u32, y.max(0) as u32);
+                        }
+                    }
+                }
+                LRESULT(0)
+            }
+
+            WM_LBUTTONUP => {
+                let window_data_ptr = GetWindowLongPtrA(window, GWLP_USERDATA) as *mut WindowData;
+                if !window_data_ptr.is_null() {
+                    let wd = &mut *window_data_ptr;
+                    wd.input.on_mouse_button_up(MouseButton::Left);
+                    if wd.scene.is_dragging_gizmo() {
+                        if let Some(command) = wd.scene.end_gizmo_drag() {
+                            wd.undo_stack.execute(Box::new(command), &mut wd.scene);
+                        }
+                        let _ = ReleaseCapture();
+                    } else if wd.scene.is_dragging_light() {
+                        if let Some(command) = wd.scene.end_light_drag() {
+                            wd.undo_stack.execute(Box::new(command), &mut wd.scene);
+                        }
+                        let _ = ReleaseCapture();
+                    }
+                }
+                LRESULT(0)
+            }
+
             // relative mouse movement + recenter when captured
             WM_MOUSEMOVE => {
                 let window_data_ptr = GetWindowLongPtrA(window, GWLP_USERDATA) as *mut WindowData;
@@ -178,11 +473,37 @@ extern "system" fn wndproc(window: HWND, message: u32, wparam: WPARAM, lparam: L
                                 SetCursorPos(p.x, p.y);
                             }
                         }
+                    } else if wd.scene.is_dragging_gizmo() {
+                        wd.scene.drag_gizmo(lparam_get_x(lparam) as f32, lparam_get_y(lparam) as f32);
+                    } else if wd.scene.is_dragging_light() {
+                        wd.scene.drag_light(lparam_get_x(lparam) as f32, lparam_get_y(lparam) as f32);
                     }
                 }
                 LRESULT(0)
             }
 
+            // raw mouse deltas while captured (registered by InputManager::toggle_mouse_capture)
+            WM_INPUT => {
+                let window_data_ptr = GetWindowLongPtrA(window, GWLP_USERDATA) as *mut WindowData;
+                if !window_data_ptr.is_null()
+                    && let Some((dx, dy)) = raw_mouse_delta(lparam) {
+                    (*window_data_ptr).input.on_raw_input(dx, dy);
+                }
+                // DefWindowProc frees the input buffer
+                DefWindowProcA(window, message, wparam, lparam)
+            }
+
+            // keep the framebuffer the same size as the client area
+            WM_SIZE => {
+                let window_data_ptr = GetWindowLongPtrA(window, GWLP_USERDATA) as *mut WindowData;
+                if !window_data_ptr.is_null() {
+                    let width = lparam_get_x(lparam).max(1) as u32;
+                    let height = lparam_get_y(lparam).max(1) as u32;
+                    (*window_data_ptr).renderer.resize(width, height);
+                }
+                LRESULT(0)
+            }
+
             // frame tick — update input, move camera, update scene, then repaint
             WM_TIMER => {
                 if wparam.0 == FRAME_TIMER_ID {
@@ -190,52 +511,40 @@ extern "system" fn wndproc(window: HWND, message: u32, wparam: WPARAM, lparam: L
                     if !window_data_ptr.is_null() {
                         let wd = &mut *window_data_ptr;
 
+                        // timer ticks can come early; wait for a whole frame interval
+                        if !wd.input.is_frame_due() {
+                            return LRESULT(0);
+                        }
+
                         // compute delta time
                         wd.input.update();
                         let dt = wd.input.get_delta_time();
-
-                        // WASD + up/down (units/second)
-                        let speed = 3.5_f32;
-                        if wd.input.is_key_pressed(VK_W) {
-                            wd.scene.camera.move_forward(speed * dt);
+                        for (vk_code, ctrl) in wd.input.take_key_presses() {
+                            handle_key_press(window, wd, vk_code, ctrl);
                         }
-                        if wd.input.is_key_pressed(VK_S) {
-                            wd.scene.camera.move_forward(-speed * dt);
-                        }
-                        if wd.input.is_key_pressed(VK_A) {
-                            wd.scene.camera.move_right(-speed * dt);
-                        }
-                        if wd.input.is_key_pressed(VK_D) {
-                            wd.scene.camera.move_right(speed * dt);
-                        }
-                        if wd.input.is_key_pressed(VK_SPACE) {
-                            wd.scene.camera.move_up(speed * dt);
-                        }
-                        if wd.input.is_key_pressed(VK_LSHIFT) {
-                            wd.scene.camera.move_up(-speed * dt);
+
+                        // WASD + mouse-look through the active controller, disabled while a path/sequence drives the camera
+                        if !wd.scene.is_camera_scripted() {
+                            let position = wd.scene.camera.position;
+                            let ground = wd.scene.ground_height_at(position.x, position.z);
+                            wd.controller.update(&mut wd.scene.camera, &mut wd.input, dt, ground);
+                        } else {
+                            // drop mouse movement so the view doesn't jump when the path finishes
+                            let _ = wd.input.get_mouse_delta();
                         }
 
-                        // mouse-look (in radians), using your camera API
-                        if wd.input.is_mouse_captured() {
-                            let md = wd.input.get_mouse_delta(); // scaled by sensitivity
-                            let yaw_delta = md.x * 0.002;
-                            let pitch_delta = -md.y * 0.002;
-
-                            let fwd = wd.scene.camera.get_forward_vector();
-                            let dist = 1.0;
-                            let mut yaw = fwd.z.atan2(fwd.x);
-                            let mut pitch = (fwd.y / fwd.length()).asin();
-
-                            let half_pi = std::f32::consts::FRAC_PI_2;
-                            yaw += yaw_delta;
-                            pitch = (pitch + pitch_delta).clamp(-half_pi + 0.001, half_pi - 0.001);
-
-                            let new_dir = Vec3f::new(
-                                dist * pitch.cos() * yaw.cos(),
-                                dist * pitch.sin(),
-                                dist * pitch.cos() * yaw.sin(),
-                            );
-                            wd.scene.camera.look_in_direction(new_dir);
+                        // arrow keys move the selected light across the view
+                        if wd.scene.selected_light.is_some() {
+                            let axis = |negative: u32, positive: u32| {
+                                match (wd.input.is_key_pressed(negative), wd.input.is_key_pressed(positive)) {
+                                    (false, true) => 1.0,
+                                    (true, false) => -1.0,
+                                    _ => 0.0,
+                                }
+                            };
+                            let step = LIGHT_NUDGE_SPEED * dt;
+                            let (right, up) = (axis(VK_LEFT, VK_RIGHT) * step, axis(VK_DOWN, VK_UP) * step);
+                            wd.scene.nudge_selected_light(right, up);
                         }
 
                         // animate scene (rotations etc.)
@@ -0,0 +1,108 @@
+use std::collections::HashMap;
+use crate::lighting::Material;
+
+/// A material in a MaterialRegistry; stays valid for as long as the registry lives
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct MaterialHandle(usize);
+
+///
+/// Named materials shared between objects. Objects point at them through
+/// GameObject::shared_materials, so editing one here changes every object using it
+/// from the next frame on.
+///
+pub struct MaterialRegistry {
+    materials: Vec<Material>,
+    names: HashMap<String, MaterialHandle>,
+}
+
+impl MaterialRegistry {
+    pub fn new() -> Self {
+        Self { materials: Vec::new(), names: HashMap::new() }
+    }
+
+    /// Adds a material under `name`. Registering a name again replaces its material and keeps the handle.
+    pub fn register(&mut self, name: &str, material: Material) -> MaterialHandle {
+        if let Some(&handle) = self.names.get(name) {
+            self.materials[handle.0] = material;
+            return handle;
+        }
+        let handle = MaterialHandle(self.materials.len());
+        self.materials.push(material);
+        self.names.insert(name.to_string(), handle);
+        handle
+    }
+
+    pub fn find(&self, name: &str) -> Option<MaterialHandle> {
+        self.names.get(name).copied()
+    }
+
+    /// The name `handle` was registered under
+    pub fn name_of(&self, handle: MaterialHandle) -> Option<&str> {
+        self.names.iter().find(|&(_, &named)| named == handle).map(|(name, _)| name.as_str())
+    }
+
+    pub fn get(&self, handle: MaterialHandle) -> Option<&Material> {
+        self.materials.get(handle.0)
+    }
+
+    pub fn get_mut(&mut self, handle: MaterialHandle) -> Option<&mut Material> {
+        self.materials.get_mut(handle.0)
+    }
+
+    pub fn len(&self) -> usize {
+        self.materials.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.materials.is_empty()
+    }
+}
+
+impl Default for MaterialRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::Vec3f;
+    use crate::mesh::{Mesh, Triangle};
+    use crate::scene::GameObject;
+
+    #[test]
+    fn one_registry_edit_recolors_every_object_sharing_the_material() {
+        let mut registry = MaterialRegistry::new();
+        let stone = registry.register("stone", Material::new(Vec3f::splat(0.5), Vec3f::zero(), 8.0));
+
+        let wall = GameObject::new(Mesh::create_cube()).with_shared_material(stone);
+        let floor = GameObject::new(Mesh::create_cube()).with_shared_material(stone);
+        let mut crate_box = GameObject::new(Mesh::create_cube());
+        crate_box.materials[0] = Material::new(Vec3f::new(0.6, 0.4, 0.2), Vec3f::zero(), 8.0);
+        let triangle = Triangle::new(0, 1, 2, 0xFFFFFFFF);
+
+        registry.get_mut(stone).unwrap().diffuse_color = Vec3f::new(0.2, 0.3, 0.9);
+
+        for object in [&wall, &floor] {
+            let diffuse = object.material_for(&triangle, &registry).diffuse_color;
+            assert!((diffuse - Vec3f::new(0.2, 0.3, 0.9)).length() < 1e-6, "{diffuse:?}");
+        }
+        // Objects with their own material don't see the registry at all
+        let local = crate_box.material_for(&triangle, &registry).diffuse_color;
+        assert!((local - Vec3f::new(0.6, 0.4, 0.2)).length() < 1e-6, "{local:?}");
+    }
+
+    #[test]
+    fn registering_a_name_again_keeps_its_handle() {
+        let mut registry = MaterialRegistry::new();
+        let first = registry.register("glass", Material::default().with_alpha(0.5));
+        let again = registry.register("glass", Material::default().with_alpha(0.25));
+
+        assert_eq!(first, again);
+        assert_eq!(registry.len(), 1);
+        assert_eq!(registry.find("glass"), Some(first));
+        assert_eq!(registry.name_of(first), Some("glass"));
+        assert_eq!(registry.get(first).map(|material| material.alpha), Some(0.25));
+    }
+}
@@ -1,6 +1,7 @@
 use crate::math::vec3::Vec3f;
 use crate::math::vec4::Vec4f;
 
+#[derive(Copy, Clone, Debug)]
 pub struct Mat4x4 {
     // Store as 16 f32 values
     pub m: [f32; 16]
@@ -149,6 +150,58 @@ impl Mat4x4 {
     /// But this translation matrix in itself, don't do much, all we need it for is to initialize it, and then multiply to translate.
     /// So it can move objects, but also the camera etc.
     ///
+    pub fn transpose(&self) -> Mat4x4 {
+        let mut result = Mat4x4::new([0.0; 16]);
+        for row in 0..4 {
+            for col in 0..4 {
+                result.set(col, row, self.get(row, col));
+            }
+        }
+        result
+    }
+
+    /// Whether every element is within `epsilon` of the identity's
+    pub fn is_identity(&self, epsilon: f32) -> bool {
+        let identity = Mat4x4::identity();
+        self.m.iter().zip(&identity.m).all(|(a, b)| (a - b).abs() <= epsilon)
+    }
+
+    ///
+    /// Whether the upper 3x3 (the rotation part) has orthonormal rows and columns, to within
+    /// `epsilon`: R * R^T is the identity. Translation is ignored, so a rigid transform like a
+    /// view matrix passes; any scaling fails.
+    ///
+    pub fn is_orthogonal(&self, epsilon: f32) -> bool {
+        let mut rotation = *self;
+        for i in 0..3 {
+            rotation.set(i, 3, 0.0);
+            rotation.set(3, i, 0.0);
+        }
+        rotation.set(3, 3, 1.0);
+        rotation.multiply(&rotation.transpose()).is_identity(epsilon)
+    }
+
+    ///
+    /// The nearest thing to this matrix with an orthonormal rotation part, by Gram-Schmidt
+    /// over the three basis vectors (columns): x is normalized, y has its x component taken
+    /// out, z its x and y. For cleaning up rotations that have drifted after many
+    /// multiplications. Scaling is lost; translation and the bottom row are kept.
+    ///
+    pub fn orthogonalize(&self) -> Mat4x4 {
+        let column = |col: usize| Vec3f::new(self.get(0, col), self.get(1, col), self.get(2, col));
+        let x = column(0).normalize();
+        let y = (column(1) - x * x.dot(&column(1))).normalize();
+        let z = (column(2) - x * x.dot(&column(2)) - y * y.dot(&column(2))).normalize();
+
+        let mut result = *self;
+        for (col, axis) in [x, y, z].into_iter().enumerate() {
+            result.set(0, col, axis.x);
+            result.set(1, col, axis.y);
+            result.set(2, col, axis.z);
+        }
+        result
+    }
+
     pub fn translation(x: f32, y: f32, z: f32) -> Mat4x4 {
         Mat4x4::new([
             1.0, 0.0, 0.0, x,
@@ -311,6 +364,38 @@ impl Mat4x4 {
         result.to_vec3f()  // Convert back to Vec3f
     }
 
+    ///
+    /// multiply_point over a whole buffer: `out` is cleared and refilled, reusing its
+    /// allocation. The 16 floats are read once up front instead of through get() per vertex.
+    ///
+    pub fn multiply_batch_points(&self, vertices: &[Vec3f], out: &mut Vec<Vec3f>) {
+        let [m00, m01, m02, m03, m10, m11, m12, m13, m20, m21, m22, m23, m30, m31, m32, m33] = self.m;
+        out.clear();
+        out.reserve(vertices.len());
+        for v in vertices {
+            let x = m00 * v.x + m01 * v.y + m02 * v.z + m03;
+            let y = m10 * v.x + m11 * v.y + m12 * v.z + m13;
+            let z = m20 * v.x + m21 * v.y + m22 * v.z + m23;
+            let w = m30 * v.x + m31 * v.y + m32 * v.z + m33;
+            // Same divide as Vec4f::to_vec3f
+            out.push(if w != 0.0 && w != 1.0 { Vec3f::new(x / w, y / w, z / w) } else { Vec3f::new(x, y, z) });
+        }
+    }
+
+    /// multiply_vector over a whole buffer, like multiply_batch_points
+    pub fn multiply_batch_vectors(&self, vectors: &[Vec3f], out: &mut Vec<Vec3f>) {
+        let [m00, m01, m02, _, m10, m11, m12, _, m20, m21, m22, _, m30, m31, m32, _] = self.m;
+        out.clear();
+        out.reserve(vectors.len());
+        for v in vectors {
+            let x = m00 * v.x + m01 * v.y + m02 * v.z;
+            let y = m10 * v.x + m11 * v.y + m12 * v.z;
+            let z = m20 * v.x + m21 * v.y + m22 * v.z;
+            let w = m30 * v.x + m31 * v.y + m32 * v.z;
+            out.push(if w != 0.0 && w != 1.0 { Vec3f::new(x / w, y / w, z / w) } else { Vec3f::new(x, y, z) });
+        }
+    }
+
     pub fn look_at(eye: Vec3f, target: Vec3f, up: Vec3f) -> Mat4x4 {
         // Step 1: Calculate forward vector (direction camera is looking)
         let forward = (target - eye).normalize();
@@ -331,6 +416,12 @@ impl Mat4x4 {
         ])
     }
 
+    ///
+    /// Same as multiply_point, but keeps the full homogeneous result instead of dividing by w.
+    /// For a projection matrix that's the clip-space position: w carries the view depth, so
+    /// its sign says whether the point was in front of the camera, which is lost after the divide.
+    /// Use Vec4f::perspective_divide to get to NDC.
+    ///
     pub fn multiply_point_4d(&self, point: &Vec3f) -> Vec4f {
         let vector_4d = Vec4f::from_point(point);
         let mut result = Vec4f::new(0.0, 0.0, 0.0, 0.0);
@@ -2,9 +2,11 @@ pub mod vec2;
 pub mod vec3;
 pub mod vec4;
 pub mod matrix;
+pub mod quat;
 
 // Re-export for convenience
 pub use vec2::Vec2f;
 pub use vec3::Vec3f;
 pub use vec4::Vec4f;
-pub use matrix::Mat4x4;
\ No newline at end of file
+pub use matrix::Mat4x4;
+pub use quat::Quat;
\ No newline at end of file
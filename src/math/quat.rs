@@ -0,0 +1,185 @@
+use std::ops::Mul;
+use crate::math::matrix::Mat4x4;
+use crate::math::vec3::Vec3f;
+
+///
+/// Unit quaternion for rotations.
+/// Euler angles follow the engine's yaw-pitch-roll convention: yaw around Y, then pitch
+/// around X, then roll around Z (R = Ry * Rx * Rz), all in radians.
+///
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Quat {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+    pub w: f32,
+}
+
+impl Quat {
+    pub fn new(x: f32, y: f32, z: f32, w: f32) -> Quat {
+        Quat { x, y, z, w }
+    }
+
+    pub fn identity() -> Quat {
+        Quat::new(0.0, 0.0, 0.0, 1.0)
+    }
+
+    pub fn from_axis_angle(axis: Vec3f, angle: f32) -> Quat {
+        let axis = axis.normalize();
+        let (sin_half, cos_half) = (angle * 0.5).sin_cos();
+        Quat::new(axis.x * sin_half, axis.y * sin_half, axis.z * sin_half, cos_half)
+    }
+
+    pub fn from_euler(yaw: f32, pitch: f32, roll: f32) -> Quat {
+        Quat::from_axis_angle(Vec3f::y_axis(), yaw)
+            * Quat::from_axis_angle(Vec3f::x_axis(), pitch)
+            * Quat::from_axis_angle(Vec3f::z_axis(), roll)
+    }
+
+    /// Rotation part of a matrix (the upper 3x3 must be a pure rotation)
+    pub fn from_rotation_matrix(m: &Mat4x4) -> Quat {
+        let trace = m.get(0, 0) + m.get(1, 1) + m.get(2, 2);
+
+        // Pick the largest of w, x, y, z to divide by, for numerical stability
+        let q = if trace > 0.0 {
+            let s = (trace + 1.0).sqrt() * 2.0; // 4w
+            Quat::new(
+                (m.get(2, 1) - m.get(1, 2)) / s,
+                (m.get(0, 2) - m.get(2, 0)) / s,
+                (m.get(1, 0) - m.get(0, 1)) / s,
+                0.25 * s,
+            )
+        } else if m.get(0, 0) > m.get(1, 1) && m.get(0, 0) > m.get(2, 2) {
+            let s = (1.0 + m.get(0, 0) - m.get(1, 1) - m.get(2, 2)).sqrt() * 2.0; // 4x
+            Quat::new(
+                0.25 * s,
+                (m.get(0, 1) + m.get(1, 0)) / s,
+                (m.get(0, 2) + m.get(2, 0)) / s,
+                (m.get(2, 1) - m.get(1, 2)) / s,
+            )
+        } else if m.get(1, 1) > m.get(2, 2) {
+            let s = (1.0 + m.get(1, 1) - m.get(0, 0) - m.get(2, 2)).sqrt() * 2.0; // 4y
+            Quat::new(
+                (m.get(0, 1) + m.get(1, 0)) / s,
+                0.25 * s,
+                (m.get(1, 2) + m.get(2, 1)) / s,
+                (m.get(0, 2) - m.get(2, 0)) / s,
+            )
+        } else {
+            let s = (1.0 + m.get(2, 2) - m.get(0, 0) - m.get(1, 1)).sqrt() * 2.0; // 4z
+            Quat::new(
+                (m.get(0, 2) + m.get(2, 0)) / s,
+                (m.get(1, 2) + m.get(2, 1)) / s,
+                0.25 * s,
+                (m.get(1, 0) - m.get(0, 1)) / s,
+            )
+        };
+
+        q.normalize()
+    }
+
+    pub fn length(&self) -> f32 {
+        (self.x * self.x + self.y * self.y + self.z * self.z + self.w * self.w).sqrt()
+    }
+
+    pub fn normalize(&self) -> Quat {
+        let len = self.length();
+        if len == 0.0 {
+            return Quat::identity();
+        }
+        Quat::new(self.x / len, self.y / len, self.z / len, self.w / len)
+    }
+
+    pub fn conjugate(&self) -> Quat {
+        Quat::new(-self.x, -self.y, -self.z, self.w)
+    }
+
+    pub fn rotate_vector(&self, v: &Vec3f) -> Vec3f {
+        // v' = v + 2w(q x v) + 2(q x (q x v))
+        let q = Vec3f::new(self.x, self.y, self.z);
+        let t = q.cross(v) * 2.0;
+        *v + t * self.w + q.cross(&t)
+    }
+
+    pub fn to_matrix(&self) -> Mat4x4 {
+        let Quat { x, y, z, w } = *self;
+        Mat4x4::new([
+            1.0 - 2.0 * (y * y + z * z), 2.0 * (x * y - w * z),       2.0 * (x * z + w * y),       0.0,
+            2.0 * (x * y + w * z),       1.0 - 2.0 * (x * x + z * z), 2.0 * (y * z - w * x),       0.0,
+            2.0 * (x * z - w * y),       2.0 * (y * z + w * x),       1.0 - 2.0 * (x * x + y * y), 0.0,
+            0.0,                         0.0,                         0.0,                         1.0,
+        ])
+    }
+
+    ///
+    /// Back to (yaw, pitch, roll) in radians, the inverse of from_euler.
+    /// With R = Ry * Rx * Rz: R[1][2] = -sin(pitch), R[0][2] / R[2][2] give yaw and
+    /// R[1][0] / R[1][1] give roll. At pitch = ±90° yaw and roll spin the same axis
+    /// (gimbal lock), so roll is reported as 0 and yaw takes the whole turn.
+    ///
+    pub fn to_euler_yaw_pitch_roll(&self) -> (f32, f32, f32) {
+        let Quat { x, y, z, w } = self.normalize();
+
+        let sin_pitch = (2.0 * (w * x - y * z)).clamp(-1.0, 1.0);
+        let pitch = sin_pitch.asin();
+
+        if sin_pitch.abs() > 0.9999 {
+            let yaw = (-2.0 * (x * z - w * y)).atan2(1.0 - 2.0 * (y * y + z * z));
+            return (yaw, pitch, 0.0);
+        }
+
+        let yaw = (2.0 * (x * z + w * y)).atan2(1.0 - 2.0 * (x * x + y * y));
+        let roll = (2.0 * (x * y + w * z)).atan2(1.0 - 2.0 * (x * x + z * z));
+        (yaw, pitch, roll)
+    }
+
+    pub fn to_euler_degrees_yaw_pitch_roll(&self) -> (f32, f32, f32) {
+        let (yaw, pitch, roll) = self.to_euler_yaw_pitch_roll();
+        (yaw.to_degrees(), pitch.to_degrees(), roll.to_degrees())
+    }
+}
+
+impl Mul for Quat {
+    type Output = Quat;
+    fn mul(self, other: Quat) -> Quat {
+        Quat::new(
+            self.w * other.x + self.x * other.w + self.y * other.z - self.z * other.y,
+            self.w * other.y - self.x * other.z + self.y * other.w + self.z * other.x,
+            self.w * other.z + self.x * other.y - self.y * other.x + self.z * other.w,
+            self.w * other.w - self.x * other.x - self.y * other.y - self.z * other.z,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn euler_angles_and_matrix_round_trip() {
+        let (yaw, pitch, roll) = (0.3, 0.5, 0.1);
+        let q = Quat::from_euler(yaw, pitch, roll);
+
+        let (back_yaw, back_pitch, back_roll) = q.to_euler_yaw_pitch_roll();
+        assert!((back_yaw - yaw).abs() < 1e-5, "yaw {back_yaw}");
+        assert!((back_pitch - pitch).abs() < 1e-5, "pitch {back_pitch}");
+        assert!((back_roll - roll).abs() < 1e-5, "roll {back_roll}");
+
+        // Same rotation as composing the axis matrices in yaw-pitch-roll order
+        let expected = Mat4x4::rotation_y(yaw)
+            .multiply(&Mat4x4::rotation_x(pitch))
+            .multiply(&Mat4x4::rotation_z(roll));
+        let matrix = q.to_matrix();
+        for row in 0..4 {
+            for col in 0..4 {
+                assert!((matrix.get(row, col) - expected.get(row, col)).abs() < 1e-5,
+                        "[{row}][{col}]: {} vs {}", matrix.get(row, col), expected.get(row, col));
+            }
+        }
+
+        // And the matrix leads back to the same quaternion (up to sign)
+        let from_matrix = Quat::from_rotation_matrix(&matrix);
+        let dot = q.x * from_matrix.x + q.y * from_matrix.y + q.z * from_matrix.z + q.w * from_matrix.w;
+        assert!((dot.abs() - 1.0).abs() < 1e-5, "{from_matrix:?} vs {q:?}");
+    }
+}
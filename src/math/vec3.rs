@@ -44,11 +44,64 @@ impl Vec3f {
         edge1.cross(&edge2).normalize()
     }
 
+    ///
+    /// Barycentric coordinates of `p` in triangle (a, b, c): each weight is the area of the
+    /// sub-triangle opposite that corner over the whole triangle's area, negative when `p` is
+    /// on the far side of the opposite edge. All three are >= 0 exactly when `p` is inside
+    /// (after projecting onto the triangle's plane). Degenerate triangles give (1, 0, 0).
+    ///
+    pub fn barycentric(p: Vec3f, a: Vec3f, b: Vec3f, c: Vec3f) -> (f32, f32, f32) {
+        let normal = (b - a).cross(&(c - a));
+        let area = normal.dot(&normal); // Twice the area, squared; the signed sub-areas below are over the same normal
+        if area <= f32::EPSILON * f32::EPSILON {
+            return (1.0, 0.0, 0.0);
+        }
+        let w0 = (c - b).cross(&(p - b)).dot(&normal) / area;
+        let w1 = (a - c).cross(&(p - c)).dot(&normal) / area;
+        (w0, w1, 1.0 - w0 - w1)
+    }
+
+    /// The point (or attribute) with barycentric weights (b0, b1, b2) over v0, v1, v2
+    pub fn barycentric_interpolate(b0: f32, b1: f32, b2: f32, v0: Vec3f, v1: Vec3f, v2: Vec3f) -> Vec3f {
+        v0 * b0 + v1 * b1 + v2 * b2
+    }
+
+    /// Each component clamped to [0, 1], with NaN going to 0 (f32::clamp would pass it through)
+    pub fn saturate(&self) -> Vec3f {
+        let channel = |v: f32| if v.is_nan() { 0.0 } else { v.clamp(0.0, 1.0) };
+        Vec3f::new(channel(self.x), channel(self.y), channel(self.z))
+    }
+
     /// Zero vector
     pub fn zero() -> Vec3f {
         Vec3f::new(0.0, 0.0, 0.0)
     }
 
+    /// All components set to one (e.g. the identity scale)
+    pub fn one() -> Vec3f {
+        Vec3f::new(1.0, 1.0, 1.0)
+    }
+
+    /// All components set to the same value, e.g. Vec3f::splat(2.0) for a uniform scale
+    pub fn splat(v: f32) -> Vec3f {
+        Vec3f::new(v, v, v)
+    }
+
+    /// Unit X axis (1, 0, 0)
+    pub fn x_axis() -> Vec3f {
+        Vec3f::new(1.0, 0.0, 0.0)
+    }
+
+    /// Unit Y axis (0, 1, 0)
+    pub fn y_axis() -> Vec3f {
+        Vec3f::new(0.0, 1.0, 0.0)
+    }
+
+    /// Unit Z axis (0, 0, 1)
+    pub fn z_axis() -> Vec3f {
+        Vec3f::new(0.0, 0.0, 1.0)
+    }
+
     /// Unit vector pointing up
     pub fn up() -> Vec3f {
         Vec3f::new(0.0, 1.0, 0.0)
@@ -1,6 +1,7 @@
 use std::ops::{Add, Sub, Mul, Div, Neg};
 use crate::math::vec3::Vec3f;
 
+#[derive(Copy, Clone, Debug)]
 pub struct Vec4f {
     pub x: f32,
     pub y: f32,
@@ -39,6 +40,14 @@ impl Vec4f {
         Vec4f::new(vec3.x, vec3.y, vec3.z, 0.0)
     }
 
+    /// Clip space to NDC; None at or behind the eye (w <= 0), where the divide would flip or blow up
+    pub fn perspective_divide(&self) -> Option<Vec3f> {
+        if self.w <= 0.0 {
+            return None;
+        }
+        Some(Vec3f::new(self.x / self.w, self.y / self.w, self.z / self.w))
+    }
+
     pub fn to_vec3f(&self) -> Vec3f {
         if self.w != 0.0 && self.w != 1.0 {
             Vec3f::new(self.x / self.w, self.y / self.w, self.z / self.w)
@@ -1,10 +1,15 @@
-use crate::math::Vec3f;
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::ops::RangeBounds;
+use crate::coordinate_system::CoordinateSystem;
+use crate::math::{Vec2f, Vec3f, Vec4f};
 
 #[derive(Copy, Clone)]
 pub struct Triangle {
     pub indices: [usize; 3],  // Indices into vertex array
     pub color: u32,
     pub material_id: Option<usize>, // Index into materials array
+    pub uvs: Option<[Vec2f; 3]>,    // Texture coordinates per corner, None = untextured
 }
 
 impl Triangle {
@@ -13,6 +18,7 @@ impl Triangle {
             indices: [i0, i1, i2],
             color,
             material_id: None,
+            uvs: None,
         }
     }
 
@@ -21,9 +27,15 @@ impl Triangle {
             indices: [i0, i1, i2],
             color,
             material_id: Some(material_id),
+            uvs: None,
         }
     }
 
+    pub fn with_uvs(mut self, uv0: Vec2f, uv1: Vec2f, uv2: Vec2f) -> Self {
+        self.uvs = Some([uv0, uv1, uv2]);
+        self
+    }
+
     pub fn get_vertices(&self, mesh: &Mesh) -> (Vec3f, Vec3f, Vec3f) {
         (
             mesh.vertices[self.indices[0]],
@@ -48,8 +60,14 @@ impl Triangle {
 }
 
 pub struct Mesh {
-    pub vertices: Vec<Vec3f>,
+    pub vertices: Vec<Vec3f>, // After moving these directly, call mark_vertices_dirty()
     pub triangles: Vec<Triangle>,
+    pub vertex_ao: Vec<f32>, // Baked ambient occlusion per vertex (1 = open, 0 = buried); empty = not baked
+    pub vertex_colors: Vec<Vec3f>, // Linear RGB per vertex, applied per Material::use_vertex_color; empty = none
+    pub normals: Vec<Vec3f>, // Unit normal per vertex for smooth shading; empty = flat, with face normals
+    pub uvs: Vec<Vec2f>, // Texture coordinates per vertex, used where a triangle has none of its own; empty = none
+    pub tangents: Vec<Vec4f>, // Per vertex, xyz along +U and w = ±1 handedness, from compute_tangents; empty = none
+    bounding_sphere: Cell<Option<(Vec3f, f32)>>, // Cached by bounding_sphere(); None = needs computing
 }
 
 impl Mesh {
@@ -57,19 +75,310 @@ impl Mesh {
         Self {
             vertices: Vec::new(),
             triangles: Vec::new(),
+            vertex_ao: Vec::new(),
+            vertex_colors: Vec::new(),
+            normals: Vec::new(),
+            uvs: Vec::new(),
+            tangents: Vec::new(),
+            bounding_sphere: Cell::new(None),
+        }
+    }
+
+    /// Baked occlusion at the triangle's corners, all 1.0 when nothing has been baked
+    pub fn triangle_ao(&self, triangle: &Triangle) -> [f32; 3] {
+        if self.vertex_ao.len() != self.vertices.len() {
+            return [1.0; 3];
+        }
+        triangle.indices.map(|index| self.vertex_ao[index])
+    }
+
+    /// The triangle's corner colors, None when the mesh has no color for every vertex
+    pub fn triangle_vertex_colors(&self, triangle: &Triangle) -> Option<[&Vec3f; 3]> {
+        if self.vertex_colors.len() != self.vertices.len() {
+            return None;
+        }
+        Some(triangle.indices.map(|index| &self.vertex_colors[index]))
+    }
+
+    ///
+    /// The triangle's corner UVs: its own when it has them, else the mesh's per-vertex UVs
+    /// when there's one for every vertex, else None (textured materials then use their
+    /// plain colors).
+    ///
+    pub fn triangle_uvs(&self, triangle: &Triangle) -> Option<[Vec2f; 3]> {
+        if triangle.uvs.is_some() || self.uvs.is_empty() || self.uvs.len() != self.vertices.len() {
+            return triangle.uvs;
+        }
+        Some(triangle.indices.map(|index| self.uvs[index]))
+    }
+
+    /// The stored normals when there's one for every vertex, else None (shade flat)
+    pub fn vertex_normals(&self) -> Option<&[Vec3f]> {
+        (!self.normals.is_empty() && self.normals.len() == self.vertices.len()).then_some(&self.normals[..])
+    }
+
+    ///
+    /// Fills `normals` for smooth shading. Each triangle corner gets the area-weighted average
+    /// of the faces around its vertex that are within `angle_threshold_degrees` of its own
+    /// face, so edges sharper than that stay hard. A vertex whose corners come out with
+    /// different normals is split into one copy per normal (baked AO, vertex colors and UVs
+    /// go with it), which is how a cube at 30 degrees keeps its creases and a sphere doesn't.
+    ///
+    pub fn compute_smooth_normals(&mut self, angle_threshold_degrees: f32) {
+        let cos_threshold = angle_threshold_degrees.to_radians().cos();
+        // Unnormalized cross products: their length is twice the face area, which does the weighting
+        let face_normals: Vec<Vec3f> = self.triangles
+            .iter()
+            .map(|triangle| {
+                let (v0, v1, v2) = triangle.get_vertices(self);
+                (v1 - v0).cross(&(v2 - v0))
+            })
+            .collect();
+        let mut faces_at = vec![Vec::new(); self.vertices.len()];
+        for (face, triangle) in self.triangles.iter().enumerate() {
+            for &index in &triangle.indices {
+                faces_at[index].push(face);
+            }
+        }
+
+        let has_ao = self.vertex_ao.len() == self.vertices.len();
+        let has_colors = self.vertex_colors.len() == self.vertices.len();
+        let has_uvs = self.uvs.len() == self.vertices.len();
+        let (old_vertices, old_ao, old_colors, old_uvs) = (
+            std::mem::take(&mut self.vertices),
+            std::mem::take(&mut self.vertex_ao),
+            std::mem::take(&mut self.vertex_colors),
+            std::mem::take(&mut self.uvs),
+        );
+        self.normals.clear();
+        self.tangents.clear(); // Built on the old normals; compute_tangents again if needed
+
+        // Summing the same faces in the same order gives the same bits, so equal normals share a vertex
+        let mut split: HashMap<(usize, [u32; 3]), usize> = HashMap::new();
+        for face in 0..self.triangles.len() {
+            let own = face_normals[face].normalize();
+            for corner in 0..3 {
+                let index = self.triangles[face].indices[corner];
+                let normal = faces_at[index]
+                    .iter()
+                    .map(|&other| face_normals[other])
+                    .filter(|other| other.normalize().dot(&own) >= cos_threshold)
+                    .fold(Vec3f::zero(), |sum, other| sum + other)
+                    .normalize();
+                let key = (index, [normal.x.to_bits(), normal.y.to_bits(), normal.z.to_bits()]);
+                let new_index = *split.entry(key).or_insert_with(|| {
+                    self.vertices.push(old_vertices[index]);
+                    self.normals.push(normal);
+                    if has_ao {
+                        self.vertex_ao.push(old_ao[index]);
+                    }
+                    if has_colors {
+                        self.vertex_colors.push(old_colors[index]);
+                    }
+                    if has_uvs {
+                        self.uvs.push(old_uvs[index]);
+                    }
+                    self.vertices.len() - 1
+                });
+                self.triangles[face].indices[corner] = new_index;
+            }
+        }
+    }
+
+    /// Per-vertex normals: the average of the face normals around each vertex
+    pub fn compute_vertex_normals(&self) -> Vec<Vec3f> {
+        let mut normals = vec![Vec3f::zero(); self.vertices.len()];
+        for triangle in &self.triangles {
+            let normal = triangle.calculate_normal(self);
+            for &index in &triangle.indices {
+                normals[index] = normals[index] + normal;
+            }
+        }
+        normals.iter().map(|normal| normal.normalize()).collect()
+    }
+
+    ///
+    /// Edges between a triangle facing along `view_dir` and one facing against it, which is
+    /// where the surface turns away from a viewer looking in that direction (in the mesh's own
+    /// space). Returned as vertex index pairs, low index first, sorted.
+    /// Edges with only one triangle, or more than two, are left out.
+    ///
+    pub fn compute_silhouette_edges(&self, view_dir: Vec3f) -> Vec<(usize, usize)> {
+        let mut edges: HashMap<(usize, usize), Vec<usize>> = HashMap::new();
+        for (index, triangle) in self.triangles.iter().enumerate() {
+            for corner in 0..3 {
+                let (a, b) = (triangle.indices[corner], triangle.indices[(corner + 1) % 3]);
+                if a != b {
+                    edges.entry((a.min(b), a.max(b))).or_default().push(index);
+                }
+            }
+        }
+
+        let faces_viewer = |index: usize| self.triangles[index].calculate_normal(self).dot(&view_dir) < 0.0;
+        let mut silhouette: Vec<(usize, usize)> = edges
+            .into_iter()
+            .filter(|(_, users)| matches!(users[..], [first, second] if faces_viewer(first) != faces_viewer(second)))
+            .map(|(edge, _)| edge)
+            .collect();
+        silhouette.sort_unstable();
+        silhouette
+    }
+
+    ///
+    /// A sphere around every vertex (center, radius), by Ritter's algorithm: within a few
+    /// percent of the smallest one, in linear time. Cached until the vertices change through
+    /// Mesh's own methods or mark_vertices_dirty. An empty mesh gives a zero sphere at the origin.
+    ///
+    pub fn bounding_sphere(&self) -> (Vec3f, f32) {
+        if let Some(sphere) = self.bounding_sphere.get() {
+            return sphere;
         }
+        let sphere = ritter_sphere(&self.vertices);
+        self.bounding_sphere.set(Some(sphere));
+        sphere
+    }
+
+    /// Drops the cached bounding sphere; needed after writing `vertices` directly
+    pub fn mark_vertices_dirty(&self) {
+        self.bounding_sphere.set(None);
     }
 
     pub fn add_vertex(&mut self, vertex: Vec3f) -> usize {
+        self.mark_vertices_dirty();
         self.vertices.push(vertex);
         self.vertices.len() - 1
     }
 
+    ///
+    /// Adds a vertex with all its attributes. Every attribute array only counts when it has
+    /// one entry per vertex, so build a mesh with either this or add_vertex, not both.
+    ///
+    pub fn add_vertex_full(&mut self, position: Vec3f, normal: Vec3f, uv: Vec2f, color: Vec3f) -> usize {
+        self.normals.push(normal);
+        self.uvs.push(uv);
+        self.vertex_colors.push(color);
+        self.add_vertex(position)
+    }
+
     pub fn add_triangle(&mut self, triangle: Triangle) {
         self.triangles.push(triangle);
     }
 
+    ///
+    /// Re-expresses the mesh, given in `from`'s coordinates, in `to`'s, e.g. a Z-up Blender
+    /// export for a Y-up scene. When that mirrors, every triangle's winding is reversed
+    /// (UVs and all) so faces still point out.
+    ///
+    pub fn convert_coordinate_system(&mut self, from: CoordinateSystem, to: CoordinateSystem) {
+        if from == to {
+            return;
+        }
+        for vertex in self.vertices.iter_mut().chain(&mut self.normals) {
+            *vertex = to.convert_from(from, *vertex);
+        }
+        let mirrors = to.mirrors(from);
+        for tangent in &mut self.tangents {
+            let direction = to.convert_from(from, Vec3f::new(tangent.x, tangent.y, tangent.z));
+            let w = if mirrors { -tangent.w } else { tangent.w };
+            *tangent = Vec4f::new(direction.x, direction.y, direction.z, w);
+        }
+        self.mark_vertices_dirty();
+        if mirrors {
+            self.flip_winding();
+        }
+    }
+
+    ///
+    /// Appends `other`'s vertices and triangles, with its triangle indices moved past this
+    /// mesh's vertices. Per-vertex normals, UVs and colors survive when both meshes have them
+    /// (or one has no vertices yet); baked AO is padded with 1.0 (open) for the side without.
+    ///
+    pub fn merge(&mut self, other: &Mesh) {
+        self.merge_with_material_offset(other, 0);
+    }
+
+    /// merge, with `other`'s material slots moved up by `material_offset` to follow this mesh's object's slots
+    pub fn merge_with_material_offset(&mut self, other: &Mesh, material_offset: usize) {
+        let (own_count, other_count) = (self.vertices.len(), other.vertices.len());
+        merge_attribute(&mut self.vertex_ao, own_count, &other.vertex_ao, other_count, Some(1.0));
+        merge_attribute(&mut self.vertex_colors, own_count, &other.vertex_colors, other_count, None);
+        merge_attribute(&mut self.normals, own_count, &other.normals, other_count, None);
+        merge_attribute(&mut self.uvs, own_count, &other.uvs, other_count, None);
+        merge_attribute(&mut self.tangents, own_count, &other.tangents, other_count, None);
+
+        self.vertices.extend_from_slice(&other.vertices);
+        self.mark_vertices_dirty();
+        self.triangles.extend(other.triangles.iter().map(|triangle| Triangle {
+            indices: triangle.indices.map(|index| index + own_count),
+            material_id: match material_offset {
+                0 => triangle.material_id,
+                offset => Some(triangle.material_id.unwrap_or(0) + offset),
+            },
+            ..*triangle
+        }));
+    }
+
+    ///
+    /// Applies `matrix` to the vertices in place, and its inverse transpose to the per-vertex
+    /// normals so they stay perpendicular under non-uniform scale. A matrix that mirrors
+    /// reverses every triangle's winding so faces still point out.
+    ///
+    pub fn bake_transform(&mut self, matrix: &crate::math::Mat4x4) {
+        for vertex in &mut self.vertices {
+            *vertex = matrix.multiply_point(vertex);
+        }
+        self.mark_vertices_dirty();
+        if let Some(normal_matrix) = matrix.inverse().map(|inverse| inverse.transpose()) {
+            for normal in &mut self.normals {
+                *normal = normal_matrix.multiply_vector(normal).normalize();
+            }
+        }
+
+        let [x, y, z] = [0, 1, 2].map(|col| {
+            let [cx, cy, cz, _] = matrix.get_col(col);
+            Vec3f::new(cx, cy, cz)
+        });
+        let mirrors = x.cross(&y).dot(&z) < 0.0;
+        // Tangents follow the surface like edges do, so they take the matrix itself
+        for tangent in &mut self.tangents {
+            let direction = matrix.multiply_vector(&Vec3f::new(tangent.x, tangent.y, tangent.z)).normalize();
+            let w = if mirrors { -tangent.w } else { tangent.w };
+            *tangent = Vec4f::new(direction.x, direction.y, direction.z, w);
+        }
+        if mirrors {
+            self.flip_winding();
+        }
+    }
+
+    /// The mesh, built Y-up right-handed like everything from create_*, in `system`'s coordinates
+    pub fn with_coordinate_system(mut self, system: CoordinateSystem) -> Self {
+        self.convert_coordinate_system(CoordinateSystem::YUpRightHanded, system);
+        self
+    }
+
+    /// Points the triangles at indices in `range` at material slot `slot`; indices past the end are skipped
+    pub fn set_material_for_triangles(&mut self, range: impl RangeBounds<usize>, slot: usize) {
+        for (_, triangle) in self.triangles.iter_mut().enumerate().filter(|(index, _)| range.contains(index)) {
+            triangle.material_id = Some(slot);
+        }
+    }
+
+    /// Points every triangle `predicate` accepts at material slot `slot`
+    pub fn set_material_where(&mut self, mut predicate: impl FnMut(&Triangle) -> bool, slot: usize) {
+        for triangle in self.triangles.iter_mut().filter(|triangle| predicate(triangle)) {
+            triangle.material_id = Some(slot);
+        }
+    }
+
     pub fn create_cube() -> Self {
+        Self::create_cube_with_materials(None)
+    }
+
+    ///
+    /// A cube whose faces use the given material slots, in the order front (+Z), back (-Z),
+    /// left (-X), right (+X), top (+Y), bottom (-Y). With None every face uses slot 0.
+    ///
+    pub fn create_cube_with_materials(face_materials: Option<[usize; 6]>) -> Self {
         let mut mesh = Self::new();
 
         // Add vertices - proper winding for outward-facing normals
@@ -115,10 +424,90 @@ impl Mesh {
             Triangle::new(1, 0, 4, 0xFF00FFFF),
         ];
 
-        for triangle in triangles {
+        // Every face is two triangles (a, b, c) and (c, d, a) over its corners
+        // bottom-left, bottom-right, top-right, top-left as seen from outside,
+        // so each face gets the whole texture the right way up
+        let bottom_left = Vec2f::new(0.0, 1.0);
+        let bottom_right = Vec2f::new(1.0, 1.0);
+        let top_right = Vec2f::new(1.0, 0.0);
+        let top_left = Vec2f::new(0.0, 0.0);
+
+        for (index, triangle) in triangles.into_iter().enumerate() {
+            let triangle = if index % 2 == 0 {
+                triangle.with_uvs(bottom_left, bottom_right, top_right)
+            } else {
+                triangle.with_uvs(top_right, top_left, bottom_left)
+            };
             mesh.add_triangle(triangle);
         }
 
+        if let Some(face_materials) = face_materials {
+            for (face, slot) in face_materials.into_iter().enumerate() {
+                mesh.set_material_for_triangles(face * 2..face * 2 + 2, slot);
+            }
+        }
+
+        mesh
+    }
+
+    ///
+    /// Flat grid in the XZ plane, centered on the origin and facing +Y, split into
+    /// subdivisions_x by subdivisions_z cells of two triangles. Vertices are shared between
+    /// cells. UVs span [0, 1] over the whole plane, with v = 0 along the -Z edge; they're
+    /// stored per vertex as well as per triangle.
+    ///
+    pub fn create_plane(width: f32, depth: f32, subdivisions_x: usize, subdivisions_z: usize) -> Self {
+        Self::create_plane_with_tiling(width, depth, subdivisions_x, subdivisions_z, None)
+    }
+
+    ///
+    /// create_plane with UVs repeating every `tile_size` world units when it's given, so a
+    /// texture keeps the same scale whatever the plane's size.
+    ///
+    pub fn create_plane_with_tiling(width: f32, depth: f32, subdivisions_x: usize, subdivisions_z: usize,
+                                    tile_size: Option<f32>) -> Self {
+        let mut mesh = Self::new();
+        let (columns, rows) = (subdivisions_x.max(1), subdivisions_z.max(1));
+        let uv_scale = match tile_size {
+            Some(tile_size) if tile_size > 0.0 => Vec2f::new(width / tile_size, depth / tile_size),
+            _ => Vec2f::new(1.0, 1.0),
+        };
+
+        let uv = |column: usize, row: usize| {
+            Vec2f::new(column as f32 / columns as f32 * uv_scale.x, row as f32 / rows as f32 * uv_scale.y)
+        };
+        for row in 0..=rows {
+            let z = depth * (row as f32 / rows as f32 - 0.5);
+            for column in 0..=columns {
+                let x = width * (column as f32 / columns as f32 - 0.5);
+                mesh.add_vertex(Vec3f::new(x, 0.0, z));
+                mesh.uvs.push(uv(column, row));
+            }
+        }
+
+        let index = |column: usize, row: usize| row * (columns + 1) + column;
+        let color = 0xFFFFFFFF;
+
+        for row in 0..rows {
+            for column in 0..columns {
+                // Corners as seen from above, with -Z at the top: a bottom-left, then anticlockwise
+                let (a, b, c, d) = (
+                    (column, row + 1),
+                    (column + 1, row + 1),
+                    (column + 1, row),
+                    (column, row),
+                );
+                mesh.add_triangle(
+                    Triangle::new(index(a.0, a.1), index(b.0, b.1), index(c.0, c.1), color)
+                        .with_uvs(uv(a.0, a.1), uv(b.0, b.1), uv(c.0, c.1)),
+                );
+                mesh.add_triangle(
+                    Triangle::new(index(c.0, c.1), index(d.0, d.1), index(a.0, a.1), color)
+                        .with_uvs(uv(c.0, c.1), uv(d.0, d.1), uv(a.0, a.1)),
+                );
+            }
+        }
+
         mesh
     }
 
@@ -135,6 +524,179 @@ impl Mesh {
         mesh
     }
 
+    ///
+    /// Sphere of `radius` around the origin, with vertices shared between neighbouring
+    /// triangles and a single vertex at each pole, so the caps are fans of proper triangles
+    /// rather than zero-area slivers. UVs are equirectangular: u goes once round from +X
+    /// towards +Z, v runs from 0 at the top pole to 1 at the bottom.
+    ///
+    pub fn create_uv_sphere(radius: f32, segments: usize, rings: usize) -> Self {
+        let mut mesh = Self::new();
+        let segments = segments.max(3);
+        let rings = rings.max(2);
+
+        let top = mesh.add_vertex(Vec3f::new(0.0, radius, 0.0));
+
+        // Rings between the poles, top to bottom
+        for ring in 1..rings {
+            let theta = std::f32::consts::PI * ring as f32 / rings as f32; // Angle from the top pole
+            for segment in 0..segments {
+                let phi = 2.0 * std::f32::consts::PI * segment as f32 / segments as f32;
+                mesh.add_vertex(Vec3f::new(
+                    radius * theta.sin() * phi.cos(),
+                    radius * theta.cos(),
+                    radius * theta.sin() * phi.sin(),
+                ));
+            }
+        }
+
+        let bottom = mesh.add_vertex(Vec3f::new(0.0, -radius, 0.0));
+
+        let ring_vertex = |ring: usize, segment: usize| 1 + (ring - 1) * segments + segment % segments;
+        // Segment `segments` is the same vertices as 0, but at u = 1 so the last column doesn't wrap back across the texture
+        let uv = |ring: usize, segment: usize| Vec2f::new(segment as f32 / segments as f32, ring as f32 / rings as f32);
+        let color = 0xFFFFFFFF;
+
+        for segment in 0..segments {
+            // Top cap, with the pole's u in the middle of the column
+            let pole_uv = Vec2f::new((segment as f32 + 0.5) / segments as f32, 0.0);
+            mesh.add_triangle(
+                Triangle::new(top, ring_vertex(1, segment + 1), ring_vertex(1, segment), color)
+                    .with_uvs(pole_uv, uv(1, segment + 1), uv(1, segment)),
+            );
+
+            // Middle bands, two triangles per quad
+            for ring in 1..rings - 1 {
+                let a = ring_vertex(ring, segment);
+                let b = ring_vertex(ring, segment + 1);
+                let c = ring_vertex(ring + 1, segment + 1);
+                let d = ring_vertex(ring + 1, segment);
+                let (uv_a, uv_b, uv_c, uv_d) =
+                    (uv(ring, segment), uv(ring, segment + 1), uv(ring + 1, segment + 1), uv(ring + 1, segment));
+                mesh.add_triangle(Triangle::new(a, b, c, color).with_uvs(uv_a, uv_b, uv_c));
+                mesh.add_triangle(Triangle::new(c, d, a, color).with_uvs(uv_c, uv_d, uv_a));
+            }
+
+            // Bottom cap
+            let pole_uv = Vec2f::new((segment as f32 + 0.5) / segments as f32, 1.0);
+            mesh.add_triangle(
+                Triangle::new(bottom, ring_vertex(rings - 1, segment), ring_vertex(rings - 1, segment + 1), color)
+                    .with_uvs(pole_uv, uv(rings - 1, segment), uv(rings - 1, segment + 1)),
+            );
+        }
+
+        mesh
+    }
+
+    ///
+    /// Sphere of `radius` made by splitting each face of an icosahedron into four,
+    /// `subdivisions` times over, and pushing the new vertices out onto the sphere. Triangles
+    /// come out much more even than a UV sphere's. UVs follow the same equirectangular mapping
+    /// as create_uv_sphere, with triangles across the seam kept on one side of it.
+    ///
+    pub fn create_icosphere(radius: f32, subdivisions: usize) -> Self {
+        const MAX_SUBDIVISIONS: usize = 6;
+        let t = (1.0 + 5.0f32.sqrt()) / 2.0;
+        let corners = [
+            (-1.0, t, 0.0), (1.0, t, 0.0), (-1.0, -t, 0.0), (1.0, -t, 0.0),
+            (0.0, -1.0, t), (0.0, 1.0, t), (0.0, -1.0, -t), (0.0, 1.0, -t),
+            (t, 0.0, -1.0), (t, 0.0, 1.0), (-t, 0.0, -1.0), (-t, 0.0, 1.0),
+        ];
+        let mut faces: Vec<[usize; 3]> = vec![
+            [0, 11, 5], [0, 5, 1], [0, 1, 7], [0, 7, 10], [0, 10, 11],
+            [1, 5, 9], [5, 11, 4], [11, 10, 2], [10, 7, 6], [7, 1, 8],
+            [3, 9, 4], [3, 4, 2], [3, 2, 6], [3, 6, 8], [3, 8, 9],
+            [4, 9, 5], [2, 4, 11], [6, 2, 10], [8, 6, 7], [9, 8, 1],
+        ];
+
+        let mut mesh = Self::new();
+        for (x, y, z) in corners {
+            mesh.add_vertex(Vec3f::new(x, y, z).normalize() * radius);
+        }
+
+        for _ in 0..subdivisions.min(MAX_SUBDIVISIONS) {
+            // Each edge's midpoint is made once and shared by the triangles on both sides
+            let mut midpoints: HashMap<(usize, usize), usize> = HashMap::new();
+            let mut midpoint = |a: usize, b: usize, mesh: &mut Mesh| {
+                *midpoints.entry((a.min(b), a.max(b))).or_insert_with(|| {
+                    let point = (mesh.vertices[a] + mesh.vertices[b]) * 0.5;
+                    mesh.add_vertex(point.normalize() * radius)
+                })
+            };
+            faces = faces
+                .into_iter()
+                .flat_map(|[a, b, c]| {
+                    let ab = midpoint(a, b, &mut mesh);
+                    let bc = midpoint(b, c, &mut mesh);
+                    let ca = midpoint(c, a, &mut mesh);
+                    [[a, ab, ca], [b, bc, ab], [c, ca, bc], [ab, bc, ca]]
+                })
+                .collect();
+        }
+
+        for indices in faces {
+            let uvs = spherical_uvs(indices.map(|index| mesh.vertices[index]));
+            let [i0, i1, i2] = indices;
+            mesh.add_triangle(Triangle::new(i0, i1, i2, 0xFFFFFFFF).with_uvs(uvs[0], uvs[1], uvs[2]));
+        }
+
+        mesh
+    }
+
+    ///
+    /// A cube with each face split into (2^subdivisions)² quads and every vertex pushed out
+    /// onto the unit sphere. Vertices along the face edges are shared, so
+    /// compute_vertex_normals gives smooth normals all round. Each face keeps the whole texture
+    /// the same way up as create_cube, which avoids the pinched poles of a UV sphere.
+    /// At 0 it has create_cube's shape, scaled to fit the unit sphere.
+    ///
+    pub fn create_subdivided_cube(subdivisions: usize) -> Self {
+        const MAX_SUBDIVISIONS: usize = 8;
+        // Outward normal, then the face's right and up directions as seen from outside
+        const FACES: [([i32; 3], [i32; 3], [i32; 3]); 6] = [
+            ([0, 0, 1], [1, 0, 0], [0, 1, 0]),   // Front
+            ([0, 0, -1], [-1, 0, 0], [0, 1, 0]), // Back
+            ([-1, 0, 0], [0, 0, 1], [0, 1, 0]),  // Left
+            ([1, 0, 0], [0, 0, -1], [0, 1, 0]),  // Right
+            ([0, 1, 0], [1, 0, 0], [0, 0, -1]),  // Top
+            ([0, -1, 0], [1, 0, 0], [0, 0, 1]),  // Bottom
+        ];
+
+        let mut mesh = Self::new();
+        let cells = 1i32 << subdivisions.min(MAX_SUBDIVISIONS);
+        // Grid points on the cube's surface in steps of 2/cells, so edges shared by two faces land on the same key
+        let mut lattice: HashMap<[i32; 3], usize> = HashMap::new();
+        let color = 0xFFFFFFFF;
+
+        for (normal, right, up) in FACES {
+            let mut vertex_at = |column: i32, row: i32, mesh: &mut Mesh| {
+                let key: [i32; 3] = std::array::from_fn(|axis| {
+                    normal[axis] * cells + right[axis] * (2 * column - cells) + up[axis] * (2 * row - cells)
+                });
+                *lattice.entry(key).or_insert_with(|| {
+                    let point = Vec3f::new(key[0] as f32, key[1] as f32, key[2] as f32);
+                    mesh.add_vertex(point.normalize())
+                })
+            };
+            let uv = |column: i32, row: i32| Vec2f::new(column as f32 / cells as f32, 1.0 - row as f32 / cells as f32);
+
+            for row in 0..cells {
+                for column in 0..cells {
+                    let a = vertex_at(column, row, &mut mesh);
+                    let b = vertex_at(column + 1, row, &mut mesh);
+                    let c = vertex_at(column + 1, row + 1, &mut mesh);
+                    let d = vertex_at(column, row + 1, &mut mesh);
+                    let (uv_a, uv_b, uv_c, uv_d) =
+                        (uv(column, row), uv(column + 1, row), uv(column + 1, row + 1), uv(column, row + 1));
+                    mesh.add_triangle(Triangle::new(a, b, c, color).with_uvs(uv_a, uv_b, uv_c));
+                    mesh.add_triangle(Triangle::new(c, d, a, color).with_uvs(uv_c, uv_d, uv_a));
+                }
+            }
+        }
+
+        mesh
+    }
+
     pub fn get_bounds(&self) -> (Vec3f, Vec3f) {
         if self.vertices.is_empty() {
             return (Vec3f::new(0.0, 0.0, 0.0), Vec3f::new(0.0, 0.0, 0.0));
@@ -157,12 +719,19 @@ impl Mesh {
     }
 
     pub fn transform_vertices(&self, transform_matrix: &crate::math::Mat4x4) -> Vec<Vec3f> {
-        self.vertices
-            .iter()
-            .map(|vertex| transform_matrix.multiply_point(vertex))
-            .collect()
+        let mut transformed = Vec::new();
+        transform_matrix.multiply_batch_points(&self.vertices, &mut transformed);
+        transformed
     }
 
+    /// The stored vertex normals through `normal_matrix`, renormalized; empty when the mesh has none
+    pub fn transform_vertex_normals(&self, normal_matrix: &crate::math::Mat4x4) -> Vec<Vec3f> {
+        self.vertex_normals()
+            .map(|normals| normals.iter().map(|normal| normal_matrix.multiply_vector(normal).normalize()).collect())
+            .unwrap_or_default()
+    }
+
+    /// Face normals, one per triangle, through `normal_matrix`; culling uses these even when the mesh has vertex normals
     pub fn transform_normals(&self, normal_matrix: &crate::math::Mat4x4) -> Vec<Vec3f> {
         self.triangles
             .iter()
@@ -172,4 +741,272 @@ impl Mesh {
             })
             .collect()
     }
-}
\ No newline at end of file
+}
+
+///
+/// Equirectangular UVs for a triangle on a sphere around the origin. Corners on either side
+/// of the u = 0 seam are moved onto the same side and held at u = 1, so a triangle across
+/// the seam squeezes its sliver of texture rather than reaching past the edge. A corner at a
+/// pole (where u means nothing) takes the middle of the other two.
+///
+fn spherical_uvs(corners: [Vec3f; 3]) -> [Vec2f; 3] {
+    let tau = std::f32::consts::TAU;
+    let at_pole = corners.map(|corner| corner.x.abs() < 1e-6 && corner.z.abs() < 1e-6);
+    let mut uvs = corners.map(|corner| {
+        let u = corner.z.atan2(corner.x).rem_euclid(tau) / tau;
+        let v = (corner.y / corner.length()).clamp(-1.0, 1.0).acos() / std::f32::consts::PI;
+        Vec2f::new(u, v)
+    });
+
+    let placed = || (0..3).filter(|&i| !at_pole[i]);
+    let max_u = placed().map(|i| uvs[i].x).fold(0.0, f32::max);
+    let min_u = placed().map(|i| uvs[i].x).fold(1.0, f32::min);
+    if max_u - min_u > 0.5 {
+        for i in placed() {
+            if uvs[i].x < 0.5 {
+                uvs[i].x = 1.0;
+            }
+        }
+    }
+    for i in (0..3).filter(|&i| at_pole[i]) {
+        let others: Vec<f32> = placed().map(|j| uvs[j].x).collect();
+        uvs[i].x = others.iter().sum::<f32>() / others.len().max(1) as f32;
+    }
+    uvs
+}
+
+/// Concatenates a per-vertex attribute for Mesh::merge: kept when each side either has it for
+/// every vertex or has no vertices, padded with `fill` where given, else dropped
+fn merge_attribute<T: Copy>(own: &mut Vec<T>, own_count: usize, other: &[T], other_count: usize, fill: Option<T>) {
+    if own_count == 0 {
+        own.clear();
+    }
+    if own.is_empty() && other.is_empty() {
+        return;
+    }
+    let own_has = own_count == 0 || own.len() == own_count;
+    let other_has = other_count == 0 || other.len() == other_count;
+    match (own_has, other_has, fill) {
+        (true, true, _) => own.extend_from_slice(other),
+        (_, _, Some(fill)) => {
+            if !own_has {
+                own.clear();
+                own.resize(own_count, fill);
+            }
+            if other_has {
+                own.extend_from_slice(other);
+            } else {
+                own.resize(own_count + other_count, fill);
+            }
+        }
+        _ => own.clear(),
+    }
+}
+
+/// Ritter's bounding sphere: the span between two far-apart points, grown to take in any point outside it
+fn ritter_sphere(points: &[Vec3f]) -> (Vec3f, f32) {
+    let Some(&first) = points.first() else {
+        return (Vec3f::zero(), 0.0);
+    };
+    let farthest_from = |from: Vec3f| {
+        points.iter().copied().max_by(|a, b| (*a - from).length().total_cmp(&(*b - from).length())).unwrap_or(from)
+    };
+    let a = farthest_from(first);
+    let b = farthest_from(a);
+    let mut center = (a + b) * 0.5;
+    let mut radius = (b - a).length() * 0.5;
+
+    for &point in points {
+        let distance = (point - center).length();
+        if distance > radius {
+            let grown = (radius + distance) * 0.5;
+            center = center + (point - center) * ((grown - radius) / distance);
+            radius = grown;
+        }
+    }
+    // Rounding in the steps above can leave a point a hair outside
+    let radius = points.iter().map(|&point| (point - center).length()).fold(radius, f32::max);
+    (center, radius)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn contains_every_vertex(mesh: &Mesh) -> bool {
+        let (center, radius) = mesh.bounding_sphere();
+        mesh.vertices.iter().all(|&vertex| (vertex - center).length() <= radius)
+    }
+
+    #[test]
+    fn bounding_sphere_holds_every_vertex() {
+        for mesh in [Mesh::create_cube(), Mesh::create_icosphere(2.0, 2), Mesh::create_uv_sphere(0.5, 12, 8)] {
+            assert!(contains_every_vertex(&mesh));
+        }
+    }
+
+    #[test]
+    fn unit_cube_sphere_reaches_its_corners() {
+        // create_cube spans ±1, so the smallest sphere is the corners' at √3; Ritter's is within a few percent
+        let (_, radius) = Mesh::create_cube().bounding_sphere();
+        let corner = 3.0_f32.sqrt();
+        assert!(radius >= corner - 1e-5 && radius <= corner * 1.05, "radius {radius}");
+    }
+
+    #[test]
+    fn bounding_sphere_cache_follows_vertex_changes() {
+        let mut mesh = Mesh::create_cube();
+        let (_, before) = mesh.bounding_sphere();
+
+        mesh.add_vertex(Vec3f::new(5.0, 0.0, 0.0));
+        assert!(contains_every_vertex(&mesh));
+        assert!(mesh.bounding_sphere().1 > before);
+
+        // Written directly, the cache is stale until marked dirty
+        let (_, grown) = mesh.bounding_sphere();
+        mesh.vertices[0] = Vec3f::new(0.0, -20.0, 0.0);
+        assert_eq!(mesh.bounding_sphere().1, grown);
+        mesh.mark_vertices_dirty();
+        assert!(contains_every_vertex(&mesh));
+        assert!(mesh.bounding_sphere().1 > grown);
+    }
+
+    fn every_vertex_at_radius(mesh: &Mesh, radius: f32) -> bool {
+        mesh.vertices.iter().all(|vertex| (vertex.length() - radius).abs() < radius * 1e-5)
+    }
+
+    #[test]
+    fn uv_sphere_counts_and_radius() {
+        for (segments, rings) in [(3, 2), (8, 4), (16, 12), (32, 16)] {
+            let mesh = Mesh::create_uv_sphere(1.5, segments, rings);
+            // One vertex per pole plus a ring of `segments` between each pair of rings
+            assert_eq!(mesh.vertices.len(), 2 + (rings - 1) * segments, "{segments}x{rings}");
+            // A fan at each pole and two per quad in the bands between
+            assert_eq!(mesh.triangles.len(), 2 * segments * (rings - 1), "{segments}x{rings}");
+            assert!(every_vertex_at_radius(&mesh, 1.5));
+        }
+    }
+
+    #[test]
+    fn icosphere_counts_and_radius() {
+        for subdivisions in 0..=4 {
+            let mesh = Mesh::create_icosphere(0.75, subdivisions);
+            let split = 4usize.pow(subdivisions as u32);
+            assert_eq!(mesh.triangles.len(), 20 * split, "subdivisions {subdivisions}");
+            // Euler: V = E - F + 2 with E = 3F / 2
+            assert_eq!(mesh.vertices.len(), 10 * split + 2, "subdivisions {subdivisions}");
+            assert!(every_vertex_at_radius(&mesh, 0.75));
+        }
+    }
+
+    #[test]
+    fn plane_counts_normals_and_uv_corners() {
+        for (sx, sz) in [(1, 1), (3, 2), (8, 8), (5, 1)] {
+            let mesh = Mesh::create_plane(6.0, 4.0, sx, sz);
+            assert_eq!(mesh.vertices.len(), (sx + 1) * (sz + 1), "{sx}x{sz}");
+            assert_eq!(mesh.triangles.len(), sx * sz * 2, "{sx}x{sz}");
+
+            let up = Vec3f::up();
+            for triangle in &mesh.triangles {
+                assert!((triangle.calculate_normal(&mesh).dot(&up) - 1.0).abs() < 1e-5, "{sx}x{sz}");
+            }
+            for normal in mesh.compute_vertex_normals() {
+                assert!((normal.dot(&up) - 1.0).abs() < 1e-5, "{sx}x{sz}");
+            }
+
+            // First vertex is the -X,-Z corner and the last the +X,+Z one
+            let (first, last) = (mesh.uvs[0], mesh.uvs[mesh.uvs.len() - 1]);
+            assert_eq!((first.x, first.y), (0.0, 0.0));
+            assert_eq!((last.x, last.y), (1.0, 1.0));
+            for triangle in &mesh.triangles {
+                let uvs = mesh.triangle_uvs(triangle).expect("plane has UVs");
+                assert!(uvs.iter().all(|uv| (0.0..=1.0).contains(&uv.x) && (0.0..=1.0).contains(&uv.y)));
+            }
+        }
+    }
+
+    #[test]
+    fn cube_smoothed_at_thirty_degrees_keeps_its_creases() {
+        let mut mesh = Mesh::create_cube();
+        mesh.compute_smooth_normals(30.0);
+
+        // Every corner is split three ways, one copy per face meeting there
+        assert_eq!(mesh.vertices.len(), 24);
+        assert_eq!(mesh.normals.len(), 24);
+        for triangle in &mesh.triangles {
+            let face = triangle.calculate_normal(&mesh);
+            for &index in &triangle.indices {
+                assert!(mesh.normals[index].dot(&face) > 1.0 - 1e-5, "{:?} vs {face:?}", mesh.normals[index]);
+            }
+        }
+    }
+
+    #[test]
+    fn smoothed_icosphere_normals_converge_to_radial() {
+        let worst_error = |subdivisions: usize| {
+            let mut mesh = Mesh::create_icosphere(2.0, subdivisions);
+            mesh.compute_smooth_normals(60.0);
+            mesh.vertices
+                .iter()
+                .zip(&mesh.normals)
+                .map(|(vertex, normal)| normal.dot(&vertex.normalize()).clamp(-1.0, 1.0).acos())
+                .fold(0.0f32, f32::max)
+        };
+
+        // From 2 on the vertices aren't all symmetric, so the averaged faces lean a little; each split halves it
+        let errors: Vec<f32> = (2..=5).map(worst_error).collect();
+        assert!(errors.windows(2).all(|pair| pair[1] < pair[0] * 0.6), "{errors:?}");
+        assert!(errors[3] < 0.25f32.to_radians(), "{errors:?}");
+    }
+
+    #[test]
+    fn primitive_uvs_stay_in_the_unit_square() {
+        let primitives = [
+            ("cube", Mesh::create_cube()),
+            ("plane", Mesh::create_plane(3.0, 2.0, 4, 3)),
+            ("uv sphere", Mesh::create_uv_sphere(1.0, 16, 8)),
+            ("icosphere", Mesh::create_icosphere(1.0, 3)),
+            ("subdivided cube", Mesh::create_subdivided_cube(2)),
+        ];
+        for (name, mesh) in &primitives {
+            for triangle in &mesh.triangles {
+                let uvs = mesh.triangle_uvs(triangle).unwrap_or_else(|| panic!("{name} triangle without UVs"));
+                for uv in uvs {
+                    assert!((0.0..=1.0).contains(&uv.x) && (0.0..=1.0).contains(&uv.y), "{name}: {uv:?}");
+                }
+            }
+        }
+        // The bare test triangle has none at all
+        let triangle = Mesh::create_triangle();
+        assert!(triangle.triangle_uvs(&triangle.triangles[0]).is_none());
+    }
+
+    #[test]
+    fn merging_two_cubes_keeps_every_index_valid() {
+        let mut merged = Mesh::create_cube();
+        merged.merge(&Mesh::create_cube());
+
+        assert_eq!(merged.vertices.len(), 16);
+        assert_eq!(merged.triangles.len(), 24);
+        assert!(merged.triangles.iter().flat_map(|triangle| triangle.indices).all(|index| index < 16));
+        // The second cube's triangles point at its own copies of the corners
+        let cube = Mesh::create_cube();
+        for (original, moved) in cube.triangles.iter().zip(&merged.triangles[12..]) {
+            assert_eq!(moved.indices, original.indices.map(|index| index + 8));
+        }
+    }
+
+    #[test]
+    fn baking_a_translation_shifts_the_bounds_exactly() {
+        let mut mesh = Mesh::create_icosphere(1.0, 1);
+        let (min, max) = mesh.get_bounds();
+        mesh.bake_transform(&crate::math::Mat4x4::translation(2.0, -3.0, 0.5));
+        let (moved_min, moved_max) = mesh.get_bounds();
+
+        let shift = Vec3f::new(2.0, -3.0, 0.5);
+        for (before, after) in [(min, moved_min), (max, moved_max)] {
+            let expected = before + shift;
+            assert_eq!((after.x, after.y, after.z), (expected.x, expected.y, expected.z));
+        }
+    }
+}
@@ -0,0 +1,291 @@
+use std::f32::consts::TAU;
+use crate::math::{Vec2f, Vec3f};
+use crate::mesh::{Mesh, Triangle};
+
+const GENERATED_COLOR: u32 = 0xFFFFFFFF;
+// Profile points this close to the Y axis are poles in lathe: one vertex, not a ring
+const POLE_RADIUS: f32 = 1e-6;
+
+impl Mesh {
+    ///
+    /// A prism: the polygon `profile` laid out in the XZ plane (profile x → X, y → Z), extruded
+    /// along Y from -height/2 to height/2. Both caps are ear-clipped, so the profile may be
+    /// concave but not self-intersecting; it can wind either way, and a last point repeating
+    /// the first is ignored. Caps and each side quad get their own vertices with flat normals
+    /// (hard edges), caps with UVs spanning the profile's bounds and sides with U running
+    /// around the outline and V from top (0) to bottom (1). Empty for fewer than three points.
+    ///
+    pub fn extrude_polygon(profile: &[Vec2f], height: f32) -> Self {
+        let mut mesh = Self::new();
+        let mut points = profile.to_vec();
+        if points.len() > 3 && (points[0] - points[points.len() - 1]).length() < 1e-6 {
+            points.pop();
+        }
+        if points.len() < 3 {
+            return mesh;
+        }
+        // Counter-clockwise from here on
+        if signed_area(&points) < 0.0 {
+            points.reverse();
+        }
+
+        let half = height.abs() * 0.5;
+        let (min, max) = points.iter().fold((points[0], points[0]), |(min, max), point| {
+            (Vec2f::new(min.x.min(point.x), min.y.min(point.y)), Vec2f::new(max.x.max(point.x), max.y.max(point.y)))
+        });
+        let size = Vec2f::new((max.x - min.x).max(1e-6), (max.y - min.y).max(1e-6));
+        let cap_uv = |point: Vec2f| Vec2f::new((point.x - min.x) / size.x, (point.y - min.y) / size.y);
+
+        let cap_triangles = ear_clip(&points);
+        for (y, normal) in [(half, Vec3f::up()), (-half, -Vec3f::up())] {
+            let start = mesh.vertices.len();
+            for &point in &points {
+                mesh.add_vertex(Vec3f::new(point.x, y, point.y));
+                mesh.normals.push(normal);
+                mesh.uvs.push(cap_uv(point));
+            }
+            // Counter-clockwise in the profile is clockwise seen from +Y, so the top cap turns it round
+            for &[a, b, c] in &cap_triangles {
+                let corners = if y > 0.0 { [a, c, b] } else { [a, b, c] };
+                let [i0, i1, i2] = corners.map(|corner| start + corner);
+                mesh.add_triangle(Triangle::new(i0, i1, i2, GENERATED_COLOR));
+            }
+        }
+
+        let perimeter: f32 = (0..points.len()).map(|i| (points[(i + 1) % points.len()] - points[i]).length()).sum();
+        let mut travelled = 0.0;
+        for i in 0..points.len() {
+            let (p, q) = (points[i], points[(i + 1) % points.len()]);
+            let edge = q - p;
+            let length = edge.length();
+            if length < 1e-6 {
+                continue;
+            }
+            // Right of a counter-clockwise outline is outside
+            let normal = Vec3f::new(edge.y, 0.0, -edge.x) * (1.0 / length);
+            let (u0, u1) = (travelled / perimeter, (travelled + length) / perimeter);
+            travelled += length;
+
+            let start = mesh.vertices.len();
+            let corners = [(p, -half, u0, 1.0), (q, -half, u1, 1.0), (q, half, u1, 0.0), (p, half, u0, 0.0)];
+            for (point, y, u, v) in corners {
+                mesh.add_vertex(Vec3f::new(point.x, y, point.y));
+                mesh.normals.push(normal);
+                mesh.uvs.push(Vec2f::new(u, v));
+            }
+            // Bottom p, bottom q, top q, top p
+            mesh.add_triangle(Triangle::new(start, start + 2, start + 1, GENERATED_COLOR));
+            mesh.add_triangle(Triangle::new(start, start + 3, start + 2, GENERATED_COLOR));
+        }
+
+        mesh
+    }
+
+    ///
+    /// Revolves `profile` about the Y axis in `segments` steps (at least 3): each point is
+    /// (distance from the axis, height), walked from bottom to top with the outside on the
+    /// right, like drawing the right-hand silhouette of a vase. Normals are smooth around the
+    /// revolution and along the profile (averaged over the two segments at each point).
+    /// A point on the axis becomes a single pole vertex with a fan of triangles, so a profile
+    /// that starts or ends there closes the shape. Triangles carry their own UVs, U around
+    /// and V down the profile, so the seam needs no duplicate vertices. Empty for fewer than
+    /// two points.
+    ///
+    pub fn lathe(profile: &[Vec2f], segments: usize) -> Self {
+        let mut mesh = Self::new();
+        if profile.len() < 2 {
+            return mesh;
+        }
+        let segments = segments.max(3);
+        let is_pole = |point: Vec2f| point.x.abs() <= POLE_RADIUS;
+
+        // Outward normal of each profile segment, in (radius, height)
+        let segment_normals: Vec<Vec2f> = profile
+            .windows(2)
+            .map(|pair| {
+                let edge = pair[1] - pair[0];
+                let length = edge.length();
+                if length < 1e-6 { Vec2f::zero() } else { Vec2f::new(edge.y, -edge.x) / length }
+            })
+            .collect();
+        let point_normal = |i: usize| {
+            let before = if i > 0 { segment_normals[i - 1] } else { Vec2f::zero() };
+            let after = segment_normals.get(i).copied().unwrap_or(Vec2f::zero());
+            let sum = before + after;
+            let length = sum.length();
+            if length < 1e-6 { Vec2f::new(1.0, 0.0) } else { sum / length }
+        };
+
+        let arc_lengths: Vec<f32> = std::iter::once(0.0)
+            .chain(profile.windows(2).scan(0.0, |total, pair| {
+                *total += (pair[1] - pair[0]).length();
+                Some(*total)
+            }))
+            .collect();
+        let total_length = arc_lengths[arc_lengths.len() - 1].max(1e-6);
+
+        // First vertex of each profile point's ring; a pole's ring is one vertex
+        let mut rings = Vec::with_capacity(profile.len());
+        for (i, &point) in profile.iter().enumerate() {
+            let normal = point_normal(i);
+            rings.push(mesh.vertices.len());
+            if is_pole(point) {
+                let up = if normal.y < 0.0 { -1.0 } else { 1.0 };
+                mesh.add_vertex(Vec3f::new(0.0, point.y, 0.0));
+                mesh.normals.push(Vec3f::new(0.0, up, 0.0));
+                continue;
+            }
+            for step in 0..segments {
+                let (sin, cos) = (TAU * step as f32 / segments as f32).sin_cos();
+                mesh.add_vertex(Vec3f::new(point.x * cos, point.y, point.x * sin));
+                mesh.normals.push(Vec3f::new(normal.x * cos, normal.y, normal.x * sin).normalize());
+            }
+        }
+
+        for i in 0..profile.len() - 1 {
+            let (p_pole, q_pole) = (is_pole(profile[i]), is_pole(profile[i + 1]));
+            if p_pole && q_pole {
+                continue; // Along the axis itself; nothing to sweep
+            }
+            let (v_p, v_q) = (1.0 - arc_lengths[i] / total_length, 1.0 - arc_lengths[i + 1] / total_length);
+            let vertex = |ring: usize, pole: bool, step: usize| if pole { rings[ring] } else { rings[ring] + step % segments };
+
+            for step in 0..segments {
+                let (u0, u1) = (step as f32 / segments as f32, (step + 1) as f32 / segments as f32);
+                let (p0, p1) = (vertex(i, p_pole, step), vertex(i, p_pole, step + 1));
+                let (q0, q1) = (vertex(i + 1, q_pole, step), vertex(i + 1, q_pole, step + 1));
+                let u_mid = (u0 + u1) * 0.5;
+                if !q_pole {
+                    let uv_p0 = Vec2f::new(if p_pole { u_mid } else { u0 }, v_p);
+                    mesh.add_triangle(
+                        Triangle::new(p0, q0, q1, GENERATED_COLOR)
+                            .with_uvs(uv_p0, Vec2f::new(u0, v_q), Vec2f::new(u1, v_q)),
+                    );
+                }
+                if !p_pole {
+                    let uv_q1 = Vec2f::new(if q_pole { u_mid } else { u1 }, v_q);
+                    mesh.add_triangle(
+                        Triangle::new(p0, q1, p1, GENERATED_COLOR)
+                            .with_uvs(Vec2f::new(u0, v_p), uv_q1, Vec2f::new(u1, v_p)),
+                    );
+                }
+            }
+        }
+
+        mesh
+    }
+}
+
+// Twice the polygon's signed area, positive when it winds counter-clockwise
+fn signed_area(points: &[Vec2f]) -> f32 {
+    (0..points.len())
+        .map(|i| {
+            let (p, q) = (points[i], points[(i + 1) % points.len()]);
+            p.x * q.y - q.x * p.y
+        })
+        .sum()
+}
+
+// (b - a) × (c - a): positive when a, b, c turn counter-clockwise
+fn cross(a: Vec2f, b: Vec2f, c: Vec2f) -> f32 {
+    (b.x - a.x) * (c.y - a.y) - (b.y - a.y) * (c.x - a.x)
+}
+
+///
+/// Triangulates a simple counter-clockwise polygon by repeatedly cutting off a convex corner
+/// with no other polygon point inside it. Returns corner indices into `polygon`,
+/// counter-clockwise.
+///
+fn ear_clip(polygon: &[Vec2f]) -> Vec<[usize; 3]> {
+    const EPSILON: f32 = 1e-9;
+    let mut remaining: Vec<usize> = (0..polygon.len()).collect();
+    let mut triangles = Vec::new();
+
+    while remaining.len() > 3 {
+        let count = remaining.len();
+        let corner = |i: usize| (remaining[(i + count - 1) % count], remaining[i], remaining[(i + 1) % count]);
+        let is_ear = |i: usize| {
+            let (a, b, c) = corner(i);
+            let (pa, pb, pc) = (polygon[a], polygon[b], polygon[c]);
+            cross(pa, pb, pc) > EPSILON
+                && remaining.iter().all(|&other| {
+                    other == a || other == b || other == c || !in_triangle(polygon[other], pa, pb, pc)
+                })
+        };
+
+        // Straight-through corners go first; they add no area
+        let clip = (0..count)
+            .find(|&i| {
+                let (a, b, c) = corner(i);
+                cross(polygon[a], polygon[b], polygon[c]).abs() <= EPSILON
+            })
+            .map(|i| (i, false))
+            .or_else(|| (0..count).find(|&i| is_ear(i)).map(|i| (i, true)));
+
+        match clip {
+            Some((i, emit)) => {
+                if emit {
+                    let (a, b, c) = corner(i);
+                    triangles.push([a, b, c]);
+                }
+                remaining.remove(i);
+            }
+            None => break, // Not a simple polygon; keep what was found rather than loop forever
+        }
+    }
+
+    if let [a, b, c] = remaining[..]
+        && cross(polygon[a], polygon[b], polygon[c]) > EPSILON {
+        triangles.push([a, b, c]);
+    }
+    triangles
+}
+
+// Inside or on the edges of counter-clockwise triangle a, b, c
+fn in_triangle(point: Vec2f, a: Vec2f, b: Vec2f, c: Vec2f) -> bool {
+    cross(a, b, point) >= 0.0 && cross(b, c, point) >= 0.0 && cross(c, a, point) >= 0.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extruded_square_is_a_cube() {
+        let square = [Vec2f::new(-1.0, -1.0), Vec2f::new(1.0, -1.0), Vec2f::new(1.0, 1.0), Vec2f::new(-1.0, 1.0)];
+        let prism = Mesh::extrude_polygon(&square, 2.0);
+        let cube = Mesh::create_cube();
+
+        let ((prism_min, prism_max), (cube_min, cube_max)) = (prism.get_bounds(), cube.get_bounds());
+        assert!((prism_min - cube_min).length() < 1e-6 && (prism_max - cube_max).length() < 1e-6);
+        assert_eq!(prism.triangles.len(), cube.triangles.len());
+        assert_eq!(prism.vertices.len(), 24); // Four per face, for the hard edges
+        assert!(prism.validate().is_valid());
+
+        // Every face points away from the middle, as its normals say
+        for triangle in &prism.triangles {
+            let normal = triangle.calculate_normal(&prism);
+            assert!(normal.dot(&triangle.get_center(&prism)) > 0.0);
+            assert!((normal - prism.normals[triangle.indices[0]]).length() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn lathed_segment_is_an_open_cylinder() {
+        let (radius, segments) = (1.5, 16);
+        let cylinder = Mesh::lathe(&[Vec2f::new(radius, 0.0), Vec2f::new(radius, 2.0)], segments);
+
+        assert_eq!(cylinder.triangles.len(), segments * 2);
+        assert_eq!(cylinder.vertices.len(), segments * 2);
+        assert!(cylinder.validate().is_valid());
+        for vertex in &cylinder.vertices {
+            assert!((Vec2f::new(vertex.x, vertex.z).length() - radius).abs() < 1e-5);
+            assert!(vertex.y == 0.0 || vertex.y == 2.0);
+        }
+        // Sides face outward
+        for triangle in &cylinder.triangles {
+            let center = triangle.get_center(&cylinder);
+            assert!(triangle.calculate_normal(&cylinder).dot(&Vec3f::new(center.x, 0.0, center.z)) > 0.0);
+        }
+    }
+}
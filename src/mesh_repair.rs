@@ -0,0 +1,494 @@
+use std::collections::{HashMap, VecDeque};
+use crate::math::Vec3f;
+use crate::mesh::{Mesh, Triangle};
+
+/// What Mesh::validate found wrong; each list is sorted, and all empty means the mesh is fine
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ValidationReport {
+    pub out_of_range_triangles: Vec<usize>,      // Triangles with an index past the last vertex
+    pub degenerate_triangles: Vec<usize>,        // Zero (or NaN) area, including repeated indices
+    pub invalid_vertices: Vec<usize>,            // NaN or infinite positions
+    pub attribute_mismatches: Vec<&'static str>, // Per-vertex arrays neither empty nor one per vertex
+    pub non_manifold_edges: Vec<(usize, usize)>, // Vertex pairs, low first, shared by more than two triangles
+    pub inconsistent_winding: Vec<usize>,        // As check_winding_consistency
+}
+
+impl ValidationReport {
+    pub fn is_valid(&self) -> bool {
+        self.out_of_range_triangles.is_empty()
+            && self.degenerate_triangles.is_empty()
+            && self.invalid_vertices.is_empty()
+            && self.attribute_mismatches.is_empty()
+            && self.non_manifold_edges.is_empty()
+            && self.inconsistent_winding.is_empty()
+    }
+}
+
+/// What Mesh::repair fixes
+#[derive(Copy, Clone, Debug)]
+pub struct RepairOptions {
+    pub clamp_indices: bool,      // Out-of-range indices become the last vertex; false drops their triangles
+    pub remove_degenerate: bool,  // Drop zero-area triangles, and with them any using NaN vertices
+    pub fix_winding: bool,        // Flip the faces check_winding_consistency reports
+}
+
+impl RepairOptions {
+    pub fn new() -> Self {
+        Self { clamp_indices: false, remove_degenerate: true, fix_winding: false }
+    }
+
+    pub fn with_clamp_indices(mut self, clamp_indices: bool) -> Self {
+        self.clamp_indices = clamp_indices;
+        self
+    }
+
+    pub fn with_fix_winding(mut self, fix_winding: bool) -> Self {
+        self.fix_winding = fix_winding;
+        self
+    }
+}
+
+impl Default for RepairOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Mesh {
+    ///
+    /// Checks the mesh for what makes the rest of the renderer panic or misbehave: indices
+    /// past the vertex list, triangles with no area (the barycentric math divides by it),
+    /// NaN/infinite positions, per-vertex arrays of the wrong length, edges shared by more
+    /// than two triangles, and faces wound against their neighbours.
+    ///
+    pub fn validate(&self) -> ValidationReport {
+        let mut report = ValidationReport::default();
+        let vertex_count = self.vertices.len();
+
+        report.invalid_vertices = (0..vertex_count)
+            .filter(|&index| {
+                let vertex = self.vertices[index];
+                !(vertex.x.is_finite() && vertex.y.is_finite() && vertex.z.is_finite())
+            })
+            .collect();
+
+        for (index, triangle) in self.triangles.iter().enumerate() {
+            if triangle.indices.iter().any(|&vertex| vertex >= vertex_count) {
+                report.out_of_range_triangles.push(index);
+            } else if self.is_degenerate(index) {
+                report.degenerate_triangles.push(index);
+            }
+        }
+
+        let attributes = [
+            ("vertex_ao", self.vertex_ao.len()),
+            ("vertex_colors", self.vertex_colors.len()),
+            ("normals", self.normals.len()),
+            ("uvs", self.uvs.len()),
+            ("tangents", self.tangents.len()),
+        ];
+        report.attribute_mismatches = attributes
+            .iter()
+            .filter(|&&(_, len)| len != 0 && len != vertex_count)
+            .map(|&(name, _)| name)
+            .collect();
+
+        let mut edge_users: HashMap<(usize, usize), usize> = HashMap::new();
+        for triangle in &self.triangles {
+            for corner in 0..3 {
+                let (a, b) = (triangle.indices[corner], triangle.indices[(corner + 1) % 3]);
+                if a != b {
+                    *edge_users.entry((a.min(b), a.max(b))).or_default() += 1;
+                }
+            }
+        }
+        report.non_manifold_edges = edge_users
+            .into_iter()
+            .filter(|&(_, users)| users > 2)
+            .map(|(edge, _)| edge)
+            .collect();
+        report.non_manifold_edges.sort_unstable();
+
+        report.inconsistent_winding = self.check_winding_consistency();
+        report
+    }
+
+    ///
+    /// Fixes what validate reports, as far as `options` allow: bad indices are clamped or
+    /// their triangles dropped, zero-area triangles removed, per-vertex arrays of the wrong
+    /// length cleared (they were being ignored anyway), and faces flipped to agree with their
+    /// neighbours. Returns validate's report on the result, listing whatever is left
+    /// (non-manifold edges are never touched).
+    ///
+    pub fn repair(&mut self, options: RepairOptions) -> ValidationReport {
+        let vertex_count = self.vertices.len();
+        if options.clamp_indices && vertex_count > 0 {
+            for triangle in &mut self.triangles {
+                triangle.indices = triangle.indices.map(|index| index.min(vertex_count - 1));
+            }
+        } else {
+            self.triangles.retain(|triangle| triangle.indices.iter().all(|&index| index < vertex_count));
+        }
+
+        if options.remove_degenerate {
+            let keep: Vec<bool> = (0..self.triangles.len()).map(|index| !self.is_degenerate(index)).collect();
+            let mut keep = keep.into_iter();
+            self.triangles.retain(|_| keep.next().unwrap_or(true));
+        }
+
+        if self.vertex_ao.len() != vertex_count {
+            self.vertex_ao.clear();
+        }
+        if self.vertex_colors.len() != vertex_count {
+            self.vertex_colors.clear();
+        }
+        if self.normals.len() != vertex_count {
+            self.normals.clear();
+        }
+        if self.uvs.len() != vertex_count {
+            self.uvs.clear();
+        }
+        if self.tangents.len() != vertex_count {
+            self.tangents.clear();
+        }
+
+        if options.fix_winding {
+            self.fix_winding();
+        }
+        self.validate()
+    }
+
+    ///
+    /// Finds triangles wound the opposite way to their neighbours.
+    /// Two triangles sharing an edge agree when they walk it in opposite directions
+    /// (a→b in one, b→a in the other), which keeps their normals on the same side of the
+    /// surface. Orientation is flood-filled across each connected piece of the mesh, and
+    /// whichever orientation covers more of that piece wins; the rest are returned, sorted.
+    /// Edges shared by more than two triangles are ignored, as they have no consistent answer.
+    ///
+    pub fn check_winding_consistency(&self) -> Vec<usize> {
+        // Undirected edge -> triangles using it, with whether they walk it low -> high
+        let mut edges: HashMap<(usize, usize), Vec<(usize, bool)>> = HashMap::new();
+        for (index, triangle) in self.triangles.iter().enumerate() {
+            for corner in 0..3 {
+                let (a, b) = (triangle.indices[corner], triangle.indices[(corner + 1) % 3]);
+                if a == b {
+                    continue;
+                }
+                edges.entry((a.min(b), a.max(b))).or_default().push((index, a < b));
+            }
+        }
+
+        // Each triangle's shared edges, as (neighbour, whether it must be flipped relative to us)
+        let mut neighbours = vec![Vec::new(); self.triangles.len()];
+        for users in edges.values() {
+            if let [(first, first_forward), (second, second_forward)] = users[..] {
+                // Same direction along the edge means one of the two is flipped
+                let must_flip = first_forward == second_forward;
+                neighbours[first].push((second, must_flip));
+                neighbours[second].push((first, must_flip));
+            }
+        }
+
+        let mut flipped = Vec::new();
+        let mut visited = vec![false; self.triangles.len()];
+        let mut queue = VecDeque::new();
+        for seed in 0..self.triangles.len() {
+            if visited[seed] {
+                continue;
+            }
+
+            // Flood fill one connected piece, recording each triangle's flip relative to the seed
+            let mut piece = Vec::new();
+            visited[seed] = true;
+            queue.push_back((seed, false));
+            while let Some((index, relative_flip)) = queue.pop_front() {
+                piece.push((index, relative_flip));
+                for &(neighbour, must_flip) in &neighbours[index] {
+                    if !visited[neighbour] {
+                        visited[neighbour] = true;
+                        queue.push_back((neighbour, relative_flip != must_flip));
+                    }
+                }
+            }
+
+            let against_seed = piece.iter().filter(|(_, relative_flip)| *relative_flip).count();
+            let seed_is_majority = against_seed * 2 <= piece.len();
+            flipped.extend(
+                piece
+                    .iter()
+                    .filter(|(_, relative_flip)| *relative_flip == seed_is_majority)
+                    .map(|(index, _)| *index),
+            );
+        }
+
+        flipped.sort_unstable();
+        flipped
+    }
+
+    /// Flips every triangle check_winding_consistency reports (UVs follow their corners)
+    pub fn fix_winding(&mut self) {
+        for index in self.check_winding_consistency() {
+            flip(&mut self.triangles[index]);
+        }
+    }
+
+    /// Reverses every triangle (UVs follow their corners), turning the mesh inside out or back
+    pub fn flip_winding(&mut self) {
+        for triangle in &mut self.triangles {
+            flip(triangle);
+        }
+    }
+
+    /// Negates the stored per-vertex normals. Face normals come from the winding, so use flip_winding for those.
+    pub fn flip_normals(&mut self) {
+        for normal in &mut self.normals {
+            *normal = -*normal;
+        }
+        // Keeps each bitangent, w * (normal × tangent), where it was
+        for tangent in &mut self.tangents {
+            tangent.w = -tangent.w;
+        }
+    }
+
+    ///
+    /// Makes every closed piece of the mesh face outwards: first fix_winding so each piece
+    /// agrees with itself, then any piece that encloses negative volume (measured from its
+    /// centroid, so pieces away from the origin work too) is flipped. Open pieces get the
+    /// same treatment, which is only a guess for them.
+    ///
+    pub fn ensure_outward_winding(&mut self) {
+        self.fix_winding();
+
+        let vertex_count = self.vertices.len();
+        for piece in self.connected_pieces() {
+            let in_range = |index: &usize| self.triangles[*index].indices.iter().all(|&vertex| vertex < vertex_count);
+            let piece: Vec<usize> = piece.into_iter().filter(in_range).collect();
+            let corners = piece.len() * 3;
+            if corners == 0 {
+                continue;
+            }
+            let centroid = piece
+                .iter()
+                .flat_map(|&index| self.triangles[index].indices)
+                .fold(Vec3f::zero(), |sum, vertex| sum + self.vertices[vertex])
+                / corners as f32;
+            // Six times the signed volume of the tetrahedra from the centroid to each face
+            let volume: f32 = piece
+                .iter()
+                .map(|&index| {
+                    let (v0, v1, v2) = self.triangles[index].get_vertices(self);
+                    (v0 - centroid).dot(&(v1 - centroid).cross(&(v2 - centroid)))
+                })
+                .sum();
+            if volume < 0.0 {
+                for index in piece {
+                    flip(&mut self.triangles[index]);
+                }
+            }
+        }
+    }
+
+    // Triangles grouped by whether they're joined through shared edges
+    fn connected_pieces(&self) -> Vec<Vec<usize>> {
+        let mut edges: HashMap<(usize, usize), Vec<usize>> = HashMap::new();
+        for (index, triangle) in self.triangles.iter().enumerate() {
+            for corner in 0..3 {
+                let (a, b) = (triangle.indices[corner], triangle.indices[(corner + 1) % 3]);
+                edges.entry((a.min(b), a.max(b))).or_default().push(index);
+            }
+        }
+
+        let mut pieces = Vec::new();
+        let mut visited = vec![false; self.triangles.len()];
+        for seed in 0..self.triangles.len() {
+            if visited[seed] {
+                continue;
+            }
+            visited[seed] = true;
+            let mut piece = vec![seed];
+            let mut next = 0;
+            while next < piece.len() {
+                let triangle = self.triangles[piece[next]];
+                next += 1;
+                for corner in 0..3 {
+                    let (a, b) = (triangle.indices[corner], triangle.indices[(corner + 1) % 3]);
+                    for &neighbour in &edges[&(a.min(b), a.max(b))] {
+                        if !visited[neighbour] {
+                            visited[neighbour] = true;
+                            piece.push(neighbour);
+                        }
+                    }
+                }
+            }
+            pieces.push(piece);
+        }
+        pieces
+    }
+
+    // Repeated corners, or an area tiny next to the longest edge (NaN positions count too)
+    fn is_degenerate(&self, index: usize) -> bool {
+        let [i0, i1, i2] = self.triangles[index].indices;
+        if i0 == i1 || i1 == i2 || i2 == i0 {
+            return true;
+        }
+        let (v0, v1, v2) = self.triangles[index].get_vertices(self);
+        let longest = (v1 - v0).length().max((v2 - v1).length()).max((v0 - v2).length());
+        let twice_area = (v1 - v0).cross(&(v2 - v0)).length();
+        twice_area.is_nan() || twice_area <= f32::EPSILON * longest * longest
+    }
+}
+
+// Reverses the winding, with the UVs following their corners
+fn flip(triangle: &mut Triangle) {
+    triangle.indices.swap(1, 2);
+    if let Some(uvs) = &mut triangle.uvs {
+        uvs.swap(1, 2);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Unit square in the XY plane, two triangles wound the same way round the diagonal 0-2
+    fn quad() -> Mesh {
+        let mut mesh = Mesh::new();
+        for (x, y) in [(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)] {
+            mesh.add_vertex(Vec3f::new(x, y, 0.0));
+        }
+        mesh.add_triangle(Triangle::new(0, 1, 2, 0xFFFFFFFF));
+        mesh.add_triangle(Triangle::new(0, 2, 3, 0xFFFFFFFF));
+        mesh
+    }
+
+    #[test]
+    fn a_clean_quad_is_valid() {
+        assert!(quad().validate().is_valid());
+    }
+
+    #[test]
+    fn out_of_range_index_is_dropped_or_clamped() {
+        let with_bad_index = || {
+            let mut mesh = quad();
+            mesh.add_triangle(Triangle::new(1, 2, 9, 0xFFFFFFFF));
+            mesh
+        };
+        assert_eq!(with_bad_index().validate().out_of_range_triangles, vec![2]);
+
+        let mut dropped = with_bad_index();
+        assert!(dropped.repair(RepairOptions::new()).is_valid());
+        assert_eq!(dropped.triangles.len(), 2);
+
+        // Clamped to vertex 3 it's a real triangle, but it overlaps the second one
+        let mut clamped = with_bad_index();
+        let report = clamped.repair(RepairOptions::new().with_clamp_indices(true));
+        assert!(report.out_of_range_triangles.is_empty());
+        assert_eq!(clamped.triangles[2].indices, [1, 2, 3]);
+    }
+
+    #[test]
+    fn degenerate_triangles_are_removed() {
+        let mut mesh = quad();
+        mesh.add_vertex(Vec3f::new(2.0, 0.0, 0.0));
+        mesh.add_triangle(Triangle::new(1, 1, 2, 0xFFFFFFFF)); // Repeated corner
+        mesh.add_triangle(Triangle::new(0, 1, 4, 0xFFFFFFFF)); // Three points in a line
+        assert_eq!(mesh.validate().degenerate_triangles, vec![2, 3]);
+
+        assert!(mesh.repair(RepairOptions::new()).is_valid());
+        assert_eq!(mesh.triangles.len(), 2);
+    }
+
+    #[test]
+    fn nan_vertex_loses_its_triangles() {
+        let mut mesh = quad();
+        mesh.vertices[3] = Vec3f::new(f32::NAN, 1.0, 0.0);
+        mesh.mark_vertices_dirty();
+        let report = mesh.validate();
+        assert_eq!(report.invalid_vertices, vec![3]);
+        assert_eq!(report.degenerate_triangles, vec![1]);
+
+        // The vertex itself stays (indices don't move) but nothing draws it any more
+        let report = mesh.repair(RepairOptions::new());
+        assert_eq!(report.invalid_vertices, vec![3]);
+        assert!(report.degenerate_triangles.is_empty());
+        assert!(mesh.triangles.iter().all(|triangle| !triangle.indices.contains(&3)));
+    }
+
+    #[test]
+    fn mismatched_attributes_are_cleared() {
+        let mut mesh = quad();
+        mesh.normals = vec![Vec3f::z_axis(); 3];
+        mesh.vertex_ao = vec![1.0; 4];
+        assert_eq!(mesh.validate().attribute_mismatches, vec!["normals"]);
+
+        assert!(mesh.repair(RepairOptions::new()).is_valid());
+        assert!(mesh.normals.is_empty());
+        assert_eq!(mesh.vertex_ao.len(), 4);
+    }
+
+    #[test]
+    fn non_manifold_edge_is_reported_but_left_alone() {
+        let mut mesh = quad();
+        mesh.add_vertex(Vec3f::new(0.5, 0.5, 1.0));
+        mesh.add_triangle(Triangle::new(0, 2, 4, 0xFFFFFFFF));
+        assert_eq!(mesh.validate().non_manifold_edges, vec![(0, 2)]);
+
+        let report = mesh.repair(RepairOptions::new().with_fix_winding(true));
+        assert_eq!(report.non_manifold_edges, vec![(0, 2)]);
+        assert_eq!(mesh.triangles.len(), 3);
+
+        // Taking the third triangle off the edge is up to the caller
+        mesh.triangles.pop();
+        assert!(mesh.validate().is_valid());
+    }
+
+    #[test]
+    fn flipped_face_is_turned_back() {
+        let mut mesh = quad();
+        mesh.triangles[1].indices = [0, 3, 2];
+        assert_eq!(mesh.validate().inconsistent_winding, vec![1]);
+
+        // Without fix_winding repair only reports it
+        assert_eq!(mesh.repair(RepairOptions::new()).inconsistent_winding, vec![1]);
+        assert_eq!(mesh.triangles[1].indices, [0, 3, 2]);
+        assert!(mesh.repair(RepairOptions::new().with_fix_winding(true)).is_valid());
+        assert_eq!(mesh.triangles[1].indices, [0, 2, 3]);
+    }
+
+    // How many faces of a convex mesh around `center` point away from it
+    fn outward_faces(mesh: &Mesh, center: Vec3f) -> usize {
+        mesh.triangles
+            .iter()
+            .filter(|triangle| triangle.calculate_normal(mesh).dot(&(triangle.get_center(mesh) - center)) > 0.0)
+            .count()
+    }
+
+    #[test]
+    fn flipping_twice_restores_the_cube() {
+        let original = Mesh::create_cube();
+        let mut mesh = Mesh::create_cube();
+        mesh.flip_winding();
+        assert!(mesh.triangles.iter().zip(&original.triangles).all(|(a, b)| a.indices != b.indices));
+
+        mesh.flip_winding();
+        for (restored, original) in mesh.triangles.iter().zip(&original.triangles) {
+            assert_eq!(restored.indices, original.indices);
+            let uvs = |triangle: &Triangle| triangle.uvs.map(|uvs| uvs.map(|uv| (uv.x, uv.y)));
+            assert_eq!(uvs(restored), uvs(original));
+        }
+    }
+
+    #[test]
+    fn inside_out_cube_is_turned_outward() {
+        let center = Vec3f::new(10.0, -4.0, 3.0);
+        let mut mesh = Mesh::create_cube();
+        mesh.bake_transform(&crate::math::Mat4x4::translation(center.x, center.y, center.z));
+        mesh.flip_winding();
+        assert_eq!(outward_faces(&mesh, center), 0);
+
+        mesh.ensure_outward_winding();
+        assert_eq!(outward_faces(&mesh, center), 12);
+        assert!(mesh.validate().is_valid());
+    }
+}
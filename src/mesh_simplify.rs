@@ -0,0 +1,417 @@
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use crate::math::Vec3f;
+use crate::mesh::{Mesh, Triangle};
+
+// Boundary edges get a plane quadric this much heavier than a face's, so open borders hold their shape
+const BOUNDARY_PENALTY: f64 = 1000.0;
+
+///
+/// Symmetric 4x4 error quadric (Garland & Heckbert), stored as its 10 unique entries.
+/// vᵀQv is the sum of squared distances from v to every plane added into Q.
+///
+#[derive(Copy, Clone, Debug, Default)]
+struct Quadric {
+    q: [f64; 10], // aa ab ac ad bb bc bd cc cd dd
+}
+
+impl Quadric {
+    /// Quadric of the plane ax + by + cz + d = 0 (with a unit normal)
+    fn from_plane(a: f64, b: f64, c: f64, d: f64) -> Self {
+        Self {
+            q: [a * a, a * b, a * c, a * d, b * b, b * c, b * d, c * c, c * d, d * d],
+        }
+    }
+
+    fn scaled(mut self, weight: f64) -> Self {
+        for entry in &mut self.q {
+            *entry *= weight;
+        }
+        self
+    }
+
+    fn add(&mut self, other: &Quadric) {
+        for (entry, other_entry) in self.q.iter_mut().zip(&other.q) {
+            *entry += other_entry;
+        }
+    }
+
+    fn error(&self, p: [f64; 3]) -> f64 {
+        let q = &self.q;
+        let [x, y, z] = p;
+        q[0] * x * x + 2.0 * q[1] * x * y + 2.0 * q[2] * x * z + 2.0 * q[3] * x
+            + q[4] * y * y + 2.0 * q[5] * y * z + 2.0 * q[6] * y
+            + q[7] * z * z + 2.0 * q[8] * z
+            + q[9]
+    }
+
+    /// The point with the lowest error, if the 3x3 part of the quadric is invertible
+    fn optimal_point(&self) -> Option<[f64; 3]> {
+        let q = &self.q;
+        // Solve A v = -b with Cramer's rule
+        let a = [[q[0], q[1], q[2]], [q[1], q[4], q[5]], [q[2], q[5], q[7]]];
+        let b = [-q[3], -q[6], -q[8]];
+
+        let det = determinant(a);
+        if det.abs() < 1e-10 {
+            return None;
+        }
+
+        let mut result = [0.0; 3];
+        for (column, value) in result.iter_mut().enumerate() {
+            let mut replaced = a;
+            for row in 0..3 {
+                replaced[row][column] = b[row];
+            }
+            *value = determinant(replaced) / det;
+        }
+        Some(result)
+    }
+}
+
+fn determinant(m: [[f64; 3]; 3]) -> f64 {
+    m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+        - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+        + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
+}
+
+/// A candidate edge collapse. Versions go stale once either vertex changes.
+struct EdgeCollapse {
+    cost: f64,
+    keep: usize,
+    remove: usize,
+    position: [f64; 3],
+    keep_version: u32,
+    remove_version: u32,
+}
+
+// BinaryHeap is a max-heap, so the ordering is reversed to pop the cheapest collapse first
+impl Ord for EdgeCollapse {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.cost.total_cmp(&self.cost)
+    }
+}
+
+impl PartialOrd for EdgeCollapse {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for EdgeCollapse {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for EdgeCollapse {}
+
+/// Working state while collapsing edges
+struct Simplifier {
+    positions: Vec<[f64; 3]>,
+    quadrics: Vec<Quadric>,
+    versions: Vec<u32>,
+    triangles: Vec<[usize; 3]>,
+    triangle_removed: Vec<bool>,
+    vertex_triangles: Vec<Vec<usize>>, // Triangles around each vertex (may include removed ones)
+}
+
+impl Simplifier {
+    fn new(mesh: &Mesh) -> Self {
+        let positions: Vec<[f64; 3]> = mesh.vertices
+            .iter()
+            .map(|v| [v.x as f64, v.y as f64, v.z as f64])
+            .collect();
+        let triangles: Vec<[usize; 3]> = mesh.triangles.iter().map(|t| t.indices).collect();
+
+        let mut quadrics = vec![Quadric::default(); positions.len()];
+        let mut vertex_triangles = vec![Vec::new(); positions.len()];
+        for (index, triangle) in triangles.iter().enumerate() {
+            if let Some((normal, d)) = plane(&positions, triangle) {
+                let quadric = Quadric::from_plane(normal[0], normal[1], normal[2], d);
+                for &vertex in triangle {
+                    quadrics[vertex].add(&quadric);
+                }
+            }
+            for &vertex in triangle {
+                vertex_triangles[vertex].push(index);
+            }
+        }
+
+        // An edge only one triangle uses is on a border: a plane through it, square to the
+        // triangle, keeps collapses from pulling the border in or along itself
+        let mut edge_users: HashMap<(usize, usize), (usize, usize)> = HashMap::new();
+        for (index, triangle) in triangles.iter().enumerate() {
+            for corner in 0..3 {
+                let (a, b) = (triangle[corner], triangle[(corner + 1) % 3]);
+                edge_users.entry((a.min(b), a.max(b))).or_insert((0, index)).0 += 1;
+            }
+        }
+        for (&(a, b), &(users, triangle)) in &edge_users {
+            if users != 1 {
+                continue;
+            }
+            let Some((face_normal, _)) = plane(&positions, &triangles[triangle]) else {
+                continue;
+            };
+            let normal = cross(sub(positions[b], positions[a]), face_normal);
+            let length = dot(normal, normal).sqrt();
+            if length < 1e-12 {
+                continue;
+            }
+            let normal = [normal[0] / length, normal[1] / length, normal[2] / length];
+            let quadric = Quadric::from_plane(normal[0], normal[1], normal[2], -dot(normal, positions[a]))
+                .scaled(BOUNDARY_PENALTY);
+            quadrics[a].add(&quadric);
+            quadrics[b].add(&quadric);
+        }
+
+        Self {
+            versions: vec![0; positions.len()],
+            triangle_removed: vec![false; triangles.len()],
+            positions,
+            quadrics,
+            triangles,
+            vertex_triangles,
+        }
+    }
+
+    ///
+    /// Cost of merging `remove` into `keep`: place the merged vertex where the combined
+    /// quadric has its minimum, or at the better of the endpoints/midpoint when that is
+    /// not well defined (flat or straight regions).
+    ///
+    fn evaluate(&self, keep: usize, remove: usize) -> EdgeCollapse {
+        let mut quadric = self.quadrics[keep];
+        quadric.add(&self.quadrics[remove]);
+
+        let a = self.positions[keep];
+        let b = self.positions[remove];
+        let midpoint = [(a[0] + b[0]) * 0.5, (a[1] + b[1]) * 0.5, (a[2] + b[2]) * 0.5];
+
+        let (position, cost) = match quadric.optimal_point() {
+            Some(point) => (point, quadric.error(point)),
+            None => [a, b, midpoint]
+                .into_iter()
+                .map(|p| (p, quadric.error(p)))
+                .min_by(|x, y| x.1.total_cmp(&y.1))
+                .unwrap(),
+        };
+
+        EdgeCollapse {
+            cost,
+            keep,
+            remove,
+            position,
+            keep_version: self.versions[keep],
+            remove_version: self.versions[remove],
+        }
+    }
+
+    fn neighbours(&self, vertex: usize) -> Vec<usize> {
+        let mut result = Vec::new();
+        for &triangle in &self.vertex_triangles[vertex] {
+            if self.triangle_removed[triangle] {
+                continue;
+            }
+            for &other in &self.triangles[triangle] {
+                if other != vertex && !result.contains(&other) {
+                    result.push(other);
+                }
+            }
+        }
+        result
+    }
+
+    /// Rejects collapses that would turn a surviving triangle inside out
+    fn flips_triangle(&self, collapse: &EdgeCollapse) -> bool {
+        for &vertex in &[collapse.keep, collapse.remove] {
+            for &triangle in &self.vertex_triangles[vertex] {
+                let indices = self.triangles[triangle];
+                if self.triangle_removed[triangle]
+                    || (indices.contains(&collapse.keep) && indices.contains(&collapse.remove)) {
+                    continue; // Removed, or removed by this collapse
+                }
+
+                let Some((old_normal, _)) = plane(&self.positions, &indices) else {
+                    continue;
+                };
+                let mut moved = indices.map(|index| self.positions[index]);
+                for (slot, &index) in indices.iter().enumerate() {
+                    if index == collapse.keep || index == collapse.remove {
+                        moved[slot] = collapse.position;
+                    }
+                }
+                let new_normal = cross(sub(moved[1], moved[0]), sub(moved[2], moved[0]));
+                if dot(old_normal, new_normal) <= 0.0 {
+                    return true;
+                }
+            }
+        }
+        false
+    }
+
+    /// Merges `remove` into `keep` and returns how many triangles disappeared
+    fn collapse(&mut self, collapse: &EdgeCollapse) -> usize {
+        let (keep, remove) = (collapse.keep, collapse.remove);
+        let mut removed = 0;
+
+        for triangle in std::mem::take(&mut self.vertex_triangles[remove]) {
+            if self.triangle_removed[triangle] {
+                continue;
+            }
+            let indices = &mut self.triangles[triangle];
+            if indices.contains(&keep) {
+                self.triangle_removed[triangle] = true;
+                removed += 1;
+            } else {
+                for index in indices.iter_mut() {
+                    if *index == remove {
+                        *index = keep;
+                    }
+                }
+                self.vertex_triangles[keep].push(triangle);
+            }
+        }
+
+        let removed_quadric = self.quadrics[remove];
+        self.quadrics[keep].add(&removed_quadric);
+        self.positions[keep] = collapse.position;
+        self.versions[keep] += 1;
+        self.versions[remove] += 1;
+
+        removed
+    }
+}
+
+// Unit normal and plane offset of a triangle, None for degenerate triangles
+fn plane(positions: &[[f64; 3]], indices: &[usize; 3]) -> Option<([f64; 3], f64)> {
+    let p0 = positions[indices[0]];
+    let normal = cross(sub(positions[indices[1]], p0), sub(positions[indices[2]], p0));
+    let length = dot(normal, normal).sqrt();
+    if length < 1e-12 {
+        return None;
+    }
+    let normal = [normal[0] / length, normal[1] / length, normal[2] / length];
+    Some((normal, -dot(normal, p0)))
+}
+
+fn sub(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn dot(a: [f64; 3], b: [f64; 3]) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn cross(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+impl Mesh {
+    ///
+    /// Quadric error metric simplification (Garland & Heckbert 1997) for LOD meshes.
+    /// Every vertex starts with the sum of the plane quadrics of the triangles around it,
+    /// then the cheapest edge is collapsed over and over (the merged vertex goes where it
+    /// moves the surface the least) until at most `target_triangle_count` triangles remain.
+    /// Stops early if no more edges can be collapsed without flipping a triangle.
+    /// Edges on an open border cost far more to move, so borders are kept where possible.
+    /// Triangle colors and materials are kept; unused vertices are dropped.
+    ///
+    pub fn simplify_qem(&self, target_triangle_count: usize) -> Mesh {
+        self.simplify_qem_with_max_error(target_triangle_count, f32::INFINITY)
+    }
+
+    ///
+    /// simplify_qem that also stops once the cheapest collapse left would move the surface
+    /// by more than about `max_error` (the root of its quadric error, i.e. of the summed
+    /// squared distances to the planes merged into it), whichever comes first.
+    ///
+    pub fn simplify_qem_with_max_error(&self, target_triangle_count: usize, max_error: f32) -> Mesh {
+        let max_cost = (max_error as f64).powi(2);
+        let mut simplifier = Simplifier::new(self);
+        let mut live_triangles = self.triangles.len();
+
+        // Each shared edge shows up in two triangles; only queue it once
+        let mut edges = HashSet::new();
+        for triangle in &simplifier.triangles {
+            for corner in 0..3 {
+                let (a, b) = (triangle[corner], triangle[(corner + 1) % 3]);
+                edges.insert((a.min(b), a.max(b)));
+            }
+        }
+        let mut heap: BinaryHeap<EdgeCollapse> = edges
+            .into_iter()
+            .map(|(a, b)| simplifier.evaluate(a, b))
+            .collect();
+
+        while live_triangles > target_triangle_count {
+            let Some(collapse) = heap.pop() else {
+                break;
+            };
+            if collapse.cost > max_cost {
+                break; // Everything left in the queue costs at least this much
+            }
+            if collapse.keep_version != simplifier.versions[collapse.keep]
+                || collapse.remove_version != simplifier.versions[collapse.remove] {
+                continue; // One of the vertices moved since this was queued
+            }
+            if simplifier.flips_triangle(&collapse) {
+                continue;
+            }
+
+            live_triangles -= simplifier.collapse(&collapse);
+
+            let keep = collapse.keep;
+            for neighbour in simplifier.neighbours(keep) {
+                heap.push(simplifier.evaluate(keep, neighbour));
+            }
+        }
+
+        // Build the result from the surviving triangles, renumbering the vertices they use
+        let mut result = Mesh::new();
+        let mut new_index = vec![usize::MAX; simplifier.positions.len()];
+        for (index, triangle) in self.triangles.iter().enumerate() {
+            if simplifier.triangle_removed[index] {
+                continue;
+            }
+
+            let mut indices = [0; 3];
+            for (slot, &vertex) in simplifier.triangles[index].iter().enumerate() {
+                if new_index[vertex] == usize::MAX {
+                    let [x, y, z] = simplifier.positions[vertex];
+                    new_index[vertex] = result.add_vertex(Vec3f::new(x as f32, y as f32, z as f32));
+                }
+                indices[slot] = new_index[vertex];
+            }
+
+            result.add_triangle(Triangle { indices, ..*triangle });
+        }
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn icosphere_simplifies_to_target_and_keeps_its_shape() {
+        let sphere = Mesh::create_icosphere(1.0, 3);
+        assert_eq!(sphere.triangles.len(), 1280);
+
+        let simplified = sphere.simplify_qem(320);
+        assert!(simplified.triangles.len() <= 320);
+        assert!(simplified.triangles.len() >= 300, "stopped early at {}", simplified.triangles.len());
+        assert!(simplified.validate().is_valid());
+
+        // Every vertex left is still on the unit sphere, give or take
+        let deviation = simplified.vertices.iter().map(|vertex| (vertex.length() - 1.0).abs()).fold(0.0, f32::max);
+        assert!(deviation < 0.05, "max radius deviation {deviation}");
+    }
+}
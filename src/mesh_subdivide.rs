@@ -0,0 +1,220 @@
+use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+use std::f32::consts::TAU;
+use crate::math::Vec3f;
+use crate::mesh::{Mesh, Triangle};
+
+/// How Mesh::subdivide places the vertices it adds
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Default)]
+pub enum SubdivisionMode {
+    #[default]
+    Midpoint, // New vertices at edge midpoints, nothing moves: same shape, more triangles
+    Loop,     // Loop's smoothing weights, for a surface that rounds off more each level
+}
+
+impl Mesh {
+    ///
+    /// Splits every triangle into four, `levels` times over (at most 6), sharing each new
+    /// edge vertex between the triangles on both sides of the edge. Triangles keep their
+    /// color, material and winding; their own UVs are split with them.
+    ///
+    /// Loop mode also moves the vertices with Loop's weights, treating edges used by a single
+    /// triangle as creases. It needs every edge shared by at most two triangles and falls back
+    /// to midpoints for any level where that isn't the case.
+    ///
+    /// Per-vertex normals, UVs, colors and baked AO are averaged at the new vertices. Loop
+    /// moves the surface away from the normals carried over, so call compute_smooth_normals
+    /// afterwards to recompute them. Tangents are dropped.
+    ///
+    pub fn subdivide(&mut self, levels: usize, mode: SubdivisionMode) {
+        const MAX_LEVELS: usize = 6;
+        for _ in 0..levels.min(MAX_LEVELS) {
+            self.subdivide_once(mode);
+        }
+    }
+
+    fn subdivide_once(&mut self, mode: SubdivisionMode) {
+        let vertex_count = self.vertices.len();
+        // Tangents would need recomputing on the new surface anyway; compute_tangents again if needed
+        self.tangents.clear();
+        // Which of normals, UVs, colors and AO there are one of for every vertex
+        let has_attributes = [
+            self.normals.len() == vertex_count,
+            self.uvs.len() == vertex_count,
+            self.vertex_colors.len() == vertex_count,
+            self.vertex_ao.len() == vertex_count,
+        ];
+
+        // Each edge with the corners opposite it, one per triangle using it
+        let mut edges: HashMap<(usize, usize), Vec<usize>> = HashMap::new();
+        for triangle in &self.triangles {
+            let [a, b, c] = triangle.indices;
+            for (start, end, opposite) in [(a, b, c), (b, c, a), (c, a, b)] {
+                edges.entry((start.min(end), start.max(end))).or_default().push(opposite);
+            }
+        }
+        let smooth = mode == SubdivisionMode::Loop && edges.values().all(|opposite| opposite.len() <= 2);
+
+        // Loop's new positions for the existing vertices, worked out before any of them move
+        let moved = smooth.then(|| loop_vertex_positions(&self.vertices, &edges));
+
+        // Made in triangle order, so the same mesh always gets the same vertex numbering
+        let mut edge_vertices: HashMap<(usize, usize), usize> = HashMap::with_capacity(edges.len());
+        for triangle_index in 0..self.triangles.len() {
+            let [a, b, c] = self.triangles[triangle_index].indices;
+            for (start, end) in [(a, b), (b, c), (c, a)] {
+                let key = (start.min(end), start.max(end));
+                if let Entry::Vacant(entry) = edge_vertices.entry(key) {
+                    entry.insert(self.add_edge_vertex(key, &edges[&key], smooth, has_attributes));
+                }
+            }
+        }
+
+        if let Some(moved) = moved {
+            self.vertices[..vertex_count].copy_from_slice(&moved);
+            self.mark_vertices_dirty();
+        }
+
+        let midpoint = |a: usize, b: usize| edge_vertices[&(a.min(b), a.max(b))];
+        self.triangles = self.triangles
+            .iter()
+            .flat_map(|triangle| {
+                let [a, b, c] = triangle.indices;
+                let (ab, bc, ca) = (midpoint(a, b), midpoint(b, c), midpoint(c, a));
+                let child = |indices: [usize; 3]| Triangle { indices, ..*triangle };
+                let mut children = [child([a, ab, ca]), child([b, bc, ab]), child([c, ca, bc]), child([ab, bc, ca])];
+                if let Some([uv_a, uv_b, uv_c]) = triangle.uvs {
+                    let (uv_ab, uv_bc, uv_ca) = ((uv_a + uv_b) * 0.5, (uv_b + uv_c) * 0.5, (uv_c + uv_a) * 0.5);
+                    children[0].uvs = Some([uv_a, uv_ab, uv_ca]);
+                    children[1].uvs = Some([uv_b, uv_bc, uv_ab]);
+                    children[2].uvs = Some([uv_c, uv_ca, uv_bc]);
+                    children[3].uvs = Some([uv_ab, uv_bc, uv_ca]);
+                }
+                children
+            })
+            .collect();
+    }
+
+    // The vertex splitting edge (a, b), whose triangles have `opposite` as their third corners
+    fn add_edge_vertex(&mut self, (a, b): (usize, usize), opposite: &[usize], smooth: bool, has_attributes: [bool; 4]) -> usize {
+        let position = match *opposite {
+            [c, d] if smooth => {
+                (self.vertices[a] + self.vertices[b]) * 0.375 + (self.vertices[c] + self.vertices[d]) * 0.125
+            }
+            _ => (self.vertices[a] + self.vertices[b]) * 0.5,
+        };
+        let [has_normals, has_uvs, has_colors, has_ao] = has_attributes;
+        if has_normals {
+            self.normals.push((self.normals[a] + self.normals[b]).normalize());
+        }
+        if has_uvs {
+            self.uvs.push((self.uvs[a] + self.uvs[b]) * 0.5);
+        }
+        if has_colors {
+            self.vertex_colors.push((self.vertex_colors[a] + self.vertex_colors[b]) * 0.5);
+        }
+        if has_ao {
+            self.vertex_ao.push((self.vertex_ao[a] + self.vertex_ao[b]) * 0.5);
+        }
+        self.add_vertex(position)
+    }
+}
+
+///
+/// Loop's update for the existing vertices: an interior vertex with n neighbours moves to
+/// (1 - nβ)v + β·Σneighbours, β = (5/8 - (3/8 + cos(2π/n)/4)²)/n. A vertex on a boundary
+/// only follows its two boundary neighbours, 3/4 v + 1/8 each, so open edges don't shrink
+/// inwards; one where boundaries meet (more than two boundary edges) stays put.
+///
+fn loop_vertex_positions(vertices: &[Vec3f], edges: &HashMap<(usize, usize), Vec<usize>>) -> Vec<Vec3f> {
+    let mut neighbours: Vec<Vec<usize>> = vec![Vec::new(); vertices.len()];
+    let mut boundary_neighbours: Vec<Vec<usize>> = vec![Vec::new(); vertices.len()];
+    for (&(a, b), opposite) in edges {
+        neighbours[a].push(b);
+        neighbours[b].push(a);
+        if opposite.len() == 1 {
+            boundary_neighbours[a].push(b);
+            boundary_neighbours[b].push(a);
+        }
+    }
+
+    vertices
+        .iter()
+        .enumerate()
+        .map(|(index, &vertex)| match (&boundary_neighbours[index][..], neighbours[index].len()) {
+            ([], 0) => vertex,
+            ([], n) => {
+                let n_f = n as f32;
+                let centre_weight = 0.375 + 0.25 * (TAU / n_f).cos();
+                let beta = (0.625 - centre_weight * centre_weight) / n_f;
+                let sum = neighbours[index].iter().fold(Vec3f::zero(), |sum, &neighbour| sum + vertices[neighbour]);
+                vertex * (1.0 - n_f * beta) + sum * beta
+            }
+            (&[left, right], _) => vertex * 0.75 + (vertices[left] + vertices[right]) * 0.125,
+            _ => vertex,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn each_level_quadruples_the_triangles() {
+        for mode in [SubdivisionMode::Midpoint, SubdivisionMode::Loop] {
+            let mut mesh = Mesh::create_icosphere(1.0, 0);
+            for level in 1..=3 {
+                let before = mesh.triangles.len();
+                mesh.subdivide(1, mode);
+                assert_eq!(mesh.triangles.len(), before * 4, "{mode:?} level {level}");
+            }
+            assert!(mesh.validate().is_valid());
+        }
+    }
+
+    #[test]
+    fn midpoint_subdivision_keeps_the_cube_exactly() {
+        let mut cube = Mesh::create_cube();
+        cube.subdivide(2, SubdivisionMode::Midpoint);
+        assert_eq!(cube.triangles.len(), 12 * 16);
+
+        // Midpoints of ±1 corners stay on the faces: every coordinate within ±1, one of them at ±1 exactly
+        for vertex in &cube.vertices {
+            let coordinates = [vertex.x, vertex.y, vertex.z];
+            assert!(coordinates.iter().all(|c| c.abs() <= 1.0));
+            assert!(coordinates.iter().any(|c| c.abs() == 1.0), "{:?} is off the surface", coordinates);
+        }
+        for axis in 0..3 {
+            let values = cube.vertices.iter().map(|vertex| [vertex.x, vertex.y, vertex.z][axis]);
+            let (min, max) = values.fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), v| (min.min(v), max.max(v)));
+            assert_eq!((min, max), (-1.0, 1.0));
+        }
+    }
+
+    #[test]
+    fn loop_subdivision_rounds_an_icosahedron_off() {
+        // Spread of distances from the centre over the vertices and the middles of the faces,
+        // relative to their mean: how far the surface is from a sphere, flat faces included
+        let roughness = |mesh: &Mesh| {
+            let radii: Vec<f32> = mesh.vertices
+                .iter()
+                .map(|vertex| vertex.length())
+                .chain(mesh.triangles.iter().map(|triangle| triangle.get_center(mesh).length()))
+                .collect();
+            let mean = radii.iter().sum::<f32>() / radii.len() as f32;
+            let (min, max) = radii.iter().fold((f32::INFINITY, 0.0_f32), |(min, max), &r| (min.min(r), max.max(r)));
+            (max - min) / mean
+        };
+
+        let mut mesh = Mesh::create_icosphere(1.0, 0);
+        let mut previous = roughness(&mesh);
+        for level in 1..=4 {
+            mesh.subdivide(1, SubdivisionMode::Loop);
+            let current = roughness(&mesh);
+            assert!(current < previous, "level {level}: {current} after {previous}");
+            previous = current;
+        }
+        assert!(previous < 0.02);
+    }
+}
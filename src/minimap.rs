@@ -0,0 +1,122 @@
+use crate::camera::Camera;
+use crate::math::Vec3f;
+use crate::renderer::{Renderer, Viewport};
+use crate::scene::Scene;
+
+const MINIMAP_BACKGROUND: u32 = 0xFF202020;
+const MINIMAP_OBJECT_COLOR: u32 = 0xFF909090;
+const MINIMAP_BORDER_COLOR: u32 = 0xFFFFFFFF;
+const MINIMAP_CAMERA_COLOR: u32 = 0xFFFFCC00;
+// Camera marker length as a fraction of the area the map covers
+const CAMERA_MARKER_SCALE: f32 = 0.06;
+// Empty space around the objects, as a fraction of their extent
+const MINIMAP_PADDING: f32 = 0.1;
+
+impl Scene {
+    ///
+    /// Overhead map in the `width` x `height` box at (corner_x, corner_y): every object in flat
+    /// gray, seen from straight above the middle of the scene (and the main camera) with the
+    /// scene's forward direction at the top, plus an arrow for the main camera's position
+    /// and heading, all inside a one-pixel border. Draws over whatever is there, so call it
+    /// once the frame is otherwise done; the renderer's viewport is left as it was.
+    ///
+    pub fn render_minimap(&self, renderer: &mut Renderer, width: u32, height: u32, corner_x: u32, corner_y: u32) {
+        if width == 0 || height == 0 {
+            return;
+        }
+        let system = self.coordinate_system;
+        let up = system.up();
+        let flatten = |point: Vec3f| point - up * point.dot(&up);
+
+        // Horizontal extent of every object's bounding sphere and the camera, and the highest and lowest points
+        let mut min = flatten(self.camera.position);
+        let mut max = min;
+        let mut top = self.camera.position.dot(&up);
+        let mut bottom = top;
+        for game_object in &self.game_objects {
+            let (center, radius) = game_object.get_bounding_sphere();
+            let flat = flatten(center);
+            min = Vec3f::new(min.x.min(flat.x - radius), min.y.min(flat.y - radius), min.z.min(flat.z - radius));
+            max = Vec3f::new(max.x.max(flat.x + radius), max.y.max(flat.y + radius), max.z.max(flat.z + radius));
+            top = top.max(center.dot(&up) + radius);
+            bottom = bottom.min(center.dot(&up) - radius);
+        }
+        let center = flatten((min + max) * 0.5);
+        let half_extent = (flatten(max - min).length() * 0.5 * (1.0 + MINIMAP_PADDING)).max(1.0);
+
+        // Far enough up that the whole extent fits the narrower side of the view
+        let mut map_camera = Camera::new(Vec3f::zero(), center, system.from_y_up(Vec3f::forward()));
+        map_camera.coordinate_system = system;
+        map_camera.set_aspect_ratio(width as f32, height as f32);
+        let half_fov = map_camera.fov * 0.5;
+        let fit = half_extent / (half_fov.tan() * map_camera.aspect.min(1.0));
+        map_camera.position = center + up * (top + fit);
+        map_camera.near = fit * 0.5;
+        map_camera.far = top + fit - bottom + 1.0;
+        let vp_matrix = map_camera.get_view_projection_matrix();
+
+        let previous_viewport = renderer.get_viewport();
+        let viewport = Viewport::new(corner_x, corner_y, width, height);
+        renderer.set_viewport(viewport);
+        renderer.clear_viewport(MINIMAP_BACKGROUND);
+
+        // Screen position and depth, or None outside the map
+        let project = |point: Vec3f| {
+            let clip = vp_matrix.multiply_point_4d(&point);
+            if clip.w <= 0.0 {
+                return None;
+            }
+            let ndc = Vec3f::new(clip.x / clip.w, clip.y / clip.w, clip.z / clip.w);
+            let inside = |value: f32| (-1.0..=1.0).contains(&value);
+            (inside(ndc.x) && inside(ndc.y) && inside(ndc.z))
+                .then(|| (viewport.ndc_to_screen(ndc.x, ndc.y), ndc.z * 0.5 + 0.5))
+        };
+
+        for game_object in &self.game_objects {
+            let world_vertices = game_object.mesh.transform_vertices(&game_object.get_world_matrix());
+            for triangle in &game_object.mesh.triangles {
+                let corners = triangle.indices.map(|index| world_vertices.get(index).and_then(|&vertex| project(vertex)));
+                if let [Some((s0, z0)), Some((s1, z1)), Some((s2, z2))] = corners {
+                    renderer.draw_triangle(s0, s1, s2, z0, z1, z2, MINIMAP_OBJECT_COLOR);
+                }
+            }
+        }
+
+        // Arrow for the main camera, pointing along its heading; drawn in front of everything
+        let forward = flatten(self.camera.get_forward_vector());
+        let forward = if forward.length() > 1e-4 { forward.normalize() } else { system.from_y_up(Vec3f::forward()) };
+        let side = forward.cross(&up).normalize();
+        let size = half_extent * CAMERA_MARKER_SCALE;
+        let position = self.camera.position;
+        let marker = [
+            position + forward * size,
+            position - forward * (size * 0.6) + side * (size * 0.6),
+            position - forward * (size * 0.6) - side * (size * 0.6),
+        ];
+        if let [Some((s0, _)), Some((s1, _)), Some((s2, _))] = marker.map(project) {
+            renderer.draw_triangle(s0, s1, s2, 0.0, 0.0, 0.0, MINIMAP_CAMERA_COLOR);
+        }
+
+        renderer.set_viewport(previous_viewport);
+
+        let (left, top) = (corner_x as i32 - 1, corner_y as i32 - 1);
+        let (right, bottom) = ((corner_x + width) as i32, (corner_y + height) as i32);
+        renderer.draw_line(left, top, right, top, MINIMAP_BORDER_COLOR);
+        renderer.draw_line(right, top, right, bottom, MINIMAP_BORDER_COLOR);
+        renderer.draw_line(right, bottom, left, bottom, MINIMAP_BORDER_COLOR);
+        renderer.draw_line(left, bottom, left, top, MINIMAP_BORDER_COLOR);
+    }
+
+    /// Where the minimap goes when show_minimap is on: a square a quarter of the window's height, bottom right
+    pub(crate) fn minimap_rect(renderer: &Renderer) -> Viewport {
+        const MARGIN: u32 = 8;
+        let (width, height) = renderer.get_dimension();
+        let size = (height / 4).max(32).min(width.saturating_sub(2 * MARGIN).max(1));
+        Viewport::new(
+            width.saturating_sub(size + MARGIN),
+            height.saturating_sub(size + MARGIN),
+            size,
+            size,
+        )
+    }
+}
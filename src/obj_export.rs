@@ -0,0 +1,242 @@
+use std::fmt::Write;
+use crate::material_registry::MaterialRegistry;
+use crate::mesh::Mesh;
+use crate::scene::GameObject;
+
+/// What Mesh::write_obj puts in the file besides positions and faces
+#[derive(Copy, Clone, Debug)]
+pub struct ObjExportOptions {
+    pub include_normals: bool, // Per-vertex normals when the mesh has them, else one per face
+    pub include_uvs: bool,     // Per-vertex UVs when the mesh has them, else each triangle's own
+}
+
+impl ObjExportOptions {
+    pub fn new() -> Self {
+        Self { include_normals: true, include_uvs: true }
+    }
+
+    pub fn with_normals(mut self, include_normals: bool) -> Self {
+        self.include_normals = include_normals;
+        self
+    }
+
+    pub fn with_uvs(mut self, include_uvs: bool) -> Self {
+        self.include_uvs = include_uvs;
+        self
+    }
+}
+
+impl Default for ObjExportOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Mesh {
+    ///
+    /// Saves the mesh as a Wavefront OBJ file, for other tools to open. Material slots become
+    /// `usemtl material_<slot>` groups; GameObject::write_obj names registry materials instead.
+    ///
+    pub fn write_obj(&self, path: &str, options: ObjExportOptions) -> std::io::Result<()> {
+        std::fs::write(path, self.to_obj(options, |slot| format!("material_{}", slot)))
+    }
+
+    ///
+    /// The OBJ text write_obj saves: `v` for every vertex, then `vn` and `vt` as `options`
+    /// ask, then `f` records (1-based) grouped by material slot under `usemtl` lines named by
+    /// `material_name`. V is flipped for `vt`, as OBJ puts v = 0 at the bottom of the image.
+    ///
+    pub fn to_obj(&self, options: ObjExportOptions, material_name: impl Fn(usize) -> String) -> String {
+        let mut obj = String::new();
+        let _ = writeln!(obj, "# {} vertices, {} triangles", self.vertices.len(), self.triangles.len());
+        for vertex in &self.vertices {
+            let _ = writeln!(obj, "v {} {} {}", vertex.x, vertex.y, vertex.z);
+        }
+
+        // Corner -> 1-based vn / vt index, where the corner has one
+        let mut corner_normals = vec![[None; 3]; self.triangles.len()];
+        if options.include_normals {
+            if let Some(normals) = self.vertex_normals() {
+                for normal in normals {
+                    let _ = writeln!(obj, "vn {} {} {}", normal.x, normal.y, normal.z);
+                }
+                for (corners, triangle) in corner_normals.iter_mut().zip(&self.triangles) {
+                    *corners = triangle.indices.map(|index| Some(index + 1));
+                }
+            } else {
+                for (index, (corners, triangle)) in corner_normals.iter_mut().zip(&self.triangles).enumerate() {
+                    let normal = triangle.calculate_normal(self);
+                    let _ = writeln!(obj, "vn {} {} {}", normal.x, normal.y, normal.z);
+                    *corners = [Some(index + 1); 3];
+                }
+            }
+        }
+
+        // Triangles' own UVs win over per-vertex ones, as in Mesh::triangle_uvs
+        let mut corner_uvs = vec![[None; 3]; self.triangles.len()];
+        if options.include_uvs {
+            let per_vertex = !self.uvs.is_empty() && self.uvs.len() == self.vertices.len();
+            let mut written = 0;
+            if per_vertex {
+                for uv in &self.uvs {
+                    let _ = writeln!(obj, "vt {} {}", uv.x, 1.0 - uv.y);
+                }
+                written = self.uvs.len();
+            }
+            for (corners, triangle) in corner_uvs.iter_mut().zip(&self.triangles) {
+                if let Some(uvs) = triangle.uvs {
+                    for (corner, uv) in corners.iter_mut().zip(uvs) {
+                        let _ = writeln!(obj, "vt {} {}", uv.x, 1.0 - uv.y);
+                        written += 1;
+                        *corner = Some(written);
+                    }
+                } else if per_vertex {
+                    *corners = triangle.indices.map(|index| Some(index + 1));
+                }
+            }
+        }
+
+        // Stable, so each group keeps the mesh's triangle order
+        let mut order: Vec<usize> = (0..self.triangles.len()).collect();
+        order.sort_by_key(|&index| self.triangles[index].material_id.unwrap_or(0));
+        let mut current_slot = None;
+        for index in order {
+            let triangle = &self.triangles[index];
+            let slot = triangle.material_id.unwrap_or(0);
+            if current_slot != Some(slot) {
+                let _ = writeln!(obj, "usemtl {}", material_name(slot));
+                current_slot = Some(slot);
+            }
+            obj.push('f');
+            for corner in 0..3 {
+                let vertex = triangle.indices[corner] + 1;
+                let _ = match (corner_uvs[index][corner], corner_normals[index][corner]) {
+                    (Some(uv), Some(normal)) => write!(obj, " {}/{}/{}", vertex, uv, normal),
+                    (Some(uv), None) => write!(obj, " {}/{}", vertex, uv),
+                    (None, Some(normal)) => write!(obj, " {}//{}", vertex, normal),
+                    (None, None) => write!(obj, " {}", vertex),
+                };
+            }
+            obj.push('\n');
+        }
+        obj
+    }
+}
+
+impl GameObject {
+    ///
+    /// Mesh::write_obj for the object's mesh (in its own space, not placed in the world),
+    /// with each slot that uses a registry material named after it in `usemtl`.
+    ///
+    pub fn write_obj(&self, path: &str, options: ObjExportOptions, registry: &MaterialRegistry) -> std::io::Result<()> {
+        let obj = self.mesh.to_obj(options, |slot| {
+            self.shared_materials
+                .get(slot)
+                .copied()
+                .flatten()
+                .and_then(|handle| registry.name_of(handle))
+                .map(|name| name.replace(char::is_whitespace, "_"))
+                .unwrap_or_else(|| format!("material_{}", slot))
+        });
+        std::fs::write(path, obj)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::Vec3f;
+
+    #[test]
+    fn cube_exports_its_vertices_and_triangles() {
+        let cube = Mesh::create_cube();
+        let obj = cube.to_obj(ObjExportOptions::new(), |slot| format!("material_{}", slot));
+
+        let mut positions = Vec::new();
+        let mut faces = Vec::new();
+        for line in obj.lines() {
+            let mut words = line.split_whitespace();
+            match words.next() {
+                Some("v") => {
+                    let xyz: Vec<f32> = words.map(|word| word.parse().unwrap()).collect();
+                    positions.push(Vec3f::new(xyz[0], xyz[1], xyz[2]));
+                }
+                // v, v/vt, v//vn or v/vt/vn: the position index comes first either way
+                Some("f") => {
+                    let corners: Vec<usize> = words.map(|word| word.split('/').next().unwrap().parse().unwrap()).collect();
+                    assert_eq!(corners.len(), 3, "{line}");
+                    faces.push([corners[0], corners[1], corners[2]]);
+                }
+                _ => {}
+            }
+        }
+
+        assert_eq!(positions.len(), cube.vertices.len());
+        for (exported, vertex) in positions.iter().zip(&cube.vertices) {
+            assert_eq!((exported.x, exported.y, exported.z), (vertex.x, vertex.y, vertex.z));
+        }
+
+        // 1-based, and the same triangles with the same winding (grouping may reorder them)
+        assert!(faces.iter().flatten().all(|&index| index >= 1 && index <= cube.vertices.len()));
+        let mut expected: Vec<[usize; 3]> = cube.triangles.iter().map(|triangle| triangle.indices.map(|index| index + 1)).collect();
+        faces.sort_unstable();
+        expected.sort_unstable();
+        assert_eq!(faces, expected);
+    }
+
+    fn same_topology(read: &Mesh, written: &Mesh) {
+        assert_eq!(read.vertices.len(), written.vertices.len());
+        for (read, written) in read.vertices.iter().zip(&written.vertices) {
+            assert_eq!((read.x, read.y, read.z), (written.x, written.y, written.z));
+        }
+        assert_eq!(read.triangles.len(), written.triangles.len());
+        for (read, written) in read.triangles.iter().zip(&written.triangles) {
+            assert_eq!(read.indices, written.indices);
+        }
+    }
+
+    #[test]
+    fn cube_reads_back_from_its_obj() {
+        let cube = Mesh::create_cube();
+        let path = std::env::temp_dir().join(format!("obj_cube_{}.obj", std::process::id()));
+        let path = path.to_str().unwrap();
+        cube.write_obj(path, ObjExportOptions::new()).unwrap();
+        let read = Mesh::load_obj(path).unwrap();
+        let _ = std::fs::remove_file(path);
+
+        same_topology(&read, &cube);
+        // Exported with one normal per face, which can't be kept per vertex
+        assert!(read.vertex_normals().is_none());
+        for (read_triangle, triangle) in read.triangles.iter().zip(&cube.triangles) {
+            let (read_uvs, uvs) = (read.triangle_uvs(read_triangle).unwrap(), cube.triangle_uvs(triangle).unwrap());
+            for (read_uv, uv) in read_uvs.iter().zip(&uvs) {
+                assert!((read_uv.x - uv.x).abs() < 1e-6 && (read_uv.y - uv.y).abs() < 1e-6);
+            }
+        }
+    }
+
+    #[test]
+    fn terrain_chunk_reads_back_with_its_normals_and_uvs() {
+        let heights: Vec<f32> = (0..6 * 5).map(|i| ((i * 7) % 11) as f32 * 0.3).collect();
+        let terrain = Mesh::from_heightmap(&heights, 6, 5, 1.5, 2.0).unwrap();
+        let read = Mesh::from_obj(&terrain.to_obj(ObjExportOptions::new(), |slot| format!("material_{}", slot))).unwrap();
+
+        same_topology(&read, &terrain);
+        let (read_normals, normals) = (read.vertex_normals().unwrap(), terrain.vertex_normals().unwrap());
+        for (read, written) in read_normals.iter().zip(normals) {
+            assert_eq!((read.x, read.y, read.z), (written.x, written.y, written.z));
+        }
+        for (read_triangle, triangle) in read.triangles.iter().zip(&terrain.triangles) {
+            let (read_uvs, uvs) = (read.triangle_uvs(read_triangle).unwrap(), terrain.triangle_uvs(triangle).unwrap());
+            for (read_uv, uv) in read_uvs.iter().zip(&uvs) {
+                assert!((read_uv.x - uv.x).abs() < 1e-6 && (read_uv.y - uv.y).abs() < 1e-6);
+            }
+        }
+
+        // Without normals and UVs only positions and faces come back
+        let bare = Mesh::from_obj(&terrain.to_obj(ObjExportOptions::new().with_normals(false).with_uvs(false), |_| "m".into())).unwrap();
+        same_topology(&bare, &terrain);
+        assert!(bare.vertex_normals().is_none());
+        assert!(bare.triangles.iter().all(|triangle| bare.triangle_uvs(triangle).is_none()));
+    }
+}
@@ -0,0 +1,153 @@
+use std::collections::HashMap;
+use crate::math::{Vec2f, Vec3f};
+use crate::mesh::{Mesh, Triangle};
+
+impl Mesh {
+    /// Reads a Wavefront OBJ file, see from_obj
+    pub fn load_obj(path: &str) -> std::io::Result<Mesh> {
+        Self::from_obj(&std::fs::read_to_string(path)?)
+    }
+
+    ///
+    /// A mesh from OBJ text: `v` positions become the vertices in file order, and each `f`
+    /// (polygons split into fans) a triangle on them. `vt` UVs go on the triangles, with V
+    /// flipped back to this renderer's top-down convention. `vn` normals are kept per vertex
+    /// only when every corner of a vertex names the same one; otherwise (say, one per face)
+    /// they're dropped and the winding gives flat shading. Each new `usemtl` name takes the
+    /// next material slot. Other records are skipped.
+    ///
+    pub fn from_obj(text: &str) -> std::io::Result<Mesh> {
+        let mut mesh = Mesh::new();
+        let mut normals: Vec<Vec3f> = Vec::new();
+        let mut uvs: Vec<Vec2f> = Vec::new();
+        let mut slots: HashMap<String, usize> = HashMap::new();
+        let mut current_slot = None;
+        // The vn index each vertex's corners have used so far; Err once they disagree or one has none
+        let mut vertex_normals: Vec<Option<Result<usize, ()>>> = Vec::new();
+
+        for line in text.lines() {
+            let mut words = line.split_whitespace();
+            match words.next() {
+                Some("v") => {
+                    let [x, y, z] = numbers(words, line)?;
+                    mesh.add_vertex(Vec3f::new(x, y, z));
+                    vertex_normals.push(None);
+                }
+                Some("vn") => {
+                    let [x, y, z] = numbers(words, line)?;
+                    normals.push(Vec3f::new(x, y, z));
+                }
+                Some("vt") => {
+                    let [u, v] = numbers(words, line)?;
+                    uvs.push(Vec2f::new(u, 1.0 - v));
+                }
+                Some("usemtl") => {
+                    let name = words.next().ok_or_else(|| bad_line(line))?;
+                    let next = slots.len();
+                    current_slot = Some(*slots.entry(name.to_string()).or_insert(next));
+                }
+                Some("f") => {
+                    let corners = words
+                        .map(|word| corner(word, mesh.vertices.len(), uvs.len(), normals.len()).ok_or_else(|| bad_line(line)))
+                        .collect::<std::io::Result<Vec<_>>>()?;
+                    if corners.len() < 3 {
+                        return Err(bad_line(line));
+                    }
+                    for &(vertex, _, normal) in &corners {
+                        let seen = &mut vertex_normals[vertex];
+                        *seen = match (*seen, normal) {
+                            (None, Some(normal)) => Some(Ok(normal)),
+                            (Some(Ok(previous)), Some(normal)) if previous == normal => Some(Ok(normal)),
+                            _ => Some(Err(())),
+                        };
+                    }
+                    for fan in 1..corners.len() - 1 {
+                        let [a, b, c] = [corners[0], corners[fan], corners[fan + 1]];
+                        let mut triangle = Triangle::new(a.0, b.0, c.0, 0xFFFFFFFF);
+                        triangle.material_id = current_slot;
+                        if let (Some(uv_a), Some(uv_b), Some(uv_c)) = (a.1, b.1, c.1) {
+                            triangle = triangle.with_uvs(uvs[uv_a], uvs[uv_b], uvs[uv_c]);
+                        }
+                        mesh.add_triangle(triangle);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let per_vertex: Option<Vec<Vec3f>> = vertex_normals
+            .iter()
+            .map(|seen| match seen {
+                Some(Ok(normal)) => Some(normals[*normal]),
+                _ => None,
+            })
+            .collect();
+        if let Some(per_vertex) = per_vertex.filter(|per_vertex| !per_vertex.is_empty()) {
+            mesh.normals = per_vertex;
+        }
+        Ok(mesh)
+    }
+}
+
+// The first N numbers on a record; extra ones (like a `v`'s w) are ignored
+fn numbers<const N: usize>(mut words: std::str::SplitWhitespace, line: &str) -> std::io::Result<[f32; N]> {
+    let mut values = [0.0; N];
+    for value in &mut values {
+        *value = words.next().and_then(|word| word.parse().ok()).ok_or_else(|| bad_line(line))?;
+    }
+    Ok(values)
+}
+
+// One `f` corner, v, v/vt, v//vn or v/vt/vn, as 0-based indices; negative ones count back from the end
+fn corner(word: &str, vertex_count: usize, uv_count: usize, normal_count: usize) -> Option<(usize, Option<usize>, Option<usize>)> {
+    let mut parts = word.split('/');
+    let vertex = index(parts.next()?, vertex_count)?;
+    let uv = match parts.next() {
+        None | Some("") => None,
+        Some(part) => Some(index(part, uv_count)?),
+    };
+    let normal = match parts.next() {
+        None | Some("") => None,
+        Some(part) => Some(index(part, normal_count)?),
+    };
+    Some((vertex, uv, normal))
+}
+
+fn index(word: &str, count: usize) -> Option<usize> {
+    let index: isize = word.parse().ok()?;
+    let index = if index < 0 { count as isize + index } else { index - 1 };
+    (0..count as isize).contains(&index).then_some(index as usize)
+}
+
+fn bad_line(line: &str) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, format!("bad obj line: {}", line))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quads_become_fans_and_materials_take_slots_in_order() {
+        let obj = "v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nv 2 0 0\n\
+                   usemtl stone\nf 1 2 3 4\nusemtl grass\nf -4 5 -3\nusemtl stone\nf 1 3 4\n";
+        let mesh = Mesh::from_obj(obj).unwrap();
+
+        let faces: Vec<([usize; 3], Option<usize>)> =
+            mesh.triangles.iter().map(|triangle| (triangle.indices, triangle.material_id)).collect();
+        assert_eq!(faces, vec![
+            ([0, 1, 2], Some(0)),
+            ([0, 2, 3], Some(0)),
+            ([1, 4, 2], Some(1)),
+            ([0, 2, 3], Some(0)),
+        ]);
+    }
+
+    #[test]
+    fn malformed_records_are_rejected() {
+        for obj in ["v 1 2\n", "v 0 0 0\nf 1 2 3\n", "v 0 0 0\nv 1 0 0\nf 1 2\n", "vt x 0\n", "usemtl\n"] {
+            let error = Mesh::from_obj(obj).err().unwrap_or_else(|| panic!("{obj:?} should fail"));
+            assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+        }
+    }
+}
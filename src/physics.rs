@@ -0,0 +1,214 @@
+use crate::math::Vec3f;
+use crate::scene::{GameObject, Scene};
+
+// Passes over the contacts per step; more settles stacks better
+const DEFAULT_SOLVER_ITERATIONS: usize = 8;
+// Overlap left alone so resting contacts don't jitter
+const PENETRATION_SLOP: f32 = 0.01;
+// Fraction of the remaining overlap pushed out per step
+const POSITION_CORRECTION: f32 = 0.8;
+
+///
+/// A box-shaped body moved by the PhysicsWorld, colliding as the world-space axis aligned
+/// box around its object's mesh. It has its own velocity; GameObject::velocity (used by
+/// springs) isn't touched. A mass of zero or less makes it static: it collides but never
+/// moves, like a floor.
+///
+#[derive(Copy, Clone, Debug)]
+pub struct RigidBody {
+    pub game_object_index: usize,
+    pub velocity: Vec3f,
+    pub angular_velocity: Vec3f, // Radians per second around each axis, added to the object's rotation
+    pub mass: f32,
+    pub restitution: f32, // Bounciness: 0 stops dead, 1 bounces back at full speed
+}
+
+impl RigidBody {
+    pub fn new(game_object_index: usize, mass: f32) -> Self {
+        Self {
+            game_object_index,
+            velocity: Vec3f::zero(),
+            angular_velocity: Vec3f::zero(),
+            mass,
+            restitution: 0.3,
+        }
+    }
+
+    pub fn with_velocity(mut self, velocity: Vec3f) -> Self {
+        self.velocity = velocity;
+        self
+    }
+
+    pub fn with_angular_velocity(mut self, angular_velocity: Vec3f) -> Self {
+        self.angular_velocity = angular_velocity;
+        self
+    }
+
+    pub fn with_restitution(mut self, restitution: f32) -> Self {
+        self.restitution = restitution;
+        self
+    }
+
+    pub fn is_static(&self) -> bool {
+        self.mass <= 0.0
+    }
+
+    fn inverse_mass(&self) -> f32 {
+        if self.is_static() { 0.0 } else { 1.0 / self.mass }
+    }
+}
+
+/// Two overlapping bodies, by index into PhysicsWorld::bodies
+struct Contact {
+    a: usize,
+    b: usize,
+    normal: Vec3f, // Unit axis from a towards b
+    penetration: f32,
+}
+
+///
+/// Rigid boxes under gravity, see Scene::enable_physics.
+/// Each step integrates velocities and positions, then resolves overlaps with impulses
+/// j = -(1 + e) (v_rel · n) / (1/m_a + 1/m_b) along the contact normal, one contact at a
+/// time and solver_iterations times over (Gauss-Seidel), so each impulse sees the ones
+/// before it. Boxes don't tip over: contacts carry no torque.
+///
+pub struct PhysicsWorld {
+    pub bodies: Vec<RigidBody>,
+    pub gravity: Vec3f,
+    pub solver_iterations: usize,
+}
+
+impl PhysicsWorld {
+    pub fn new() -> Self {
+        Self {
+            bodies: Vec::new(),
+            gravity: Vec3f::new(0.0, -9.81, 0.0),
+            solver_iterations: DEFAULT_SOLVER_ITERATIONS,
+        }
+    }
+
+    pub fn add_body(&mut self, body: RigidBody) {
+        self.bodies.push(body);
+    }
+
+    pub fn has_body(&self, game_object_index: usize) -> bool {
+        self.bodies.iter().any(|body| body.game_object_index == game_object_index)
+    }
+
+    pub fn update(&mut self, scene: &mut Scene, delta_time: f32) {
+        if delta_time <= 0.0 {
+            return;
+        }
+        // Bodies whose object has gone are dropped rather than left pointing at the wrong one
+        self.bodies.retain(|body| body.game_object_index < scene.game_objects.len());
+
+        for body in self.bodies.iter_mut().filter(|body| !body.is_static()) {
+            body.velocity = body.velocity + self.gravity * delta_time;
+            let game_object = &mut scene.game_objects[body.game_object_index];
+            game_object.set_position(game_object.position + body.velocity * delta_time);
+            if body.angular_velocity.length() > 0.0 {
+                game_object.set_rotation(game_object.rotation + body.angular_velocity * delta_time);
+            }
+        }
+
+        let contacts = self.find_contacts(&scene.game_objects);
+
+        for _ in 0..self.solver_iterations {
+            for contact in &contacts {
+                self.resolve_velocity(contact);
+            }
+        }
+
+        // Push overlapping bodies apart, the lighter one further
+        for contact in &contacts {
+            let (body_a, body_b) = (&self.bodies[contact.a], &self.bodies[contact.b]);
+            let (inverse_a, inverse_b) = (body_a.inverse_mass(), body_b.inverse_mass());
+            let correction = (contact.penetration - PENETRATION_SLOP).max(0.0) * POSITION_CORRECTION
+                / (inverse_a + inverse_b);
+            let push = contact.normal * correction;
+
+            let object_a = &mut scene.game_objects[body_a.game_object_index];
+            object_a.set_position(object_a.position - push * inverse_a);
+            let object_b = &mut scene.game_objects[body_b.game_object_index];
+            object_b.set_position(object_b.position + push * inverse_b);
+        }
+    }
+
+    fn resolve_velocity(&mut self, contact: &Contact) {
+        let (body_a, body_b) = (self.bodies[contact.a], self.bodies[contact.b]);
+        let approach = (body_b.velocity - body_a.velocity).dot(&contact.normal);
+        if approach >= 0.0 {
+            return; // Already separating
+        }
+
+        let restitution = body_a.restitution.min(body_b.restitution);
+        let (inverse_a, inverse_b) = (body_a.inverse_mass(), body_b.inverse_mass());
+        let impulse = contact.normal * (-(1.0 + restitution) * approach / (inverse_a + inverse_b));
+
+        self.bodies[contact.a].velocity = body_a.velocity - impulse * inverse_a;
+        self.bodies[contact.b].velocity = body_b.velocity + impulse * inverse_b;
+    }
+
+    // Every overlapping pair with at least one moving body
+    fn find_contacts(&self, game_objects: &[GameObject]) -> Vec<Contact> {
+        let boxes: Vec<(Vec3f, Vec3f)> = self.bodies
+            .iter()
+            .map(|body| world_bounds(&game_objects[body.game_object_index]))
+            .collect();
+
+        let mut contacts = Vec::new();
+        for a in 0..self.bodies.len() {
+            for b in a + 1..self.bodies.len() {
+                if self.bodies[a].is_static() && self.bodies[b].is_static() {
+                    continue;
+                }
+                if let Some((normal, penetration)) = box_overlap(boxes[a], boxes[b]) {
+                    contacts.push(Contact { a, b, normal, penetration });
+                }
+            }
+        }
+        contacts
+    }
+}
+
+impl Default for PhysicsWorld {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// World-space axis aligned box (min, max) around the object's transformed mesh
+fn world_bounds(game_object: &GameObject) -> (Vec3f, Vec3f) {
+    let vertices = game_object.mesh.transform_vertices(&game_object.get_world_matrix());
+    let Some(&first) = vertices.first() else {
+        return (game_object.position, game_object.position);
+    };
+    vertices.iter().fold((first, first), |(min, max), vertex| {
+        (
+            Vec3f::new(min.x.min(vertex.x), min.y.min(vertex.y), min.z.min(vertex.z)),
+            Vec3f::new(max.x.max(vertex.x), max.y.max(vertex.y), max.z.max(vertex.z)),
+        )
+    })
+}
+
+///
+/// The axis two boxes overlap least along (pointing from a to b) and by how much,
+/// or None when they don't overlap.
+///
+fn box_overlap((min_a, max_a): (Vec3f, Vec3f), (min_b, max_b): (Vec3f, Vec3f)) -> Option<(Vec3f, f32)> {
+    let axes = [
+        (max_a.x.min(max_b.x) - min_a.x.max(min_b.x), (min_b.x + max_b.x) - (min_a.x + max_a.x), Vec3f::x_axis()),
+        (max_a.y.min(max_b.y) - min_a.y.max(min_b.y), (min_b.y + max_b.y) - (min_a.y + max_a.y), Vec3f::y_axis()),
+        (max_a.z.min(max_b.z) - min_a.z.max(min_b.z), (min_b.z + max_b.z) - (min_a.z + max_a.z), Vec3f::z_axis()),
+    ];
+    if axes.iter().any(|(overlap, _, _)| *overlap <= 0.0) {
+        return None;
+    }
+
+    let (overlap, center_offset, axis) = axes
+        .into_iter()
+        .min_by(|a, b| a.0.total_cmp(&b.0))?;
+    let normal = if center_offset < 0.0 { -axis } else { axis };
+    Some((normal, overlap))
+}
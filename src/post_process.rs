@@ -0,0 +1,356 @@
+use crate::camera::Camera;
+use crate::math::Vec3f;
+
+/// Everything a post effect gets to work with after the scene is rasterized
+pub struct PostContext<'a> {
+    pub framebuffer: &'a mut [u32], // ARGB Pixels
+    pub depth_buffer: &'a [f32],    // View distance / camera.far, INFINITY for empty pixels
+    pub width: u32,
+    pub height: u32,
+    pub camera: &'a Camera,
+}
+
+/// A full-screen effect run after all geometry has been drawn
+pub trait PostEffect {
+    fn apply(&self, context: &mut PostContext);
+}
+
+///
+/// Cheap depth of field.
+/// Every pixel gets a circle of confusion from the camera's focus settings, and is then
+/// blurred with a separable box blur whose radius scales with it (0 = untouched).
+///
+pub struct DepthOfField {
+    pub max_radius: usize, // Blur radius in pixels for a fully defocused pixel
+}
+
+impl DepthOfField {
+    pub fn new(max_radius: usize) -> Self {
+        Self { max_radius }
+    }
+}
+
+impl PostEffect for DepthOfField {
+    fn apply(&self, context: &mut PostContext) {
+        let width = context.width as usize;
+        let height = context.height as usize;
+        if self.max_radius == 0 || width == 0 || height == 0 {
+            return;
+        }
+
+        let camera = context.camera;
+        let depth = context.depth_buffer;
+        let coc: Vec<f32> = depth
+            .iter()
+            .map(|d| camera.circle_of_confusion(d * camera.far))
+            .collect();
+
+        let colors: Vec<[f32; 3]> = context.framebuffer.iter().map(|c| unpack(*c)).collect();
+
+        // Horizontal pass, then vertical pass over its result
+        let horizontal = self.blur_pass(&colors, &coc, depth, 1, width);
+        let blurred = self.blur_pass(&horizontal, &coc, depth, width, height);
+
+        for (pixel, color) in context.framebuffer.iter_mut().zip(&blurred) {
+            *pixel = pack(*color);
+        }
+    }
+}
+
+impl DepthOfField {
+    ///
+    /// One direction of the blur. `stride` is the index step between neighbouring samples
+    /// and `line_length` how many samples fit on a row/column.
+    /// A sharp sample that is in front of the pixel being blurred is skipped, so in-focus
+    /// foreground objects don't smear a halo into the blurry background behind them.
+    ///
+    fn blur_pass(&self, colors: &[[f32; 3]], coc: &[f32], depth: &[f32],
+                 stride: usize, line_length: usize) -> Vec<[f32; 3]> {
+        let mut result = colors.to_vec();
+
+        for index in 0..colors.len() {
+            let radius = (coc[index] * self.max_radius as f32).round() as usize;
+            if radius == 0 {
+                continue;
+            }
+
+            // Position of this pixel along the row (stride 1) or column (stride = width)
+            let position = (index / stride) % line_length;
+            let start = position.saturating_sub(radius);
+            let end = (position + radius).min(line_length - 1);
+
+            let mut sum = [0.0; 3];
+            let mut count = 0.0;
+            for sample_position in start..=end {
+                let sample = index - position * stride + sample_position * stride;
+                let sharp_foreground = depth[sample] < depth[index] && coc[sample] < coc[index];
+                if sample != index && sharp_foreground {
+                    continue;
+                }
+                for channel in 0..3 {
+                    sum[channel] += colors[sample][channel];
+                }
+                count += 1.0;
+            }
+
+            result[index] = [sum[0] / count, sum[1] / count, sum[2] / count];
+        }
+
+        result
+    }
+}
+
+// Largest sampling disk in pixels, however close the surface is
+const SSAO_MAX_PIXEL_RADIUS: f32 = 48.0;
+// Samples rising less steeply than this (sine of the angle above the surface) don't occlude.
+// The depth buffer is interpolated linearly on screen, which bows flat triangles away from
+// the camera a little; without this their insides would darken.
+const SSAO_MIN_ELEVATION: f32 = 0.15;
+// Half-width of the box blur applied to the raw occlusion
+const SSAO_BLUR_RADIUS: usize = 2;
+// Neighbours whose depth differs by more than this fraction are left out of the blur
+const SSAO_BLUR_DEPTH_TOLERANCE: f32 = 0.05;
+
+///
+/// Screen-space ambient occlusion from the depth buffer.
+/// Each pixel is turned back into a view-space point, with a normal taken from its
+/// neighbours' depths. `samples` depths on a disk of `radius` world units around it are
+/// read, and one counts as occluding when its point is within `radius` of the pixel's and
+/// more than `bias` above the surface along the normal (and not at a shallow angle). The
+/// fraction of samples that occlude is blurred (without mixing across depth edges) and
+/// darkens the pixel by up to `strength`. A flat, open surface occludes nothing whatever
+/// its angle; creases and contacts darken.
+///
+/// There's no separate ambient buffer, so the whole color is darkened, not just the ambient light.
+///
+pub struct ScreenSpaceAmbientOcclusion {
+    pub radius: f32,    // World units
+    pub bias: f32,      // World units above the surface a sample must be to count
+    pub strength: f32,  // 0 leaves the image alone, 1 turns fully occluded pixels black
+    pub samples: usize, // Per pixel; the disk is rotated per pixel and the blur hides the noise
+}
+
+impl ScreenSpaceAmbientOcclusion {
+    pub fn new(radius: f32, bias: f32, strength: f32) -> Self {
+        Self { radius, bias, strength: strength.clamp(0.0, 1.0), samples: 12 }
+    }
+
+    pub fn with_samples(mut self, samples: usize) -> Self {
+        self.samples = samples;
+        self
+    }
+
+    ///
+    /// Blurred occlusion per pixel, 0 (open) to 1 (every sample occluded), 0 where nothing
+    /// was drawn. `depth_buffer` is as in PostContext.
+    ///
+    pub fn occlusion(&self, depth_buffer: &[f32], width: u32, height: u32, camera: &Camera) -> Vec<f32> {
+        let (width, height) = (width as usize, height as usize);
+        let positions = view_positions(depth_buffer, width, height, camera);
+        if self.samples == 0 || self.radius <= 0.0 {
+            return vec![0.0; positions.len()];
+        }
+
+        // Golden-angle spiral filling the unit disk evenly
+        let disk: Vec<(f32, f32)> = (0..self.samples)
+            .map(|i| {
+                let distance = ((i as f32 + 0.5) / self.samples as f32).sqrt();
+                let angle = i as f32 * 2.399_963;
+                (angle.cos() * distance, angle.sin() * distance)
+            })
+            .collect();
+        // Pixels per world unit at view distance 1
+        let viewport = camera.compute_viewport(width as u32, height as u32);
+        let focal_pixels = viewport.height as f32 * 0.5 / (camera.fov * 0.5).tan();
+
+        let mut raw = vec![0.0; positions.len()];
+        for y in 0..height {
+            for x in 0..width {
+                let index = y * width + x;
+                let Some(point) = positions[index] else {
+                    continue;
+                };
+                let Some(normal) = reconstruct_normal(&positions, width, height, x, y) else {
+                    continue;
+                };
+
+                // 4x4 tile of rotations, so neighbouring pixels sample different directions
+                let rotation = ((x % 4) + (y % 4) * 4) as f32 / 16.0 * std::f32::consts::TAU;
+                let (sin, cos) = rotation.sin_cos();
+                let pixel_radius = (self.radius * focal_pixels / -point.z).min(SSAO_MAX_PIXEL_RADIUS);
+
+                let mut occluded = 0;
+                for &(dx, dy) in &disk {
+                    let sample_x = x as f32 + (dx * cos - dy * sin) * pixel_radius;
+                    let sample_y = y as f32 + (dx * sin + dy * cos) * pixel_radius;
+                    if sample_x < 0.0 || sample_y < 0.0 {
+                        continue;
+                    }
+                    let (sample_x, sample_y) = (sample_x.round() as usize, sample_y.round() as usize);
+                    if sample_x >= width || sample_y >= height {
+                        continue;
+                    }
+                    let Some(sample) = positions[sample_y * width + sample_x] else {
+                        continue;
+                    };
+                    let offset = sample - point;
+                    let (distance, height) = (offset.length(), offset.dot(&normal));
+                    if distance < self.radius && height > self.bias && height > distance * SSAO_MIN_ELEVATION {
+                        occluded += 1;
+                    }
+                }
+                raw[index] = occluded as f32 / self.samples as f32;
+            }
+        }
+
+        let horizontal = blur_occlusion(&raw, depth_buffer, 1, width, width, height);
+        blur_occlusion(&horizontal, depth_buffer, width, height, width, height)
+    }
+}
+
+impl PostEffect for ScreenSpaceAmbientOcclusion {
+    fn apply(&self, context: &mut PostContext) {
+        if self.strength <= 0.0 || context.width == 0 || context.height == 0 {
+            return;
+        }
+        let occlusion = self.occlusion(context.depth_buffer, context.width, context.height, context.camera);
+        for (pixel, occlusion) in context.framebuffer.iter_mut().zip(occlusion) {
+            if occlusion > 0.0 {
+                let [r, g, b] = unpack(*pixel);
+                let scale = 1.0 - self.strength * occlusion;
+                *pixel = pack([r * scale, g * scale, b * scale]);
+            }
+        }
+    }
+}
+
+// View-space point under every pixel center (camera at the origin looking down -z), None where nothing was drawn
+fn view_positions(depth_buffer: &[f32], width: usize, height: usize, camera: &Camera) -> Vec<Option<Vec3f>> {
+    let viewport = camera.compute_viewport(width as u32, height as u32);
+    let tan_half_fov = (camera.fov * 0.5).tan();
+    let aspect = viewport.aspect_ratio();
+
+    (0..width * height)
+        .map(|index| {
+            let depth = depth_buffer[index];
+            if !depth.is_finite() {
+                return None;
+            }
+            let ndc = viewport.screen_to_ndc((index % width) as f32 + 0.5, (index / width) as f32 + 0.5)?;
+            let distance = depth * camera.far;
+            Some(Vec3f::new(ndc.x * tan_half_fov * aspect * distance, ndc.y * tan_half_fov * distance, -distance))
+        })
+        .collect()
+}
+
+///
+/// Surface normal at a pixel from its neighbours' points, facing the camera. On each axis
+/// the neighbour nearer in depth is used, so edges don't bend normals towards the background.
+///
+fn reconstruct_normal(positions: &[Option<Vec3f>], width: usize, height: usize, x: usize, y: usize) -> Option<Vec3f> {
+    let point = positions[y * width + x]?;
+    let at = |x: usize, y: usize| positions[y * width + x];
+    let pick = |before: Option<Vec3f>, after: Option<Vec3f>| match (before, after) {
+        (Some(before), Some(after)) => {
+            if (point.z - before.z).abs() < (after.z - point.z).abs() { Some(point - before) } else { Some(after - point) }
+        }
+        (Some(before), None) => Some(point - before),
+        (None, Some(after)) => Some(after - point),
+        (None, None) => None,
+    };
+
+    let along_x = pick(x.checked_sub(1).and_then(|x| at(x, y)), (x + 1 < width).then(|| at(x + 1, y)).flatten())?;
+    let along_y = pick(y.checked_sub(1).and_then(|y| at(x, y)), (y + 1 < height).then(|| at(x, y + 1)).flatten())?;
+    let normal = along_x.cross(&along_y).normalize();
+    if normal.length() == 0.0 {
+        return None;
+    }
+    Some(if normal.dot(&point) > 0.0 { -normal } else { normal })
+}
+
+// Box blur along rows (stride 1) or columns (stride = width), skipping samples across depth edges
+fn blur_occlusion(values: &[f32], depth: &[f32], stride: usize, line_length: usize,
+                  width: usize, height: usize) -> Vec<f32> {
+    let mut result = values.to_vec();
+    for index in 0..width * height {
+        if !depth[index].is_finite() {
+            continue;
+        }
+        let position = (index / stride) % line_length;
+        let start = position.saturating_sub(SSAO_BLUR_RADIUS);
+        let end = (position + SSAO_BLUR_RADIUS).min(line_length - 1);
+
+        let (mut sum, mut count) = (0.0, 0.0);
+        for sample_position in start..=end {
+            let sample = index - position * stride + sample_position * stride;
+            if (depth[sample] - depth[index]).abs() <= depth[index] * SSAO_BLUR_DEPTH_TOLERANCE {
+                sum += values[sample];
+                count += 1.0;
+            }
+        }
+        result[index] = sum / count;
+    }
+    result
+}
+
+fn unpack(color: u32) -> [f32; 3] {
+    [
+        ((color >> 16) & 0xFF) as f32,
+        ((color >> 8) & 0xFF) as f32,
+        (color & 0xFF) as f32,
+    ]
+}
+
+fn pack(color: [f32; 3]) -> u32 {
+    let r = color[0].round().clamp(0.0, 255.0) as u32;
+    let g = color[1].round().clamp(0.0, 255.0) as u32;
+    let b = color[2].round().clamp(0.0, 255.0) as u32;
+    0xFF000000 | (r << 16) | (g << 8) | b
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Depth buffer of a plane seen by `camera`, given in view space by its normal and a point on it
+    fn plane_depths(camera: &Camera, width: u32, height: u32, normal: Vec3f, point: Vec3f) -> Vec<f32> {
+        let viewport = camera.compute_viewport(width, height);
+        let (tan_half_fov, aspect) = ((camera.fov * 0.5).tan(), viewport.aspect_ratio());
+        (0..width * height)
+            .map(|index| {
+                let Some(ndc) = viewport.screen_to_ndc((index % width) as f32 + 0.5, (index / width) as f32 + 0.5) else {
+                    return f32::INFINITY;
+                };
+                // View-space ray with z = -1, so the distance along it is the view depth
+                let ray = Vec3f::new(ndc.x * tan_half_fov * aspect, ndc.y * tan_half_fov, -1.0);
+                let distance = normal.dot(&point) / normal.dot(&ray);
+                if distance > 0.0 && distance < camera.far { distance / camera.far } else { f32::INFINITY }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn flat_open_plane_is_not_occluded() {
+        let (width, height) = (64, 48);
+        let camera = Camera::builder().aspect(width as f32 / height as f32).build().unwrap();
+        let ssao = ScreenSpaceAmbientOcclusion::new(0.5, 0.02, 1.0);
+
+        // Facing the camera at an angle, filling the whole view
+        for normal in [Vec3f::new(0.0, 0.6, 0.8), Vec3f::new(-0.5, 0.3, 0.8).normalize(), Vec3f::z_axis()] {
+            let depths = plane_depths(&camera, width, height, normal, Vec3f::new(0.0, 0.0, -5.0));
+            assert!(depths.iter().all(|depth| depth.is_finite()));
+
+            let occlusion = ssao.occlusion(&depths, width, height, &camera);
+            let mean = occlusion.iter().sum::<f32>() / occlusion.len() as f32;
+            assert!(mean < 0.01, "mean occlusion {mean} for a plane facing {:?}", (normal.x, normal.y, normal.z));
+        }
+
+        // While a valley (the nearer of two planes crossing in front of the camera) does darken
+        let point = Vec3f::new(0.0, 0.0, -5.0);
+        let left = plane_depths(&camera, width, height, Vec3f::new(0.6, 0.0, 0.8), point);
+        let right = plane_depths(&camera, width, height, Vec3f::new(-0.6, 0.0, 0.8), point);
+        let valley: Vec<f32> = left.iter().zip(&right).map(|(left, right)| left.min(*right)).collect();
+        let deepest = ssao.occlusion(&valley, width, height, &camera).into_iter().fold(0.0, f32::max);
+        assert!(deepest > 0.2, "valley occlusion only reaches {deepest}");
+    }
+}
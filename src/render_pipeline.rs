@@ -0,0 +1,166 @@
+use crate::renderer::Renderer;
+use crate::scene::{OutlineStyle, Scene};
+
+///
+/// One step of drawing a frame. Passes run in pipeline order against the same renderer,
+/// after Scene::render has cleared it, set the viewport and culled objects by distance.
+///
+pub trait RenderPass {
+    fn execute(&self, scene: &Scene, renderer: &mut Renderer);
+}
+
+///
+/// Renders the shadow cubes of point lights with shadows turned on, see Scene::render_point_shadows.
+/// Draws nothing to the renderer; without this pass lights keep the last shadows rendered.
+///
+pub struct ShadowPass;
+
+impl RenderPass for ShadowPass {
+    fn execute(&self, scene: &Scene, _renderer: &mut Renderer) {
+        scene.render_point_shadows();
+    }
+}
+
+/// Sky, outlines, every visible object (opaque front to back, transparent back to front), point lists and the portal
+pub struct GeometryPass;
+
+impl RenderPass for GeometryPass {
+    fn execute(&self, scene: &Scene, renderer: &mut Renderer) {
+        // Sky goes in first so transparent geometry blends over it
+        if let Some(skybox) = &scene.skybox {
+            skybox.render(renderer, &scene.camera);
+        }
+
+        let view_matrix = scene.camera.get_view_matrix();
+        let proj_matrix = scene.camera.get_projection_matrix();
+        let vp_matrix = scene.camera.get_view_projection_matrix();
+
+        // Outline shells first, so the fill covers their inside
+        if scene.outline_enabled && scene.outline_style == OutlineStyle::InflatedShell {
+            for &index in &scene.visible_objects {
+                scene.render_game_object_outline(&scene.game_objects[index], &view_matrix, &proj_matrix, renderer);
+            }
+        }
+
+        scene.count_triangles(
+            scene.render_objects_sorted(&scene.visible_objects, &scene.camera, &vp_matrix, renderer)
+        );
+        scene.render_point_lists(&view_matrix, &proj_matrix, renderer);
+
+        // Silhouette lines last, so the filled geometry can hide the ones behind it
+        if scene.outline_enabled && scene.outline_style == OutlineStyle::SilhouetteEdges {
+            for &index in &scene.visible_objects {
+                scene.render_silhouette_edges(&scene.game_objects[index], &vp_matrix, renderer);
+            }
+        }
+
+        if scene.portal.is_some() {
+            scene.count_triangles(scene.render_portal(&vp_matrix, renderer));
+        }
+    }
+}
+
+///
+/// Resolves the HDR light buffer into displayable colors. Passes after it see the final
+/// lit image in the framebuffer; without it, PostProcessPass does the same thing.
+///
+pub struct LightingPass;
+
+impl RenderPass for LightingPass {
+    fn execute(&self, _scene: &Scene, renderer: &mut Renderer) {
+        renderer.tonemap_hdr();
+    }
+}
+
+/// Tonemapping (if still pending), the scene's post effects, then debug overlays and the HUD
+pub struct PostProcessPass;
+
+impl RenderPass for PostProcessPass {
+    fn execute(&self, scene: &Scene, renderer: &mut Renderer) {
+        // HDR geometry is all drawn, bring it down to displayable colors before the post effects
+        renderer.tonemap_hdr();
+
+        if scene.post_effects_enabled {
+            for effect in &scene.post_effects {
+                effect.apply(&mut renderer.post_context(&scene.camera));
+            }
+        }
+
+        if scene.debug_draw_vertices {
+            let view_matrix = scene.camera.get_view_matrix();
+            let proj_matrix = scene.camera.get_projection_matrix();
+            scene.draw_vertex_dots(&view_matrix, &proj_matrix, renderer);
+        }
+
+        if scene.show_grid {
+            Scene::draw_debug_grid(renderer, &scene.camera.get_view_projection_matrix(), 1.0, 10, 0xFF606060);
+        }
+
+        if scene.show_light_volumes {
+            scene.lighting.debug_draw_light_volumes(renderer, &scene.camera.get_view_projection_matrix(), scene.camera.position);
+        }
+
+        for debug_camera in &scene.debug_cameras {
+            scene.draw_frustum(debug_camera, renderer, 0xFFFFFF00);
+        }
+
+        if scene.show_light_gizmos {
+            scene.draw_light_gizmos(renderer);
+        }
+
+        scene.draw_gizmo(renderer);
+
+        if scene.show_minimap {
+            let rect = Scene::minimap_rect(renderer);
+            scene.render_minimap(renderer, rect.width, rect.height, rect.x, rect.y);
+        }
+
+        if scene.show_hud {
+            Scene::render_debug_hud(
+                renderer, &scene.hud_font, &scene.frame_stats(), &scene.camera, scene.lighting.debug_mode
+            );
+            if let Some(selected) = scene.selected_object.and_then(|index| scene.game_objects.get(index)) {
+                Scene::render_inspector(renderer, &scene.hud_font, selected);
+            }
+            if let Some(light) = scene.selected_light.and_then(|handle| scene.lighting.get(handle)) {
+                Scene::render_light_inspector(renderer, &scene.hud_font, light);
+            }
+        }
+    }
+}
+
+///
+/// The passes Scene::render runs, in order. The default is forward rendering
+/// (shadows, geometry, post processing); build your own to try other setups,
+/// e.g. a separate LightingPass before custom passes that read the lit image.
+///
+pub struct RenderPipeline {
+    pub passes: Vec<Box<dyn RenderPass>>,
+}
+
+impl RenderPipeline {
+    pub fn new(passes: Vec<Box<dyn RenderPass>>) -> Self {
+        Self { passes }
+    }
+
+    pub fn forward() -> Self {
+        Self::new(vec![Box::new(ShadowPass), Box::new(GeometryPass), Box::new(PostProcessPass)])
+    }
+
+    pub fn with_pass(mut self, pass: impl RenderPass + 'static) -> Self {
+        self.passes.push(Box::new(pass));
+        self
+    }
+
+    pub fn execute(&self, scene: &Scene, renderer: &mut Renderer) {
+        for pass in &self.passes {
+            pass.execute(scene, renderer);
+        }
+    }
+}
+
+impl Default for RenderPipeline {
+    fn default() -> Self {
+        Self::forward()
+    }
+}
@@ -1,10 +1,158 @@
-use crate::math::Vec2f;
+use crate::camera::Camera;
+use crate::font::{BitmapFont, GLYPH_WIDTH};
+use crate::math::{Vec2f, Vec3f, Vec4f};
+use crate::post_process::PostContext;
+
+/// How the stencil value already in the buffer is compared against StencilOp::test_value
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum StencilMode {
+    Always,
+    Equal,
+    NotEqual,
+    Less,    // Passes when test_value < stored value
+    Greater, // Passes when test_value > stored value
+}
+
+/// Stencil state: pixels that pass the test get write_value stored in the stencil buffer
+#[derive(Copy, Clone, Debug)]
+pub struct StencilOp {
+    pub test_value: u8,
+    pub write_value: u8,
+    pub mode: StencilMode,
+}
+
+impl StencilOp {
+    pub fn new(mode: StencilMode, test_value: u8, write_value: u8) -> Self {
+        Self { test_value, write_value, mode }
+    }
+
+    pub fn passes(&self, stored: u8) -> bool {
+        match self.mode {
+            StencilMode::Always => true,
+            StencilMode::Equal => self.test_value == stored,
+            StencilMode::NotEqual => self.test_value != stored,
+            StencilMode::Less => self.test_value < stored,
+            StencilMode::Greater => self.test_value > stored,
+        }
+    }
+}
+
+/// How set_pixel_blend combines a color with the pixel already there
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum BlendMode {
+    Replace,    // The color's alpha byte is ignored
+    Alpha(f32), // Source over with this opacity, in place of the alpha byte
+    Additive,   // RGB added to what's there, saturating at white; for glow, fire and sparks
+}
+
+/// The rectangle of the framebuffer the scene is drawn into (the rest is letterbox/pillarbox bars)
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Viewport {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Viewport {
+    pub fn new(x: u32, y: u32, width: u32, height: u32) -> Self {
+        Self { x, y, width, height }
+    }
+
+    pub fn aspect_ratio(&self) -> f32 {
+        self.width as f32 / self.height.max(1) as f32
+    }
+
+    pub fn contains(&self, x: f32, y: f32) -> bool {
+        x >= self.x as f32 && x < (self.x + self.width) as f32
+            && y >= self.y as f32 && y < (self.y + self.height) as f32
+    }
+
+    /// NDC (-1..1, y up) to pixel coordinates inside the viewport (y down)
+    pub fn ndc_to_screen(&self, ndc_x: f32, ndc_y: f32) -> Vec2f {
+        Vec2f::new(
+            self.x as f32 + (ndc_x + 1.0) * 0.5 * self.width as f32,
+            self.y as f32 + (1.0 - ndc_y) * 0.5 * self.height as f32,
+        )
+    }
+
+    /// Pixel coordinates to NDC, or None if the pixel is outside the viewport (e.g. in the bars)
+    pub fn screen_to_ndc(&self, x: f32, y: f32) -> Option<Vec2f> {
+        if !self.contains(x, y) {
+            return None;
+        }
+        Some(Vec2f::new(
+            (x - self.x as f32) / self.width as f32 * 2.0 - 1.0,
+            1.0 - (y - self.y as f32) / self.height as f32 * 2.0,
+        ))
+    }
+}
+
+/// A rectangle of pixels, for clearing or tracking parts of the framebuffer
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Rect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Rect {
+    pub fn new(x: u32, y: u32, width: u32, height: u32) -> Self {
+        Self { x, y, width, height }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.width == 0 || self.height == 0
+    }
+
+    /// The part inside a `width` x `height` buffer
+    pub fn clipped_to(&self, width: u32, height: u32) -> Rect {
+        let x = self.x.min(width);
+        let y = self.y.min(height);
+        Rect::new(x, y, self.width.min(width - x), self.height.min(height - y))
+    }
+}
+
+impl From<Viewport> for Rect {
+    fn from(viewport: Viewport) -> Self {
+        Rect::new(viewport.x, viewport.y, viewport.width, viewport.height)
+    }
+}
+
+/// Marks an HDR pixel that nothing was drawn to in HDR since the last clear
+const HDR_UNWRITTEN: [f32; 3] = [-1.0, -1.0, -1.0];
+
+/// Color handed to the rasterizer: a display-ready ARGB pixel, or linear HDR radiance with an alpha
+#[derive(Copy, Clone)]
+enum PixelColor {
+    Ldr(u32),
+    Hdr([f32; 3], f32),
+    Additive(u32), // ARGB whose RGB is added, alpha byte ignored
+}
+
+impl PixelColor {
+    fn is_opaque(&self) -> bool {
+        match *self {
+            PixelColor::Ldr(argb) => argb >> 24 == 0xFF,
+            PixelColor::Hdr(_, alpha) => alpha >= 1.0,
+            PixelColor::Additive(_) => false,
+        }
+    }
+}
 
 pub struct Renderer {
     width: u32,
     height: u32,
     framebuffer: Vec<u32>, // ARGB Pixels
     z_buffer: Vec<f32>,
+    stencil_buffer: Vec<u8>,
+    stencil_op: Option<StencilOp>, // None = stencil test disabled
+    color_write: bool,             // When false, only depth/stencil get written
+    viewport: Viewport,
+    hdr_buffer: Option<Vec<[f32; 3]>>, // Linear RGB, unbounded; None = HDR mode off
+    log_average_luminance: Option<f32>, // Measured by the last tonemap_hdr since the last clear
+    dirty_rects: Vec<Rect>, // Regions changed since the last full clear, as given to mark_dirty
 }
 
 impl Renderer {
@@ -13,7 +161,118 @@ impl Renderer {
             width,
             height,
             framebuffer: vec![0xFF000000; (width * height) as usize],
-            z_buffer: vec![f32::INFINITY; (width * height) as usize]
+            z_buffer: vec![f32::INFINITY; (width * height) as usize],
+            stencil_buffer: vec![0; (width * height) as usize],
+            stencil_op: None,
+            color_write: true,
+            viewport: Viewport::new(0, 0, width, height),
+            hdr_buffer: None,
+            log_average_luminance: None,
+            dirty_rects: Vec::new(),
+        }
+    }
+
+    /// Reallocates all buffers for a new window size and resets the viewport to cover it
+    pub fn resize(&mut self, width: u32, height: u32) {
+        let width = width.max(1);
+        let height = height.max(1);
+        if width == self.width && height == self.height {
+            return;
+        }
+
+        self.width = width;
+        self.height = height;
+        self.framebuffer = vec![0xFF000000; (width * height) as usize];
+        self.z_buffer = vec![f32::INFINITY; (width * height) as usize];
+        self.stencil_buffer = vec![0; (width * height) as usize];
+        self.viewport = Viewport::new(0, 0, width, height);
+        self.dirty_rects.clear();
+        if self.hdr_buffer.is_some() {
+            self.hdr_buffer = Some(vec![HDR_UNWRITTEN; (width * height) as usize]);
+        }
+    }
+
+    ///
+    /// Switches on the floating point color buffer.
+    /// Triangles drawn with draw_triangle_hdr (and set_pixel_hdr) keep their full range
+    /// there until tonemap_hdr converts them into the framebuffer.
+    ///
+    pub fn enable_hdr_mode(&mut self) {
+        if self.hdr_buffer.is_none() {
+            self.hdr_buffer = Some(vec![HDR_UNWRITTEN; (self.width * self.height) as usize]);
+        }
+    }
+
+    pub fn disable_hdr_mode(&mut self) {
+        self.hdr_buffer = None;
+    }
+
+    pub fn is_hdr_enabled(&self) -> bool {
+        self.hdr_buffer.is_some()
+    }
+
+    pub fn set_viewport(&mut self, viewport: Viewport) {
+        self.viewport = viewport;
+    }
+
+    pub fn get_viewport(&self) -> Viewport {
+        self.viewport
+    }
+
+    /// Paints the viewport's part of row `y` wherever nothing has been drawn yet (depth still infinite)
+    pub fn fill_empty_row(&mut self, y: u32, color: u32) {
+        let viewport = self.viewport;
+        if y < viewport.y || y >= viewport.y + viewport.height || y >= self.height {
+            return;
+        }
+        let row_start = y * self.width;
+        for x in viewport.x..(viewport.x + viewport.width).min(self.width) {
+            let index = (row_start + x) as usize;
+            if self.z_buffer[index] == f32::INFINITY && self.stencil_test(index) {
+                self.write_color(index, PixelColor::Ldr(color));
+            }
+        }
+    }
+
+    /// Paints everything outside the viewport (letterbox/pillarbox bars)
+    pub fn fill_outside_viewport(&mut self, color: u32) {
+        let viewport = self.viewport;
+        for y in 0..self.height {
+            for x in 0..self.width {
+                if !viewport.contains(x as f32, y as f32) {
+                    self.framebuffer[(y * self.width + x) as usize] = color;
+                }
+            }
+        }
+    }
+
+    pub fn set_stencil_op(&mut self, op: Option<StencilOp>) {
+        self.stencil_op = op;
+    }
+
+    pub fn get_stencil_op(&self) -> Option<StencilOp> {
+        self.stencil_op
+    }
+
+    /// Enables/disables writing to the framebuffer (e.g. to draw into the stencil buffer only)
+    pub fn set_color_write(&mut self, enabled: bool) {
+        self.color_write = enabled;
+    }
+
+    pub fn get_stencil_buffer(&self) -> &[u8] {
+        &self.stencil_buffer
+    }
+
+    fn stencil_test(&self, index: usize) -> bool {
+        match self.stencil_op {
+            Some(op) => op.passes(self.stencil_buffer[index]),
+            None => true,
+        }
+    }
+
+    fn stencil_write(&mut self, index: usize) {
+        if let Some(op) = self.stencil_op {
+            self.stencil_buffer[index] = op.write_value;
         }
     }
 
@@ -40,9 +299,57 @@ impl Renderer {
         (u, v, w)
     }
 
-    /// Core triangle rasterization function
+    ///
+    /// Core triangle rasterization function.
+    /// An alpha byte below 0xFF blends the triangle over what is already there; translucent
+    /// triangles are depth tested but don't write depth, so draw them back to front after
+    /// everything opaque.
+    ///
     pub fn draw_triangle(&mut self, v0: Vec2f, v1: Vec2f, v2: Vec2f,
                          z0: f32, z1: f32, z2: f32, color: u32) {
+        self.rasterize_triangle([v0, v1, v2], [z0, z1, z2], |_| PixelColor::Ldr(color));
+    }
+
+    /// draw_triangle with the color added to what's already there (BlendMode::Additive).
+    /// Depth tested without writing depth, like any translucent triangle.
+    pub fn draw_triangle_additive(&mut self, screen: [Vec2f; 3], depth: [f32; 3], color: u32) {
+        self.rasterize_triangle(screen, depth, |_| PixelColor::Additive(color));
+    }
+
+    /// Same as draw_triangle, but the color is linear RGB that may go above 1.0.
+    /// Falls back to a clamped framebuffer write when HDR mode is off.
+    pub fn draw_triangle_hdr(&mut self, screen: [Vec2f; 3], depth: [f32; 3], color: Vec3f) {
+        let rgb = [color.x, color.y, color.z];
+        self.rasterize_triangle(screen, depth, |_| PixelColor::Hdr(rgb, 1.0));
+    }
+
+    /// draw_triangle_hdr with color.w as opacity (blended, no depth write when below 1.0)
+    pub fn draw_triangle_hdr_alpha(&mut self, screen: [Vec2f; 3], depth: [f32; 3], color: Vec4f) {
+        let rgb = [color.x, color.y, color.z];
+        let alpha = color.w.clamp(0.0, 1.0);
+        self.rasterize_triangle(screen, depth, |_| PixelColor::Hdr(rgb, alpha));
+    }
+
+    ///
+    /// Per-pixel shaded triangle. `shader` gets perspective-correct barycentric weights for
+    /// the three vertices (for interpolating UVs and other attributes) and returns linear RGB
+    /// plus opacity in w. It only runs for pixels that pass the stencil and depth tests.
+    /// Writes to the HDR buffer in HDR mode, otherwise clamps into the framebuffer.
+    ///
+    pub fn draw_triangle_shaded(&mut self, screen: [Vec2f; 3], depth: [f32; 3],
+                                mut shader: impl FnMut([f32; 3]) -> Vec4f) {
+        self.rasterize_triangle(screen, depth, |weights| {
+            let color = shader(weights);
+            PixelColor::Hdr([color.x, color.y, color.z], color.w.clamp(0.0, 1.0))
+        });
+    }
+
+    /// Shared scan loop; `color` gets the perspective-correct weights of a pixel that passed the tests
+    fn rasterize_triangle(&mut self, screen: [Vec2f; 3], depth: [f32; 3],
+                          mut color: impl FnMut([f32; 3]) -> PixelColor) {
+        let [v0, v1, v2] = screen;
+        let [z0, z1, z2] = depth;
+
         // Find bounding box of triangle
         let min_x = (v0.x.min(v1.x).min(v2.x)).floor() as i32;
         let max_x = (v0.x.max(v1.x).max(v2.x)).ceil() as i32;
@@ -60,16 +367,81 @@ impl Renderer {
                     // Interpolate depth using barycentric coordinates
                     let depth = u * z0 + v * z1 + w * z2;
                     // Z-buffer test and pixel drawing
+                    if x < 0 || y < 0 || x >= self.width as i32 || y >= self.height as i32 {
+                        continue;
+                    }
                     let pixel_index = (y * self.width as i32 + x) as usize;
-                    if pixel_index < self.z_buffer.len() && depth < self.z_buffer[pixel_index] {
-                        self.z_buffer[pixel_index] = depth;
-                        self.set_pixel(x as u32, y as u32, color);
+                    // Stencil test first, then the z-buffer test
+                    if self.stencil_test(pixel_index) && depth < self.z_buffer[pixel_index] {
+                        let pixel_color = color(perspective_weights([u, v, w], [z0, z1, z2]));
+                        if pixel_color.is_opaque() {
+                            self.z_buffer[pixel_index] = depth;
+                        }
+                        self.write_color(pixel_index, pixel_color);
+                        self.stencil_write(pixel_index);
                     }
                 }
             }
         }
     }
 
+    ///
+    /// Opaque writes replace the pixel, and a plain color write also drops any HDR value under it.
+    /// Translucent writes blend with whichever buffer holds the pixel's current color; an HDR
+    /// blend over a pixel that only exists in the framebuffer (e.g. the background) moves it
+    /// into the HDR buffer. Additive writes add in whichever buffer holds the pixel, so they
+    /// only saturate in the framebuffer.
+    ///
+    fn write_color(&mut self, index: usize, color: PixelColor) {
+        if !self.color_write {
+            return;
+        }
+        let framebuffer = &mut self.framebuffer[index];
+        match (color, &mut self.hdr_buffer) {
+            (PixelColor::Hdr(rgb, alpha), Some(hdr_buffer)) => {
+                let under = if hdr_buffer[index][0] < 0.0 {
+                    unpack_unit_rgb(*framebuffer)
+                } else {
+                    hdr_buffer[index]
+                };
+                hdr_buffer[index] = [0, 1, 2].map(|c| under[c] + (rgb[c] - under[c]) * alpha);
+            }
+            (PixelColor::Hdr(rgb, alpha), None) => {
+                let argb = pack_unit_rgb(rgb) & 0x00FFFFFF | (((alpha * 255.0).round() as u32) << 24);
+                *framebuffer = blend(*framebuffer, argb);
+            }
+            (PixelColor::Ldr(argb), hdr_buffer) => {
+                match hdr_buffer {
+                    Some(hdr_buffer) if argb >> 24 != 0xFF && hdr_buffer[index][0] >= 0.0 => {
+                        // Blending over HDR content: stay in the HDR buffer
+                        let alpha = (argb >> 24) as f32 / 255.0;
+                        let rgb = unpack_unit_rgb(argb);
+                        let under = hdr_buffer[index];
+                        hdr_buffer[index] = [0, 1, 2].map(|c| under[c] + (rgb[c] - under[c]) * alpha);
+                    }
+                    Some(hdr_buffer) => {
+                        *framebuffer = blend(*framebuffer, argb);
+                        hdr_buffer[index] = HDR_UNWRITTEN;
+                    }
+                    None => *framebuffer = blend(*framebuffer, argb),
+                }
+            }
+            (PixelColor::Additive(argb), Some(hdr_buffer)) if hdr_buffer[index][0] >= 0.0 => {
+                let rgb = unpack_unit_rgb(argb);
+                hdr_buffer[index] = [0, 1, 2].map(|c| hdr_buffer[index][c] + rgb[c]);
+            }
+            (PixelColor::Additive(argb), _) => *framebuffer = add_saturating(*framebuffer, argb),
+        }
+    }
+
+    /// Outline pass for the inflated shell method: each vertex is pushed outward along its
+    /// screen-space normal (unit length) by `outline_width` pixels, then rasterized as usual
+    pub fn draw_triangle_outline_pass(&mut self, screen: [Vec2f; 3], normals: [Vec2f; 3], depth: [f32; 3],
+                                      outline_width: f32, color: u32) {
+        let pushed = [0, 1, 2].map(|corner| screen[corner] + normals[corner] * outline_width);
+        self.rasterize_triangle(pushed, depth, |_| PixelColor::Ldr(color));
+    }
+
     /// Bresenham's line algorithm (for debugging wireframes)
     pub fn draw_line(&mut self, x0: i32, y0: i32, x1: i32, y1: i32, color: u32) {
         let dx = (x1 - x0).abs();
@@ -100,10 +472,289 @@ impl Renderer {
         }
     }
 
+    ///
+    /// Bresenham line for 3D wireframes between the two `screen` points: depth goes linearly
+    /// from depth[0] to depth[1] (same units as draw_triangle) and every pixel is tested against
+    /// and written to the z-buffer, so the line disappears behind solid geometry. Translucent
+    /// colors blend without writing depth.
+    ///
+    pub fn draw_line_with_depth(&mut self, screen: [Vec2f; 2], depth: [f32; 2], color: u32) {
+        const DEPTH_BIAS: f32 = 0.0005; // Keeps edges visible on top of the faces they belong to
+
+        let ([start, end], [z0, z1]) = (screen, depth);
+        let (x0, y0) = (start.x.round() as i32, start.y.round() as i32);
+        let (x1, y1) = (end.x.round() as i32, end.y.round() as i32);
+        let dx = (x1 - x0).abs();
+        let dy = (y1 - y0).abs();
+        let steps = dx.max(dy).max(1) as f32;
+
+        let sx = if x0 < x1 { 1 } else { -1 };
+        let sy = if y0 < y1 { 1 } else { -1 };
+
+        let mut err = dx - dy;
+
+        let mut x = x0;
+        let mut y = y0;
+        let mut step = 0;
+        loop {
+            if x >= 0 && y >= 0 && x < self.width as i32 && y < self.height as i32 {
+                let index = (y as u32 * self.width + x as u32) as usize;
+                let depth = z0 + (z1 - z0) * (step as f32 / steps);
+                self.write_depth_tested(index, depth - DEPTH_BIAS, depth, color);
+            }
+
+            if x == x1 && y == y1 { break; }
+
+            let e2 = 2 * err;
+            if e2 > -dy {
+                err -= dy;
+                x += sx;
+            }
+
+            if e2 < dx {
+                err += dx;
+                y += sy;
+            }
+            step += 1;
+        }
+    }
+
+    ///
+    /// Round point for point clouds, particles and markers: a disc of radius ceil(size) pixels
+    /// around (x, y), or just the pixel under it when size is 1 or less. Each pixel is depth
+    /// tested on its own like draw_line_with_depth, so points can sink partly into surfaces.
+    /// Clipped to the viewport.
+    ///
+    pub fn draw_point(&mut self, x: f32, y: f32, depth: f32, size: f32, color: u32) {
+        // Nothing to clip to, and the bounds below would underflow
+        if self.viewport.width == 0 || self.viewport.height == 0 {
+            return;
+        }
+        if size <= 1.0 {
+            if self.viewport.contains(x, y) {
+                let index = (y as u32 * self.width + x as u32) as usize;
+                self.write_depth_tested(index, depth, depth, color);
+            }
+            return;
+        }
+
+        let radius = size.ceil();
+        let min_x = (x - radius).floor().max(self.viewport.x as f32) as u32;
+        let min_y = (y - radius).floor().max(self.viewport.y as f32) as u32;
+        let max_x = ((x + radius).ceil() as u32).min(self.viewport.x + self.viewport.width - 1);
+        let max_y = ((y + radius).ceil() as u32).min(self.viewport.y + self.viewport.height - 1);
+
+        for py in min_y..=max_y {
+            for px in min_x..=max_x {
+                let dx = px as f32 + 0.5 - x;
+                let dy = py as f32 + 0.5 - y;
+                if dx * dx + dy * dy <= radius * radius {
+                    self.write_depth_tested((py * self.width + px) as usize, depth, depth, color);
+                }
+            }
+        }
+    }
+
+    // One pixel of a depth-tested primitive: drawn if `test_depth` is nearer than the z-buffer,
+    // writing `depth` for opaque colors; translucent ones blend without writing depth
+    fn write_depth_tested(&mut self, index: usize, test_depth: f32, depth: f32, color: u32) {
+        if !self.stencil_test(index) || test_depth >= self.z_buffer[index] {
+            return;
+        }
+        if color >> 24 == 0xFF {
+            self.z_buffer[index] = depth.min(self.z_buffer[index]);
+        }
+        self.write_color(index, PixelColor::Ldr(color));
+        self.stencil_write(index);
+    }
+
+    /// Draws a line whose endpoints may lie far outside the screen.
+    /// The segment is clipped to the viewport first (Liang-Barsky) so Bresenham only walks visible pixels.
+    pub fn draw_line_clipped(&mut self, start: Vec2f, end: Vec2f, color: u32) {
+        let min_x = self.viewport.x as f32;
+        let min_y = self.viewport.y as f32;
+        let max_x = (self.viewport.x + self.viewport.width) as f32 - 1.0;
+        let max_y = (self.viewport.y + self.viewport.height) as f32 - 1.0;
+        let delta = end - start;
+
+        let mut t_enter = 0.0_f32;
+        let mut t_exit = 1.0_f32;
+
+        // Each pair is (p, q) for one screen edge: the line is inside when t * p <= q
+        let edges = [
+            (-delta.x, start.x - min_x),
+            (delta.x, max_x - start.x),
+            (-delta.y, start.y - min_y),
+            (delta.y, max_y - start.y),
+        ];
+
+        for (p, q) in edges {
+            if p == 0.0 {
+                if q < 0.0 {
+                    return; // Parallel to this edge and outside it
+                }
+            } else {
+                let t = q / p;
+                if p < 0.0 {
+                    t_enter = t_enter.max(t);
+                } else {
+                    t_exit = t_exit.min(t);
+                }
+            }
+        }
+
+        if t_enter > t_exit {
+            return;
+        }
+
+        let clipped_start = start + delta * t_enter;
+        let clipped_end = start + delta * t_exit;
+        self.draw_line(
+            clipped_start.x.round() as i32, clipped_start.y.round() as i32,
+            clipped_end.x.round() as i32, clipped_end.y.round() as i32,
+            color,
+        );
+    }
+
+    ///
+    /// Filled disc for debug markers, centered at (x, y) in pixels.
+    /// `depth` uses the z-buffer's units; the whole disc is skipped when the pixel under its
+    /// center holds something closer, so markers hide behind geometry without breaking up.
+    /// Depth is only tested, not written, and the disc is clipped to the viewport.
+    ///
+    pub fn draw_filled_circle(&mut self, x: f32, y: f32, radius: f32, color: u32, depth: f32) {
+        const DEPTH_BIAS: f32 = 0.002; // Lets a vertex win against the triangles it belongs to
+
+        // Nothing to clip to, and the bounds below would underflow
+        if self.viewport.width == 0 || self.viewport.height == 0 || !self.viewport.contains(x, y) {
+            return;
+        }
+        let center_index = (y as u32 * self.width + x as u32) as usize;
+        if depth > self.z_buffer[center_index] + DEPTH_BIAS {
+            return;
+        }
+
+        let radius = radius.max(0.5);
+        let min_x = (x - radius).floor().max(self.viewport.x as f32) as u32;
+        let min_y = (y - radius).floor().max(self.viewport.y as f32) as u32;
+        let max_x = ((x + radius).ceil() as u32).min(self.viewport.x + self.viewport.width - 1);
+        let max_y = ((y + radius).ceil() as u32).min(self.viewport.y + self.viewport.height - 1);
+
+        for py in min_y..=max_y {
+            for px in min_x..=max_x {
+                let dx = px as f32 + 0.5 - x;
+                let dy = py as f32 + 0.5 - y;
+                if dx * dx + dy * dy <= radius * radius {
+                    self.framebuffer[(py * self.width + px) as usize] = color;
+                }
+            }
+        }
+    }
+
+    ///
+    /// Screen-space rectangle for overlays, ignoring depth, stencil and the viewport.
+    /// The alpha byte of `color` blends it over what is already there (0xFF = opaque).
+    ///
+    pub fn draw_filled_rect(&mut self, x: i32, y: i32, width: u32, height: u32, color: u32) {
+        let min_x = x.max(0) as u32;
+        let min_y = y.max(0) as u32;
+        let max_x = (x + width as i32).clamp(0, self.width as i32) as u32;
+        let max_y = (y + height as i32).clamp(0, self.height as i32) as u32;
+
+        for py in min_y..max_y {
+            for px in min_x..max_x {
+                let index = (py * self.width + px) as usize;
+                self.framebuffer[index] = blend(self.framebuffer[index], color);
+            }
+        }
+    }
+
+    ///
+    /// Blends a `src_width` x `src_height` ARGB image (row by row, top first) onto the
+    /// framebuffer with its top-left corner at (dst_x, dst_y). Each pixel's own alpha byte is
+    /// scaled by `alpha`. Like draw_filled_rect it ignores depth, stencil and the viewport, and
+    /// whatever falls off the screen is clipped.
+    ///
+    pub fn composite_image(&mut self, src: &[u32], src_width: u32, src_height: u32, dst_x: i32, dst_y: i32, alpha: f32) {
+        self.composite_image_scaled(src, src_width, src_height, dst_x, dst_y, src_width, src_height, alpha);
+    }
+
+    /// composite_image stretched to dst_width x dst_height, picking the nearest source pixel
+    #[allow(clippy::too_many_arguments)]
+    pub fn composite_image_scaled(&mut self, src: &[u32], src_width: u32, src_height: u32, dst_x: i32, dst_y: i32,
+                                  dst_width: u32, dst_height: u32, alpha: f32) {
+        if src_width == 0 || src_height == 0 || src.len() < (src_width * src_height) as usize {
+            return;
+        }
+        let alpha = alpha.clamp(0.0, 1.0);
+        let min_x = dst_x.max(0);
+        let min_y = dst_y.max(0);
+        let max_x = (dst_x + dst_width as i32).min(self.width as i32);
+        let max_y = (dst_y + dst_height as i32).min(self.height as i32);
+
+        for py in min_y..max_y {
+            let src_y = ((py - dst_y) as u64 * src_height as u64 / dst_height as u64) as u32;
+            for px in min_x..max_x {
+                let src_x = ((px - dst_x) as u64 * src_width as u64 / dst_width as u64) as u32;
+                let pixel = src[(src_y * src_width + src_x) as usize];
+                let pixel_alpha = ((pixel >> 24) as f32 * alpha).round() as u32;
+                if pixel_alpha == 0 {
+                    continue;
+                }
+                let index = (py as u32 * self.width + px as u32) as usize;
+                self.framebuffer[index] = blend(self.framebuffer[index], pixel & 0x00FFFFFF | (pixel_alpha << 24));
+            }
+        }
+    }
+
+    /// Draws text with its top-left corner at (x, y); '\n' starts a new line
+    pub fn draw_text(&mut self, x: i32, y: i32, text: &str, font: &BitmapFont, color: u32) {
+        let scale = font.scale as i32;
+        let mut cursor_x = x;
+        let mut cursor_y = y;
+
+        for c in text.chars() {
+            if c == '\n' {
+                cursor_x = x;
+                cursor_y += font.line_height() as i32;
+                continue;
+            }
+
+            let rows = font.glyph(c);
+            for (row, bits) in rows.iter().enumerate() {
+                for column in 0..GLYPH_WIDTH {
+                    if bits & (1 << (GLYPH_WIDTH - 1 - column)) == 0 {
+                        continue;
+                    }
+                    let px = cursor_x + column as i32 * scale;
+                    let py = cursor_y + row as i32 * scale;
+                    self.draw_filled_rect(px, py, font.scale, font.scale, color);
+                }
+            }
+            cursor_x += font.advance() as i32;
+        }
+    }
+
     pub fn get_framebuffer(&self) -> &[u32] {
         &self.framebuffer
     }
 
+    /// Depth values as written by draw_triangle (view distance / camera.far, INFINITY where nothing was drawn)
+    pub fn get_depth_buffer(&self) -> &[f32] {
+        &self.z_buffer
+    }
+
+    /// Hands post effects the framebuffer together with the depth buffer it was rendered with
+    pub fn post_context<'a>(&'a mut self, camera: &'a Camera) -> PostContext<'a> {
+        PostContext {
+            framebuffer: &mut self.framebuffer,
+            depth_buffer: &self.z_buffer,
+            width: self.width,
+            height: self.height,
+            camera,
+        }
+    }
+
     pub fn get_dimension(&self) -> (u32, u32) {
         (self.width, self.height)
     }
@@ -116,12 +767,231 @@ impl Renderer {
         for depth in &mut self.z_buffer {
             *depth = f32::INFINITY;
         }
+        self.clear_stencil(0);
+        self.log_average_luminance = None;
+        self.dirty_rects.clear();
+        if let Some(hdr_buffer) = &mut self.hdr_buffer {
+            for pixel in hdr_buffer.iter_mut() {
+                *pixel = HDR_UNWRITTEN;
+            }
+        }
+    }
+
+    /// clear, but only inside the viewport, e.g. for an inset view drawn over a finished frame
+    pub fn clear_viewport(&mut self, color: u32) {
+        self.clear_partial(self.viewport.into(), color);
+    }
+
+    ///
+    /// clear, but only inside `rect` (clipped to the screen): color, depth, stencil and HDR
+    /// are reset there and nowhere else, for redrawing a small part of the frame such as a
+    /// cursor or a HUD element. The rectangle is marked dirty.
+    ///
+    pub fn clear_partial(&mut self, rect: Rect, color: u32) {
+        let rect = rect.clipped_to(self.width, self.height);
+        if rect.is_empty() {
+            return;
+        }
+        for y in rect.y..rect.y + rect.height {
+            let row = (y * self.width) as usize;
+            let span = row + rect.x as usize..row + (rect.x + rect.width) as usize;
+            self.framebuffer[span.clone()].fill(color);
+            self.z_buffer[span.clone()].fill(f32::INFINITY);
+            self.stencil_buffer[span.clone()].fill(0);
+            if let Some(hdr_buffer) = &mut self.hdr_buffer {
+                hdr_buffer[span].fill(HDR_UNWRITTEN);
+            }
+        }
+        self.mark_dirty(rect);
+    }
+
+    /// Records that `rect` (clipped to the screen) changed; forgotten at the next full clear
+    pub fn mark_dirty(&mut self, rect: Rect) {
+        let rect = rect.clipped_to(self.width, self.height);
+        if !rect.is_empty() && !self.dirty_rects.contains(&rect) {
+            self.dirty_rects.push(rect);
+        }
+    }
+
+    /// Everything passed to mark_dirty (or cleared by clear_partial) since the last full clear
+    pub fn dirty_rects(&self) -> &[Rect] {
+        &self.dirty_rects
+    }
+
+    pub fn clear_stencil(&mut self, value: u8) {
+        for stencil in &mut self.stencil_buffer {
+            *stencil = value;
+        }
+    }
+
+    /// Resets depth only where the stencil buffer holds `value`,
+    /// so a portal region can be drawn into without the frame's depth in the way
+    pub fn clear_depth_where_stencil(&mut self, value: u8) {
+        for (depth, stencil) in self.z_buffer.iter_mut().zip(&self.stencil_buffer) {
+            if *stencil == value {
+                *depth = f32::INFINITY;
+            }
+        }
     }
 
     pub fn set_pixel(&mut self, x: u32, y: u32, color: u32) {
         if x < self.width && y < self.height {
             let index = (y * self.width + x) as usize;
-            self.framebuffer[index] = color;
+            if !self.stencil_test(index) {
+                return;
+            }
+            self.write_color(index, PixelColor::Ldr(color));
+            self.stencil_write(index);
         }
     }
-}
\ No newline at end of file
+
+    /// set_pixel with an explicit blend mode; stencil tested like set_pixel, no depth test
+    pub fn set_pixel_blend(&mut self, x: u32, y: u32, color: u32, blend: BlendMode) {
+        if x < self.width && y < self.height {
+            let index = (y * self.width + x) as usize;
+            if !self.stencil_test(index) {
+                return;
+            }
+            let pixel_color = match blend {
+                BlendMode::Replace => PixelColor::Ldr(color | 0xFF000000),
+                BlendMode::Alpha(alpha) => {
+                    let alpha = (alpha.clamp(0.0, 1.0) * 255.0).round() as u32;
+                    PixelColor::Ldr(color & 0x00FFFFFF | (alpha << 24))
+                }
+                BlendMode::Additive => PixelColor::Additive(color),
+            };
+            self.write_color(index, pixel_color);
+            self.stencil_write(index);
+        }
+    }
+
+    /// Writes linear RGB into the HDR buffer (values above 1.0 are kept until tonemap_hdr)
+    pub fn set_pixel_hdr(&mut self, x: u32, y: u32, r: f32, g: f32, b: f32) {
+        if x < self.width && y < self.height {
+            let index = (y * self.width + x) as usize;
+            if !self.stencil_test(index) {
+                return;
+            }
+            self.write_color(index, PixelColor::Hdr([r, g, b], 1.0));
+            self.stencil_write(index);
+        }
+    }
+
+    ///
+    /// Resolves the HDR buffer into the framebuffer: Reinhard tonemapping c / (1 + c)
+    /// squeezes any brightness into [0, 1), then gamma 2.2 encodes it for the display.
+    /// Pixels that weren't drawn in HDR since the last clear keep their framebuffer color.
+    /// Resolved pixels are emptied from the HDR buffer, so anything drawn afterwards goes
+    /// straight to the framebuffer and calling this twice doesn't tonemap twice.
+    ///
+    pub fn tonemap_hdr(&mut self) {
+        // Keeps log(0) for black pixels finite
+        const LUMINANCE_EPSILON: f32 = 1e-4;
+
+        let Some(hdr_buffer) = &mut self.hdr_buffer else {
+            return;
+        };
+
+        let mut log_sum = 0.0;
+        let mut resolved = 0;
+        for (pixel, hdr) in self.framebuffer.iter_mut().zip(hdr_buffer.iter_mut()) {
+            if hdr[0] < 0.0 {
+                continue;
+            }
+            let luminance = 0.2126 * hdr[0] + 0.7152 * hdr[1] + 0.0722 * hdr[2];
+            log_sum += (LUMINANCE_EPSILON + luminance.max(0.0)).ln();
+            resolved += 1;
+
+            let mapped = hdr.map(|channel| (channel / (1.0 + channel)).powf(1.0 / 2.2));
+            *pixel = pack_unit_rgb(mapped);
+            *hdr = HDR_UNWRITTEN;
+        }
+
+        if resolved > 0 {
+            self.log_average_luminance = Some((log_sum / resolved as f32).exp());
+        }
+    }
+
+    ///
+    /// Log-average luminance exp(mean(ln L)) of the pixels the last tonemap_hdr resolved,
+    /// before tonemapping. None when nothing was drawn in HDR since the last clear.
+    ///
+    pub fn get_log_average_luminance(&self) -> Option<f32> {
+        self.log_average_luminance
+    }
+}
+
+// Alpha-blends an ARGB color over an opaque pixel
+fn blend(destination: u32, source: u32) -> u32 {
+    let alpha = source >> 24;
+    if alpha == 0xFF {
+        return source;
+    }
+
+    let mix = |shift: u32| {
+        let src = (source >> shift) & 0xFF;
+        let dst = (destination >> shift) & 0xFF;
+        (src * alpha + dst * (255 - alpha)) / 255
+    };
+    0xFF000000 | (mix(16) << 16) | (mix(8) << 8) | mix(0)
+}
+
+// Adds the RGB of an ARGB color to an opaque pixel, each channel clamped at 255
+fn add_saturating(destination: u32, source: u32) -> u32 {
+    let add = |shift: u32| (((destination >> shift) & 0xFF) + ((source >> shift) & 0xFF)).min(0xFF);
+    0xFF000000 | (add(16) << 16) | (add(8) << 8) | add(0)
+}
+
+///
+/// Screen-space barycentrics are off for attributes under perspective; dividing each weight
+/// by its vertex depth (proportional to view distance) and renormalizing corrects that.
+///
+fn perspective_weights(screen_weights: [f32; 3], depth: [f32; 3]) -> [f32; 3] {
+    if depth.iter().any(|z| *z <= 0.0) {
+        return screen_weights;
+    }
+    let scaled = [0, 1, 2].map(|i| screen_weights[i] / depth[i]);
+    let sum = scaled[0] + scaled[1] + scaled[2];
+    if sum <= 0.0 {
+        return screen_weights;
+    }
+    scaled.map(|weight| weight / sum)
+}
+
+// Opaque ARGB pixel to [0, 1] floats
+fn unpack_unit_rgb(argb: u32) -> [f32; 3] {
+    [
+        ((argb >> 16) & 0xFF) as f32 / 255.0,
+        ((argb >> 8) & 0xFF) as f32 / 255.0,
+        (argb & 0xFF) as f32 / 255.0,
+    ]
+}
+
+// [0, 1] floats to an opaque ARGB pixel
+fn pack_unit_rgb(rgb: [f32; 3]) -> u32 {
+    let r = (rgb[0].clamp(0.0, 1.0) * 255.0) as u32;
+    let g = (rgb[1].clamp(0.0, 1.0) * 255.0) as u32;
+    let b = (rgb[2].clamp(0.0, 1.0) * 255.0) as u32;
+    0xFF000000 | (r << 16) | (g << 8) | b
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn points_in_an_empty_viewport_draw_nothing() {
+        let mut renderer = Renderer::new(4, 4);
+        renderer.set_viewport(Viewport::new(0, 0, 0, 0));
+        renderer.draw_point(1.5, 1.5, 0.5, 3.0, 0xFFFFFFFF);
+        renderer.draw_point(1.5, 1.5, 0.5, 1.0, 0xFFFFFFFF);
+        assert!(renderer.get_framebuffer().iter().all(|&pixel| pixel == 0xFF000000));
+    }
+
+    #[test]
+    fn circles_in_an_empty_viewport_draw_nothing() {
+        let mut renderer = Renderer::new(4, 4);
+        renderer.set_viewport(Viewport::new(0, 0, 0, 0));
+        renderer.draw_filled_circle(0.0, 0.0, 3.0, 0xFFFFFFFF, 0.0);
+        assert!(renderer.get_framebuffer().iter().all(|&pixel| pixel == 0xFF000000));
+    }
+}
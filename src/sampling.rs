@@ -0,0 +1,130 @@
+use std::f32::consts::TAU;
+use crate::math::Vec3f;
+
+///
+/// Direction over the hemisphere around `normal` with density proportional to cos θ
+/// (Malley's method): (u, v) picks a point on the unit disk at radius √u and angle 2πv,
+/// which is then lifted straight up onto the hemisphere. `u` and `v` are in [0, 1).
+///
+pub fn hemisphere_cosine_sample(normal: Vec3f, random_u: f32, random_v: f32) -> Vec3f {
+    let radius = random_u.sqrt();
+    let (sin_phi, cos_phi) = (TAU * random_v).sin_cos();
+    let height = (1.0 - random_u).max(0.0).sqrt();
+    to_hemisphere(normal, radius * cos_phi, radius * sin_phi, height)
+}
+
+///
+/// Direction over the hemisphere around `normal` with every direction equally likely:
+/// cos θ = u and φ = 2πv. `u` and `v` are in [0, 1).
+///
+pub fn hemisphere_uniform_sample(normal: Vec3f, u: f32, v: f32) -> Vec3f {
+    let cos_theta = u;
+    let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+    let (sin_phi, cos_phi) = (TAU * v).sin_cos();
+    to_hemisphere(normal, sin_theta * cos_phi, sin_theta * sin_phi, cos_theta)
+}
+
+// (x, y, z) in a tangent frame with z along `normal`, out to world space
+fn to_hemisphere(normal: Vec3f, x: f32, y: f32, z: f32) -> Vec3f {
+    // Any tangent frame works, the hemisphere is symmetric around the normal
+    let helper = if normal.x.abs() < 0.9 { Vec3f::x_axis() } else { Vec3f::y_axis() };
+    let tangent = normal.cross(&helper).normalize();
+    let bitangent = normal.cross(&tangent);
+    (tangent * x + bitangent * y + normal * z).normalize()
+}
+
+///
+/// Small linear congruential generator: fast and the same sequence for the same seed, which is
+/// all sampling needs. Not suitable for anything where the numbers must be unpredictable.
+///
+#[derive(Debug, Clone)]
+pub struct LcgRng {
+    pub state: u64,
+}
+
+impl LcgRng {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        // Knuth's MMIX constants; the high bits are the well-mixed ones
+        self.state = self.state.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1_442_695_040_888_963_407);
+        (self.state >> 32) as u32
+    }
+
+    /// Uniform in [0, 1)
+    pub fn next_f32(&mut self) -> f32 {
+        (self.next_u32() >> 8) as f32 / (1 << 24) as f32
+    }
+}
+
+impl Default for LcgRng {
+    fn default() -> Self {
+        Self::new(0x853C_49E6_748F_EA9B)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLES: usize = 40_000;
+    const BINS: usize = 10;
+
+    // Counts of `values` (each in [0, 1]) in BINS equal slices, checked against an even split
+    fn assert_even(values: impl Iterator<Item = f32>, what: &str) {
+        let mut bins = [0usize; BINS];
+        for value in values {
+            bins[((value * BINS as f32) as usize).min(BINS - 1)] += 1;
+        }
+        let expected = (SAMPLES / BINS) as f32;
+        for (bin, &count) in bins.iter().enumerate() {
+            assert!((count as f32 - expected).abs() < expected * 0.1, "{what} bin {bin}: {count} of {SAMPLES}");
+        }
+    }
+
+    // Height over the normal and angle around it (as a fraction of a turn) for each direction
+    fn spherical(normal: Vec3f, directions: &[Vec3f]) -> (Vec<f32>, Vec<f32>) {
+        let helper = if normal.x.abs() < 0.9 { Vec3f::x_axis() } else { Vec3f::y_axis() };
+        let tangent = normal.cross(&helper).normalize();
+        let bitangent = normal.cross(&tangent);
+        directions
+            .iter()
+            .map(|direction| {
+                let phi = direction.dot(&bitangent).atan2(direction.dot(&tangent));
+                (direction.dot(&normal), phi.rem_euclid(TAU) / TAU)
+            })
+            .unzip()
+    }
+
+    fn draw(normal: Vec3f, sample: fn(Vec3f, f32, f32) -> Vec3f) -> Vec<Vec3f> {
+        let mut rng = LcgRng::new(7);
+        (0..SAMPLES).map(|_| sample(normal, rng.next_f32(), rng.next_f32())).collect()
+    }
+
+    #[test]
+    fn uniform_samples_cover_the_hemisphere_evenly() {
+        let normal = Vec3f::new(1.0, 2.0, -0.5).normalize();
+        let directions = draw(normal, hemisphere_uniform_sample);
+        let (heights, turns) = spherical(normal, &directions);
+
+        assert!(directions.iter().all(|direction| (direction.length() - 1.0).abs() < 1e-4));
+        assert!(heights.iter().all(|&height| height >= 0.0));
+        // Equal solid angles hold equal slices of cos θ
+        assert_even(heights.into_iter(), "cos θ");
+        assert_even(turns.into_iter(), "φ");
+    }
+
+    #[test]
+    fn cosine_samples_lean_towards_the_normal() {
+        let normal = Vec3f::new(0.0, -1.0, 0.0);
+        let directions = draw(normal, hemisphere_cosine_sample);
+        let (heights, turns) = spherical(normal, &directions);
+
+        assert!(heights.iter().all(|&height| height >= 0.0));
+        // A density of cos θ makes cos² θ uniform
+        assert_even(heights.iter().map(|height| height * height), "cos² θ");
+        assert_even(turns.into_iter(), "φ");
+    }
+}
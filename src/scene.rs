@@ -1,71 +1,441 @@
-use crate::math::{Mat4x4, Vec2f, Vec3f};
-use crate::mesh::Mesh;
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use crate::math::{Mat4x4, Quat, Vec2f, Vec3f, Vec4f};
+use crate::mesh::{Mesh, Triangle};
 use crate::camera::Camera;
-use crate::lighting::{Light, LightingSystem, Material};
-use crate::renderer::Renderer;
+use crate::coordinate_system::CoordinateSystem;
+use crate::camera_path::CameraPath;
+use crate::camera_sequence::CameraSequence;
+use crate::event_queue::EventQueue;
+use crate::spring::Spring;
+use crate::physics::{PhysicsWorld, RigidBody};
+use crate::lightmap::Lightmap;
+use crate::material_registry::{MaterialHandle, MaterialRegistry};
+use crate::lighting::{Light, LightHandle, LightReceiver, LightingDebug, LightingSystem, Material, VertexColorMode};
+use crate::renderer::{Renderer, StencilMode, StencilOp};
+use crate::post_process::PostEffect;
+use crate::font::BitmapFont;
+use crate::render_pipeline::RenderPipeline;
+use crate::skybox::SkyboxRenderer;
+use crate::gizmo::TransformGizmo;
+use crate::light_gizmo::LightDrag;
+use crate::light_debug::draw_clip_line;
+use crate::draw_list::{DrawList, ScreenTriangle, TriangleFill};
+use crate::lighting_lod::{LightingCounts, LightingLod, LightingLodPolicy, ObjectLighting};
+
+///
+/// Identifies a GameObject for as long as the program runs, wherever it ends up stored.
+/// Every GameObject::new gets a fresh one.
+///
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ObjectId(u64);
+
+static NEXT_OBJECT_ID: AtomicU64 = AtomicU64::new(0);
+
+impl ObjectId {
+    fn next() -> Self {
+        ObjectId(NEXT_OBJECT_ID.fetch_add(1, Ordering::Relaxed))
+    }
+}
 
 pub struct GameObject {
     pub mesh: Mesh,
+    // Prefer the setters below; after writing these fields directly, call mark_dirty()
     pub position: Vec3f,
     pub rotation: Vec3f,
     pub scale: Vec3f,
+    pub pivot_offset: Vec3f,  // Local-space point that rotation and scale happen around
+    pub velocity: Vec3f,      // Units per second, applied by Scene::update
     pub materials: Vec<Material>,
+    pub shared_materials: Vec<Option<MaterialHandle>>, // Registry materials used in place of `materials`, slot by slot
+    pub layers: u32, // Bit set; lights reach the object when their mask shares a bit with it
+    pub static_lighting: bool, // Lit from a lightmap once Scene::bake_lightmaps runs
+    pub lightmap: Option<Lightmap>,
+    id: ObjectId,
+    dirty: Cell<bool>,
+    warned_missing_material: Cell<bool>, // Set once a triangle's material id has been reported out of range
+    cached_world_matrix: Cell<Mat4x4>,
 }
 
 impl GameObject {
     pub fn new(mesh: Mesh) -> Self {
         Self {
             mesh,
-            position: Vec3f::new(0.0, 0.0, 0.0),
-            rotation: Vec3f::new(0.0, 0.0, 0.0),
-            scale: Vec3f::new(1.0, 1.0, 1.0),
+            position: Vec3f::zero(),
+            rotation: Vec3f::zero(),
+            scale: Vec3f::one(),
+            pivot_offset: Vec3f::zero(),
+            velocity: Vec3f::zero(),
             materials: vec![Material::default()],
+            shared_materials: Vec::new(),
+            layers: 1,
+            static_lighting: false,
+            lightmap: None,
+            id: ObjectId::next(),
+            dirty: Cell::new(true),
+            warned_missing_material: Cell::new(false),
+            cached_world_matrix: Cell::new(Mat4x4::identity()),
         }
     }
 
     pub fn with_position(mut self, position: Vec3f) -> Self {
-        self.position = position;
+        self.set_position(position);
         self
     }
 
     pub fn with_rotation(mut self, rotation: Vec3f) -> Self {
-        self.rotation = rotation;
+        self.set_rotation(rotation);
         self
     }
 
     pub fn with_scale(mut self, scale: Vec3f) -> Self {
-        self.scale = scale;
+        self.set_scale(scale);
+        self
+    }
+
+    pub fn with_static_lighting(mut self, static_lighting: bool) -> Self {
+        self.static_lighting = static_lighting;
+        self
+    }
+
+    pub fn with_layers(mut self, layers: u32) -> Self {
+        self.layers = layers;
+        self
+    }
+
+    pub fn id(&self) -> ObjectId {
+        self.id
+    }
+
+    /// What lights see when shading this object, before ambient occlusion
+    pub fn light_receiver(&self) -> LightReceiver {
+        LightReceiver { layers: self.layers, object: Some(self.id), ..LightReceiver::default() }
+    }
+
+    /// light_receiver with the mesh's mean baked occlusion and vertex color, for lighting the object as a whole
+    pub fn average_light_receiver(&self) -> LightReceiver {
+        let mean = |count: usize| 1.0 / count.max(1) as f32;
+        let ao = &self.mesh.vertex_ao;
+        let colors = &self.mesh.vertex_colors;
+        LightReceiver {
+            occlusion: if ao.is_empty() { 1.0 } else { ao.iter().sum::<f32>() * mean(ao.len()) },
+            vertex_color: (!colors.is_empty() && colors.len() == self.mesh.vertices.len())
+                .then(|| colors.iter().fold(Vec3f::zero(), |sum, &color| sum + color) * mean(colors.len())),
+            ..self.light_receiver()
+        }
+    }
+
+    pub fn with_pivot_offset(mut self, pivot_offset: Vec3f) -> Self {
+        self.set_pivot_offset(pivot_offset);
         self
     }
 
+    pub fn set_position(&mut self, position: Vec3f) {
+        self.position = position;
+        self.mark_dirty();
+    }
+
+    pub fn set_rotation(&mut self, rotation: Vec3f) {
+        self.rotation = rotation;
+        self.mark_dirty();
+    }
+
+    pub fn set_scale(&mut self, scale: Vec3f) {
+        self.scale = scale;
+        self.mark_dirty();
+    }
+
+    pub fn set_pivot_offset(&mut self, pivot_offset: Vec3f) {
+        self.pivot_offset = pivot_offset;
+        self.mark_dirty();
+    }
+
+    /// Forces the world matrix to be rebuilt on next use
+    pub fn mark_dirty(&self) {
+        self.dirty.set(true);
+    }
+
+    pub fn is_dirty(&self) -> bool {
+        self.dirty.get()
+    }
+
+    ///
+    /// The model matrix, cached until the transform changes.
+    /// Static objects only pay for the matrix multiplications once.
+    ///
+    pub fn get_world_matrix(&self) -> Mat4x4 {
+        if self.dirty.get() {
+            self.cached_world_matrix.set(self.get_model_matrix());
+            self.dirty.set(false);
+        }
+        self.cached_world_matrix.get()
+    }
+
     pub fn add_material(&mut self, material: Material) -> usize {
         self.materials.push(material);
         self.materials.len() - 1
     }
 
+    /// Points material slot `slot` at a registry material, in place of the object's own one
+    pub fn set_shared_material(&mut self, slot: usize, handle: MaterialHandle) {
+        if self.shared_materials.len() <= slot {
+            self.shared_materials.resize(slot + 1, None);
+        }
+        self.shared_materials[slot] = Some(handle);
+    }
+
+    /// Adds a slot that uses a registry material; returns its index for Triangle::material_id
+    pub fn add_shared_material(&mut self, handle: MaterialHandle) -> usize {
+        let slot = self.add_material(Material::default()); // Placeholder, never drawn while the handle is set
+        self.set_shared_material(slot, handle);
+        slot
+    }
+
+    pub fn with_shared_material(mut self, handle: MaterialHandle) -> Self {
+        self.set_shared_material(0, handle);
+        self
+    }
+
     pub fn get_model_matrix(&self) -> Mat4x4 {
         let translation = Mat4x4::translation(self.position.x, self.position.y, self.position.z);
         let rotation_x = Mat4x4::rotation_x(self.rotation.x);
         let rotation_y = Mat4x4::rotation_y(self.rotation.y);
         let rotation_z = Mat4x4::rotation_z(self.rotation.z);
         let scale = Mat4x4::scale(self.scale.x, self.scale.y, self.scale.z);
+        let pivot = Mat4x4::translation(-self.pivot_offset.x, -self.pivot_offset.y, -self.pivot_offset.z);
+
+        translation.multiply(&rotation_z.multiply(&rotation_y.multiply(&rotation_x.multiply(&scale.multiply(&pivot)))))
+    }
+
+    ///
+    /// World-space bounding sphere (center, radius): the mesh's cached one moved by the world
+    /// matrix and grown by the largest scale, so rotating never means going over the vertices.
+    ///
+    pub fn get_bounding_sphere(&self) -> (Vec3f, f32) {
+        let (local_center, local_radius) = self.mesh.bounding_sphere();
 
-        translation.multiply(&rotation_z.multiply(&rotation_y.multiply(&rotation_x.multiply(&scale))))
+        let max_scale = self.scale.x.abs().max(self.scale.y.abs()).max(self.scale.z.abs());
+        let center = self.get_world_matrix().multiply_point(&local_center);
+        (center, local_radius * max_scale)
+    }
+
+    ///
+    /// The triangle's material, falling back to the first slot for missing/invalid ids.
+    /// An id past the last slot is reported on stderr, once per object.
+    /// Slots with a shared material read it from `registry`, or the local one if the handle is stale.
+    ///
+    pub fn material_for<'a>(&'a self, triangle: &Triangle, registry: &'a MaterialRegistry) -> &'a Material {
+        let slot = match triangle.material_id {
+            Some(slot) if slot >= self.materials.len() => {
+                if !self.warned_missing_material.replace(true) {
+                    eprintln!(
+                        "warning: material id {} out of range for object {:?} with {} slots, using slot 0",
+                        slot, self.id, self.materials.len()
+                    );
+                }
+                0
+            }
+            slot => slot.unwrap_or(0),
+        };
+        self.shared_materials
+            .get(slot)
+            .copied()
+            .flatten()
+            .and_then(|handle| registry.get(handle))
+            .unwrap_or(&self.materials[slot])
+    }
+
+    /// The object's rotation (Rz * Ry * Rx of the Euler angles) as a quaternion
+    pub fn get_orientation(&self) -> Quat {
+        let rotation = Mat4x4::rotation_z(self.rotation.z)
+            .multiply(&Mat4x4::rotation_y(self.rotation.y))
+            .multiply(&Mat4x4::rotation_x(self.rotation.x));
+        Quat::from_rotation_matrix(&rotation)
     }
 
     pub fn get_normal_matrix(&self) -> Mat4x4 {
         // For normal transformation, we need inverse transpose of upper 3x3 of model matrix
         // For uniform scaling and rotation, we can use the model matrix directly
         // For non-uniform scaling, we'd need proper inverse transpose
-        self.get_model_matrix()
+        self.get_world_matrix()
+    }
+}
+
+/// A triangle in draw order, as returned by Scene::sort_triangles_by_depth
+#[derive(Copy, Clone, Debug)]
+pub struct SortedTriangle {
+    pub object_index: usize,   // Into Scene::game_objects
+    pub triangle_index: usize, // Into that object's mesh.triangles
+    pub center: Vec3f,         // World space
+    pub distance: f32,         // From the camera position used for sorting
+    pub transparent: bool,
+}
+
+/// An object's vertices and face normals in world space, computed once per frame
+struct WorldMesh<'a> {
+    object: &'a GameObject,
+    vertices: Vec<Vec3f>,
+    normals: Vec<Vec3f>,
+    vertex_normals: Vec<Vec3f>, // Empty when the mesh is flat shaded
+    lighting: ObjectLighting<'a>, // How finely it's lit, per triangle unless render_game_object says otherwise
+}
+
+impl<'a> WorldMesh<'a> {
+    fn new(object: &'a GameObject) -> Self {
+        Self::with_vertex_buffer(object, Vec::new())
+    }
+
+    // Transforms the vertices into `buffer`, reusing its allocation; into_vertex_buffer hands it back
+    fn with_vertex_buffer(object: &'a GameObject, mut buffer: Vec<Vec3f>) -> Self {
+        object.get_world_matrix().multiply_batch_points(&object.mesh.vertices, &mut buffer);
+        Self {
+            object,
+            vertices: buffer,
+            normals: object.mesh.transform_normals(&object.get_normal_matrix()),
+            vertex_normals: object.mesh.transform_vertex_normals(&object.get_normal_matrix()),
+            lighting: ObjectLighting::per_triangle(),
+        }
+    }
+
+    fn into_vertex_buffer(self) -> Vec<Vec3f> {
+        self.vertices
+    }
+
+    fn triangle_center(&self, triangle_index: usize) -> Vec3f {
+        let [i0, i1, i2] = self.object.mesh.triangles[triangle_index].indices;
+        (self.vertices[i0] + self.vertices[i1] + self.vertices[i2]) * (1.0 / 3.0)
+    }
+
+    // Mean normal of the triangles facing `camera_position`, or the direction to it when none do
+    fn average_facing_normal(&self, camera_position: Vec3f) -> Vec3f {
+        let sum = (0..self.normals.len())
+            .map(|index| (self.normals[index], camera_position - self.triangle_center(index)))
+            .filter(|(normal, to_camera)| normal.dot(to_camera) > 0.0)
+            .fold(Vec3f::zero(), |sum, (normal, _)| sum + normal);
+        if sum.length() > 0.0 {
+            sum.normalize()
+        } else {
+            (camera_position - self.object.position).normalize()
+        }
+    }
+}
+
+/// Counters from the last Scene::render call
+#[derive(Copy, Clone, Debug, Default)]
+pub struct RenderStats {
+    pub objects_rendered: usize,
+    pub objects_culled_distance: usize, // Skipped for being beyond max_render_distance
+    pub triangles_drawn: usize,         // Triangles sent to the rasterizer, portal view included
+    pub lighting: LightingCounts,       // Lighting evaluations, by LightingLod
+    pub frame_time_ms: f32,             // Length of the last update step
+}
+
+/// A window into another view of the scene.
+/// The frame marks the stencil buffer, and everything visible through it is drawn from `view`.
+pub struct Portal {
+    pub frame: GameObject,
+    pub view: Camera,
+}
+
+impl Portal {
+    pub fn new(frame: GameObject, view: Camera) -> Self {
+        Self { frame, view }
+    }
+}
+
+/// World-space points drawn as round dots, e.g. a depth sensor scan or particles
+pub struct PointList {
+    pub points: Vec<Vec3f>,
+    pub point_size: f32, // Radius in pixels, the same at any distance
+    pub color: u32,
+}
+
+impl PointList {
+    pub fn new(points: Vec<Vec3f>, point_size: f32, color: u32) -> Self {
+        Self { points, point_size, color }
+    }
+}
+
+/// What Scene::pick found under a pixel
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum PickTarget {
+    Object(usize), // Index into game_objects
+    Light(LightHandle),
+}
+
+/// How cartoon outlines are drawn when Scene::outline_enabled is set
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum OutlineStyle {
+    /// Back faces pushed out along smoothed normals; outline_width thick, but gappy on sharp edges
+    InflatedShell,
+    /// One-pixel lines along the mesh's true silhouette edges
+    SilhouetteEdges,
+}
+
+/// An object taken out by Scene::remove_game_object, with what was attached to it
+pub struct RemovedObject {
+    pub game_object: GameObject,
+    pub springs: Vec<Spring>,
+    pub rigid_bodies: Vec<RigidBody>,
+}
+
+impl RemovedObject {
+    /// A new object with nothing attached, for Scene::insert_game_object
+    pub fn new(game_object: GameObject) -> Self {
+        Self { game_object, springs: Vec::new(), rigid_bodies: Vec::new() }
     }
 }
 
 pub struct Scene {
     pub game_objects: Vec<GameObject>,
+    pub point_lists: Vec<PointList>,
+    pub springs: Vec<Spring>,
+    pub physics: Option<PhysicsWorld>, // Rigid boxes under gravity, see enable_physics
     pub camera: Camera,
+    pub camera_stack: Vec<Camera>, // Cameras set aside by push_camera, the most recent last
+    pub coordinate_system: CoordinateSystem, // Of world positions; set with with_coordinate_system
     pub lighting: LightingSystem,
+    pub material_registry: MaterialRegistry, // Named materials objects can share, see GameObject::shared_materials
     pub rotation_time: f32,
+    pub camera_path: Option<CameraPath>,
+    pub path_t: f32,             // Progress along the camera path, 0..1
+    pub path_playing: bool,
+    pub camera_sequence: Option<CameraSequence>,
+    pub portal: Option<Portal>,
+    pub debug_cameras: Vec<Camera>, // Drawn as wireframe frustums
+    pub event_queue: EventQueue,
+    pub outline_enabled: bool,
+    pub outline_style: OutlineStyle,
+    pub outline_width: f32,      // In pixels, InflatedShell only
+    pub outline_color: u32,
+    pub smooth_vertex_colors: bool, // Blend vertex colors across each triangle; false flat-shades with their average
+    pub max_render_distance: f32, // Objects further than this are skipped (never more than camera.far)
+    pub lighting_lod: LightingLodPolicy, // How finely objects are lit, by distance from the camera
+    pub(crate) lighting_lods: HashMap<ObjectId, LightingLod>, // Each visible object's level this frame
+    pub stats: RenderStats,
+    pub post_effects: Vec<Box<dyn PostEffect>>, // Run in order after everything is drawn
+    pub post_effects_enabled: bool,
+    pub floor_height: f32, // Ground level for first-person movement
+    pub debug_draw_vertices: bool,
+    pub vertex_debug_size: f32, // Radius of the vertex dots in pixels
+    pub show_hud: bool,
+    pub hud_font: BitmapFont,
+    pub selected_object: Option<usize>, // Index into game_objects, shown in the HUD inspector
+    pub gizmo: TransformGizmo, // Translate arrows on the selected object
+    pub selected_light: Option<LightHandle>, // Picked through its gizmo; never set together with selected_object
+    pub show_light_gizmos: bool, // Draggable markers for every light
+    pub(crate) light_drag: Option<LightDrag>,
+    pub show_grid: bool, // XZ reference grid overlay
+    pub show_light_volumes: bool, // Wireframe ranges of point and spot lights
+    pub show_minimap: bool, // Overhead map in the bottom-right corner
+    pub skybox: Option<SkyboxRenderer>, // Gradient behind everything; None keeps the flat clear color
+    pub pipeline: RenderPipeline, // Passes run by render(), forward rendering by default
+    pub(crate) visible_objects: Vec<usize>, // Objects that survived this frame's distance cull
+    frame_triangles: Cell<usize>,
+    frame_lighting: Cell<LightingCounts>,
+    scratch_world_vertices: Cell<Vec<Vec3f>>, // Reused by render_game_object for each object's world-space vertices
+    pub(crate) frame_size: (u32, u32), // Window size at the last render, for turning pixels back into rays
 }
 
 impl Scene {
@@ -75,15 +445,61 @@ impl Scene {
         // Set up better ambient lighting
         lighting.set_ambient(Vec3f::new(0.4, 0.4, 0.5), 0.15);
 
+        let camera = Camera::look_at(
+            Vec3f::new(0.0, 0.0, 8.0),  // Move camera further back
+            Vec3f::zero(),
+            Vec3f::up(),
+        );
+
         Self {
             game_objects: Vec::new(),
-            camera: Camera::look_at(
-                Vec3f::new(0.0, 0.0, 8.0),  // Move camera further back
-                Vec3f::new(0.0, 0.0, 0.0),
-                Vec3f::new(0.0, 1.0, 0.0),
-            ),
+            point_lists: Vec::new(),
+            springs: Vec::new(),
+            physics: None,
+            camera,
+            camera_stack: Vec::new(),
+            coordinate_system: CoordinateSystem::default(),
             lighting,
+            material_registry: MaterialRegistry::new(),
             rotation_time: 0.0,
+            camera_path: None,
+            path_t: 0.0,
+            path_playing: false,
+            camera_sequence: None,
+            portal: None,
+            debug_cameras: Vec::new(),
+            event_queue: EventQueue::new(),
+            outline_enabled: false,
+            outline_style: OutlineStyle::InflatedShell,
+            outline_width: 3.0,
+            outline_color: 0xFF000000,
+            smooth_vertex_colors: true,
+            max_render_distance: camera.far,
+            lighting_lod: LightingLodPolicy::default(),
+            lighting_lods: HashMap::new(),
+            stats: RenderStats::default(),
+            post_effects: Vec::new(),
+            post_effects_enabled: true,
+            floor_height: -3.0,
+            debug_draw_vertices: false,
+            vertex_debug_size: 2.0,
+            show_hud: false,
+            hud_font: BitmapFont::new(2),
+            selected_object: None,
+            gizmo: TransformGizmo::new(),
+            selected_light: None,
+            show_light_gizmos: false,
+            light_drag: None,
+            show_grid: false,
+            show_light_volumes: false,
+            show_minimap: false,
+            skybox: None,
+            pipeline: RenderPipeline::default(),
+            visible_objects: Vec::new(),
+            frame_triangles: Cell::new(0),
+            frame_lighting: Cell::new(LightingCounts::default()),
+            scratch_world_vertices: Cell::new(Vec::new()),
+            frame_size: (1, 1),
         }
     }
 
@@ -91,154 +507,908 @@ impl Scene {
         self.game_objects.push(game_object);
     }
 
+    ///
+    /// Takes the object at `index` out of the scene, along with the springs and rigid bodies
+    /// attached to it. Later objects move down one place, and the selection and everything
+    /// else that refers to objects by index follows them.
+    ///
+    pub fn remove_game_object(&mut self, index: usize) -> Option<RemovedObject> {
+        if index >= self.game_objects.len() {
+            return None;
+        }
+        let game_object = self.game_objects.remove(index);
+
+        let (springs, kept): (Vec<Spring>, Vec<Spring>) = self.springs
+            .drain(..)
+            .partition(|spring| spring.object_a == index || spring.object_b == index);
+        self.springs = kept;
+        for spring in &mut self.springs {
+            spring.object_a -= usize::from(spring.object_a > index);
+            spring.object_b -= usize::from(spring.object_b > index);
+        }
+
+        let mut rigid_bodies = Vec::new();
+        if let Some(physics) = &mut self.physics {
+            let (attached, kept): (Vec<RigidBody>, Vec<RigidBody>) = physics.bodies
+                .drain(..)
+                .partition(|body| body.game_object_index == index);
+            physics.bodies = kept;
+            for body in &mut physics.bodies {
+                body.game_object_index -= usize::from(body.game_object_index > index);
+            }
+            rigid_bodies = attached;
+        }
+
+        self.selected_object = match self.selected_object {
+            Some(selected) if selected == index => None,
+            Some(selected) if selected > index => Some(selected - 1),
+            selected => selected,
+        };
+        Some(RemovedObject { game_object, springs, rigid_bodies })
+    }
+
+    ///
+    /// Puts an object in at `index` (at most the end), moving later ones up a place, then
+    /// adds its springs and rigid bodies, which use the indices as they are after the insert.
+    /// Undoes remove_game_object.
+    ///
+    pub fn insert_game_object(&mut self, index: usize, removed: RemovedObject) {
+        let index = index.min(self.game_objects.len());
+        self.game_objects.insert(index, removed.game_object);
+
+        for spring in &mut self.springs {
+            spring.object_a += usize::from(spring.object_a >= index);
+            spring.object_b += usize::from(spring.object_b >= index);
+        }
+        self.springs.extend(removed.springs);
+
+        if let Some(physics) = &mut self.physics {
+            for body in &mut physics.bodies {
+                body.game_object_index += usize::from(body.game_object_index >= index);
+            }
+        }
+        if !removed.rigid_bodies.is_empty() {
+            self.enable_physics().bodies.extend(removed.rigid_bodies);
+        }
+
+        if let Some(selected) = &mut self.selected_object
+            && *selected >= index {
+            *selected += 1;
+        }
+    }
+
+    ///
+    /// Replaces the objects at `indices` with one object whose mesh is all of theirs baked
+    /// into world space, for props that never move apart (a table from five boxes). Their
+    /// material slots are appended in order and the triangles renumbered to match.
+    /// The new object sits at the origin with an identity transform, goes at the end, and is
+    /// static if they all were; springs, rigid bodies and lightmaps of the originals are dropped.
+    /// Returns its index, or None (changing nothing) if any index is out of range.
+    ///
+    pub fn combine_objects(&mut self, indices: &[usize]) -> Option<usize> {
+        let mut indices = indices.to_vec();
+        indices.sort_unstable();
+        indices.dedup();
+        if indices.is_empty() || indices.iter().any(|&index| index >= self.game_objects.len()) {
+            return None;
+        }
+
+        // From the back, so the earlier indices stay put
+        let mut originals: Vec<GameObject> = indices
+            .iter()
+            .rev()
+            .filter_map(|&index| self.remove_game_object(index))
+            .map(|removed| removed.game_object)
+            .collect();
+        originals.reverse();
+
+        let mut mesh = Mesh::new();
+        let mut materials = Vec::new();
+        let mut shared_materials = Vec::new();
+        let static_lighting = originals.iter().all(|original| original.static_lighting);
+        let layers = originals.iter().fold(0, |layers, original| layers | original.layers);
+        for mut original in originals {
+            let mut part = Mesh::new();
+            part.merge(&original.mesh);
+            part.bake_transform(&original.get_world_matrix());
+            mesh.merge_with_material_offset(&part, materials.len());
+
+            original.shared_materials.resize(original.materials.len(), None);
+            shared_materials.append(&mut original.shared_materials);
+            materials.append(&mut original.materials);
+        }
+
+        let mut combined = GameObject::new(mesh)
+            .with_static_lighting(static_lighting)
+            .with_layers(layers);
+        combined.materials = materials;
+        combined.shared_materials = shared_materials;
+        self.add_game_object(combined);
+        Some(self.game_objects.len() - 1)
+    }
+
+    ///
+    /// The scene for a world in `coordinate_system`: the default camera keeps its place
+    /// relative to the world but takes the system's up axis, physics pulls down along it,
+    /// and the add_* helpers convert their meshes and layouts into it.
+    /// Y-up right-handed is what Scene::new gives.
+    ///
+    pub fn with_coordinate_system(mut self, coordinate_system: CoordinateSystem) -> Self {
+        let convert = |v: Vec3f| coordinate_system.convert_from(self.coordinate_system, v);
+        self.camera.position = convert(self.camera.position);
+        self.camera.target = convert(self.camera.target);
+        self.camera.up = coordinate_system.up();
+        self.camera.coordinate_system = coordinate_system;
+        if let Some(physics) = &mut self.physics {
+            physics.gravity = convert(physics.gravity);
+        }
+        self.coordinate_system = coordinate_system;
+        self
+    }
+
+    ///
+    /// Draws a frame: sets up the viewport and culls by distance, then runs `pipeline`.
+    /// Stats are updated once the pipeline is done.
+    ///
     pub fn render(&mut self, renderer: &mut Renderer) {
+        self.begin_frame(renderer);
+
+        let scene: &Scene = self;
+        scene.pipeline.execute(scene, renderer);
+
+        self.stats = self.frame_stats();
+
+        if let Some(luminance) = renderer.get_log_average_luminance() {
+            self.lighting.adapt_exposure(luminance, self.stats.frame_time_ms / 1000.0);
+        }
+    }
+
+    // Per-frame setup shared by every pipeline
+    fn begin_frame(&mut self, renderer: &mut Renderer) {
         renderer.clear(0xFF111111); // Dark gray background
 
-        // Update camera aspect ratio
+        // Work out the viewport from the aspect policy; the projection uses its aspect, not the window's
         let (width, height) = renderer.get_dimension();
-        self.camera.set_aspect_ratio(width as f32, height as f32);
+        self.frame_size = (width, height);
+        let viewport = self.camera.compute_viewport(width, height);
+        renderer.set_viewport(viewport);
+        renderer.fill_outside_viewport(0xFF000000);
+        self.camera.set_aspect_ratio(viewport.width as f32, viewport.height as f32);
+        self.camera.update_matrices();
+        if let Some(portal) = &mut self.portal {
+            portal.view.set_aspect_ratio(viewport.width as f32, viewport.height as f32);
+            portal.view.update_matrices();
+        }
+
+        // Cheap distance cull before any per-triangle work; the projection would clip these anyway
+        self.stats = RenderStats {
+            frame_time_ms: self.stats.frame_time_ms,
+            ..RenderStats::default()
+        };
+        self.frame_triangles.set(0);
+        self.frame_lighting.set(LightingCounts::default());
+        let render_distance = self.max_render_distance.min(self.camera.far);
+
+        self.visible_objects.clear();
+        for (index, game_object) in self.game_objects.iter().enumerate() {
+            let (center, radius) = game_object.get_bounding_sphere();
+            if (center - self.camera.position).length() - radius > render_distance {
+                self.stats.objects_culled_distance += 1;
+                continue;
+            }
+            self.stats.objects_rendered += 1;
+            self.visible_objects.push(index);
+        }
+        self.update_lighting_lods();
+    }
+
+    /// Stats for the frame being drawn, including the triangles passes have drawn so far
+    pub(crate) fn frame_stats(&self) -> RenderStats {
+        RenderStats {
+            triangles_drawn: self.frame_triangles.get(),
+            lighting: self.frame_lighting.get(),
+            ..self.stats
+        }
+    }
+
+    /// Passes call this with the number of triangles they sent to the rasterizer
+    pub(crate) fn count_triangles(&self, count: usize) {
+        self.frame_triangles.set(self.frame_triangles.get() + count);
+    }
+
+    /// Records lighting evaluations for this frame's stats
+    pub(crate) fn count_lighting(&self, update: impl FnOnce(&mut LightingCounts)) {
+        let mut counts = self.frame_lighting.get();
+        update(&mut counts);
+        self.frame_lighting.set(counts);
+    }
+
+    /// Top-right panel with the selected object's position and orientation (in degrees)
+    pub(crate) fn render_inspector(renderer: &mut Renderer, font: &BitmapFont, game_object: &GameObject) {
+        const MARGIN: i32 = 6;
+
+        let (yaw, pitch, roll) = game_object.get_orientation().to_euler_degrees_yaw_pitch_roll();
+        let position = game_object.position;
+        let lines = [
+            format!("POS: {:.2}, {:.2}, {:.2}", position.x, position.y, position.z),
+            format!("YAW: {:.1}", yaw),
+            format!("PITCH: {:.1}", pitch),
+            format!("ROLL: {:.1}", roll),
+        ];
+
+        let (screen_width, _) = renderer.get_dimension();
+        let width = lines.iter().map(|line| font.text_width(line)).max().unwrap_or(0) + 2 * MARGIN as u32;
+        let height = lines.len() as u32 * font.line_height() + 2 * MARGIN as u32;
+        let left = screen_width as i32 - width as i32;
+        renderer.draw_filled_rect(left, 0, width, height, 0x99000000);
+
+        let mut y = MARGIN;
+        for line in &lines {
+            renderer.draw_text(left + MARGIN, y, line, font, 0xFFFFFFFF);
+            y += font.line_height() as i32;
+        }
+    }
+
+    ///
+    /// Index of the closest object whose bounding sphere the ray hits, if any.
+    /// `direction` must be normalized (as from Camera::screen_to_ray).
+    ///
+    pub fn ray_pick(&self, origin: Vec3f, direction: Vec3f) -> Option<usize> {
+        let mut closest: Option<(usize, f32)> = None;
+
+        for (index, game_object) in self.game_objects.iter().enumerate() {
+            let (center, radius) = game_object.get_bounding_sphere();
+
+            // Solve |origin + t*direction - center|² = radius² for the nearest t >= 0
+            let to_origin = origin - center;
+            let b = to_origin.dot(&direction);
+            let c = to_origin.dot(&to_origin) - radius * radius;
+            let discriminant = b * b - c;
+            if discriminant < 0.0 {
+                continue;
+            }
+            let sqrt_d = discriminant.sqrt();
+            let t = if -b - sqrt_d >= 0.0 { -b - sqrt_d } else { -b + sqrt_d };
+            if t < 0.0 {
+                continue;
+            }
+
+            if closest.is_none_or(|(_, best)| t < best) {
+                closest = Some((index, t));
+            }
+        }
+
+        closest.map(|(index, _)| index)
+    }
+
+    ///
+    /// What's under a window pixel as of the last rendered frame: a light gizmo if one is
+    /// close enough (they're drawn over the objects), otherwise the object the ray through
+    /// it hits first.
+    ///
+    pub fn pick(&self, x: u32, y: u32) -> Option<PickTarget> {
+        let (pixel_x, pixel_y) = (x as f32 + 0.5, y as f32 + 0.5);
+        if let Some(handle) = self.pick_light(pixel_x, pixel_y) {
+            return Some(PickTarget::Light(handle));
+        }
+        let (width, height) = self.frame_size;
+        let (origin, direction) = self.camera.screen_to_ray(pixel_x, pixel_y, width, height)?;
+        self.ray_pick(origin, direction).map(PickTarget::Object)
+    }
+
+    /// Selects whatever is under the pixel, or clears the selection when nothing is
+    pub fn select_with_ray(&mut self, origin: Vec3f, direction: Vec3f) {
+        self.selected_object = self.ray_pick(origin, direction);
+        self.selected_light = None;
+    }
+
+    /// Same as select_with_ray, for a window pixel as of the last rendered frame, lights included
+    pub fn select_at_pixel(&mut self, x: u32, y: u32) {
+        match self.pick(x, y) {
+            Some(PickTarget::Light(handle)) => self.select_light(handle),
+            target => {
+                self.selected_object = target.and_then(|target| match target {
+                    PickTarget::Object(index) => Some(index),
+                    PickTarget::Light(_) => None,
+                });
+                self.selected_light = None;
+            }
+        }
+    }
+
+    /// Selects a light, dropping any selected object
+    pub fn select_light(&mut self, handle: LightHandle) {
+        self.selected_light = Some(handle);
+        self.selected_object = None;
+    }
+
+    /// Top-left overlay with frame rate, triangle and lighting counts and where the camera is and looks,
+    /// plus the lighting debug view when one is active
+    pub fn render_debug_hud(renderer: &mut Renderer, font: &BitmapFont, stats: &RenderStats, camera: &Camera,
+                            lighting_debug: Option<LightingDebug>) {
+        const MARGIN: i32 = 6;
+
+        let fps = if stats.frame_time_ms > 0.0 { 1.0 / stats.frame_time_ms * 1000.0 } else { 0.0 };
+        let forward = camera.get_forward_vector();
+        let mut lines = vec![
+            format!("FPS: {:.1} ({:.1} MS)", fps, stats.frame_time_ms),
+            format!("TRIS: {}", stats.triangles_drawn),
+            format!(
+                "LIT: {} OBJ {} TRI {} PX",
+                stats.lighting.per_object, stats.lighting.per_triangle, stats.lighting.per_pixel
+            ),
+            format!("POS: {:.2}, {:.2}, {:.2}", camera.position.x, camera.position.y, camera.position.z),
+            format!("DIR: {:.2}, {:.2}, {:.2}", forward.x, forward.y, forward.z),
+        ];
+        if let Some(debug) = lighting_debug {
+            lines.push(format!("DEBUG: {}", debug.name()));
+        }
+
+        let width = lines.iter().map(|line| font.text_width(line)).max().unwrap_or(0);
+        let height = lines.len() as u32 * font.line_height();
+        renderer.draw_filled_rect(0, 0, width + 2 * MARGIN as u32, height + 2 * MARGIN as u32, 0x99000000);
 
+        let mut y = MARGIN;
+        for line in &lines {
+            renderer.draw_text(MARGIN, y, line, font, 0xFFFFFFFF);
+            y += font.line_height() as i32;
+        }
+    }
+
+    pub fn add_post_effect(&mut self, effect: Box<dyn PostEffect>) {
+        self.post_effects.push(effect);
+    }
+
+    ///
+    /// Debug view of mesh density: every vertex becomes a dot, colored by its index so
+    /// neighbouring vertices, seams and duplicates stand out.
+    /// Stops after MAX_DEBUG_VERTICES vertices to keep dense meshes usable.
+    ///
+    pub(crate) fn draw_vertex_dots(&self, view_matrix: &Mat4x4, proj_matrix: &Mat4x4, renderer: &mut Renderer) {
+        const MAX_DEBUG_VERTICES: usize = 1000;
+        const PALETTE: [u32; 8] = [
+            0xFFFF4040, 0xFF40FF40, 0xFF4080FF, 0xFFFFFF40,
+            0xFFFF40FF, 0xFF40FFFF, 0xFFFF9020, 0xFFFFFFFF,
+        ];
+
+        let mut drawn = 0;
+        for game_object in &self.game_objects {
+            let world_vertices = game_object.mesh.transform_vertices(&game_object.get_world_matrix());
+            for (index, vertex) in world_vertices.iter().enumerate() {
+                if drawn >= MAX_DEBUG_VERTICES {
+                    return;
+                }
+
+                let camera_point = view_matrix.multiply_point(vertex);
+                if let Some(screen) = self.project_to_screen(&camera_point, proj_matrix, renderer) {
+                    let depth = -camera_point.z / self.camera.far;
+                    let color = PALETTE[index % PALETTE.len()];
+                    renderer.draw_filled_circle(screen.x, screen.y, self.vertex_debug_size, color, depth);
+                    drawn += 1;
+                }
+            }
+        }
+    }
+
+    /// Draws every point list, depth tested against the geometry
+    pub(crate) fn render_point_lists(&self, view_matrix: &Mat4x4, proj_matrix: &Mat4x4, renderer: &mut Renderer) {
+        for point_list in &self.point_lists {
+            for point in &point_list.points {
+                let camera_point = view_matrix.multiply_point(point);
+                if let Some(screen) = self.project_to_screen(&camera_point, proj_matrix, renderer) {
+                    let depth = -camera_point.z / self.camera.far;
+                    renderer.draw_point(screen.x, screen.y, depth, point_list.point_size, point_list.color);
+                }
+            }
+        }
+    }
+
+    /// Draws another camera's view frustum as 12 lines, as seen from the active camera
+    pub fn draw_frustum(&self, frustum_camera: &Camera, renderer: &mut Renderer, color: u32) {
+        let corners = frustum_camera.frustum_corners();
+
+        for i in 0..4 {
+            let next = (i + 1) % 4;
+            self.draw_world_line(corners[i], corners[next], renderer, color);         // Near rectangle
+            self.draw_world_line(corners[i + 4], corners[next + 4], renderer, color); // Far rectangle
+            self.draw_world_line(corners[i], corners[i + 4], renderer, color);        // Edges
+        }
+    }
+
+    ///
+    /// Reference grid on the XZ plane around the origin: lines every `spacing` units out to
+    /// `count` cells in each direction, plus a vertical Y axis in a contrasting color.
+    /// Lines with an endpoint behind the camera are skipped rather than clipped.
+    ///
+    pub fn draw_debug_grid(renderer: &mut Renderer, vp_matrix: &Mat4x4, spacing: f32, count: i32, color: u32) {
+        const Y_AXIS_COLOR: u32 = 0xFF40FF40;
+
+        let viewport = renderer.get_viewport();
+        let project = |point: Vec3f| {
+            let clip = vp_matrix.multiply_point_4d(&point);
+            (clip.w > 0.0).then(|| viewport.ndc_to_screen(clip.x / clip.w, clip.y / clip.w))
+        };
+        let mut draw = |start: Vec3f, end: Vec3f, color: u32| {
+            if let (Some(a), Some(b)) = (project(start), project(end)) {
+                renderer.draw_line_clipped(a, b, color);
+            }
+        };
+
+        let extent = count as f32 * spacing;
+        for i in -count..=count {
+            let offset = i as f32 * spacing;
+            draw(Vec3f::new(-extent, 0.0, offset), Vec3f::new(extent, 0.0, offset), color); // Along X
+            draw(Vec3f::new(offset, 0.0, -extent), Vec3f::new(offset, 0.0, extent), color); // Along Z
+        }
+        draw(Vec3f::zero(), Vec3f::new(0.0, extent, 0.0), Y_AXIS_COLOR);
+    }
+
+    ///
+    /// Draws a world-space line from the active camera.
+    /// The segment is clipped against the near plane in camera space before projecting,
+    /// so lines that pass behind the camera still show their visible part.
+    ///
+    pub fn draw_world_line(&self, start: Vec3f, end: Vec3f, renderer: &mut Renderer, color: u32) {
         let view_matrix = self.camera.get_view_matrix();
         let proj_matrix = self.camera.get_projection_matrix();
 
-        // Render all game objects
-        for game_object in &self.game_objects {
-            self.render_game_object(game_object, &view_matrix, &proj_matrix, renderer);
+        let mut a = view_matrix.multiply_point(&start);
+        let mut b = view_matrix.multiply_point(&end);
+
+        // Camera looks down -Z, so visible points have z <= -near
+        let near_z = -self.camera.near;
+        if a.z > near_z && b.z > near_z {
+            return;
+        }
+        if a.z > near_z {
+            let t = (near_z - b.z) / (a.z - b.z);
+            a = b + (a - b) * t;
+        } else if b.z > near_z {
+            let t = (near_z - a.z) / (b.z - a.z);
+            b = a + (b - a) * t;
         }
+
+        let viewport = renderer.get_viewport();
+        let to_screen = |point: &Vec3f| {
+            let clip = proj_matrix.multiply_point_4d(point);
+            viewport.ndc_to_screen(clip.x / clip.w, clip.y / clip.w)
+        };
+
+        renderer.draw_line_clipped(to_screen(&a), to_screen(&b), color);
     }
 
-    fn render_game_object(&self, game_object: &GameObject, view_matrix: &Mat4x4,
-                          proj_matrix: &Mat4x4, renderer: &mut Renderer) {
-        let model_matrix = game_object.get_model_matrix();
-        let normal_matrix = game_object.get_normal_matrix();
+    ///
+    /// Portal rendering, done after the main scene:
+    /// 1. Draw the portal frame into the stencil buffer only (depth tested, so anything in front of it still hides it)
+    /// 2. Reset depth inside the marked region
+    /// 3. Draw the scene from the portal's camera, but only where the stencil was marked
+    ///
+    pub(crate) fn render_portal(&self, vp_matrix: &Mat4x4, renderer: &mut Renderer) -> usize {
+        const PORTAL_STENCIL: u8 = 1;
 
-        // Transform vertices to world space
-        let world_vertices = game_object.mesh.transform_vertices(&model_matrix);
+        let portal = match &self.portal {
+            Some(portal) => portal,
+            None => return 0,
+        };
 
-        // Transform normals to world space
-        let world_normals = game_object.mesh.transform_normals(&normal_matrix);
+        renderer.set_color_write(false);
+        renderer.set_stencil_op(Some(StencilOp::new(StencilMode::Always, 0, PORTAL_STENCIL)));
+        let mut frame_list = DrawList::new();
+        self.render_game_object(&portal.frame, &self.camera, vp_matrix, renderer, &mut frame_list);
+        let mut triangles = frame_list.submit(renderer, &self.lighting, self.camera.position);
+        renderer.set_color_write(true);
 
-        // Process each triangle
-        for (triangle_index, triangle) in game_object.mesh.triangles.iter().enumerate() {
-            let (v0_world, v1_world, v2_world) = (
-                world_vertices[triangle.indices[0]],
-                world_vertices[triangle.indices[1]],
-                world_vertices[triangle.indices[2]],
-            );
+        renderer.clear_depth_where_stencil(PORTAL_STENCIL);
 
-            let world_normal = if triangle_index < world_normals.len() {
-                world_normals[triangle_index]
+        let portal_vp = portal.view.get_view_projection_matrix();
+        renderer.set_stencil_op(Some(StencilOp::new(StencilMode::Equal, PORTAL_STENCIL, PORTAL_STENCIL)));
+        let all_objects: Vec<usize> = (0..self.game_objects.len()).collect();
+        triangles += self.render_objects_sorted(&all_objects, &portal.view, &portal_vp, renderer);
+        renderer.set_stencil_op(None);
+        triangles
+    }
+
+    pub fn set_portal(&mut self, portal: Portal) {
+        self.portal = Some(portal);
+    }
+
+    /// Adds the object's visible triangles to `draw_list`, lit and projected by `vp_matrix` (the camera's view-projection)
+    fn render_game_object<'a>(&'a self, game_object: &'a GameObject, camera: &Camera, vp_matrix: &Mat4x4,
+                              renderer: &Renderer, draw_list: &mut DrawList<'a>) {
+        let mut world_mesh = WorldMesh::with_vertex_buffer(game_object, self.scratch_world_vertices.take());
+        // Levels are picked by distance from the scene camera, so other views (the portal's) light per triangle
+        let lod = if std::ptr::eq(camera, &self.camera) {
+            self.lighting_lods.get(&game_object.id()).copied().unwrap_or(LightingLod::PerTriangle)
+        } else {
+            LightingLod::PerTriangle
+        };
+        world_mesh.lighting = match lod {
+            LightingLod::PerObject => ObjectLighting::per_object(
+                game_object.get_bounding_sphere().0,
+                world_mesh.average_facing_normal(camera.position),
+            ),
+            LightingLod::PerTriangle => ObjectLighting::per_triangle(),
+            LightingLod::PerPixel => ObjectLighting::per_pixel(),
+        };
+
+        for triangle_index in 0..game_object.mesh.triangles.len() {
+            if let Some(triangle) = self.project_triangle(&mut world_mesh, triangle_index, camera, vp_matrix, renderer) {
+                draw_list.push(triangle);
+            }
+        }
+        self.scratch_world_vertices.set(world_mesh.into_vertex_buffer());
+    }
+
+    /// Draws the given objects through one DrawList (opaque front to back, transparent
+    /// back to front) and returns how many triangles reached the rasterizer
+    pub(crate) fn render_objects_sorted(&self, object_indices: &[usize], camera: &Camera, vp_matrix: &Mat4x4,
+                             renderer: &mut Renderer) -> usize {
+        let mut draw_list = DrawList::new();
+        for &index in object_indices {
+            self.render_game_object(&self.game_objects[index], camera, vp_matrix, renderer, &mut draw_list);
+        }
+        draw_list.submit(renderer, &self.lighting, camera.position)
+    }
+
+    ///
+    /// Draw order for every triangle in the scene as seen from `camera_pos`.
+    /// Opaque triangles come first, front to back, so the z-buffer rejects as much hidden work
+    /// as possible. Transparent ones (material alpha below 1) follow back to front, so each
+    /// blends over everything behind it. Equal distances keep their original order.
+    ///
+    pub fn sort_triangles_by_depth(&self, camera_pos: Vec3f) -> Vec<SortedTriangle> {
+        let world_meshes: Vec<(usize, WorldMesh)> = self.game_objects
+            .iter()
+            .enumerate()
+            .map(|(index, game_object)| (index, WorldMesh::new(game_object)))
+            .collect();
+        Scene::order_triangles(&world_meshes, &self.material_registry, camera_pos)
+    }
+
+    fn order_triangles(world_meshes: &[(usize, WorldMesh)], registry: &MaterialRegistry,
+                       camera_pos: Vec3f) -> Vec<SortedTriangle> {
+        let mut opaque = Vec::new();
+        let mut transparent = Vec::new();
+
+        for (object_index, world_mesh) in world_meshes {
+            for (triangle_index, triangle) in world_mesh.object.mesh.triangles.iter().enumerate() {
+                let center = world_mesh.triangle_center(triangle_index);
+                let sorted = SortedTriangle {
+                    object_index: *object_index,
+                    triangle_index,
+                    center,
+                    distance: (center - camera_pos).length(),
+                    transparent: world_mesh.object.material_for(triangle, registry).is_transparent(),
+                };
+                if sorted.transparent {
+                    transparent.push(sorted);
+                } else {
+                    opaque.push(sorted);
+                }
+            }
+        }
+
+        // sort_by is stable
+        opaque.sort_by(|a, b| a.distance.total_cmp(&b.distance));
+        transparent.sort_by(|a, b| b.distance.total_cmp(&a.distance));
+        opaque.extend(transparent);
+        opaque
+    }
+
+    ///
+    /// Lights and projects one triangle; None if it was culled or clipped.
+    /// The mesh's `lighting` says how finely; at LightingLod::PerObject it supplies the color of untextured triangles.
+    ///
+    fn project_triangle<'a>(&'a self, world_mesh: &mut WorldMesh<'a>, triangle_index: usize, camera: &Camera,
+                            vp_matrix: &Mat4x4, renderer: &Renderer) -> Option<ScreenTriangle<'a>> {
+        let game_object = world_mesh.object;
+        let triangle = &game_object.mesh.triangles[triangle_index];
+        let world_vertices = &world_mesh.vertices;
+        let world_normals = &world_mesh.normals;
+
+        let (v0_world, v1_world, v2_world) = (
+            world_vertices[triangle.indices[0]],
+            world_vertices[triangle.indices[1]],
+            world_vertices[triangle.indices[2]],
+        );
+
+        let world_normal = if triangle_index < world_normals.len() {
+            world_normals[triangle_index]
+        } else {
+            Vec3f::calculate_triangle_normal(v0_world, v1_world, v2_world)
+        };
+
+        let material = game_object.material_for(triangle, &self.material_registry);
+
+        // Backface culling; two-sided materials keep their back faces and the lighting flips the normal
+        let triangle_center = world_mesh.triangle_center(triangle_index);
+
+        let view_direction = (camera.position - triangle_center).normalize();
+        if world_normal.dot(&view_direction) < 0.0 && !material.two_sided {
+            return None; // Skip back-facing triangles
+        }
+
+        // Transform to clip space; w is the distance in front of the camera
+        let v0_clip = vp_matrix.multiply_point_4d(&v0_world);
+        let v1_clip = vp_matrix.multiply_point_4d(&v1_world);
+        let v2_clip = vp_matrix.multiply_point_4d(&v2_world);
+
+        // Skip if triangle is behind camera
+        if v0_clip.w <= 0.0 || v1_clip.w <= 0.0 || v2_clip.w <= 0.0 {
+            return None;
+        }
+
+        // Project to screen space
+        let (Some(screen0), Some(screen1), Some(screen2)) = (
+            self.clip_to_screen(&v0_clip, renderer),
+            self.clip_to_screen(&v1_clip, renderer),
+            self.clip_to_screen(&v2_clip, renderer),
+        ) else {
+            return None;
+        };
+
+        // Camera distance to normalized depth for z-buffer
+        let z0 = v0_clip.w / camera.far; // Normalize by far plane distance
+        let z1 = v1_clip.w / camera.far;
+        let z2 = v2_clip.w / camera.far;
+
+        // Baked ambient occlusion (all 1.0 until Scene::bake_vertex_ao runs)
+        let occlusion = game_object.mesh.triangle_ao(triangle);
+
+        let uvs = game_object.mesh.triangle_uvs(triangle).filter(|_| material.has_texture_maps());
+        let lightmap = game_object.lightmap
+            .as_ref()
+            .and_then(|lightmap| Some((lightmap, *lightmap.triangle_uvs.get(triangle_index)?)));
+        let vertex_colors = game_object.mesh
+            .triangle_vertex_colors(triangle)
+            .filter(|_| material.use_vertex_color != VertexColorMode::Ignore);
+
+        // Far objects lit as a whole don't need them
+        let smooth_normals = (!world_mesh.vertex_normals.is_empty() && world_mesh.lighting.lod != LightingLod::PerObject)
+            .then(|| triangle.indices.map(|index| world_mesh.vertex_normals[index]));
+
+        let per_pixel = world_mesh.lighting.lod == LightingLod::PerPixel
+            || smooth_normals.is_some()
+            || uvs.is_some()
+            || lightmap.is_some()
+            || (vertex_colors.is_some() && self.smooth_vertex_colors);
+
+        let fill = if per_pixel {
+            // Textured, lightmapped, vertex colored, smooth or close up: lit per pixel at its own UV, world position
+            // and normal when drawn
+            self.count_lighting(|counts| counts.per_pixel += 1);
+            TriangleFill::Textured {
+                world: [v0_world, v1_world, v2_world],
+                uvs,
+                normals: smooth_normals.unwrap_or([world_normal; 3]),
+                occlusion,
+                vertex_colors,
+                material,
+                object: game_object,
+                lightmap,
+            }
+        } else {
+            let lit_color = if world_mesh.lighting.lod == LightingLod::PerObject {
+                world_mesh.lighting.color_for(self, material, &game_object.average_light_receiver(), camera.position)
             } else {
-                Vec3f::calculate_triangle_normal(v0_world, v1_world, v2_world)
+                let receiver = LightReceiver {
+                    occlusion: (occlusion[0] + occlusion[1] + occlusion[2]) / 3.0,
+                    vertex_color: vertex_colors.map(|[c0, c1, c2]| (*c0 + *c1 + *c2) / 3.0),
+                    ..game_object.light_receiver()
+                };
+                self.count_lighting(|counts| counts.per_triangle += 1);
+                self.lighting.calculate_lighting(&triangle_center, &world_normal, &camera.position, material, &receiver)
             };
 
-            // Backface culling
-            let triangle_center = Vec3f::new(
-                (v0_world.x + v1_world.x + v2_world.x) / 3.0,
-                (v0_world.y + v1_world.y + v2_world.y) / 3.0,
-                (v0_world.z + v1_world.z + v2_world.z) / 3.0,
-            );
+            if renderer.is_hdr_enabled() {
+                // Keep the full range; the renderer tonemaps once the frame is done
+                TriangleFill::Hdr(Vec4f::new(lit_color.x, lit_color.y, lit_color.z, material.alpha.clamp(0.0, 1.0)))
+            } else {
+                // Convert to u32 color, with the material's opacity in the alpha byte
+                let alpha = (material.alpha.clamp(0.0, 1.0) * 255.0).round() as u32;
+                TriangleFill::Color(self.vec3_to_color(lit_color) & 0x00FFFFFF | (alpha << 24))
+            }
+        };
+
+        Some(ScreenTriangle {
+            screen: [screen0, screen1, screen2],
+            depth: [z0, z1, z2],
+            fill,
+        })
+    }
 
+    ///
+    /// Outline pass using the inflated shell method.
+    /// Only back faces are drawn here (the opposite of normal culling), with every vertex pushed
+    /// outward along its screen-space normal. The fill pass then covers everything except the
+    /// rim of the shell that sticks out past the silhouette.
+    ///
+    pub(crate) fn render_game_object_outline(&self, game_object: &GameObject, view_matrix: &Mat4x4,
+                                  proj_matrix: &Mat4x4, renderer: &mut Renderer) {
+        let model_matrix = game_object.get_world_matrix();
+        let world_vertices = game_object.mesh.transform_vertices(&model_matrix);
+
+        // Per-vertex normals by averaging the faces around each vertex
+        let mut vertex_normals = vec![Vec3f::zero(); world_vertices.len()];
+        for triangle in &game_object.mesh.triangles {
+            let [i0, i1, i2] = triangle.indices;
+            let face_normal = Vec3f::calculate_triangle_normal(world_vertices[i0], world_vertices[i1], world_vertices[i2]);
+            for index in triangle.indices {
+                vertex_normals[index] = vertex_normals[index] + face_normal;
+            }
+        }
+
+        // Camera space x/y, with y flipped because screen y points down
+        let screen_normals: Vec<Vec2f> = vertex_normals
+            .iter()
+            .map(|normal| {
+                let camera_normal = view_matrix.multiply_vector(&normal.normalize());
+                Vec2f::new(camera_normal.x, -camera_normal.y).normalize()
+            })
+            .collect();
+
+        for triangle in &game_object.mesh.triangles {
+            let [i0, i1, i2] = triangle.indices;
+            let (v0_world, v1_world, v2_world) = (world_vertices[i0], world_vertices[i1], world_vertices[i2]);
+
+            // Flipped culling: keep only the triangles facing away from the camera
+            let world_normal = Vec3f::calculate_triangle_normal(v0_world, v1_world, v2_world);
+            let triangle_center = (v0_world + v1_world + v2_world) / 3.0;
             let view_direction = (self.camera.position - triangle_center).normalize();
-            if world_normal.dot(&view_direction) < 0.0 {
-                continue; // Skip back-facing triangles
+            if world_normal.dot(&view_direction) >= 0.0 {
+                continue;
             }
 
-            // Transform to camera space
             let v0_camera = view_matrix.multiply_point(&v0_world);
             let v1_camera = view_matrix.multiply_point(&v1_world);
             let v2_camera = view_matrix.multiply_point(&v2_world);
 
-            // Skip if triangle is behind camera
-            if v0_camera.z >= 0.0 || v1_camera.z >= 0.0 || v2_camera.z >= 0.0 {
-                continue;
-            }
-
-            // Project to screen space
             if let (Some(screen0), Some(screen1), Some(screen2)) = (
                 self.project_to_screen(&v0_camera, proj_matrix, renderer),
                 self.project_to_screen(&v1_camera, proj_matrix, renderer),
                 self.project_to_screen(&v2_camera, proj_matrix, renderer),
             ) {
-                // Calculate lighting
-                let material = game_object.materials.get(
-                    triangle.material_id.unwrap_or(0)
-                ).unwrap_or(&game_object.materials[0]);
-
-                let lit_color = self.lighting.calculate_lighting(
-                    &triangle_center,
-                    &world_normal,
-                    &self.camera.position,
-                    material
+                renderer.draw_triangle_outline_pass(
+                    [screen0, screen1, screen2],
+                    [screen_normals[i0], screen_normals[i1], screen_normals[i2]],
+                    [-v0_camera.z / self.camera.far, -v1_camera.z / self.camera.far, -v2_camera.z / self.camera.far],
+                    self.outline_width,
+                    self.outline_color,
                 );
+            }
+        }
+    }
 
-                // Convert to u32 color
-                let final_color = self.vec3_to_color(lit_color);
+    ///
+    /// Draws the object's silhouette edges in outline_color, depth tested so only the visible
+    /// parts show. Faces are classified against the direction from the camera to the object's
+    /// origin, so on objects that fill much of the view, edges near the screen border can be
+    /// slightly off. Has to run after the object is filled for the depth test to hide anything.
+    ///
+    pub(crate) fn render_silhouette_edges(&self, game_object: &GameObject, vp_matrix: &Mat4x4, renderer: &mut Renderer) {
+        let model_matrix = game_object.get_world_matrix();
+        let Some(inverse_model) = model_matrix.inverse() else {
+            return;
+        };
 
-                // Convert camera Z to normalized depth for z-buffer
-                let z0 = -v0_camera.z / 100.0; // Normalize by far plane distance
-                let z1 = -v1_camera.z / 100.0;
-                let z2 = -v2_camera.z / 100.0;
+        // Normals go through the inverse transpose, so n_world · d = n_local · (M⁻¹ d)
+        let view_direction = game_object.position - self.camera.position;
+        let local_view_direction = inverse_model.multiply_vector(&view_direction);
 
-                renderer.draw_triangle(screen0, screen1, screen2, z0, z1, z2, final_color);
-            }
+        let viewport = renderer.get_viewport();
+        for (a, b) in game_object.mesh.compute_silhouette_edges(local_view_direction) {
+            let start = vp_matrix.multiply_point_4d(&model_matrix.multiply_point(&game_object.mesh.vertices[a]));
+            let end = vp_matrix.multiply_point_4d(&model_matrix.multiply_point(&game_object.mesh.vertices[b]));
+            draw_clip_line(renderer, &viewport, self.camera.far, start, end, self.outline_color);
         }
     }
 
+    /// Outlines off, then inflated shells, then silhouette edges, then off again
+    pub fn cycle_outline_style(&mut self) {
+        (self.outline_enabled, self.outline_style) = match (self.outline_enabled, self.outline_style) {
+            (false, _) => (true, OutlineStyle::InflatedShell),
+            (true, OutlineStyle::InflatedShell) => (true, OutlineStyle::SilhouetteEdges),
+            (true, OutlineStyle::SilhouetteEdges) => (false, OutlineStyle::InflatedShell),
+        };
+    }
+
     fn project_to_screen(&self, camera_point: &Vec3f, proj_matrix: &Mat4x4,
                          renderer: &Renderer) -> Option<Vec2f> {
         if camera_point.z >= 0.0 {
             return None;
         }
+        self.clip_to_screen(&proj_matrix.multiply_point_4d(camera_point), renderer)
+    }
 
-        let projected_4d = proj_matrix.multiply_point_4d(camera_point);
-
-        if projected_4d.w == 0.0 {
-            return None;
-        }
-
-        let ndc_x = projected_4d.x / projected_4d.w;
-        let ndc_y = projected_4d.y / projected_4d.w;
+    // Screen position of a clip-space point, None if it's behind the camera or off screen
+    fn clip_to_screen(&self, clip: &Vec4f, renderer: &Renderer) -> Option<Vec2f> {
+        let ndc = clip.perspective_divide()?;
+        let (ndc_x, ndc_y) = (ndc.x, ndc.y);
 
         // Check if point is within NDC bounds
         if ndc_x < -1.0 || ndc_x > 1.0 || ndc_y < -1.0 || ndc_y > 1.0 {
             return None;
         }
 
-        let (width, height) = renderer.get_dimension();
-        let pixel_x = (ndc_x + 1.0) * 0.5 * width as f32;
-        let pixel_y = (1.0 - ndc_y) * 0.5 * height as f32;
-
-        Some(Vec2f::new(pixel_x, pixel_y))
+        Some(renderer.get_viewport().ndc_to_screen(ndc_x, ndc_y))
     }
 
     fn vec3_to_color(&self, color: Vec3f) -> u32 {
-        let r = (color.x.min(1.0).max(0.0) * 255.0) as u32;
-        let g = (color.y.min(1.0).max(0.0) * 255.0) as u32;
-        let b = (color.z.min(1.0).max(0.0) * 255.0) as u32;
+        // A NaN from lighting (e.g. a zero-length normal) comes out black rather than as garbage
+        let color = color.saturate();
+        let r = (color.x * 255.0) as u32;
+        let g = (color.y * 255.0) as u32;
+        let b = (color.z * 255.0) as u32;
+        debug_assert!(r <= 0xFF && g <= 0xFF && b <= 0xFF, "channel spilled into its neighbour");
 
         0xFF000000 | (r << 16) | (g << 8) | b
     }
 
     // Utility methods
     pub fn add_cube_at(&mut self, position: Vec3f) {
-        let cube_mesh = Mesh::create_cube();
+        let cube_mesh = Mesh::create_cube().with_coordinate_system(self.coordinate_system);
         let mut cube_object = GameObject::new(cube_mesh).with_position(position);
 
         // Add some interesting materials
         let shiny_material = Material::new(
             Vec3f::new(0.8, 0.2, 0.2), // Red diffuse
-            Vec3f::new(0.9, 0.9, 0.9), // High specular
+            Vec3f::splat(0.9), // High specular
             128.0                      // Very shiny
         );
-        cube_object.add_material(shiny_material);
+        let shiny_slot = cube_object.add_material(shiny_material);
+        cube_object.mesh.set_material_for_triangles(.., shiny_slot);
 
         self.add_game_object(cube_object);
     }
 
+    ///
+    /// PBR test scene: two rows of spheres starting at `origin` and running along +X,
+    /// roughness going from 0 to 1 left to right. The front row is dielectric, the back row metal.
+    ///
+    pub fn add_pbr_test_spheres(&mut self, origin: Vec3f) {
+        const COUNT: usize = 5;
+        const SPACING: f32 = 1.2;
+        let albedo = Vec3f::new(0.9, 0.6, 0.2);
+
+        for (row, metallic) in [0.0, 1.0].into_iter().enumerate() {
+            for column in 0..COUNT {
+                let roughness = column as f32 / (COUNT - 1) as f32;
+                let offset = Vec3f::new(column as f32 * SPACING, 0.0, -(row as f32) * SPACING);
+                let position = origin + self.coordinate_system.from_y_up(offset);
+                let sphere_mesh = Mesh::create_uv_sphere(0.5, 16, 8).with_coordinate_system(self.coordinate_system);
+                let mut sphere = GameObject::new(sphere_mesh).with_position(position);
+                sphere.materials[0] = Material::pbr(albedo, roughness, metallic);
+                self.add_game_object(sphere);
+            }
+        }
+    }
+
+    ///
+    /// Turns on rigid body physics (if it isn't already) and returns the world to add bodies to.
+    /// Gravity pulls down the scene's up axis.
+    ///
+    pub fn enable_physics(&mut self) -> &mut PhysicsWorld {
+        let coordinate_system = self.coordinate_system;
+        self.physics.get_or_insert_with(|| {
+            let mut physics = PhysicsWorld::new();
+            physics.gravity = coordinate_system.from_y_up(physics.gravity);
+            physics
+        })
+    }
+
+    /// Connects two objects (indices into game_objects) with a damped spring
+    pub fn add_spring(&mut self, a: usize, b: usize, rest_length: f32, stiffness: f32, damping: f32) {
+        self.springs.push(Spring::new(a, b, rest_length, stiffness, damping));
+    }
+
+    pub fn add_point_cloud(&mut self, points: Vec<Vec3f>, point_size: f32, color: u32) {
+        self.point_lists.push(PointList::new(points, point_size, color));
+    }
+
     pub fn add_triangle_at(&mut self, position: Vec3f) {
-        let triangle_mesh = Mesh::create_triangle();
-        let triangle_object = GameObject::new(triangle_mesh).with_position(position);
+        let triangle_mesh = Mesh::create_triangle().with_coordinate_system(self.coordinate_system);
+        let mut triangle_object = GameObject::new(triangle_mesh).with_position(position);
+        triangle_object.materials[0].two_sided = true; // A lone triangle has no back of its own
         self.add_game_object(triangle_object);
     }
 
@@ -250,18 +1420,244 @@ impl Scene {
         self.camera.target = target;
     }
 
-    pub fn add_light(&mut self, light: Light) {
-        self.lighting.add_light(light);
+    /// Height of the ground at a world position (a flat floor for now)
+    pub fn ground_height_at(&self, _x: f32, _z: f32) -> f32 {
+        self.floor_height
+    }
+
+    pub fn add_light(&mut self, light: Light) -> LightHandle {
+        self.lighting.add_light(light)
+    }
+
+    /// Starts flying the camera along the given path from the beginning
+    pub fn play_camera_path(&mut self, path: CameraPath) {
+        self.camera_path = Some(path);
+        self.path_t = 0.0;
+        self.path_playing = true;
+    }
+
+    /// Pauses/resumes the camera path, restarting it if it already finished
+    pub fn toggle_camera_path(&mut self) {
+        if self.camera_path.is_none() {
+            return;
+        }
+        if !self.path_playing && self.path_t >= 1.0 {
+            self.path_t = 0.0;
+        }
+        self.path_playing = !self.path_playing;
+    }
+
+    pub fn is_camera_path_playing(&self) -> bool {
+        self.path_playing
+    }
+
+    /// Starts playing a keyframed camera sequence from the beginning
+    pub fn play_camera_sequence(&mut self, mut sequence: CameraSequence) {
+        sequence.seek(0.0);
+        sequence.play();
+        self.camera_sequence = Some(sequence);
+    }
+
+    /// Pauses/resumes the camera sequence
+    pub fn toggle_camera_sequence(&mut self) {
+        if let Some(sequence) = &mut self.camera_sequence {
+            if sequence.is_playing() {
+                sequence.pause();
+            } else {
+                sequence.play();
+            }
+        }
+    }
+
+    pub fn is_camera_sequence_playing(&self) -> bool {
+        self.camera_sequence.as_ref().is_some_and(|sequence| sequence.is_playing())
+    }
+
+    /// True while a path or sequence is driving the camera, so manual input should be ignored
+    pub fn is_camera_scripted(&self) -> bool {
+        self.is_camera_path_playing() || self.is_camera_sequence_playing()
+    }
+
+    /// Makes `camera` the scene camera for a while, keeping the current one on camera_stack
+    pub fn push_camera(&mut self, camera: Camera) {
+        let previous = std::mem::replace(&mut self.camera, camera);
+        self.camera_stack.push(previous);
+    }
+
+    /// Puts back the camera the last push_camera set aside; false if there was none
+    pub fn pop_camera(&mut self) -> bool {
+        match self.camera_stack.pop() {
+            Some(camera) => {
+                self.camera = camera;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn update_camera_sequence(&mut self, delta_time: f32) {
+        if let Some(sequence) = &mut self.camera_sequence
+            && sequence.is_playing()
+        {
+            sequence.advance(delta_time);
+            sequence.apply_to(&mut self.camera);
+        }
+    }
+
+    fn update_camera_path(&mut self, delta_time: f32) {
+        if !self.path_playing {
+            return;
+        }
+        if let Some(path) = &self.camera_path {
+            if path.duration > 0.0 {
+                self.path_t += delta_time / path.duration;
+            } else {
+                self.path_t = 1.0;
+            }
+            if self.path_t >= 1.0 {
+                self.path_t = 1.0;
+                self.path_playing = false;
+            }
+
+            let (pos, target) = path.evaluate(self.path_t);
+            self.camera.position = pos;
+            self.camera.look_in_direction(target - pos);
+        }
+    }
+
+    /// Runs `action` on the scene `delay` seconds from now
+    pub fn schedule_event(&mut self, delay: f32, action: impl FnOnce(&mut Scene) + 'static) {
+        self.event_queue.schedule(delay, action);
+    }
+
+    fn fire_due_events(&mut self, delta_time: f32) {
+        self.event_queue.advance(delta_time);
+
+        // Events are popped one at a time because each one needs the whole scene mutably
+        while let Some(event) = self.event_queue.pop_due() {
+            (event.action)(self);
+        }
     }
 
     pub fn update(&mut self, delta_time: f32) {
         self.rotation_time += delta_time;
+        self.stats.frame_time_ms = delta_time * 1000.0;
+
+        self.fire_due_events(delta_time);
+        self.lighting.update(delta_time);
+
+        self.update_camera_path(delta_time);
+        self.update_camera_sequence(delta_time);
+
+        // Springs change velocities first, then everything moves (semi-implicit Euler, which keeps oscillations from growing)
+        for spring in &self.springs {
+            spring.apply_forces(&mut self.game_objects, delta_time);
+        }
+        for game_object in &mut self.game_objects {
+            if game_object.velocity.length() > 0.0 {
+                let position = game_object.position + game_object.velocity * delta_time;
+                game_object.set_position(position);
+            }
+        }
+
+        // Taken out for the step, as it moves the scene's objects
+        if let Some(mut physics) = self.physics.take() {
+            physics.update(self, delta_time);
+            self.physics = Some(physics);
+        }
 
-        // Rotate cubes
+        // Rotate cubes; static ones stay put so their lightmaps still fit, and physics turns its own
         for (i, game_object) in self.game_objects.iter_mut().enumerate() {
+            if game_object.static_lighting || self.physics.as_ref().is_some_and(|physics| physics.has_body(i)) {
+                continue;
+            }
             let offset = i as f32 * 0.5;
-            game_object.rotation.y = self.rotation_time + offset;
-            game_object.rotation.x = self.rotation_time * 0.3 + offset;
+            let rotation = Vec3f::new(
+                self.rotation_time * 0.3 + offset,
+                self.rotation_time + offset,
+                game_object.rotation.z,
+            );
+            game_object.set_rotation(rotation);
+        }
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use crate::texture::Texture;
+
+    #[test]
+    fn nan_channel_packs_as_black_without_touching_the_others() {
+        let scene = Scene::new();
+        assert_eq!(scene.vec3_to_color(Vec3f::new(f32::NAN, 1.0, 0.5)), 0xFF00FF7F);
+        assert_eq!(scene.vec3_to_color(Vec3f::new(2.0, -1.0, f32::NAN)), 0xFFFF0000);
+    }
+
+    // Object with six materials, slot i having diffuse (i, 0, 0)
+    fn six_material_object(mesh: Mesh) -> GameObject {
+        let mut object = GameObject::new(mesh);
+        for slot in 1..6 {
+            object.add_material(Material { diffuse_color: Vec3f::new(slot as f32, 0.0, 0.0), ..Material::default() });
         }
+        object.materials[0].diffuse_color = Vec3f::zero();
+        object
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn each_cube_face_resolves_to_its_own_material() {
+        let slots = [5, 4, 3, 2, 1, 0];
+        let object = six_material_object(Mesh::create_cube_with_materials(Some(slots)));
+        let registry = MaterialRegistry::new();
+        let face_normals = [
+            Vec3f::new(0.0, 0.0, 1.0), Vec3f::new(0.0, 0.0, -1.0),
+            Vec3f::new(-1.0, 0.0, 0.0), Vec3f::new(1.0, 0.0, 0.0),
+            Vec3f::new(0.0, 1.0, 0.0), Vec3f::new(0.0, -1.0, 0.0),
+        ];
+
+        for triangle in &object.mesh.triangles {
+            let normal = triangle.calculate_normal(&object.mesh);
+            let face = face_normals.iter().position(|n| n.dot(&normal) > 0.99).expect("axis-aligned face");
+            let material = object.material_for(triangle, &registry);
+            assert_eq!(material.diffuse_color.x, slots[face] as f32, "face {face}");
+        }
+        assert!(!object.warned_missing_material.get());
+    }
+
+    #[test]
+    fn out_of_range_material_id_falls_back_to_slot_zero_with_a_warning() {
+        let mut mesh = Mesh::create_cube();
+        mesh.set_material_for_triangles(0..2, 2);
+        mesh.set_material_for_triangles(2..4, 17);
+        let object = six_material_object(mesh);
+        let registry = MaterialRegistry::new();
+
+        let triangles = &object.mesh.triangles;
+        assert_eq!(object.material_for(&triangles[0], &registry).diffuse_color.x, 2.0);
+        assert!(!object.warned_missing_material.get());
+        assert_eq!(object.material_for(&triangles[2], &registry).diffuse_color.x, 0.0);
+        assert!(object.warned_missing_material.get(), "out-of-range id goes through the warning");
+        // Reported once, still resolved every time
+        assert_eq!(object.material_for(&triangles[3], &registry).diffuse_color.x, 0.0);
+        assert_eq!(object.material_for(&triangles[4], &registry).diffuse_color.x, 0.0);
+    }
+
+    #[test]
+    fn textured_material_on_a_mesh_without_uvs_draws_its_plain_colors() {
+        let render = |diffuse_map: Option<Arc<Texture>>| {
+            let mut scene = Scene::new();
+            let mut object = GameObject::new(Mesh::create_triangle()).with_position(Vec3f::new(0.0, 0.0, -4.0));
+            object.materials[0].diffuse_map = diffuse_map;
+            scene.add_game_object(object);
+            let mut renderer = Renderer::new(64, 64);
+            scene.render(&mut renderer);
+            renderer.get_framebuffer().to_vec()
+        };
+
+        let red = Arc::new(Texture::new(1, 1, vec![Vec3f::new(1.0, 0.0, 0.0)]));
+        let textured = render(Some(red));
+        let plain = render(None);
+        assert!(plain.iter().any(|&pixel| pixel != plain[0]), "the triangle should be in view");
+        assert_eq!(textured, plain);
+    }
+}
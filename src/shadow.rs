@@ -0,0 +1,366 @@
+use crate::lighting::{LightHandle, LightType, ShadowSettings};
+use crate::math::Vec3f;
+use crate::scene::Scene;
+
+// Geometry closer to the light than this (along a face's axis) is cut off before rasterizing
+const SHADOW_NEAR: f32 = 0.01;
+
+/// Shadowed point lights per frame when LightingSystem::max_shadowed_point_lights isn't changed
+pub const DEFAULT_MAX_SHADOWED_POINT_LIGHTS: usize = 4;
+
+/// One face of a cube map, named by the axis it looks down
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum CubeFace {
+    PositiveX,
+    NegativeX,
+    PositiveY,
+    NegativeY,
+    PositiveZ,
+    NegativeZ,
+}
+
+impl CubeFace {
+    pub const ALL: [CubeFace; 6] = [
+        CubeFace::PositiveX,
+        CubeFace::NegativeX,
+        CubeFace::PositiveY,
+        CubeFace::NegativeY,
+        CubeFace::PositiveZ,
+        CubeFace::NegativeZ,
+    ];
+
+    ///
+    /// The face a direction from the cube's center passes through: its largest component
+    /// picks the axis and that component's sign the side. Exact ties (directions through a
+    /// cube edge or corner) go to X, then Y, as both faces hold the same texels there.
+    /// The zero vector gives PositiveX.
+    ///
+    pub fn from_direction(direction: Vec3f) -> CubeFace {
+        let (x, y, z) = (direction.x.abs(), direction.y.abs(), direction.z.abs());
+        if x >= y && x >= z {
+            if direction.x >= 0.0 { CubeFace::PositiveX } else { CubeFace::NegativeX }
+        } else if y >= z {
+            if direction.y >= 0.0 { CubeFace::PositiveY } else { CubeFace::NegativeY }
+        } else if direction.z >= 0.0 {
+            CubeFace::PositiveZ
+        } else {
+            CubeFace::NegativeZ
+        }
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+
+    /// (forward, right, up) of the face's 90° view, all unit length and at right angles
+    fn axes(self) -> (Vec3f, Vec3f, Vec3f) {
+        match self {
+            CubeFace::PositiveX => (Vec3f::new(1.0, 0.0, 0.0), Vec3f::new(0.0, 0.0, -1.0), Vec3f::new(0.0, 1.0, 0.0)),
+            CubeFace::NegativeX => (Vec3f::new(-1.0, 0.0, 0.0), Vec3f::new(0.0, 0.0, 1.0), Vec3f::new(0.0, 1.0, 0.0)),
+            CubeFace::PositiveY => (Vec3f::new(0.0, 1.0, 0.0), Vec3f::new(1.0, 0.0, 0.0), Vec3f::new(0.0, 0.0, -1.0)),
+            CubeFace::NegativeY => (Vec3f::new(0.0, -1.0, 0.0), Vec3f::new(1.0, 0.0, 0.0), Vec3f::new(0.0, 0.0, 1.0)),
+            CubeFace::PositiveZ => (Vec3f::new(0.0, 0.0, 1.0), Vec3f::new(1.0, 0.0, 0.0), Vec3f::new(0.0, 1.0, 0.0)),
+            CubeFace::NegativeZ => (Vec3f::new(0.0, 0.0, -1.0), Vec3f::new(-1.0, 0.0, 0.0), Vec3f::new(0.0, 1.0, 0.0)),
+        }
+    }
+
+    /// `offset` in this face's view space: (right, up, depth along forward)
+    fn to_view(self, offset: Vec3f) -> Vec3f {
+        let (forward, right, up) = self.axes();
+        Vec3f::new(offset.dot(&right), offset.dot(&up), offset.dot(&forward))
+    }
+}
+
+///
+/// Depth cube around a point light: for each face, the distance along that face's axis to
+/// the nearest surface, per texel of a 90° view. Built by the ShadowPass each frame.
+///
+pub struct PointShadowMap {
+    pub light: LightHandle,
+    pub position: Vec3f, // Where the light was when the map was rendered
+    pub settings: ShadowSettings,
+    faces: [Vec<f32>; 6],
+}
+
+impl PointShadowMap {
+    /// Renders the six faces from `position` with every triangle in `triangles` as a caster
+    pub fn render(light: LightHandle, position: Vec3f, settings: ShadowSettings, triangles: &[[Vec3f; 3]]) -> Self {
+        let resolution = settings.resolution.max(1);
+        let mut faces: [Vec<f32>; 6] = std::array::from_fn(|_| vec![f32::INFINITY; resolution * resolution]);
+
+        for face in CubeFace::ALL {
+            let depths = &mut faces[face.index()];
+            for triangle in triangles {
+                let view = triangle.map(|vertex| face.to_view(vertex - position));
+                rasterize_depth(depths, resolution, view);
+            }
+        }
+
+        Self { light, position, settings: ShadowSettings { resolution, ..settings }, faces }
+    }
+
+    ///
+    /// How much of the light reaches `point` on a surface facing `normal`: 1.0 when nothing
+    /// is in the way, down to 1 - settings.strength when everything is. The point is moved
+    /// normal_bias along the normal, then compared, depth_bias closer to the light, against
+    /// the texels around it on the face it falls on (one, or the PCF square).
+    ///
+    pub fn visibility(&self, point: &Vec3f, normal: &Vec3f) -> f32 {
+        let settings = &self.settings;
+        let offset = *point + *normal * settings.normal_bias - self.position;
+        let face = CubeFace::from_direction(offset);
+        let view = face.to_view(offset);
+        if view.z <= SHADOW_NEAR {
+            return 1.0;
+        }
+
+        let (x, y) = texel_position(view, settings.resolution);
+        let max_texel = settings.resolution as i64 - 1;
+        let (x, y) = (x.floor() as i64, y.floor() as i64);
+        let radius = settings.pcf_radius as i64;
+        let depths = &self.faces[face.index()];
+
+        // Texels past the face's edge are clamped rather than read from the next face
+        let mut lit = 0;
+        for dy in -radius..=radius {
+            for dx in -radius..=radius {
+                let texel_x = (x + dx).clamp(0, max_texel) as usize;
+                let texel_y = (y + dy).clamp(0, max_texel) as usize;
+                if view.z - settings.depth_bias <= depths[texel_y * settings.resolution + texel_x] {
+                    lit += 1;
+                }
+            }
+        }
+        let occlusion = 1.0 - lit as f32 / ((2 * radius + 1) * (2 * radius + 1)) as f32;
+
+        1.0 - settings.strength * occlusion
+    }
+}
+
+// Face view space to texel coordinates: the 90° frustum's [-1, 1] square maps onto the face
+fn texel_position(view: Vec3f, resolution: usize) -> (f32, f32) {
+    let scale = resolution as f32 * 0.5;
+    ((view.x / view.z + 1.0) * scale, (1.0 - view.y / view.z) * scale)
+}
+
+///
+/// Writes the nearest depth of a view-space triangle into `depths`, cutting it at SHADOW_NEAR.
+/// Both sides are drawn, so closed and open meshes cast the same shadow.
+///
+fn rasterize_depth(depths: &mut [f32], resolution: usize, view: [Vec3f; 3]) {
+    // Entirely outside one side of the frustum: nothing to draw
+    let outside = |test: fn(&Vec3f) -> bool| view.iter().all(test);
+    if outside(|v| v.z <= SHADOW_NEAR)
+        || outside(|v| v.x > v.z)
+        || outside(|v| v.x < -v.z)
+        || outside(|v| v.y > v.z)
+        || outside(|v| v.y < -v.z)
+    {
+        return;
+    }
+
+    // Cut against the near plane; what's left is a triangle or a quad
+    let mut polygon: Vec<Vec3f> = Vec::with_capacity(4);
+    for corner in 0..3 {
+        let (a, b) = (view[corner], view[(corner + 1) % 3]);
+        if a.z > SHADOW_NEAR {
+            polygon.push(a);
+        }
+        if (a.z > SHADOW_NEAR) != (b.z > SHADOW_NEAR) {
+            polygon.push(a + (b - a) * ((SHADOW_NEAR - a.z) / (b.z - a.z)));
+        }
+    }
+
+    // Screen position plus 1/z, which interpolates linearly across the screen
+    let projected: Vec<(f32, f32, f32)> = polygon
+        .iter()
+        .map(|vertex| {
+            let (x, y) = texel_position(*vertex, resolution);
+            (x, y, 1.0 / vertex.z)
+        })
+        .collect();
+
+    for fan in 1..projected.len().saturating_sub(1) {
+        rasterize_fan_triangle(depths, resolution, [projected[0], projected[fan], projected[fan + 1]]);
+    }
+}
+
+fn rasterize_fan_triangle(depths: &mut [f32], resolution: usize, corners: [(f32, f32, f32); 3]) {
+    let [(x0, y0, w0), (x1, y1, w1), (x2, y2, w2)] = corners;
+    let area = (x1 - x0) * (y2 - y0) - (x2 - x0) * (y1 - y0);
+    if area.abs() < 1e-12 {
+        return;
+    }
+
+    let max_texel = resolution as f32 - 1.0;
+    let min_x = x0.min(x1).min(x2).floor().clamp(0.0, max_texel) as usize;
+    let max_x = x0.max(x1).max(x2).ceil().clamp(0.0, max_texel) as usize;
+    let min_y = y0.min(y1).min(y2).floor().clamp(0.0, max_texel) as usize;
+    let max_y = y0.max(y1).max(y2).ceil().clamp(0.0, max_texel) as usize;
+
+    for y in min_y..=max_y {
+        for x in min_x..=max_x {
+            let (px, py) = (x as f32 + 0.5, y as f32 + 0.5);
+            let b0 = ((x1 - px) * (y2 - py) - (x2 - px) * (y1 - py)) / area;
+            let b1 = ((x2 - px) * (y0 - py) - (x0 - px) * (y2 - py)) / area;
+            let b2 = 1.0 - b0 - b1;
+            if b0 < 0.0 || b1 < 0.0 || b2 < 0.0 {
+                continue;
+            }
+
+            let depth = 1.0 / (w0 * b0 + w1 * b1 + w2 * b2);
+            let texel = &mut depths[y * resolution + x];
+            if depth < *texel {
+                *texel = depth;
+            }
+        }
+    }
+}
+
+impl Scene {
+    ///
+    /// Re-renders the shadow cubes of the enabled point lights that have shadows turned on,
+    /// at most lighting.max_shadowed_point_lights of them, picking the ones closest to the
+    /// camera. Every object casts, whether or not it's in view.
+    ///
+    pub(crate) fn render_point_shadows(&self) {
+        let mut shadowed: Vec<(LightHandle, Vec3f, ShadowSettings)> = self.lighting
+            .handles()
+            .filter(|&handle| self.lighting.is_enabled(handle))
+            .filter_map(|handle| {
+                let light = self.lighting.get(handle)?;
+                let settings = light.shadow.filter(|_| matches!(light.light_type, LightType::Point))?;
+                Some((handle, light.position, settings))
+            })
+            .collect();
+        shadowed.sort_by(|a, b| {
+            let distance = |position: Vec3f| (position - self.camera.position).length();
+            distance(a.1).total_cmp(&distance(b.1))
+        });
+        shadowed.truncate(self.lighting.max_shadowed_point_lights);
+
+        let triangles: Vec<[Vec3f; 3]> = if shadowed.is_empty() {
+            Vec::new()
+        } else {
+            self.game_objects
+                .iter()
+                .flat_map(|game_object| {
+                    let vertices = game_object.mesh.transform_vertices(&game_object.get_world_matrix());
+                    game_object.mesh.triangles
+                        .iter()
+                        .map(|triangle| triangle.indices.map(|index| vertices[index]))
+                        .collect::<Vec<_>>()
+                })
+                .collect()
+        };
+
+        self.lighting.set_point_shadows(
+            shadowed
+                .into_iter()
+                .map(|(handle, position, settings)| PointShadowMap::render(handle, position, settings, &triangles))
+                .collect(),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lighting::{Light, LightingSystem};
+
+    #[test]
+    fn face_selection_near_face_boundaries() {
+        let epsilon = 1e-4;
+        let cases = [
+            (Vec3f::new(1.0, 0.999, 0.0), CubeFace::PositiveX),
+            (Vec3f::new(0.999, 1.0, 0.0), CubeFace::PositiveY),
+            (Vec3f::new(-0.5, 0.5 + epsilon, 0.0), CubeFace::PositiveY),
+            (Vec3f::new(-0.5 - epsilon, 0.5, 0.0), CubeFace::NegativeX),
+            (Vec3f::new(0.0, -0.7, 0.7 + epsilon), CubeFace::PositiveZ),
+            (Vec3f::new(0.0, -0.7 - epsilon, 0.7), CubeFace::NegativeY),
+            (Vec3f::new(0.3, -0.3, -0.3 - epsilon), CubeFace::NegativeZ),
+            // Exact ties go to X, then Y
+            (Vec3f::new(1.0, 1.0, 0.0), CubeFace::PositiveX),
+            (Vec3f::new(-1.0, 1.0, -1.0), CubeFace::NegativeX),
+            (Vec3f::new(0.0, -1.0, 1.0), CubeFace::NegativeY),
+            (Vec3f::zero(), CubeFace::PositiveX),
+        ];
+
+        for (direction, expected) in cases {
+            let face = CubeFace::from_direction(direction);
+            assert_eq!(face, expected, "{direction:?}");
+            if direction.length() == 0.0 {
+                continue;
+            }
+
+            // The chosen face sees the direction in front of it and inside its 90° view, so a
+            // lookup there lands on one of its texels (at the very edge for these vectors)
+            let view = face.to_view(direction);
+            assert!(view.z > 0.0, "{direction:?} is behind {face:?}");
+            let (x, y) = texel_position(view, 64);
+            assert!((0.0..=64.0).contains(&x) && (0.0..=64.0).contains(&y), "{direction:?} -> ({x}, {y})");
+        }
+    }
+
+    // Any handle will do, the maps are looked at directly
+    fn handle() -> LightHandle {
+        LightingSystem::new().add_light(Light::point(Vec3f::zero(), Vec3f::one(), 1.0, 10.0))
+    }
+
+    // A floor at y = 0 spanning ±4, as two triangles
+    fn floor() -> Vec<[Vec3f; 3]> {
+        let corner = |x: f32, z: f32| Vec3f::new(x, 0.0, z);
+        vec![
+            [corner(-4.0, -4.0), corner(4.0, -4.0), corner(4.0, 4.0)],
+            [corner(4.0, 4.0), corner(-4.0, 4.0), corner(-4.0, -4.0)],
+        ]
+    }
+
+    // Visibility at a grid of points on the floor under the light
+    fn floor_visibility(map: &PointShadowMap) -> Vec<f32> {
+        (0..40)
+            .flat_map(|i| (0..40).map(move |j| Vec3f::new(i as f32 * 0.1 - 2.0, 0.0, j as f32 * 0.1 - 2.0)))
+            .map(|point| map.visibility(&point, &Vec3f::up()))
+            .collect()
+    }
+
+    #[test]
+    fn zero_bias_gives_acne_that_the_default_bias_removes() {
+        let light = handle();
+        let position = Vec3f::new(0.5, 2.0, 0.3);
+
+        let unbiased = PointShadowMap::render(light, position, ShadowSettings::new(64, 0.0), &floor());
+        let acne = floor_visibility(&unbiased);
+        let shadowed = acne.iter().filter(|&&visibility| visibility < 1.0).count();
+        assert!(shadowed > 0 && shadowed < acne.len(),
+                "the floor shadowing itself should come out speckled, {shadowed} of {} in shadow", acne.len());
+
+        let biased = PointShadowMap::render(light, position, ShadowSettings::default(), &floor());
+        assert!(floor_visibility(&biased).iter().all(|&visibility| visibility == 1.0), "default bias left acne");
+
+        // Strength 0 takes no light away, acne or not
+        let weightless = ShadowSettings::new(64, 0.0).with_strength(0.0);
+        let no_strength = PointShadowMap::render(light, position, weightless, &floor());
+        assert!(floor_visibility(&no_strength).iter().all(|&visibility| visibility == 1.0));
+    }
+
+    #[test]
+    fn strength_scales_the_darkening() {
+        let light = handle();
+        let position = Vec3f::new(0.0, 2.0, 0.0);
+        // A small square halfway down, right under the light
+        let mut triangles = floor();
+        let corner = |x: f32, z: f32| Vec3f::new(x, 1.0, z);
+        triangles.push([corner(-0.3, -0.3), corner(0.3, -0.3), corner(0.3, 0.3)]);
+        triangles.push([corner(0.3, 0.3), corner(-0.3, 0.3), corner(-0.3, -0.3)]);
+
+        let under = Vec3f::zero();
+        let visibility = |strength: f32| {
+            let settings = ShadowSettings::new(64, 0.05).with_strength(strength);
+            PointShadowMap::render(light, position, settings, &triangles).visibility(&under, &Vec3f::up())
+        };
+        assert_eq!(visibility(1.0), 0.0);
+        assert_eq!(visibility(0.5), 0.5, "half strength should take away exactly half the light");
+        assert_eq!(visibility(0.0), 1.0);
+    }
+}
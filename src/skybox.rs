@@ -0,0 +1,64 @@
+use std::f32::consts::FRAC_PI_2;
+use crate::camera::Camera;
+use crate::math::Vec3f;
+use crate::renderer::Renderer;
+
+///
+/// Gradient sky: a color per screen row, picked by how far above or below the horizon
+/// that row looks. Costs one ray per row instead of rasterizing a skybox cube, and only
+/// fills pixels no geometry has been drawn to. Ignores camera roll, since rows are uniform.
+///
+#[derive(Copy, Clone, Debug)]
+pub struct SkyboxRenderer {
+    pub top_color: Vec3f,     // Straight up
+    pub horizon_color: Vec3f,
+    pub bottom_color: Vec3f,  // Straight down
+}
+
+impl SkyboxRenderer {
+    pub fn new(top_color: Vec3f, horizon_color: Vec3f, bottom_color: Vec3f) -> Self {
+        Self { top_color, horizon_color, bottom_color }
+    }
+
+    /// Color for a view direction `pitch` radians above the horizon (negative = below)
+    pub fn color_at_pitch(&self, pitch: f32) -> Vec3f {
+        let t = (pitch / FRAC_PI_2).clamp(-1.0, 1.0);
+        if t >= 0.0 {
+            self.horizon_color + (self.top_color - self.horizon_color) * t
+        } else {
+            self.horizon_color + (self.bottom_color - self.horizon_color) * -t
+        }
+    }
+
+    pub fn render(&self, renderer: &mut Renderer, camera: &Camera) {
+        let (width, height) = renderer.get_dimension();
+        let viewport = renderer.get_viewport();
+        let center_x = viewport.x as f32 + viewport.width as f32 * 0.5;
+
+        for y in viewport.y..viewport.y + viewport.height {
+            let Some((_, direction)) = camera.screen_to_ray(center_x, y as f32 + 0.5, width, height) else {
+                continue;
+            };
+            let pitch = direction.y.clamp(-1.0, 1.0).asin();
+            renderer.fill_empty_row(y, to_argb(self.color_at_pitch(pitch)));
+        }
+    }
+}
+
+impl Default for SkyboxRenderer {
+    /// Daytime blue fading to a pale horizon, with dark ground below
+    fn default() -> Self {
+        Self::new(
+            Vec3f::new(0.2, 0.4, 0.85),
+            Vec3f::new(0.75, 0.85, 0.95),
+            Vec3f::new(0.15, 0.13, 0.12),
+        )
+    }
+}
+
+fn to_argb(color: Vec3f) -> u32 {
+    let r = (color.x.clamp(0.0, 1.0) * 255.0) as u32;
+    let g = (color.y.clamp(0.0, 1.0) * 255.0) as u32;
+    let b = (color.z.clamp(0.0, 1.0) * 255.0) as u32;
+    0xFF000000 | (r << 16) | (g << 8) | b
+}
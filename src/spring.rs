@@ -0,0 +1,44 @@
+use crate::scene::GameObject;
+
+///
+/// Damped spring between two objects, by index into Scene::game_objects.
+/// Objects are treated as unit masses; the spring only changes their velocities, and
+/// Scene::update moves everything by its velocity once all springs have pulled.
+///
+#[derive(Copy, Clone, Debug)]
+pub struct Spring {
+    pub object_a: usize,
+    pub object_b: usize,
+    pub rest_length: f32,
+    pub stiffness: f32, // k: force per unit of stretch
+    pub damping: f32,   // c: force per unit of relative speed
+}
+
+impl Spring {
+    pub fn new(object_a: usize, object_b: usize, rest_length: f32, stiffness: f32, damping: f32) -> Self {
+        Self { object_a, object_b, rest_length, stiffness, damping }
+    }
+
+    ///
+    /// Hooke's law with damping: with d = b - a, b feels F = -k(|d| - rest) d̂ - c(v_b - v_a)
+    /// and a feels -F, applied over `delta_time` as a change in velocity.
+    /// Springs with a missing or repeated index do nothing.
+    ///
+    pub fn apply_forces(&self, objects: &mut [GameObject], delta_time: f32) {
+        if self.object_a == self.object_b || self.object_a >= objects.len() || self.object_b >= objects.len() {
+            return;
+        }
+
+        let (a, b) = (&objects[self.object_a], &objects[self.object_b]);
+        let offset = b.position - a.position;
+        let length = offset.length();
+        let direction = if length > 0.0 { offset / length } else { offset };
+        let relative_velocity = b.velocity - a.velocity;
+
+        let force = direction * (-self.stiffness * (length - self.rest_length)) - relative_velocity * self.damping;
+        let impulse = force * delta_time;
+
+        objects[self.object_b].velocity = objects[self.object_b].velocity + impulse;
+        objects[self.object_a].velocity = objects[self.object_a].velocity - impulse;
+    }
+}
@@ -0,0 +1,204 @@
+use std::collections::HashMap;
+use std::fmt;
+use crate::math::{Vec3f, Vec4f};
+use crate::mesh::Mesh;
+
+/// Why compute_tangents couldn't run
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TangentError {
+    MissingNormals, // No per-vertex normal for every vertex
+    MissingUvs,     // Some triangle has neither its own UVs nor per-vertex ones
+}
+
+impl fmt::Display for TangentError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TangentError::MissingNormals => write!(f, "tangents need a normal for every vertex"),
+            TangentError::MissingUvs => write!(f, "tangents need UVs on every triangle"),
+        }
+    }
+}
+
+impl std::error::Error for TangentError {}
+
+impl Mesh {
+    ///
+    /// Fills `tangents` for normal mapping. Each triangle's tangent and bitangent (the
+    /// directions of increasing U and V across it, from its edges and UV deltas) give each
+    /// corner a handedness, and the tangents are summed per vertex and handedness. Each
+    /// vertex's tangent is then made perpendicular to its normal (Gram-Schmidt) and given
+    /// w = ±1 such that bitangent = w * (normal × tangent).
+    ///
+    /// Where a vertex is shared by triangles with mirrored UVs (w would disagree), it's
+    /// split into one copy per side, with all its other attributes. Triangles whose UVs
+    /// have no area add nothing. Needs per-vertex normals and UVs on every triangle.
+    ///
+    pub fn compute_tangents(&mut self) -> Result<(), TangentError> {
+        if self.vertex_normals().is_none() {
+            return Err(TangentError::MissingNormals);
+        }
+        let mut corner_uvs = Vec::with_capacity(self.triangles.len());
+        for triangle in &self.triangles {
+            corner_uvs.push(self.triangle_uvs(triangle).ok_or(TangentError::MissingUvs)?);
+        }
+
+        // Tangent sums per (vertex, handedness), and each corner's handedness
+        let mut sums: HashMap<(usize, bool), Vec3f> = HashMap::new();
+        let mut corner_sides = vec![[None; 3]; self.triangles.len()];
+        for (face, triangle) in self.triangles.iter().enumerate() {
+            let (p0, p1, p2) = triangle.get_vertices(self);
+            let [uv0, uv1, uv2] = corner_uvs[face];
+            let (edge1, edge2) = (p1 - p0, p2 - p0);
+            let (delta1, delta2) = (uv1 - uv0, uv2 - uv0);
+            let determinant = delta1.x * delta2.y - delta2.x * delta1.y;
+            if determinant.abs() < 1e-12 {
+                continue;
+            }
+            let tangent = (edge1 * delta2.y - edge2 * delta1.y) * (1.0 / determinant);
+            let bitangent = (edge2 * delta1.x - edge1 * delta2.x) * (1.0 / determinant);
+
+            for (corner, &index) in triangle.indices.iter().enumerate() {
+                let right_handed = self.normals[index].cross(&tangent).dot(&bitangent) >= 0.0;
+                let sum = sums.entry((index, right_handed)).or_insert(Vec3f::zero());
+                *sum = *sum + tangent;
+                corner_sides[face][corner] = Some(right_handed);
+            }
+        }
+
+        // A vertex keeps its index for the first handedness its corners use; the other gets a copy
+        let mut side_index: HashMap<(usize, bool), usize> = HashMap::new();
+        let mut taken = vec![false; self.vertices.len()];
+        for (face, sides) in corner_sides.iter().enumerate() {
+            for (corner, &side) in sides.iter().enumerate() {
+                let Some(right_handed) = side else {
+                    continue;
+                };
+                let index = self.triangles[face].indices[corner];
+                let new_index = match side_index.get(&(index, right_handed)) {
+                    Some(&new_index) => new_index,
+                    None => {
+                        let new_index = if taken[index] { self.duplicate_vertex(index) } else { index };
+                        taken[index] = true;
+                        side_index.insert((index, right_handed), new_index);
+                        new_index
+                    }
+                };
+                self.triangles[face].indices[corner] = new_index;
+            }
+        }
+
+        self.tangents = vec![Vec4f::new(0.0, 0.0, 0.0, 1.0); self.vertices.len()];
+        let mut fallback = vec![true; self.vertices.len()];
+        for (&(index, right_handed), &new_index) in &side_index {
+            let normal = self.normals[new_index];
+            let tangent_sum = sums[&(index, right_handed)];
+            let tangent = tangent_sum - normal * normal.dot(&tangent_sum);
+            if tangent.length() > 1e-12 {
+                let tangent = tangent.normalize();
+                let w = if right_handed { 1.0 } else { -1.0 };
+                self.tangents[new_index] = Vec4f::new(tangent.x, tangent.y, tangent.z, w);
+                fallback[new_index] = false;
+            }
+        }
+
+        // Vertices with nothing to go on still get a unit tangent perpendicular to their normal
+        for index in (0..self.vertices.len()).filter(|&index| fallback[index]) {
+            let normal = self.normals[index];
+            let helper = if normal.x.abs() < 0.9 { Vec3f::x_axis() } else { Vec3f::y_axis() };
+            let tangent = (helper - normal * normal.dot(&helper)).normalize();
+            self.tangents[index] = Vec4f::new(tangent.x, tangent.y, tangent.z, 1.0);
+        }
+        Ok(())
+    }
+
+    /// The tangent's bitangent, w * (normal × tangent); None without tangents for every vertex
+    pub fn vertex_bitangent(&self, index: usize) -> Option<Vec3f> {
+        if self.tangents.len() != self.vertices.len() || self.normals.len() != self.vertices.len() {
+            return None;
+        }
+        let tangent = self.tangents[index];
+        Some(self.normals[index].cross(&Vec3f::new(tangent.x, tangent.y, tangent.z)) * tangent.w)
+    }
+
+    // A copy of vertex `index` with every per-vertex attribute it has, returning the copy's index
+    fn duplicate_vertex(&mut self, index: usize) -> usize {
+        let vertex_count = self.vertices.len();
+        if self.normals.len() == vertex_count {
+            self.normals.push(self.normals[index]);
+        }
+        if self.uvs.len() == vertex_count {
+            self.uvs.push(self.uvs[index]);
+        }
+        if self.vertex_colors.len() == vertex_count {
+            self.vertex_colors.push(self.vertex_colors[index]);
+        }
+        if self.vertex_ao.len() == vertex_count {
+            self.vertex_ao.push(self.vertex_ao[index]);
+        }
+        self.add_vertex(self.vertices[index])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::Vec2f;
+    use crate::mesh::Triangle;
+
+    // Unit squares side by side in the XY plane, facing +Z; the second shares the first's
+    // x = 1 edge (vertices 1 and 2) and has U mirrored, running back from 1 to 0
+    fn mirrored_pair() -> Mesh {
+        let mut mesh = Mesh::new();
+        for (x, y, u) in [(0.0, 0.0, 0.0), (1.0, 0.0, 1.0), (1.0, 1.0, 1.0), (0.0, 1.0, 0.0), (2.0, 0.0, 0.0), (2.0, 1.0, 0.0)] {
+            mesh.add_vertex_full(Vec3f::new(x, y, 0.0), Vec3f::z_axis(), Vec2f::new(u, y), Vec3f::one());
+        }
+        for [a, b, c] in [[0, 1, 2], [2, 3, 0], [1, 4, 5], [5, 2, 1]] {
+            mesh.add_triangle(Triangle::new(a, b, c, 0xFFFFFFFF));
+        }
+        mesh
+    }
+
+    fn tangent_direction(mesh: &Mesh, index: usize) -> Vec3f {
+        let tangent = mesh.tangents[index];
+        Vec3f::new(tangent.x, tangent.y, tangent.z)
+    }
+
+    #[test]
+    fn uvs_along_the_axes_give_x_tangents() {
+        let mut mesh = mirrored_pair();
+        mesh.triangles.truncate(2);
+        mesh.compute_tangents().unwrap();
+
+        for index in 0..4 {
+            assert!((tangent_direction(&mesh, index) - Vec3f::x_axis()).length() < 1e-6);
+            assert_eq!(mesh.tangents[index].w, 1.0);
+            let bitangent = mesh.vertex_bitangent(index).unwrap();
+            assert!((bitangent - Vec3f::y_axis()).length() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn mirrored_uvs_flip_w_and_split_the_shared_edge() {
+        let mut mesh = mirrored_pair();
+        mesh.compute_tangents().unwrap();
+
+        // Vertices 1 and 2 keep the first quad's side and get a copy each for the mirrored one
+        assert_eq!(mesh.vertices.len(), 8);
+        assert_eq!(mesh.normals.len(), 8);
+        for &index in &mesh.triangles[0].indices {
+            assert_eq!(mesh.tangents[index].w, 1.0);
+        }
+        for triangle in &mesh.triangles[2..] {
+            for &index in &triangle.indices {
+                assert!(index != 1 && index != 2, "mirrored quad still uses vertex {index}");
+                assert_eq!(mesh.tangents[index].w, -1.0);
+                // U grows towards -X there, and the bitangent still points up V
+                assert!((tangent_direction(&mesh, index) + Vec3f::x_axis()).length() < 1e-6);
+                assert!((mesh.vertex_bitangent(index).unwrap() - Vec3f::y_axis()).length() < 1e-6);
+            }
+        }
+        for copy in 6..8 {
+            assert!(mesh.triangles[2..].iter().any(|triangle| triangle.indices.contains(&copy)));
+        }
+    }
+}
@@ -0,0 +1,313 @@
+use std::collections::HashMap;
+use crate::font::{BitmapFont, GLYPH_HEIGHT, GLYPH_WIDTH};
+use crate::math::Vec3f;
+use crate::mesh::{Mesh, Triangle};
+
+const TEXT_COLOR: u32 = 0xFFFFFFFF;
+
+// A corner of the font pixel grid: x to the right, y up from the bottom of the glyph
+type GridPoint = (i32, i32);
+
+impl Mesh {
+    ///
+    /// Solid 3D text: each glyph of `font_data` is traced into outline polygons, the front
+    /// (facing +Z at z = 0) is ear-clipped into triangles, a copy `extrude_depth` behind it
+    /// faces -Z, and quads along the outlines close the sides. Every font pixel is `scale`
+    /// units square; the text starts at the origin and runs along +X with its baseline on
+    /// y = 0, one font advance per character. The font's own `scale` (for screen text) is ignored.
+    ///
+    /// Pixels that only touch at a corner are kept as separate pieces.
+    ///
+    pub fn create_text_mesh(text: &str, font_data: &BitmapFont, extrude_depth: f32, scale: f32) -> Mesh {
+        let mut mesh = Mesh::new();
+        let advance = (GLYPH_WIDTH + 1) as i32;
+
+        for (column, c) in text.chars().enumerate() {
+            let rows = font_data.glyph(c);
+            let filled = |x: i32, y: i32| {
+                let row = GLYPH_HEIGHT as i32 - 1 - y;
+                (0..GLYPH_WIDTH as i32).contains(&x)
+                    && (0..GLYPH_HEIGHT as i32).contains(&y)
+                    && rows[row as usize] & (1 << (GLYPH_WIDTH as i32 - 1 - x)) != 0
+            };
+
+            let offset = column as i32 * advance;
+            let to_front = |(x, y): GridPoint| Vec3f::new((x + offset) as f32 * scale, y as f32 * scale, 0.0);
+            let to_back = |point: GridPoint| to_front(point) + Vec3f::new(0.0, 0.0, -extrude_depth);
+
+            let loops = trace_outlines(filled);
+            let (outers, holes): (Vec<Vec<GridPoint>>, Vec<Vec<GridPoint>>) =
+                loops.into_iter().partition(|outline| signed_area(outline) > 0);
+
+            // Sides straight from the outlines, before holes get bridged into anything
+            for outline in outers.iter().chain(&holes) {
+                for i in 0..outline.len() {
+                    let (p, q) = (outline[i], outline[(i + 1) % outline.len()]);
+                    let first = mesh.vertices.len();
+                    for vertex in [to_front(p), to_front(q), to_back(p), to_back(q)] {
+                        mesh.add_vertex(vertex);
+                    }
+                    // Front p, front q, back p, back q; wound to face away from the filled side
+                    mesh.add_triangle(Triangle::new(first, first + 2, first + 3, TEXT_COLOR));
+                    mesh.add_triangle(Triangle::new(first, first + 3, first + 1, TEXT_COLOR));
+                }
+            }
+
+            // Front and back caps: each outer outline with the holes inside it
+            for outer in &outers {
+                let inner: Vec<&Vec<GridPoint>> = holes
+                    .iter()
+                    .filter(|hole| contains_pixel_beside(outer, hole))
+                    .collect();
+                let polygon = bridge_holes(outer.clone(), &inner);
+
+                let front_start = mesh.vertices.len();
+                for &point in &polygon {
+                    mesh.add_vertex(to_front(point));
+                }
+                let back_start = mesh.vertices.len();
+                for &point in &polygon {
+                    mesh.add_vertex(to_back(point));
+                }
+                for [a, b, c] in ear_clip(&polygon) {
+                    mesh.add_triangle(Triangle::new(front_start + a, front_start + b, front_start + c, TEXT_COLOR));
+                    mesh.add_triangle(Triangle::new(back_start + c, back_start + b, back_start + a, TEXT_COLOR));
+                }
+            }
+        }
+
+        mesh
+    }
+}
+
+///
+/// Closed outlines around the filled pixels, each walked with the filled side on its left:
+/// outer edges counter-clockwise, holes clockwise. Straight runs are merged into single edges.
+///
+fn trace_outlines(filled: impl Fn(i32, i32) -> bool) -> Vec<Vec<GridPoint>> {
+    // Every pixel edge between a filled pixel and an empty one, pointing counter-clockwise around the filled one
+    let mut outgoing: HashMap<GridPoint, Vec<GridPoint>> = HashMap::new();
+    for y in 0..GLYPH_HEIGHT as i32 {
+        for x in 0..GLYPH_WIDTH as i32 {
+            if !filled(x, y) {
+                continue;
+            }
+            let sides = [
+                (!filled(x, y - 1), (x, y), (x + 1, y)),             // Bottom
+                (!filled(x + 1, y), (x + 1, y), (x + 1, y + 1)),     // Right
+                (!filled(x, y + 1), (x + 1, y + 1), (x, y + 1)),     // Top
+                (!filled(x - 1, y), (x, y + 1), (x, y)),             // Left
+            ];
+            for (exposed, from, to) in sides {
+                if exposed {
+                    outgoing.entry(from).or_default().push(to);
+                }
+            }
+        }
+    }
+
+    // Where two pixels touch only at a corner, two outlines pass through the same point;
+    // turning left there keeps each one hugging its own pixel
+    let next_edge = |from: GridPoint, at: GridPoint| -> GridPoint {
+        let incoming = (at.0 - from.0, at.1 - from.1);
+        *outgoing[&at]
+            .iter()
+            .max_by_key(|to| {
+                let direction = (to.0 - at.0, to.1 - at.1);
+                incoming.0 * direction.1 - incoming.1 * direction.0
+            })
+            .unwrap()
+    };
+
+    let mut starts: Vec<(GridPoint, GridPoint)> = outgoing
+        .iter()
+        .flat_map(|(&from, targets)| targets.iter().map(move |&to| (from, to)))
+        .collect();
+    starts.sort_unstable();
+
+    let mut used: Vec<(GridPoint, GridPoint)> = Vec::new();
+    let mut outlines = Vec::new();
+    for start in starts {
+        if used.contains(&start) {
+            continue;
+        }
+        let mut points = Vec::new();
+        let mut edge = start;
+        loop {
+            used.push(edge);
+            points.push(edge.0);
+            edge = (edge.1, next_edge(edge.0, edge.1));
+            if edge == start {
+                break;
+            }
+        }
+        outlines.push(remove_collinear(points));
+    }
+    outlines
+}
+
+fn remove_collinear(points: Vec<GridPoint>) -> Vec<GridPoint> {
+    let count = points.len();
+    (0..count)
+        .filter(|&i| cross(points[(i + count - 1) % count], points[i], points[(i + 1) % count]) != 0)
+        .map(|i| points[i])
+        .collect()
+}
+
+// Twice the area, positive for counter-clockwise outlines
+fn signed_area(outline: &[GridPoint]) -> i64 {
+    (0..outline.len())
+        .map(|i| {
+            let (a, b) = (outline[i], outline[(i + 1) % outline.len()]);
+            a.0 as i64 * b.1 as i64 - b.0 as i64 * a.1 as i64
+        })
+        .sum()
+}
+
+// z of (b - a) x (c - b): positive when a -> b -> c turns left
+fn cross(a: GridPoint, b: GridPoint, c: GridPoint) -> i64 {
+    (b.0 - a.0) as i64 * (c.1 - b.1) as i64 - (b.1 - a.1) as i64 * (c.0 - b.0) as i64
+}
+
+// Even-odd test; callers pass points in twice-size coordinates so pixel centers never sit on an edge
+fn contains_doubled(outline: &[GridPoint], (px, py): GridPoint) -> bool {
+    let mut inside = false;
+    for i in 0..outline.len() {
+        let (a, b) = (outline[i], outline[(i + 1) % outline.len()]);
+        let (ax, ay, bx, by) = (a.0 * 2, a.1 * 2, b.0 * 2, b.1 * 2);
+        if (ay > py) != (by > py) {
+            let crossing_x = ax as f32 + (py - ay) as f32 * (bx - ax) as f32 / (by - ay) as f32;
+            if (px as f32) < crossing_x {
+                inside = !inside;
+            }
+        }
+    }
+    inside
+}
+
+// Whether `outer` surrounds `hole`: tests the filled pixel on the left of the hole's first edge
+fn contains_pixel_beside(outer: &[GridPoint], hole: &[GridPoint]) -> bool {
+    let (a, b) = (hole[0], hole[1 % hole.len()]);
+    let direction = ((b.0 - a.0).signum(), (b.1 - a.1).signum());
+    // Doubled: start of the edge, half a pixel along it, half a pixel to its left
+    let center = (a.0 * 2 + direction.0 - direction.1, a.1 * 2 + direction.1 + direction.0);
+    contains_doubled(outer, center)
+}
+
+///
+/// Cuts each hole open and joins it to the outline with a pair of coincident edges, giving one
+/// polygon ear clipping can handle. Holes are joined rightmost first, each from its rightmost
+/// vertex to the nearest polygon vertex whose connecting segment crosses nothing.
+///
+fn bridge_holes(mut polygon: Vec<GridPoint>, holes: &[&Vec<GridPoint>]) -> Vec<GridPoint> {
+    let mut holes: Vec<&Vec<GridPoint>> = holes.to_vec();
+    holes.sort_by_key(|hole| std::cmp::Reverse(hole.iter().map(|point| point.0).max()));
+
+    for (index, hole) in holes.iter().enumerate() {
+        let start = (0..hole.len()).max_by_key(|&i| (hole[i].0, hole[i].1)).unwrap();
+        let from = hole[start];
+
+        // Everything a bridge mustn't cross: the polygon so far and the holes not yet joined
+        let mut blockers: Vec<(GridPoint, GridPoint)> = Vec::new();
+        for outline in std::iter::once(&polygon).chain(holes[index..].iter().copied()) {
+            blockers.extend((0..outline.len()).map(|i| (outline[i], outline[(i + 1) % outline.len()])));
+        }
+
+        let mut candidates: Vec<usize> = (0..polygon.len()).collect();
+        candidates.sort_by_key(|&i| {
+            let (dx, dy) = ((polygon[i].0 - from.0) as i64, (polygon[i].1 - from.1) as i64);
+            dx * dx + dy * dy
+        });
+        let Some(target) = candidates
+            .into_iter()
+            .find(|&i| blockers.iter().all(|&(a, b)| !segment_blocked(from, polygon[i], a, b)))
+        else {
+            continue; // No clear line; leaving the hole filled beats tearing the cap
+        };
+
+        let to = polygon[target];
+        let mut bridged = Vec::with_capacity(polygon.len() + hole.len() + 2);
+        bridged.extend_from_slice(&polygon[..=target]);
+        bridged.extend((0..=hole.len()).map(|i| hole[(start + i) % hole.len()]));
+        bridged.push(to);
+        bridged.extend_from_slice(&polygon[target + 1..]);
+        polygon = bridged;
+    }
+    polygon
+}
+
+// Whether edge a-b gets in the way of segment p-q anywhere but at p and q themselves
+fn segment_blocked(p: GridPoint, q: GridPoint, a: GridPoint, b: GridPoint) -> bool {
+    let orientation = |u: GridPoint, v: GridPoint, w: GridPoint| cross(u, v, w).signum();
+    let on_segment = |u: GridPoint, v: GridPoint, w: GridPoint| {
+        // w on the closed segment u-v, given it's collinear with it
+        w.0 >= u.0.min(v.0) && w.0 <= u.0.max(v.0) && w.1 >= u.1.min(v.1) && w.1 <= u.1.max(v.1)
+    };
+
+    let (o1, o2) = (orientation(p, q, a), orientation(p, q, b));
+    let (o3, o4) = (orientation(a, b, p), orientation(a, b, q));
+    if o1 * o2 < 0 && o3 * o4 < 0 {
+        return true; // Proper crossing
+    }
+    // Edge endpoints lying on the segment, or the segment's ends lying inside the edge
+    let inside_pq = |w: GridPoint| w != p && w != q && orientation(p, q, w) == 0 && on_segment(p, q, w);
+    let inside_ab = |w: GridPoint| w != a && w != b && orientation(a, b, w) == 0 && on_segment(a, b, w);
+    inside_pq(a) || inside_pq(b) || inside_ab(p) || inside_ab(q)
+}
+
+///
+/// Triangulates a counter-clockwise polygon (which may touch itself along hole bridges) by
+/// repeatedly cutting off a convex corner with no other polygon point inside it.
+/// Returns corner indices into `polygon`, counter-clockwise.
+///
+fn ear_clip(polygon: &[GridPoint]) -> Vec<[usize; 3]> {
+    let mut remaining: Vec<usize> = (0..polygon.len()).collect();
+    let mut triangles = Vec::new();
+
+    while remaining.len() > 3 {
+        let count = remaining.len();
+        let corner = |i: usize| {
+            (remaining[(i + count - 1) % count], remaining[i], remaining[(i + 1) % count])
+        };
+        let is_ear = |i: usize| {
+            let (a, b, c) = corner(i);
+            let (pa, pb, pc) = (polygon[a], polygon[b], polygon[c]);
+            cross(pa, pb, pc) > 0
+                && remaining.iter().all(|&other| {
+                    let point = polygon[other];
+                    point == pa || point == pb || point == pc || !in_triangle(point, pa, pb, pc)
+                })
+        };
+
+        // Straight-through corners (left by bridges) go first; they add no area
+        let clip = (0..count)
+            .find(|&i| {
+                let (a, b, c) = corner(i);
+                cross(polygon[a], polygon[b], polygon[c]) == 0
+            })
+            .map(|i| (i, false))
+            .or_else(|| (0..count).find(|&i| is_ear(i)).map(|i| (i, true)));
+
+        match clip {
+            Some((i, emit)) => {
+                if emit {
+                    let (a, b, c) = corner(i);
+                    triangles.push([a, b, c]);
+                }
+                remaining.remove(i);
+            }
+            None => break, // Not a simple polygon; keep what was found rather than loop forever
+        }
+    }
+
+    if let [a, b, c] = remaining[..]
+        && cross(polygon[a], polygon[b], polygon[c]) > 0 {
+        triangles.push([a, b, c]);
+    }
+    triangles
+}
+
+// Inside or on the edges of counter-clockwise triangle a, b, c
+fn in_triangle(point: GridPoint, a: GridPoint, b: GridPoint, c: GridPoint) -> bool {
+    cross(a, b, point) >= 0 && cross(b, c, point) >= 0 && cross(c, a, point) >= 0
+}
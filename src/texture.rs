@@ -0,0 +1,285 @@
+use std::{fmt, fs, io};
+use crate::math::{Vec2f, Vec3f};
+
+/// Reasons an image file can't be loaded as a Texture
+#[derive(Debug)]
+pub enum TextureError {
+    IoError(io::Error),         // Couldn't read the file
+    InvalidFormat(String),      // Not the format asked for, or truncated or corrupt
+    UnsupportedVariant(String), // The right format, in a flavour the loader doesn't read
+}
+
+impl fmt::Display for TextureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TextureError::IoError(error) => write!(f, "couldn't read texture: {}", error),
+            TextureError::InvalidFormat(reason) => write!(f, "invalid image: {}", reason),
+            TextureError::UnsupportedVariant(variant) => write!(f, "unsupported image variant: {}", variant),
+        }
+    }
+}
+
+impl std::error::Error for TextureError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            TextureError::IoError(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for TextureError {
+    fn from(error: io::Error) -> Self {
+        TextureError::IoError(error)
+    }
+}
+
+fn invalid(reason: &str) -> TextureError {
+    TextureError::InvalidFormat(reason.to_string())
+}
+
+///
+/// An RGB image for material maps, stored as linear [0, 1] floats.
+/// UV (0, 0) is the top-left corner and (1, 1) the bottom-right; coordinates outside
+/// that range wrap around, so textures repeat.
+///
+pub struct Texture {
+    pub width: u32,
+    pub height: u32,
+    pixels: Vec<Vec3f>, // Row by row, top to bottom
+}
+
+impl Texture {
+    /// `pixels` must hold width * height colors, row by row from the top
+    pub fn new(width: u32, height: u32, pixels: Vec<Vec3f>) -> Self {
+        assert_eq!(pixels.len(), (width * height) as usize, "texture size doesn't match its pixels");
+        Self { width, height, pixels }
+    }
+
+    pub fn solid(color: Vec3f) -> Self {
+        Self::new(1, 1, vec![color])
+    }
+
+    /// From ARGB pixels as used by the framebuffer (alpha is ignored)
+    pub fn from_argb(width: u32, height: u32, pixels: &[u32]) -> Self {
+        let colors = pixels
+            .iter()
+            .map(|pixel| Vec3f::new(
+                ((pixel >> 16) & 0xFF) as f32 / 255.0,
+                ((pixel >> 8) & 0xFF) as f32 / 255.0,
+                (pixel & 0xFF) as f32 / 255.0,
+            ))
+            .collect();
+        Self::new(width, height, colors)
+    }
+
+    /// `size` x `size` pixels with `cells` x `cells` alternating squares, starting with `a` top-left
+    pub fn checkerboard(size: u32, cells: u32, a: Vec3f, b: Vec3f) -> Self {
+        let size = size.max(1);
+        let cell_size = (size / cells.max(1)).max(1);
+        let mut pixels = Vec::with_capacity((size * size) as usize);
+        for y in 0..size {
+            for x in 0..size {
+                let even = (x / cell_size + y / cell_size).is_multiple_of(2);
+                pixels.push(if even { a } else { b });
+            }
+        }
+        Self::new(size, size, pixels)
+    }
+
+    ///
+    /// `size` x `size` pixels fading from `center` in the middle to `edge` at the inscribed
+    /// circle's rim, `edge` beyond it. A round glow when used as an emissive map.
+    ///
+    pub fn radial_gradient(size: u32, center: Vec3f, edge: Vec3f) -> Self {
+        let size = size.max(1);
+        let half = size as f32 * 0.5;
+        let mut pixels = Vec::with_capacity((size * size) as usize);
+        for y in 0..size {
+            for x in 0..size {
+                let (dx, dy) = (x as f32 + 0.5 - half, y as f32 + 0.5 - half);
+                let t = ((dx * dx + dy * dy).sqrt() / half).min(1.0);
+                pixels.push(center + (edge - center) * t);
+            }
+        }
+        Self::new(size, size, pixels)
+    }
+
+    pub fn get_pixel(&self, x: u32, y: u32) -> Vec3f {
+        self.pixels[(y.min(self.height - 1) * self.width + x.min(self.width - 1)) as usize]
+    }
+
+    /// Nearest-neighbour lookup with wrapping
+    pub fn sample(&self, uv: Vec2f) -> Vec3f {
+        let u = uv.x - uv.x.floor();
+        let v = uv.y - uv.y.floor();
+        let x = ((u * self.width as f32) as u32).min(self.width - 1);
+        let y = ((v * self.height as f32) as u32).min(self.height - 1);
+        self.get_pixel(x, y)
+    }
+
+    /// Reads a PPM file, ASCII (P3) or binary (P6), see from_ppm_bytes
+    pub fn load_ppm(path: &str) -> Result<Texture, TextureError> {
+        Self::from_ppm_bytes(&fs::read(path)?)
+    }
+
+    /// Reads an uncompressed 24-bit BMP file, see from_bmp_bytes
+    pub fn load_bmp(path: &str) -> Result<Texture, TextureError> {
+        Self::from_bmp_bytes(&fs::read(path)?)
+    }
+
+    ///
+    /// Decodes a PPM image: P3 (ASCII) or P6 (binary), with any maximum value up to 65535.
+    /// Samples are divided by the maximum value and used as they are, like from_argb.
+    ///
+    pub fn from_ppm_bytes(bytes: &[u8]) -> Result<Texture, TextureError> {
+        let mut reader = PpmReader { bytes, position: 0 };
+        let binary = match reader.token()? {
+            b"P3" => false,
+            b"P6" => true,
+            b"P1" | b"P2" | b"P4" | b"P5" | b"P7" => {
+                return Err(TextureError::UnsupportedVariant("only P3 and P6 PPM images are read".to_string()));
+            }
+            _ => return Err(invalid("not a PPM image")),
+        };
+        let width = reader.number()?;
+        let height = reader.number()?;
+        let max_value = reader.number()?;
+        if max_value == 0 || max_value > 65535 {
+            return Err(invalid("PPM maximum value must be between 1 and 65535"));
+        }
+        let pixel_count = pixel_count(width, height)?;
+
+        let mut samples = Vec::with_capacity(pixel_count * 3);
+        if binary {
+            // Exactly one whitespace byte separates the header from the samples
+            reader.position += 1;
+            let sample_size = if max_value > 255 { 2 } else { 1 };
+            let data = reader.bytes
+                .get(reader.position..reader.position + pixel_count * 3 * sample_size)
+                .ok_or_else(|| invalid("PPM pixel data is cut short"))?;
+            samples.extend(data.chunks_exact(sample_size).map(|sample| match *sample {
+                [high, low] => u32::from(high) << 8 | u32::from(low),
+                _ => u32::from(sample[0]),
+            }));
+        } else {
+            for _ in 0..pixel_count * 3 {
+                samples.push(reader.number()?);
+            }
+        }
+
+        let scale = 1.0 / max_value as f32;
+        let pixels = samples
+            .chunks_exact(3)
+            .map(|rgb| Vec3f::new(
+                rgb[0].min(max_value) as f32 * scale,
+                rgb[1].min(max_value) as f32 * scale,
+                rgb[2].min(max_value) as f32 * scale,
+            ))
+            .collect();
+        Ok(Self::new(width, height, pixels))
+    }
+
+    ///
+    /// Decodes a BMP image with a BITMAPINFOHEADER (or later) header, 24 bits per pixel and
+    /// no compression. Rows may be stored bottom-up (the usual) or top-down.
+    ///
+    pub fn from_bmp_bytes(bytes: &[u8]) -> Result<Texture, TextureError> {
+        let u16_at = |offset: usize| bytes.get(offset..offset + 2).map(|b| u16::from_le_bytes([b[0], b[1]]));
+        let u32_at = |offset: usize| {
+            bytes.get(offset..offset + 4).map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        };
+
+        if bytes.get(0..2) != Some(b"BM") {
+            return Err(invalid("not a BMP image"));
+        }
+        let truncated = || invalid("BMP header is cut short");
+        let data_offset = u32_at(10).ok_or_else(truncated)? as usize;
+        let header_size = u32_at(14).ok_or_else(truncated)?;
+        if header_size < 40 {
+            return Err(TextureError::UnsupportedVariant("OS/2 BMP headers aren't read".to_string()));
+        }
+        let width = u32_at(18).ok_or_else(truncated)? as i32;
+        let height = u32_at(22).ok_or_else(truncated)? as i32;
+        let bits_per_pixel = u16_at(28).ok_or_else(truncated)?;
+        let compression = u32_at(30).ok_or_else(truncated)?;
+        if bits_per_pixel != 24 {
+            return Err(TextureError::UnsupportedVariant(format!("{}-bit BMP, only 24-bit is read", bits_per_pixel)));
+        }
+        if compression != 0 {
+            return Err(TextureError::UnsupportedVariant("compressed BMP".to_string()));
+        }
+        if width <= 0 || height == 0 {
+            return Err(invalid("BMP image has no pixels"));
+        }
+
+        let (width, top_down, height) = (width as u32, height < 0, height.unsigned_abs());
+        pixel_count(width, height)?;
+        // Each row is padded to a multiple of 4 bytes
+        let stride = (width as usize * 3).div_ceil(4) * 4;
+        let data = bytes
+            .get(data_offset..)
+            .filter(|data| data.len() >= stride * (height as usize - 1) + width as usize * 3)
+            .ok_or_else(|| invalid("BMP pixel data is cut short"))?;
+
+        let mut pixels = Vec::with_capacity(width as usize * height as usize);
+        for y in 0..height as usize {
+            let row = if top_down { y } else { height as usize - 1 - y };
+            let start = row * stride;
+            pixels.extend(data[start..start + width as usize * 3].chunks_exact(3).map(|bgr| Vec3f::new(
+                bgr[2] as f32 / 255.0,
+                bgr[1] as f32 / 255.0,
+                bgr[0] as f32 / 255.0,
+            )));
+        }
+        Ok(Self::new(width, height, pixels))
+    }
+}
+
+// width * height, refusing empty images and ones too big to index
+fn pixel_count(width: u32, height: u32) -> Result<usize, TextureError> {
+    if width == 0 || height == 0 {
+        return Err(invalid("image has no pixels"));
+    }
+    width
+        .checked_mul(height)
+        .filter(|count| count.checked_mul(3).is_some())
+        .map(|count| count as usize)
+        .ok_or_else(|| invalid("image is too large"))
+}
+
+// Whitespace-separated PPM header and P3 sample tokens, skipping # comments
+struct PpmReader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> PpmReader<'a> {
+    fn token(&mut self) -> Result<&'a [u8], TextureError> {
+        loop {
+            match self.bytes.get(self.position) {
+                Some(byte) if byte.is_ascii_whitespace() => self.position += 1,
+                Some(b'#') => {
+                    while self.bytes.get(self.position).is_some_and(|&byte| byte != b'\n') {
+                        self.position += 1;
+                    }
+                }
+                Some(_) => break,
+                None => return Err(invalid("PPM image is cut short")),
+            }
+        }
+        let start = self.position;
+        while self.bytes.get(self.position).is_some_and(|byte| !byte.is_ascii_whitespace()) {
+            self.position += 1;
+        }
+        Ok(&self.bytes[start..self.position])
+    }
+
+    fn number(&mut self) -> Result<u32, TextureError> {
+        let token = self.token()?;
+        std::str::from_utf8(token)
+            .ok()
+            .and_then(|text| text.parse().ok())
+            .ok_or_else(|| invalid("PPM value isn't a number"))
+    }
+}
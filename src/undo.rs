@@ -0,0 +1,172 @@
+use crate::lighting::LightHandle;
+use crate::math::Vec3f;
+use crate::scene::{GameObject, RemovedObject, Scene};
+
+///
+/// A reversible edit to a scene. `undo` has to put the scene back the way `execute` found
+/// it, so that an UndoStack can step back and forth through edits in order.
+///
+pub trait SceneCommand {
+    fn execute(&mut self, scene: &mut Scene);
+    fn undo(&mut self, scene: &mut Scene);
+}
+
+/// Moves the object at `index` between two positions
+pub struct MoveObjectCommand {
+    pub index: usize,
+    pub old_pos: Vec3f,
+    pub new_pos: Vec3f,
+}
+
+impl MoveObjectCommand {
+    pub fn new(index: usize, old_pos: Vec3f, new_pos: Vec3f) -> Self {
+        Self { index, old_pos, new_pos }
+    }
+}
+
+impl SceneCommand for MoveObjectCommand {
+    fn execute(&mut self, scene: &mut Scene) {
+        if let Some(game_object) = scene.game_objects.get_mut(self.index) {
+            game_object.set_position(self.new_pos);
+        }
+    }
+
+    fn undo(&mut self, scene: &mut Scene) {
+        if let Some(game_object) = scene.game_objects.get_mut(self.index) {
+            game_object.set_position(self.old_pos);
+        }
+    }
+}
+
+/// Moves a light between two positions; does nothing once the light has been removed
+pub struct MoveLightCommand {
+    pub light: LightHandle,
+    pub old_pos: Vec3f,
+    pub new_pos: Vec3f,
+}
+
+impl MoveLightCommand {
+    pub fn new(light: LightHandle, old_pos: Vec3f, new_pos: Vec3f) -> Self {
+        Self { light, old_pos, new_pos }
+    }
+}
+
+impl SceneCommand for MoveLightCommand {
+    fn execute(&mut self, scene: &mut Scene) {
+        if let Some(light) = scene.lighting.get_mut(self.light) {
+            light.position = self.new_pos;
+        }
+    }
+
+    fn undo(&mut self, scene: &mut Scene) {
+        if let Some(light) = scene.lighting.get_mut(self.light) {
+            light.position = self.old_pos;
+        }
+    }
+}
+
+/// Removes the object at `index` (and its springs and rigid bodies), keeping it to put back on undo
+pub struct DeleteObjectCommand {
+    pub index: usize,
+    removed: Option<RemovedObject>,
+}
+
+impl DeleteObjectCommand {
+    pub fn new(index: usize) -> Self {
+        Self { index, removed: None }
+    }
+}
+
+impl SceneCommand for DeleteObjectCommand {
+    fn execute(&mut self, scene: &mut Scene) {
+        self.removed = scene.remove_game_object(self.index);
+    }
+
+    fn undo(&mut self, scene: &mut Scene) {
+        if let Some(removed) = self.removed.take() {
+            scene.insert_game_object(self.index, removed);
+        }
+    }
+}
+
+/// Adds an object at the end of the scene; undo takes it back out (with anything attached to it since)
+pub struct AddObjectCommand {
+    object: Option<RemovedObject>,
+    index: Option<usize>, // Where it went, while it's in the scene
+}
+
+impl AddObjectCommand {
+    pub fn new(game_object: GameObject) -> Self {
+        Self { object: Some(RemovedObject::new(game_object)), index: None }
+    }
+}
+
+impl SceneCommand for AddObjectCommand {
+    fn execute(&mut self, scene: &mut Scene) {
+        if let Some(object) = self.object.take() {
+            let index = scene.game_objects.len();
+            scene.insert_game_object(index, object);
+            self.index = Some(index);
+        }
+    }
+
+    fn undo(&mut self, scene: &mut Scene) {
+        self.object = self.index.take().and_then(|index| scene.remove_game_object(index));
+    }
+}
+
+///
+/// Commands that have been run, and how far back through them undo has gone.
+/// Running a new command after undoing drops the undone ones, so redo can't bring them back.
+///
+pub struct UndoStack {
+    commands: Vec<Box<dyn SceneCommand>>,
+    position: usize, // Commands before this are applied, the rest were undone
+}
+
+impl UndoStack {
+    pub fn new() -> Self {
+        Self { commands: Vec::new(), position: 0 }
+    }
+
+    pub fn execute(&mut self, mut command: Box<dyn SceneCommand>, scene: &mut Scene) {
+        self.commands.truncate(self.position);
+        command.execute(scene);
+        self.commands.push(command);
+        self.position = self.commands.len();
+    }
+
+    /// Undoes the last applied command; false when there is nothing left to undo
+    pub fn undo(&mut self, scene: &mut Scene) -> bool {
+        if self.position == 0 {
+            return false;
+        }
+        self.position -= 1;
+        self.commands[self.position].undo(scene);
+        true
+    }
+
+    /// Re-applies the last undone command; false when there is nothing to redo
+    pub fn redo(&mut self, scene: &mut Scene) -> bool {
+        let Some(command) = self.commands.get_mut(self.position) else {
+            return false;
+        };
+        command.execute(scene);
+        self.position += 1;
+        true
+    }
+
+    pub fn can_undo(&self) -> bool {
+        self.position > 0
+    }
+
+    pub fn can_redo(&self) -> bool {
+        self.position < self.commands.len()
+    }
+}
+
+impl Default for UndoStack {
+    fn default() -> Self {
+        Self::new()
+    }
+}